@@ -0,0 +1,716 @@
+//! The devsql command line.
+//!
+//! It lives in the library so a crate can register its own
+//! [data sources](crate::source) and then run the stock CLI with [`main`].
+
+use std::io::{BufWriter, Write};
+use std::sync::Arc;
+
+use crate::engine::{QueryParams, RowStream};
+use crate::saved::SavedQuery;
+use crate::UnifiedEngine;
+use futures::StreamExt;
+use incurs::cli::Cli;
+use incurs::command::{
+    CommandContext, CommandDef, CommandHandler, Example, McpCommandOptions, TypedContext,
+    TypedResult,
+};
+use incurs::output::{CommandResult, Format, StreamRecord};
+use incurs_codemode::{
+    CodeMode, CodeModeRunOptions, CodeModeService, ExecutionState, IncurConnector, MemoryStore,
+    SearchOutput,
+};
+use incurs_codemode_local::{LocalCodeModeService, LocalExecutor};
+use incurs_extras::{CliExtras, ExtraFormat};
+use serde_json::Value;
+use vcsql::TimeWindow;
+
+#[derive(Clone)]
+struct DurableCodeModeService(LocalCodeModeService);
+
+fn durable_options(options: CodeModeRunOptions) -> CodeModeRunOptions {
+    CodeModeRunOptions {
+        request: options.request,
+        ..CodeModeRunOptions::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl CodeModeService for DurableCodeModeService {
+    async fn search(&self, query: String) -> Result<SearchOutput, String> {
+        self.0.search(query).await
+    }
+
+    async fn execute(
+        &self,
+        code: String,
+        options: CodeModeRunOptions,
+    ) -> Result<ExecutionState, String> {
+        self.0.execute(code, durable_options(options)).await
+    }
+
+    async fn execution(&self, execution_id: String) -> Result<ExecutionState, String> {
+        self.0.execution(execution_id).await
+    }
+
+    async fn artifact(&self, execution_id: String, artifact_id: String) -> Result<Value, String> {
+        self.0.artifact(execution_id, artifact_id).await
+    }
+
+    async fn approve(
+        &self,
+        execution_id: String,
+        seq: u64,
+        options: CodeModeRunOptions,
+    ) -> Result<ExecutionState, String> {
+        self.0
+            .approve(execution_id, seq, durable_options(options))
+            .await
+    }
+
+    async fn reject(&self, execution_id: String, seq: u64) -> Result<ExecutionState, String> {
+        self.0.reject(execution_id, seq).await
+    }
+
+    async fn cancel(&self, execution_id: String) -> Result<ExecutionState, String> {
+        self.0.cancel(execution_id).await
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Schemas (derive macros replace manual FieldMeta construction)
+// ---------------------------------------------------------------------------
+
+#[derive(incurs::Args, serde::Deserialize)]
+#[allow(dead_code)]
+struct QueryArgs {
+    /// SQL query to execute
+    query: String,
+}
+
+#[derive(incurs::Options, serde::Deserialize)]
+#[allow(dead_code)]
+struct QueryOptions {
+    /// Git repository path or glob; repeat to query several repositories
+    #[incurs(alias = "r")]
+    #[serde(default)]
+    repo: Vec<String>,
+    /// File listing one repository root (or glob) per line
+    #[incurs(alias = "w")]
+    workspace: Option<String>,
+    /// Claude data directory (defaults to ~/.claude)
+    #[incurs(alias = "d")]
+    data_dir: Option<String>,
+    /// Query parameters: a JSON array for `?` placeholders or a JSON object
+    /// for `:name` placeholders
+    #[incurs(alias = "p")]
+    params: Option<Value>,
+    /// Only load rows at or after this time: an RFC 3339 timestamp, a
+    /// YYYY-MM-DD date (UTC), or a span back from now such as 7d or 12h
+    since: Option<String>,
+    /// Only load rows before this time (same formats as --since)
+    until: Option<String>,
+    /// Query a snapshot written by `devsql export` instead of live sources
+    db: Option<String>,
+    /// Load a CSV, JSON, JSON Lines, or SQLite file as a table, given as
+    /// name=path; repeat to attach several
+    #[serde(default)]
+    attach: Vec<String>,
+    /// Load the query's tables, then return a report of what was loaded and
+    /// SQLite's query plan instead of running it
+    #[serde(default)]
+    explain: bool,
+    /// Run the query and write a JSON report of what was loaded, what it
+    /// cost, and SQLite's query plan to stderr
+    #[serde(default)]
+    profile: bool,
+    /// Omit header row in table/csv output
+    #[incurs(alias = "H")]
+    #[serde(default)]
+    no_header: bool,
+}
+
+// ---------------------------------------------------------------------------
+// Handler
+// ---------------------------------------------------------------------------
+
+type QueryOutput = Vec<Value>;
+
+/// Read `--params`: JSON text on the command line, or a JSON value from Code
+/// Mode and MCP callers.
+fn query_params(params: Option<Value>) -> Result<QueryParams, String> {
+    let params = match params {
+        Some(Value::String(text)) => {
+            serde_json::from_str(&text).map_err(|e| format!("params must be valid JSON: {e}"))?
+        }
+        Some(value) => value,
+        None => Value::Null,
+    };
+    QueryParams::from_json(params).map_err(|e| e.to_string())
+}
+
+/// Open an engine over the requested repositories and load every table the
+/// query mentions. Errors are `(code, message)` pairs.
+fn prepare_query(
+    query: &str,
+    options: &QueryOptions,
+) -> Result<(UnifiedEngine, QueryParams), (&'static str, String)> {
+    let params = query_params(options.params.clone()).map_err(|e| ("INVALID_PARAMS", e))?;
+    let mut engine = match &options.db {
+        Some(db) => snapshot_engine(db, options)?,
+        None => live_engine(options)?,
+    };
+    crate::tools::attach_files(&mut engine, &options.attach)
+        .map_err(|error| (error.code, error.message))?;
+    if options.explain || options.profile {
+        engine.enable_profile();
+    }
+
+    // Saved queries the SQL reads become views; load the tables behind them too.
+    let named = match &params {
+        QueryParams::Named(values) => values.clone(),
+        _ => serde_json::Map::new(),
+    };
+    let views =
+        crate::saved::views(query, &named).map_err(|e| ("SAVED_QUERY_ERROR", e.to_string()))?;
+    let mut tables = crate::source::tables_in(query);
+    engine.note_selected(&tables, "referenced by the query");
+    for view in &views {
+        let read = crate::source::tables_in(&view.sql);
+        engine.note_selected(&read, &format!("read by saved query {}", view.name));
+        for table in read {
+            if !tables.contains(&table) {
+                tables.push(table);
+            }
+        }
+    }
+    let table_refs: Vec<&str> = tables.iter().map(|s| s.as_str()).collect();
+    engine
+        .load_tables(&table_refs)
+        .map_err(|e| ("LOAD_ERROR", e.to_string()))?;
+    for view in &views {
+        engine.create_view(&view.name, &view.sql).map_err(|e| {
+            (
+                "SAVED_QUERY_ERROR",
+                format!("Failed to register saved query {}: {e}", view.name),
+            )
+        })?;
+    }
+    Ok((engine, params))
+}
+
+fn live_engine(options: &QueryOptions) -> Result<UnifiedEngine, (&'static str, String)> {
+    let window = TimeWindow::parse(options.since.as_deref(), options.until.as_deref())
+        .map_err(|e| ("INVALID_TIME", e.to_string()))?;
+    let (mut engine, _) = crate::tools::engine_from_repos(
+        &options.repo,
+        options.workspace.as_deref(),
+        options.data_dir.as_deref(),
+    )
+    .map_err(|error| (error.code, error.message))?;
+    engine.set_time_window(window);
+    Ok(engine)
+}
+
+/// An engine over a snapshot. Options that pick sources were fixed at
+/// export time, so they are refused rather than ignored.
+fn snapshot_engine(
+    db: &str,
+    options: &QueryOptions,
+) -> Result<UnifiedEngine, (&'static str, String)> {
+    if !options.repo.is_empty()
+        || options.workspace.is_some()
+        || options.data_dir.is_some()
+        || options.since.is_some()
+        || options.until.is_some()
+    {
+        return Err((
+            "INVALID_OPTIONS",
+            "--db reads a snapshot; --repo, --workspace, --data-dir, --since, and --until \
+             apply to `devsql export`"
+                .to_string(),
+        ));
+    }
+    UnifiedEngine::open_snapshot(std::path::Path::new(db))
+        .map_err(|e| ("SNAPSHOT_ERROR", e.to_string()))
+}
+
+async fn run_query(ctx: TypedContext<QueryArgs, QueryOptions, ()>) -> TypedResult<QueryOutput> {
+    execute_query(&ctx.args.query, &ctx.options)
+}
+
+fn execute_query(query: &str, options: &QueryOptions) -> TypedResult<QueryOutput> {
+    let (mut engine, params) = match prepare_query(query, options) {
+        Ok(prepared) => prepared,
+        Err((code, message)) => return TypedResult::error(code, message),
+    };
+    if options.explain || options.profile {
+        return profile_query(&mut engine, query, &params, options.explain);
+    }
+
+    match engine.query_with_params(query, &params) {
+        Ok(results) => TypedResult::ok(results),
+        Err(e) => TypedResult::error("QUERY_ERROR", format!("Query failed: {e}")),
+    }
+}
+
+/// `--explain` returns the profile as the only row, without running the
+/// query; `--profile` runs it, returns its rows, and writes the profile,
+/// with the query's own cost, to stderr.
+fn profile_query(
+    engine: &mut UnifiedEngine,
+    query: &str,
+    params: &QueryParams,
+    explain_only: bool,
+) -> TypedResult<QueryOutput> {
+    let plan = match engine.explain(query, params) {
+        Ok(plan) => plan,
+        Err(e) => return TypedResult::error("QUERY_ERROR", format!("Query failed: {e}")),
+    };
+    let mut results = Vec::new();
+    if !explain_only {
+        let started = std::time::Instant::now();
+        let files_read = crate::profile::files_read();
+        results = match engine.query_with_params(query, params) {
+            Ok(results) => results,
+            Err(e) => return TypedResult::error("QUERY_ERROR", format!("Query failed: {e}")),
+        };
+        if let Some(profile) = engine.profile_mut() {
+            profile.query = Some(crate::profile::QueryStep {
+                wall_ms: crate::profile::millis(started.elapsed()),
+                rows: results.len(),
+                files_read: crate::profile::files_read() - files_read,
+            });
+        }
+    }
+    let Some(profile) = engine.profile_mut() else {
+        return TypedResult::ok(results);
+    };
+    profile.query_plan = plan;
+    let report = match serde_json::to_value(&*profile) {
+        Ok(report) => report,
+        Err(e) => return TypedResult::error("SERIALIZATION_ERROR", e.to_string()),
+    };
+    if explain_only {
+        return TypedResult::ok(vec![report]);
+    }
+    eprintln!("{}", serde_json::json!({ "profile": report }));
+    TypedResult::ok(results)
+}
+
+/// Streams `jsonl` output, and `csv` output on the command line, row by row
+/// so large results use constant memory. Every other format needs the whole
+/// result (aligned tables, JSON arrays) and goes to the typed handler.
+struct StreamingQuery {
+    buffered: Box<dyn CommandHandler>,
+}
+
+#[async_trait::async_trait]
+impl CommandHandler for StreamingQuery {
+    async fn run(&self, ctx: CommandContext) -> CommandResult {
+        let csv = ctx.format == Format::Csv && ctx.request.is_none();
+        if ctx.format != Format::Jsonl && !csv {
+            return self.buffered.run(ctx).await;
+        }
+        let (Ok(args), Ok(options)) = (
+            serde_json::from_value::<QueryArgs>(ctx.args.clone()),
+            serde_json::from_value::<QueryOptions>(ctx.options.clone()),
+        ) else {
+            // Let the typed handler report the validation error.
+            return self.buffered.run(ctx).await;
+        };
+        // A profile times the whole query, so it is not streamed.
+        if options.explain || options.profile {
+            return self.buffered.run(ctx).await;
+        }
+        let (engine, params) = match prepare_query(&args.query, &options) {
+            Ok(prepared) => prepared,
+            Err((code, message)) => {
+                return CommandResult::Error {
+                    code: code.into(),
+                    message,
+                    retryable: false,
+                    exit_code: Some(1),
+                    cta: None,
+                }
+            }
+        };
+
+        if csv {
+            // The header comes from the statement, so a query returning no
+            // rows still prints it.
+            let header = match engine.column_names(&args.query) {
+                Ok(columns) => (!options.no_header).then_some(columns),
+                Err(e) => {
+                    return CommandResult::Error {
+                        code: "QUERY_ERROR".into(),
+                        message: format!("Query failed: {e}"),
+                        retryable: false,
+                        exit_code: Some(1),
+                        cta: None,
+                    }
+                }
+            };
+            let rows = engine.into_row_stream(args.query, params);
+            return CommandResult::RecordStream(Box::pin(futures::stream::once(write_csv(
+                rows, header,
+            ))));
+        }
+        let rows = engine.into_row_stream(args.query, params);
+        let done = futures::stream::once(async { StreamRecord::Ok { cta: None } });
+        CommandResult::RecordStream(Box::pin(rows.map(row_record).chain(done)))
+    }
+
+    fn mcp_options(&self) -> Option<&McpCommandOptions> {
+        self.buffered.mcp_options()
+    }
+
+    fn mcp_input_schema(&self) -> Option<&Value> {
+        self.buffered.mcp_input_schema()
+    }
+}
+
+fn row_record(row: crate::Result<Value>) -> StreamRecord {
+    match row {
+        Ok(row) => StreamRecord::Chunk(row),
+        Err(e) => query_failed(e),
+    }
+}
+
+fn query_failed(e: crate::Error) -> StreamRecord {
+    StreamRecord::Error {
+        code: "QUERY_ERROR".into(),
+        message: format!("Query failed: {e}"),
+        retryable: false,
+        exit_code: Some(1),
+        cta: None,
+    }
+}
+
+/// Write `header`, if any, then streamed rows to stdout as CSV, returning
+/// the stream's terminal record. Output stops early if stdout closes (e.g.
+/// piped into `head`).
+async fn write_csv(mut rows: RowStream, header: Option<Vec<String>>) -> StreamRecord {
+    let mut out =
+        csv::Writer::from_writer(HoldFinalNewline::new(BufWriter::new(std::io::stdout())));
+    if let Some(header) = header {
+        if out.write_record(&header).is_err() {
+            let _ = out.flush();
+            return StreamRecord::Ok { cta: None };
+        }
+    }
+    while let Some(row) = rows.next().await {
+        let row = match row {
+            Ok(Value::Object(row)) => row,
+            Ok(_) => continue,
+            Err(e) => return query_failed(e),
+        };
+        let cells = row.values().map(|value| match value {
+            Value::Null => String::new(),
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        });
+        if out.write_record(cells).is_err() {
+            break;
+        }
+    }
+    let _ = out.flush();
+    StreamRecord::Ok { cta: None }
+}
+
+/// Passes writes through but holds back a trailing newline until more
+/// output follows. incurs prints the (empty) buffered result after a record
+/// stream ends, and that supplies the final newline.
+struct HoldFinalNewline<W> {
+    inner: W,
+    pending: bool,
+}
+
+impl<W> HoldFinalNewline<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            pending: false,
+        }
+    }
+}
+
+impl<W: Write> Write for HoldFinalNewline<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let Some((last, body)) = buf.split_last() else {
+            return Ok(0);
+        };
+        if self.pending {
+            self.inner.write_all(b"\n")?;
+            self.pending = false;
+        }
+        if *last == b'\n' {
+            self.inner.write_all(body)?;
+            self.pending = true;
+        } else {
+            self.inner.write_all(buf)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn query_command(name: &str, description: &str) -> CommandDef {
+    let mut command =
+        CommandDef::typed::<QueryArgs, QueryOptions, (), QueryOutput, _, _>(name, run_query)
+            .description(description)
+            .examples(query_examples())
+            .hint(query_hint())
+            .mcp(crate::tools::read_only_mcp())
+            .done();
+    command.handler = Box::new(StreamingQuery {
+        buffered: command.handler,
+    });
+    command
+}
+
+fn query_examples() -> Vec<Example> {
+    vec![
+        Example {
+            command: r#""SELECT * FROM commits LIMIT 5""#.to_string(),
+            description: Some("List recent commits".to_string()),
+        },
+        Example {
+            command: r#""SELECT h.message, COUNT(c.id) as commits FROM history h LEFT JOIN commits c ON DATE(h.timestamp) = DATE(c.authored_at) GROUP BY h.message HAVING commits > 0 ORDER BY commits DESC LIMIT 10""#.to_string(),
+            description: Some("Most productive prompts".to_string()),
+        },
+        Example {
+            command: r#""SELECT DATE(h.timestamp) as day, COUNT(*) as prompts, COUNT(DISTINCT c.id) as commits FROM history h LEFT JOIN commits c ON DATE(h.timestamp) = DATE(c.authored_at) GROUP BY day ORDER BY prompts DESC LIMIT 10""#.to_string(),
+            description: Some("Struggle days".to_string()),
+        },
+        Example {
+            command: r#""SELECT datetime(timestamp/1000, 'unixepoch') as time, display FROM jhistory ORDER BY timestamp DESC LIMIT 10""#.to_string(),
+            description: Some("Recent Codex prompts".to_string()),
+        },
+        Example {
+            command: r#""SELECT thread_id, cwd, last_event_at FROM codex_threads ORDER BY last_event_at DESC LIMIT 10""#.to_string(),
+            description: Some("Recent Codex conversations".to_string()),
+        },
+        Example {
+            command: r#""SELECT source, timestamp, command FROM shell_history ORDER BY timestamp DESC LIMIT 10""#.to_string(),
+            description: Some("Recent Atuin, zsh, and bash commands".to_string()),
+        },
+        Example {
+            command: r#""SELECT c.repo, COUNT(DISTINCT c.id) as commits FROM commits c JOIN diff_files f ON f.commit_id = c.id AND f.repo = c.repo WHERE f.path LIKE '%auth%' AND c.authored_at >= DATE('now', '-7 days') GROUP BY c.repo" --repo '~/src/*'"#.to_string(),
+            description: Some("Services with commits touching auth this week".to_string()),
+        },
+        Example {
+            command: r#""SELECT tool_name, COUNT(*) AS uses FROM transcripts WHERE type = 'tool_use' GROUP BY tool_name ORDER BY uses DESC" --since 7d"#.to_string(),
+            description: Some("Tool use this week, reading only the last week of transcripts".to_string()),
+        },
+        Example {
+            command: r#""SELECT short_id, summary FROM commits WHERE author_email = :email LIMIT :n" --params '{"email": "me@example.com", "n": 5}'"#.to_string(),
+            description: Some("Bind values instead of splicing them into the SQL".to_string()),
+        },
+        Example {
+            command: r#""SELECT c.short_id, COUNT(*) AS files FROM commits c JOIN diff_files f ON f.commit_id = c.id GROUP BY c.id" --profile"#.to_string(),
+            description: Some("Report load times, rows, files read, and the query plan on stderr".to_string()),
+        },
+    ]
+}
+
+fn query_hint() -> &'static str {
+    "PRIMARY AGENT INTERFACE:\n  devsql --mcp                 # five-tool Code Mode server\n  codemode_search              # discover devsql.* methods\n  codemode_execute             # run JavaScript across one or more methods\n  codemode_execution           # inspect a durable execution\n  codemode_decide / cancel     # approve writes or stop work\n\n  The direct CLI below is the human and scripting fallback.\n\nTABLES:\n  Claude Code:  history (prompts), transcripts (conversations), sessions (per-session stats), todos\n  Codex CLI:    jhistory / codex_history, codex_threads, codex_messages, codex_events,\n                codex_tool_executions / codex_tool_calls, codex_compactions, codex_ingest_errors\n  Git:          commits, commit_parents, diffs, diff_files, blame, branches, tags, refs,\n                stashes, reflog, config, remotes, submodules, status, worktrees, hooks, notes\n  Shell:        shell_history (Atuin, zsh, bash), command_events (shell + agent commands)\n  Worklog:      work_tasks, work_events (durable day memory; write via `devsql work`)\n\nWORKDAY MEMORY:\n  devsql work start|update|done|note|list   # agents write structured work events\n  devsql today | day [date] | days          # human day timeline\n\nTELL YOUR AI AGENT:\n  \"Use DevSQL Code Mode to find my most effective prompts from the past month\"\n  \"Start a worklog task when beginning non-trivial work\"\n  \"Show me what I did today with DevSQL Code Mode\"\n\nLearn more: https://github.com/douglance/devsql"
+}
+
+// ---------------------------------------------------------------------------
+// Saved queries
+// ---------------------------------------------------------------------------
+
+#[derive(incurs::Args, serde::Deserialize)]
+#[allow(dead_code)]
+struct RunArgs {}
+
+#[derive(incurs::Options, serde::Deserialize)]
+#[allow(dead_code)]
+struct RunOptions {
+    /// Parameter value as name=value; repeat for each parameter
+    #[serde(default)]
+    param: Vec<String>,
+    /// Git repository path or glob; repeat to query several repositories
+    #[incurs(alias = "r")]
+    #[serde(default)]
+    repo: Vec<String>,
+    /// File listing one repository root (or glob) per line
+    #[incurs(alias = "w")]
+    workspace: Option<String>,
+    /// Claude data directory (defaults to ~/.claude)
+    #[incurs(alias = "d")]
+    data_dir: Option<String>,
+    /// Only load rows at or after this time: an RFC 3339 timestamp, a
+    /// YYYY-MM-DD date (UTC), or a span back from now such as 7d or 12h
+    since: Option<String>,
+    /// Only load rows before this time (same formats as --since)
+    until: Option<String>,
+    /// Run against a snapshot written by `devsql export` instead of live sources
+    db: Option<String>,
+    /// Load a CSV, JSON, JSON Lines, or SQLite file as a table, given as
+    /// name=path; repeat to attach several
+    #[serde(default)]
+    attach: Vec<String>,
+}
+
+async fn run_saved(
+    query: Arc<SavedQuery>,
+    ctx: TypedContext<RunArgs, RunOptions, ()>,
+) -> TypedResult<QueryOutput> {
+    let mut given = serde_json::Map::new();
+    for assignment in &ctx.options.param {
+        match crate::saved::parse_assignment(assignment) {
+            Ok((name, value)) => {
+                given.insert(name, value);
+            }
+            Err(message) => return TypedResult::error("INVALID_PARAMS", message),
+        }
+    }
+    let params = match query.bind(given) {
+        Ok(params) => params,
+        Err(message) => return TypedResult::error("INVALID_PARAMS", message),
+    };
+    let options = QueryOptions {
+        repo: ctx.options.repo,
+        workspace: ctx.options.workspace,
+        data_dir: ctx.options.data_dir,
+        params: Some(Value::Object(params)),
+        since: ctx.options.since,
+        until: ctx.options.until,
+        db: ctx.options.db,
+        attach: ctx.options.attach,
+        explain: false,
+        profile: false,
+        no_header: false,
+    };
+    execute_query(&query.sql, &options)
+}
+
+/// `devsql run <name>`: one command per saved query, so each is listed in
+/// help and reaches Code Mode as `devsql.run_<name>`. A library that fails
+/// to load is reported and leaves the group empty.
+fn saved_query_group() -> Cli {
+    let mut group = Cli::create("run").description(
+        "Run a saved query from ~/.devsql/queries, passing parameters as --param name=value",
+    );
+    let library = crate::saved::library().unwrap_or_else(|e| {
+        eprintln!("Warning: {e}");
+        Vec::new()
+    });
+    for query in library {
+        let query = Arc::new(query);
+        let handler = {
+            let query = query.clone();
+            move |ctx| run_saved(query.clone(), ctx)
+        };
+        let command = CommandDef::typed::<RunArgs, RunOptions, (), QueryOutput, _, _>(
+            query.name.clone(),
+            handler,
+        )
+        .description(crate::tools::saved::describe(&query))
+        .mcp(crate::tools::read_only_mcp())
+        .done();
+        group = group.command(query.name.clone(), command);
+    }
+    group
+}
+
+// ---------------------------------------------------------------------------
+// CLI construction
+// ---------------------------------------------------------------------------
+
+/// The devsql CLI with every built-in command.
+pub fn build_cli() -> Cli {
+    Cli::create("devsql")
+        .description(
+            "Code Mode is the primary agent interface for querying AI coding, Git, source code, \
+             shell history, and worklog data.\n\n\
+             Run `devsql --mcp` for the Code Mode server. Use the direct CLI for human queries \
+             and scripts.",
+        )
+        .version(env!("CARGO_PKG_VERSION"))
+        .default_extra_format(ExtraFormat::Table)
+        .root(query_command(
+            "devsql",
+            "Run a direct SQL query. For agents, prefer the Code Mode server: `devsql --mcp`.",
+        ))
+        .command(
+            "query",
+            query_command("query", "Execute a SQL query against developer-local data"),
+        )
+        .command("tables", crate::tools::schema::build_tables())
+        .command("schema", crate::tools::schema::build_schema())
+        .command("examples", crate::tools::schema::build_examples())
+        .command("queries", crate::tools::saved::build_list())
+        .command("shell", crate::tools::shell::build())
+        .command("serve", crate::tools::serve::build())
+        .command("daemon", crate::tools::daemon::build())
+        .command("export", crate::tools::export::build())
+        .group(saved_query_group())
+        .group(crate::tools::config::build_group())
+        .command("diff", crate::tools::diff::build())
+        .command("search", crate::tools::search::build())
+        .command("context", crate::tools::context::build())
+        .command("history", crate::tools::history::build())
+        .command("impact", crate::tools::impact::build())
+        .command("recall", crate::tools::recall::build())
+        .command("gather", crate::tools::gather::build())
+        .group(crate::tools::work::build_group())
+        .command("today", crate::tools::day::build_today())
+        .command("day", crate::tools::day::build_day())
+        .command("days", crate::tools::day::build_days())
+}
+
+fn code_mode_requested() -> bool {
+    let mut args = std::env::args_os().skip(1);
+    args.next().is_some_and(|arg| arg == "--mcp") && args.next().is_none()
+}
+
+async fn serve_code_mode(cli: &Cli) -> Result<(), String> {
+    let connector = Arc::new(
+        IncurConnector::new(cli.tool_catalog())
+            .with_name("devsql")
+            .with_instructions(
+                "Use devsql.tables and devsql.schema to find tables and columns, and \
+                 devsql.examples for sample queries. Team-standard saved queries run as \
+                 devsql.run_<name> (list them with devsql.queries), and any query can read a saved \
+                 query as a table by name. Use devsql.query for cross-source SQL, passing \
+                 values through its params object rather than splicing them into the SQL, and \
+                 its attach list (name=path) to join local CSV, JSON, or SQLite files; use \
+                 devsql.gather when prior work and repository context should be loaded \
+                 together. Use devsql.work_start, \
+                 devsql.work_update, devsql.work_done, and devsql.work_note to maintain the \
+                 durable day timeline.",
+            ),
+    );
+    let service = LocalCodeModeService::spawn(move || {
+        CodeMode::new(
+            Arc::new(MemoryStore::default()),
+            LocalExecutor::default(),
+            vec![connector],
+        )
+    })?;
+    incurs_codemode_mcp::serve_stdio(Arc::new(DurableCodeModeService(service))).await
+}
+
+/// Run the CLI (or the Code Mode server for `devsql --mcp`) on the
+/// process arguments, exiting with status 1 on error.
+pub async fn main() {
+    let cli = build_cli();
+    let result = if code_mode_requested() {
+        serve_code_mode(&cli)
+            .await
+            .map_err(|error| -> Box<dyn std::error::Error> { error.into() })
+    } else {
+        cli.serve().await
+    };
+    if let Err(e) = result {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    }
+}
@@ -1,26 +1,29 @@
+use crate::index_cache;
 use crate::Result;
 use ccql::datasources::codex_journal::{
     discover_codex_journals, read_first_journal_record, visit_journal_records, CodexJournalFile,
-    CodexJournalRecord, JournalState,
-};
-use chrono::Utc;
-use rusqlite::{
-    params, Connection, ErrorCode, OptionalExtension, Transaction, TransactionBehavior,
+    CodexJournalRecord, JournalProgress, JournalState,
 };
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
 use serde_json::Value;
 use sha2::{Digest, Sha256};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::time::{Duration, Instant, UNIX_EPOCH};
+use std::time::UNIX_EPOCH;
 
-const SCHEMA_VERSION: i64 = 3;
+const SCHEMA_VERSION: i64 = 4;
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub(crate) struct SyncStats {
     pub parsed_journals: usize,
+    /// Of `parsed_journals`, those read from their last complete record
+    /// rather than from the start.
+    pub appended_journals: usize,
     pub parsed_records: usize,
     pub unchanged_journals: usize,
+    pub skipped_journals: usize,
     pub pruned_threads: usize,
 }
 
@@ -32,39 +35,12 @@ pub(crate) struct CodexIndex {
 
 impl CodexIndex {
     pub(crate) fn open(codex_home: &Path) -> Result<Self> {
-        let cache_root = dirs::cache_dir()
-            .unwrap_or_else(|| std::env::temp_dir().join("devsql-cache"))
-            .join("devsql")
-            .join("codex-index");
-        let canonical_home = codex_home
-            .canonicalize()
-            .unwrap_or_else(|_| codex_home.to_path_buf());
-        let digest = Sha256::digest(canonical_home.to_string_lossy().as_bytes());
-        let cache_path = cache_root.join(format!("{digest:x}.sqlite"));
+        let cache_path = index_cache::cache_path_for("codex-index", codex_home);
         Self::open_at(codex_home, &cache_path)
     }
 
     pub(crate) fn open_at(codex_home: &Path, cache_path: &Path) -> Result<Self> {
-        if let Some(parent) = cache_path.parent() {
-            fs::create_dir_all(parent)?;
-            set_private_directory_permissions(parent)?;
-        }
-        let mut conn = match open_cache_connection(cache_path) {
-            Ok(conn) => conn,
-            Err(error) if is_corrupt_cache_error(&error) => {
-                remove_cache_files(cache_path)?;
-                open_cache_connection(cache_path)?
-            }
-            Err(error) => return Err(error),
-        };
-        let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
-        if version != 0 && version != SCHEMA_VERSION {
-            drop(conn);
-            remove_cache_files(cache_path)?;
-            conn = open_cache_connection(cache_path)?;
-        }
-        create_schema(&conn)?;
-        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        let conn = index_cache::open_versioned(cache_path, SCHEMA_VERSION, create_schema)?;
         Ok(Self {
             conn,
             codex_home: codex_home.to_path_buf(),
@@ -72,7 +48,18 @@ impl CodexIndex {
         })
     }
 
+    #[cfg(test)]
     pub(crate) fn sync(&mut self) -> Result<SyncStats> {
+        self.sync_since(None)
+    }
+
+    /// Index the journals changed since the last sync, except those last
+    /// written more than a day before `since`: they hold no newer records,
+    /// so they are not read and only their cached rows (if any) are kept.
+    ///
+    /// Journals are read and parsed in parallel and written to the index in
+    /// path order, one at a time.
+    pub(crate) fn sync_since(&mut self, since: Option<DateTime<Utc>>) -> Result<SyncStats> {
         if !self.codex_home.exists() {
             return Ok(SyncStats::default());
         }
@@ -81,43 +68,77 @@ impl CodexIndex {
         let tx = self
             .conn
             .transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let states = source_states(&tx)?;
         let mut seen_threads = HashSet::new();
         let mut seen_paths = HashSet::new();
         let mut all_journals_readable = true;
 
-        for journal in journals {
-            seen_paths.insert(journal.path.to_string_lossy().into_owned());
-            tx.execute_batch("SAVEPOINT codex_journal_sync")?;
-            match sync_journal(&tx, &journal, &mut stats) {
-                Ok(Some(thread_id)) => {
-                    tx.execute_batch("RELEASE SAVEPOINT codex_journal_sync")?;
-                    seen_threads.insert(thread_id);
-                }
-                Ok(None) => {
-                    tx.execute_batch("RELEASE SAVEPOINT codex_journal_sync")?;
+        crate::parallel::for_each_ordered(
+            &journals,
+            |journal| {
+                if since.is_some_and(|since| written_before(&journal.path, since)) {
+                    return None;
                 }
-                Err(error) => {
-                    tx.execute_batch(
-                        "ROLLBACK TO SAVEPOINT codex_journal_sync;
-                         RELEASE SAVEPOINT codex_journal_sync;",
-                    )?;
-                    all_journals_readable = false;
-                    tx.execute(
-                        "DELETE FROM codex_ingest_errors
-                         WHERE journal_path = ?1 AND error_kind = 'journal'",
-                        [journal.path.to_string_lossy().as_ref()],
-                    )?;
-                    record_ingest_error(
-                        &tx,
-                        &journal.path,
-                        None,
-                        None,
-                        "journal",
-                        &error.to_string(),
-                    )?;
+                let existing = states.get(journal.path.to_string_lossy().as_ref()).cloned();
+                Some(read_journal(journal, existing))
+            },
+            |journal, read| {
+                seen_paths.insert(journal.path.to_string_lossy().into_owned());
+                let Some(read) = read else {
+                    stats.skipped_journals += 1;
+                    let cached = tx
+                        .query_row(
+                            "SELECT thread_id FROM source_files WHERE journal_path = ?1",
+                            [journal.path.to_string_lossy().as_ref()],
+                            |row| row.get::<_, String>(0),
+                        )
+                        .optional()?;
+                    seen_threads.extend(cached);
+                    return Ok(());
+                };
+                tx.execute_batch("SAVEPOINT codex_journal_sync")?;
+                let synced = read.and_then(|read| {
+                    // An earlier journal of this sync may have changed the
+                    // cached state this one was read against; if so, read it
+                    // again now.
+                    if source_state(&tx, &journal.path)? == read.existing {
+                        write_journal(&tx, journal, read, &mut stats)
+                    } else {
+                        sync_journal(&tx, journal, &mut stats)
+                    }
+                });
+                match synced {
+                    Ok(Some(thread_id)) => {
+                        tx.execute_batch("RELEASE SAVEPOINT codex_journal_sync")?;
+                        seen_threads.insert(thread_id);
+                    }
+                    Ok(None) => {
+                        tx.execute_batch("RELEASE SAVEPOINT codex_journal_sync")?;
+                    }
+                    Err(error) => {
+                        tx.execute_batch(
+                            "ROLLBACK TO SAVEPOINT codex_journal_sync;
+                             RELEASE SAVEPOINT codex_journal_sync;",
+                        )?;
+                        all_journals_readable = false;
+                        tx.execute(
+                            "DELETE FROM codex_ingest_errors
+                             WHERE journal_path = ?1 AND error_kind = 'journal'",
+                            [journal.path.to_string_lossy().as_ref()],
+                        )?;
+                        record_ingest_error(
+                            &tx,
+                            &journal.path,
+                            None,
+                            None,
+                            "journal",
+                            &error.to_string(),
+                        )?;
+                    }
                 }
-            }
-        }
+                Ok::<_, crate::Error>(())
+            },
+        )?;
 
         if all_journals_readable {
             let cached_threads = {
@@ -162,116 +183,70 @@ impl CodexIndex {
     }
 }
 
-fn open_cache_connection(cache_path: &Path) -> Result<Connection> {
-    let conn = Connection::open(cache_path)?;
-    set_private_file_permissions(cache_path)?;
-    conn.pragma_update(None, "foreign_keys", "ON")?;
-    conn.busy_timeout(Duration::from_secs(30))?;
-    ensure_wal_mode(&conn)?;
-    set_private_file_permissions(cache_path)?;
-    set_cache_sidecar_permissions(cache_path)?;
-    Ok(conn)
-}
-
-fn ensure_wal_mode(conn: &Connection) -> Result<()> {
-    let started = Instant::now();
-    loop {
-        let result = (|| -> rusqlite::Result<()> {
-            let mode: String = conn.pragma_query_value(None, "journal_mode", |row| row.get(0))?;
-            if !mode.eq_ignore_ascii_case("wal") {
-                conn.pragma_update(None, "journal_mode", "WAL")?;
-            }
-            Ok(())
-        })();
-
-        match result {
-            Ok(()) => return Ok(()),
-            Err(error)
-                if is_busy_sql_error(&error) && started.elapsed() < Duration::from_secs(30) =>
-            {
-                std::thread::sleep(Duration::from_millis(10));
-            }
-            Err(error) => return Err(error.into()),
-        }
-    }
-}
-
-fn is_busy_sql_error(error: &rusqlite::Error) -> bool {
-    matches!(
-        error,
-        rusqlite::Error::SqliteFailure(code, _)
-            if matches!(code.code, ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
-    )
-}
-
-fn is_corrupt_cache_error(error: &crate::Error) -> bool {
-    matches!(
-        error,
-        crate::Error::Sql(rusqlite::Error::SqliteFailure(code, _))
-            if matches!(code.code, ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase)
-    )
-}
-
-fn remove_cache_files(cache_path: &Path) -> Result<()> {
-    for path in [
-        cache_path.to_path_buf(),
-        PathBuf::from(format!("{}-wal", cache_path.to_string_lossy())),
-        PathBuf::from(format!("{}-shm", cache_path.to_string_lossy())),
-    ] {
-        match fs::remove_file(path) {
-            Ok(()) => {}
-            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
-            Err(error) => return Err(error.into()),
-        }
-    }
-    Ok(())
+#[derive(Debug, Clone, PartialEq)]
+struct SourceState {
+    thread_id: String,
+    size: u64,
+    modified_ns: i64,
+    fingerprint: String,
+    last_complete_offset: u64,
+    last_record_index: i64,
+    compressed: bool,
 }
 
-fn set_cache_sidecar_permissions(cache_path: &Path) -> Result<()> {
-    for path in [
-        PathBuf::from(format!("{}-wal", cache_path.to_string_lossy())),
-        PathBuf::from(format!("{}-shm", cache_path.to_string_lossy())),
-    ] {
-        if path.exists() {
-            set_private_file_permissions(&path)?;
-        }
-    }
-    Ok(())
+const SOURCE_STATE_SQL: &str = "SELECT thread_id, size, modified_ns, leading_fingerprint,
+        last_complete_offset, last_record_index, compressed, journal_path
+ FROM source_files";
+
+fn source_state_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<SourceState> {
+    Ok(SourceState {
+        thread_id: row.get(0)?,
+        size: row.get::<_, i64>(1)? as u64,
+        modified_ns: row.get(2)?,
+        fingerprint: row.get(3)?,
+        last_complete_offset: row.get::<_, i64>(4)? as u64,
+        last_record_index: row.get(5)?,
+        compressed: row.get::<_, i64>(6)? != 0,
+    })
 }
 
-#[cfg(unix)]
-fn set_private_directory_permissions(path: &Path) -> Result<()> {
-    use std::os::unix::fs::PermissionsExt;
-    fs::set_permissions(path, fs::Permissions::from_mode(0o700))?;
-    Ok(())
+/// The cached state of the journal at `path`.
+fn source_state(tx: &Transaction<'_>, path: &Path) -> Result<Option<SourceState>> {
+    Ok(tx
+        .query_row(
+            &format!("{SOURCE_STATE_SQL} WHERE journal_path = ?1"),
+            [path.to_string_lossy().as_ref()],
+            source_state_row,
+        )
+        .optional()?)
 }
 
-#[cfg(not(unix))]
-fn set_private_directory_permissions(_path: &Path) -> Result<()> {
-    Ok(())
+/// The cached state of every journal, keyed by path.
+fn source_states(tx: &Transaction<'_>) -> Result<HashMap<String, SourceState>> {
+    let mut statement = tx.prepare(SOURCE_STATE_SQL)?;
+    let rows = statement.query_map([], |row| Ok((row.get(7)?, source_state_row(row)?)))?;
+    Ok(rows.collect::<std::result::Result<_, _>>()?)
 }
 
-#[cfg(unix)]
-fn set_private_file_permissions(path: &Path) -> Result<()> {
-    use std::os::unix::fs::PermissionsExt;
-    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
-    Ok(())
-}
-
-#[cfg(not(unix))]
-fn set_private_file_permissions(_path: &Path) -> Result<()> {
-    Ok(())
+/// What reading one journal against its cached state found. Reading needs no
+/// access to the index, so journals are read in parallel; [`write_journal`]
+/// applies the result.
+struct JournalRead {
+    /// The cached state the journal was read against.
+    existing: Option<SourceState>,
+    size: u64,
+    modified_ns: i64,
+    /// `None` when the journal is unchanged since it was cached.
+    parsed: Option<ParsedJournal>,
 }
 
-#[derive(Debug)]
-struct SourceState {
+struct ParsedJournal {
     thread_id: String,
-    size: u64,
-    modified_ns: i64,
     fingerprint: String,
-    last_complete_offset: u64,
-    last_record_index: i64,
-    compressed: bool,
+    /// Whether `records` continue the cached ones rather than replace them.
+    append: bool,
+    records: Vec<CodexJournalRecord>,
+    progress: JournalProgress,
 }
 
 fn sync_journal(
@@ -279,35 +254,26 @@ fn sync_journal(
     journal: &CodexJournalFile,
     stats: &mut SyncStats,
 ) -> Result<Option<String>> {
+    let existing = source_state(tx, &journal.path)?;
+    let read = read_journal(journal, existing)?;
+    write_journal(tx, journal, read, stats)
+}
+
+fn read_journal(journal: &CodexJournalFile, existing: Option<SourceState>) -> Result<JournalRead> {
     let metadata = fs::metadata(&journal.path)?;
     let size = metadata.len();
     let modified_ns = modified_ns(&metadata);
-    let path_text = journal.path.to_string_lossy().into_owned();
-    let existing = tx
-        .query_row(
-            "SELECT thread_id, size, modified_ns, leading_fingerprint,
-                    last_complete_offset, last_record_index, compressed
-             FROM source_files WHERE journal_path = ?1",
-            [&path_text],
-            |row| {
-                Ok(SourceState {
-                    thread_id: row.get(0)?,
-                    size: row.get::<_, i64>(1)? as u64,
-                    modified_ns: row.get(2)?,
-                    fingerprint: row.get(3)?,
-                    last_complete_offset: row.get::<_, i64>(4)? as u64,
-                    last_record_index: row.get(5)?,
-                    compressed: row.get::<_, i64>(6)? != 0,
-                })
-            },
-        )
-        .optional()?;
 
-    if let Some(existing) = &existing {
-        if existing.size == size && existing.modified_ns == modified_ns {
-            stats.unchanged_journals += 1;
-            return Ok(Some(existing.thread_id.clone()));
-        }
+    if existing
+        .as_ref()
+        .is_some_and(|existing| existing.size == size && existing.modified_ns == modified_ns)
+    {
+        return Ok(JournalRead {
+            existing,
+            size,
+            modified_ns,
+            parsed: None,
+        });
     }
 
     let fingerprint = leading_fingerprint(journal)?;
@@ -319,88 +285,102 @@ fn sync_journal(
     } else {
         journal_thread_id(journal)?
     };
+    let (start_offset, start_record_index) = match &existing {
+        Some(old) if append => (old.last_complete_offset, old.last_record_index + 1),
+        _ => (0, 0),
+    };
 
-    if append {
-        let old = existing.as_ref().expect("append requires existing source");
-        let progress = parse_journal(
-            tx,
-            journal,
-            &thread_id,
-            old.last_complete_offset,
-            old.last_record_index + 1,
-            stats,
-        )?;
-        update_source_file(
-            tx,
-            journal,
-            &thread_id,
-            size,
-            modified_ns,
-            &fingerprint,
-            progress.last_complete_offset,
-            progress.last_record_index,
-        )?;
-    } else {
+    crate::profile::file_read();
+    let mut records = Vec::new();
+    let progress = visit_journal_records(journal, start_offset, start_record_index, |record| {
+        records.push(record);
+        Ok(())
+    })?;
+    Ok(JournalRead {
+        existing,
+        size,
+        modified_ns,
+        parsed: Some(ParsedJournal {
+            thread_id,
+            fingerprint,
+            append,
+            records,
+            progress,
+        }),
+    })
+}
+
+fn write_journal(
+    tx: &Transaction<'_>,
+    journal: &CodexJournalFile,
+    read: JournalRead,
+    stats: &mut SyncStats,
+) -> Result<Option<String>> {
+    let Some(parsed) = read.parsed else {
+        stats.unchanged_journals += 1;
+        return Ok(read.existing.map(|existing| existing.thread_id));
+    };
+    let thread_id = parsed.thread_id;
+
+    if !parsed.append {
         tx.execute(
             "DELETE FROM codex_ingest_errors
              WHERE journal_path = ?1 OR thread_id = ?2",
-            params![path_text, thread_id],
+            params![journal.path.to_string_lossy(), thread_id],
         )?;
         tx.execute(
             "DELETE FROM codex_threads WHERE thread_id = ?1",
             [&thread_id],
         )?;
         insert_thread_shell(tx, journal, &thread_id)?;
-        let progress = parse_journal(tx, journal, &thread_id, 0, 0, stats)?;
-        update_source_file(
-            tx,
-            journal,
-            &thread_id,
-            size,
-            modified_ns,
-            &fingerprint,
-            progress.last_complete_offset,
-            progress.last_record_index,
-        )?;
+    }
+    let source_path = journal.path.to_string_lossy().into_owned();
+    for record in &parsed.records {
+        stats.parsed_records += 1;
+        write_record(tx, journal, &thread_id, &source_path, record)?;
+    }
+    update_source_file(
+        tx,
+        journal,
+        &thread_id,
+        read.size,
+        read.modified_ns,
+        &parsed.fingerprint,
+        parsed.progress.last_complete_offset,
+        parsed.progress.last_record_index,
+    )?;
+    if parsed.append {
+        stats.appended_journals += 1;
     }
     recompute_thread(tx, &thread_id)?;
     stats.parsed_journals += 1;
     Ok(Some(thread_id))
 }
 
-fn parse_journal(
+fn write_record(
     tx: &Transaction<'_>,
     journal: &CodexJournalFile,
     thread_id: &str,
-    start_offset: u64,
-    start_record_index: i64,
-    stats: &mut SyncStats,
-) -> Result<ccql::datasources::codex_journal::JournalProgress> {
-    let source_path = journal.path.to_string_lossy().into_owned();
-    let progress = visit_journal_records(journal, start_offset, start_record_index, |record| {
-        stats.parsed_records += 1;
-        if let Some(error) = &record.parse_error {
-            tx.execute(
-                "INSERT OR REPLACE INTO codex_events
-                     (thread_id, record_index, source_path)
-                     VALUES (?1, ?2, ?3)",
-                params![thread_id, record.record_index, source_path],
-            )
-            .map_err(|error| sql_to_io(error.into()))?;
-            record_ingest_error(
-                tx,
-                &journal.path,
-                Some(thread_id),
-                Some(record.record_index),
-                "json",
-                error,
-            )
-            .map_err(sql_to_io)?;
-            return Ok(());
-        }
-        normalize_record(tx, thread_id, &source_path, &record).map_err(sql_to_io)
-    })?;
-    Ok(progress)
+    source_path: &str,
+    record: &CodexJournalRecord,
+) -> Result<()> {
+    if let Some(error) = &record.parse_error {
+        tx.execute(
+            "INSERT OR REPLACE INTO codex_events
+                 (thread_id, record_index, source_path)
+                 VALUES (?1, ?2, ?3)",
+            params![thread_id, record.record_index, source_path],
+        )?;
+        return record_ingest_error(
+            tx,
+            &journal.path,
+            Some(thread_id),
+            Some(record.record_index),
+            "json",
+            error,
+        );
+    }
+    normalize_record(tx, thread_id, source_path, record)
 }
 
 fn normalize_record(
@@ -443,9 +423,16 @@ fn normalize_record(
     }
     if let Some(message) = normalize_message(record_type, payload_type, payload) {
         tx.execute(
-            "INSERT OR REPLACE INTO codex_messages
+            "INSERT INTO codex_messages
              (thread_id, record_index, timestamp, role, text, content_json, is_canonical, source_path)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT (thread_id, record_index) DO UPDATE SET
+               timestamp = excluded.timestamp,
+               role = excluded.role,
+               text = excluded.text,
+               content_json = excluded.content_json,
+               is_canonical = excluded.is_canonical,
+               source_path = excluded.source_path",
             params![
                 thread_id,
                 record.record_index,
@@ -965,7 +952,7 @@ fn journal_thread_id(journal: &CodexJournalFile) -> Result<String> {
         .to_string())
 }
 
-fn leading_fingerprint(journal: &CodexJournalFile) -> Result<String> {
+pub(crate) fn leading_fingerprint(journal: &CodexJournalFile) -> Result<String> {
     let bytes = read_first_journal_record(journal)?
         .and_then(|record| record.value)
         .and_then(|value| serde_json::to_vec(&value).ok())
@@ -974,7 +961,14 @@ fn leading_fingerprint(journal: &CodexJournalFile) -> Result<String> {
     Ok(format!("{digest:x}"))
 }
 
-fn modified_ns(metadata: &fs::Metadata) -> i64 {
+/// Whether `path` was last modified more than a day before `since`.
+fn written_before(path: &Path, since: DateTime<Utc>) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| DateTime::<Utc>::from(modified) + chrono::Duration::days(1) < since)
+}
+
+pub(crate) fn modified_ns(metadata: &fs::Metadata) -> i64 {
     metadata
         .modified()
         .ok()
@@ -1014,11 +1008,7 @@ fn record_ingest_error(
     Ok(())
 }
 
-fn sql_to_io(error: crate::Error) -> std::io::Error {
-    std::io::Error::other(error)
-}
-
-fn create_schema(conn: &Connection) -> Result<()> {
+pub(crate) fn create_schema(conn: &Connection) -> Result<()> {
     conn.execute_batch(
         "
         CREATE TABLE IF NOT EXISTS index_meta (
@@ -1026,7 +1016,7 @@ fn create_schema(conn: &Connection) -> Result<()> {
             value TEXT NOT NULL
         );
         INSERT OR REPLACE INTO index_meta (key, value)
-        VALUES ('schema_version', '3');
+        VALUES ('schema_version', '4');
 
         CREATE TABLE IF NOT EXISTS codex_threads (
             thread_id TEXT PRIMARY KEY,
@@ -1089,6 +1079,31 @@ fn create_schema(conn: &Connection) -> Result<()> {
             PRIMARY KEY (thread_id, record_index)
         );
 
+        -- External-content index over codex_messages.text. The triggers keep
+        -- it in step with inserts, upserts, and cascaded thread deletes, so
+        -- messages are only tokenized when their journal is parsed.
+        CREATE VIRTUAL TABLE IF NOT EXISTS codex_messages_fts USING fts5(
+            text,
+            content = 'codex_messages',
+            content_rowid = 'rowid',
+            tokenize = 'porter unicode61'
+        );
+        CREATE TRIGGER IF NOT EXISTS codex_messages_fts_insert
+        AFTER INSERT ON codex_messages BEGIN
+            INSERT INTO codex_messages_fts (rowid, text) VALUES (new.rowid, new.text);
+        END;
+        CREATE TRIGGER IF NOT EXISTS codex_messages_fts_delete
+        AFTER DELETE ON codex_messages BEGIN
+            INSERT INTO codex_messages_fts (codex_messages_fts, rowid, text)
+            VALUES ('delete', old.rowid, old.text);
+        END;
+        CREATE TRIGGER IF NOT EXISTS codex_messages_fts_update
+        AFTER UPDATE OF text ON codex_messages BEGIN
+            INSERT INTO codex_messages_fts (codex_messages_fts, rowid, text)
+            VALUES ('delete', old.rowid, old.text);
+            INSERT INTO codex_messages_fts (rowid, text) VALUES (new.rowid, new.text);
+        END;
+
         CREATE TABLE IF NOT EXISTS codex_tool_executions (
             thread_id TEXT NOT NULL REFERENCES codex_threads(thread_id) ON DELETE CASCADE,
             call_id TEXT NOT NULL,
@@ -1163,6 +1178,7 @@ mod tests {
         let stats = index.sync().expect("sync");
 
         assert_eq!(stats.parsed_journals, 1);
+        assert_eq!(stats.appended_journals, 0);
         assert_eq!(stats.parsed_records, 5);
         let thread: (String, String, i64, i64, i64) = index
             .connection()
@@ -1234,6 +1250,7 @@ mod tests {
 
         let appended = index.sync().expect("append sync");
         assert_eq!(appended.parsed_records, 1);
+        assert_eq!(appended.appended_journals, 1);
         let count: i64 = index
             .connection()
             .query_row(
@@ -1245,6 +1262,51 @@ mod tests {
         assert_eq!(count, 3);
     }
 
+    #[test]
+    fn sync_since_leaves_old_journals_and_their_cached_threads_alone() {
+        let temp = tempfile::tempdir().expect("temp");
+        let codex_home = temp.path().join("codex");
+        let journal = |id: &str| codex_home.join(format!("sessions/rollout-{id}.jsonl"));
+        let meta =
+            |id: &str| format!("{{\"type\":\"session_meta\",\"payload\":{{\"id\":\"{id}\"}}}}\n");
+        let age = |path: &Path, days: i64| {
+            let modified =
+                std::time::SystemTime::now() - std::time::Duration::from_secs(86_400 * days as u64);
+            fs::File::options()
+                .write(true)
+                .open(path)
+                .and_then(|file| file.set_modified(modified))
+                .expect("age journal");
+        };
+        write(&journal("cached"), &meta("cached"));
+        let cache = temp.path().join("cache/index.sqlite");
+        let mut index = CodexIndex::open_at(&codex_home, &cache).expect("open");
+        index.sync().expect("initial sync");
+
+        write(
+            &journal("cached"),
+            &format!("{}{}", meta("cached"), meta("ignored")),
+        );
+        age(&journal("cached"), 30);
+        write(&journal("old"), &meta("old"));
+        age(&journal("old"), 30);
+        write(&journal("new"), &meta("new"));
+
+        let since = Utc::now() - chrono::Duration::days(7);
+        let stats = index.sync_since(Some(since)).expect("windowed sync");
+        assert_eq!(stats.skipped_journals, 2);
+        assert_eq!(stats.pruned_threads, 0);
+        let threads: Vec<String> = index
+            .connection()
+            .prepare("SELECT thread_id FROM codex_threads ORDER BY thread_id")
+            .expect("prepare")
+            .query_map([], |row| row.get(0))
+            .expect("query")
+            .collect::<std::result::Result<_, _>>()
+            .expect("rows");
+        assert_eq!(threads, ["cached", "new"]);
+    }
+
     #[cfg(unix)]
     #[test]
     fn unchanged_compressed_journal_is_not_reopened() {
@@ -1435,6 +1497,41 @@ mod tests {
         assert_eq!(count, 0);
     }
 
+    #[test]
+    fn message_search_index_follows_sync_and_prune() {
+        let temp = tempfile::tempdir().expect("temp");
+        let codex_home = temp.path().join("codex");
+        let journal = codex_home.join("sessions/rollout-fts.jsonl");
+        write(
+            &journal,
+            concat!(
+                "{\"type\":\"session_meta\",\"payload\":{\"id\":\"fts-thread\"}}\n",
+                "{\"type\":\"response_item\",\"payload\":{\"type\":\"message\",\"role\":\"user\",\"content\":[{\"type\":\"input_text\",\"text\":\"retrying flaky uploads\"}]}}\n"
+            ),
+        );
+        let cache = temp.path().join("cache/index.sqlite");
+        let mut index = CodexIndex::open_at(&codex_home, &cache).expect("open");
+        index.sync().expect("initial sync");
+        let matches = |index: &CodexIndex, query: &str| -> i64 {
+            index
+                .connection()
+                .query_row(
+                    "SELECT COUNT(*) FROM codex_messages_fts WHERE codex_messages_fts MATCH ?1",
+                    [query],
+                    |row| row.get(0),
+                )
+                .expect("match")
+        };
+
+        // Porter stemming lets `retry` and `upload` find the inflected forms.
+        assert_eq!(matches(&index, "retry upload"), 1);
+
+        fs::remove_file(&journal).expect("delete journal");
+        index.sync().expect("delete sync");
+
+        assert_eq!(matches(&index, "retry"), 0);
+    }
+
     #[test]
     fn unreadable_changed_journal_keeps_last_good_rows_and_records_error() {
         let temp = tempfile::tempdir().expect("temp");
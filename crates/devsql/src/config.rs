@@ -0,0 +1,541 @@
+//! Central configuration in `~/.devsql/config.toml` (under `DEVSQL_HOME`
+//! when set).
+//!
+//! ```toml
+//! # Repositories a query reads when it names none with --repo or --workspace
+//! repos = ["~/src/api", "~/src/web"]
+//! # Calendar days for the worklog: "local", "UTC", or an offset like "-05:00"
+//! timezone = "local"
+//! # Sources whose tables may be loaded; all of them when absent
+//! sources = ["claude", "codex", "git", "code", "shell", "worklog", "full-text"]
+//!
+//! [paths]
+//! claude_data_dir = "~/.claude"
+//! codex_home = "~/.codex"
+//! atuin_db = "~/.local/share/atuin/history.db"
+//! zsh_history = "~/.zsh_history"
+//! bash_history = "~/.bash_history"
+//!
+//! [source_walk]
+//! # Directory names or globs skipped, on top of node_modules, target, ...
+//! exclude = ["fixtures", "*.generated"]
+//!
+//! [redaction]
+//! # Regular expressions masked as <redacted>, after the built-in patterns
+//! patterns = ["ACME-[0-9]{6}"]
+//!
+//! [gather]
+//! budget = 8000
+//! section_limits = { code_search = 20, excerpts = 3 }
+//! ```
+//!
+//! `CLAUDE_DATA_DIR`, `CODEX_HOME`, `DEVSQL_ATUIN_DB`, `DEVSQL_ZSH_HISTORY`,
+//! and `DEVSQL_BASH_HISTORY` override the file, and command-line options
+//! such as `--data-dir` and `--repo` override both. `devsql config show`
+//! prints every resolved value and where it came from.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+/// Gather sections with a row limit, and their default limits.
+pub const SECTION_LIMITS: [(&str, usize); 5] = [
+    ("prior_work", 10),
+    ("code_search", 10),
+    ("symbols", 10),
+    ("excerpts", 5),
+    ("activity", 10),
+];
+
+/// Where a resolved setting came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    Default,
+    File,
+    Env(&'static str),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => f.write_str("default"),
+            Self::File => f.write_str("config file"),
+            Self::Env(name) => write!(f, "${name}"),
+        }
+    }
+}
+
+/// A resolved value and its [`Origin`].
+#[derive(Debug, Clone)]
+pub struct Setting<T> {
+    pub value: T,
+    pub origin: Origin,
+}
+
+impl<T> Setting<T> {
+    fn new(value: T, origin: Origin) -> Self {
+        Self { value, origin }
+    }
+}
+
+/// How calendar days and clock times are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timezone {
+    Local,
+    Fixed(FixedOffset),
+}
+
+impl Timezone {
+    /// Read `local`, `UTC`, or a fixed offset such as `+05:30`.
+    pub fn parse(text: &str) -> Result<Self> {
+        match text.trim() {
+            zone if zone.eq_ignore_ascii_case("local") => Ok(Self::Local),
+            zone if zone.eq_ignore_ascii_case("utc") || zone == "Z" => {
+                Ok(Self::Fixed(FixedOffset::east_opt(0).expect("zero offset")))
+            }
+            zone => zone.parse::<FixedOffset>().map(Self::Fixed).map_err(|_| {
+                Error::Config(format!(
+                    "timezone must be \"local\", \"UTC\", or an offset like \"-05:00\", got {zone:?}"
+                ))
+            }),
+        }
+    }
+
+    /// Today's date in this timezone.
+    pub fn today(&self) -> NaiveDate {
+        self.from_utc(Utc::now()).date_naive()
+    }
+
+    /// `time` in this timezone.
+    pub fn from_utc(&self, time: DateTime<Utc>) -> DateTime<FixedOffset> {
+        match self {
+            Self::Local => time.with_timezone(&Local).fixed_offset(),
+            Self::Fixed(offset) => time.with_timezone(offset),
+        }
+    }
+}
+
+impl fmt::Display for Timezone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local => f.write_str("local"),
+            Self::Fixed(offset) if offset.local_minus_utc() == 0 => f.write_str("UTC"),
+            Self::Fixed(offset) => write!(f, "{offset}"),
+        }
+    }
+}
+
+/// Every setting, resolved from the defaults, the config file, and the
+/// environment.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// The file read, whether or not it exists.
+    pub path: PathBuf,
+    pub claude_data_dir: Setting<PathBuf>,
+    pub codex_home: Setting<PathBuf>,
+    /// `None` looks for the database the way Atuin does.
+    pub atuin_db: Setting<Option<PathBuf>>,
+    /// `None` uses `$HISTFILE` under zsh, then `~/.zsh_history`.
+    pub zsh_history: Setting<Option<PathBuf>>,
+    /// `None` uses `$HISTFILE` under bash, then `~/.bash_history`.
+    pub bash_history: Setting<Option<PathBuf>>,
+    /// Empty means the current directory.
+    pub repos: Setting<Vec<String>>,
+    pub exclude: Setting<Vec<String>>,
+    pub redaction_patterns: Setting<Vec<String>>,
+    pub gather_budget: Setting<usize>,
+    pub section_limits: BTreeMap<String, Setting<usize>>,
+    pub timezone: Setting<Timezone>,
+    /// Lowercase source names; `None` enables every source.
+    pub sources: Setting<Option<Vec<String>>>,
+    exclude_patterns: Vec<glob::Pattern>,
+    redaction: Vec<Regex>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    repos: Option<Vec<String>>,
+    timezone: Option<String>,
+    sources: Option<Vec<String>>,
+    #[serde(default)]
+    paths: PathsSection,
+    #[serde(default)]
+    source_walk: SourceWalkSection,
+    #[serde(default)]
+    redaction: RedactionSection,
+    #[serde(default)]
+    gather: GatherSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PathsSection {
+    claude_data_dir: Option<String>,
+    codex_home: Option<String>,
+    atuin_db: Option<String>,
+    zsh_history: Option<String>,
+    bash_history: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SourceWalkSection {
+    exclude: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RedactionSection {
+    patterns: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GatherSection {
+    budget: Option<usize>,
+    #[serde(default)]
+    section_limits: BTreeMap<String, usize>,
+}
+
+/// Path of the config file.
+pub fn path() -> PathBuf {
+    crate::worklog::home_dir().join("config.toml")
+}
+
+/// The configuration, read once per process. A file that does not parse
+/// is reported on stderr and the defaults are used instead.
+pub fn current() -> &'static Config {
+    static CURRENT: OnceLock<Config> = OnceLock::new();
+    CURRENT.get_or_init(|| {
+        Config::load().unwrap_or_else(|e| {
+            eprintln!("Warning: {e}");
+            Config::resolve(&path(), None, env_value).expect("defaults resolve without a file")
+        })
+    })
+}
+
+fn env_value(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+impl Config {
+    /// Read [`path`] and the environment. A missing file leaves the
+    /// defaults; one that does not parse is an error naming it.
+    pub fn load() -> Result<Self> {
+        let path = path();
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => Some(text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(Error::Config(format!("{}: {e}", path.display()))),
+        };
+        Self::resolve(&path, text.as_deref(), env_value)
+    }
+
+    /// Resolve settings from the file's `text`, if any, and the environment
+    /// variables `env` returns.
+    pub fn resolve(
+        path: &Path,
+        text: Option<&str>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self> {
+        let file: ConfigFile = match text {
+            Some(text) => toml::from_str(text)
+                .map_err(|e| Error::Config(format!("{}: {e}", path.display())))?,
+            None => ConfigFile::default(),
+        };
+        let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+
+        // Environment over file over default.
+        let path_setting = |variable: &'static str, configured: Option<String>| {
+            env(variable)
+                .map(|value| Setting::new(Some(expand_home(&value)), Origin::Env(variable)))
+                .or_else(|| {
+                    configured.map(|value| Setting::new(Some(expand_home(&value)), Origin::File))
+                })
+                .unwrap_or_else(|| Setting::new(None, Origin::Default))
+        };
+        let paths = file.paths;
+        let claude_data_dir = path_setting("CLAUDE_DATA_DIR", paths.claude_data_dir);
+        let codex_home = path_setting("CODEX_HOME", paths.codex_home);
+
+        let exclude = listed(file.source_walk.exclude);
+        let exclude_patterns = exclude
+            .value
+            .iter()
+            .map(|pattern| {
+                glob::Pattern::new(pattern).map_err(|e| {
+                    Error::Config(format!("source_walk.exclude pattern {pattern:?}: {e}"))
+                })
+            })
+            .collect::<Result<_>>()?;
+        let redaction_patterns = listed(file.redaction.patterns);
+        let redaction = redaction_patterns
+            .value
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern)
+                    .map_err(|e| Error::Config(format!("redaction pattern {pattern:?}: {e}")))
+            })
+            .collect::<Result<_>>()?;
+
+        let mut section_limits: BTreeMap<String, Setting<usize>> = SECTION_LIMITS
+            .iter()
+            .map(|&(name, limit)| (name.to_string(), Setting::new(limit, Origin::Default)))
+            .collect();
+        for (name, limit) in file.gather.section_limits {
+            let Some(setting) = section_limits.get_mut(&name) else {
+                return Err(Error::Config(format!(
+                    "gather.section_limits has no section {name:?}; sections are {}",
+                    SECTION_LIMITS.map(|(name, _)| name).join(", ")
+                )));
+            };
+            *setting = Setting::new(limit, Origin::File);
+        }
+
+        let timezone = match file.timezone {
+            Some(zone) => Setting::new(Timezone::parse(&zone)?, Origin::File),
+            None => Setting::new(Timezone::Local, Origin::Default),
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            claude_data_dir: Setting::new(
+                claude_data_dir
+                    .value
+                    .unwrap_or_else(|| home.join(".claude")),
+                claude_data_dir.origin,
+            ),
+            codex_home: Setting::new(
+                codex_home.value.unwrap_or_else(|| home.join(".codex")),
+                codex_home.origin,
+            ),
+            atuin_db: path_setting("DEVSQL_ATUIN_DB", paths.atuin_db),
+            zsh_history: path_setting("DEVSQL_ZSH_HISTORY", paths.zsh_history),
+            bash_history: path_setting("DEVSQL_BASH_HISTORY", paths.bash_history),
+            repos: listed(file.repos),
+            exclude,
+            redaction_patterns,
+            gather_budget: file.gather.budget.map_or_else(
+                || Setting::new(8000, Origin::Default),
+                |budget| Setting::new(budget, Origin::File),
+            ),
+            section_limits,
+            timezone,
+            sources: match file.sources {
+                Some(sources) => Setting::new(
+                    Some(sources.iter().map(|name| name.to_lowercase()).collect()),
+                    Origin::File,
+                ),
+                None => Setting::new(None, Origin::Default),
+            },
+            exclude_patterns,
+            redaction,
+        })
+    }
+
+    /// Whether tables from the source named `name` (as listed by `devsql
+    /// tables`, in any case) may be loaded.
+    pub fn source_enabled(&self, name: &str) -> bool {
+        self.sources
+            .value
+            .as_ref()
+            .is_none_or(|sources| sources.contains(&name.to_lowercase()))
+    }
+
+    /// Whether the source walk skips a directory called `name`.
+    pub fn excludes_dir(&self, name: &str) -> bool {
+        crate::providers::should_skip_dir(name)
+            || self
+                .exclude_patterns
+                .iter()
+                .any(|pattern| pattern.matches(name))
+    }
+
+    /// Rows fetched for a gather section before budget trimming.
+    pub fn section_limit(&self, section: &str) -> usize {
+        self.section_limits
+            .get(section)
+            .map_or(10, |setting| setting.value)
+    }
+
+    /// Mask the built-in secret patterns, then the configured ones.
+    pub fn redact(&self, text: &str) -> String {
+        let mut redacted = vcsql::redaction::redact_sensitive_text(text);
+        for pattern in &self.redaction {
+            redacted = pattern.replace_all(&redacted, "<redacted>").into_owned();
+        }
+        redacted
+    }
+
+    /// `(setting, value, origin)` for every setting, in file order.
+    pub fn entries(&self) -> Vec<ConfigEntry> {
+        fn entry<T>(name: &str, setting: &Setting<T>, show: impl Fn(&T) -> String) -> ConfigEntry {
+            ConfigEntry {
+                setting: name.to_string(),
+                value: show(&setting.value),
+                source: setting.origin.to_string(),
+            }
+        }
+        let path = |path: &PathBuf| path.display().to_string();
+        let optional_path = |path: &Option<PathBuf>| {
+            path.as_ref().map_or_else(
+                || "(discovered)".to_string(),
+                |path| path.display().to_string(),
+            )
+        };
+        let list = |values: &Vec<String>| values.join(", ");
+
+        let mut entries = vec![
+            entry("repos", &self.repos, |repos| {
+                if repos.is_empty() {
+                    "(current directory)".to_string()
+                } else {
+                    list(repos)
+                }
+            }),
+            entry("timezone", &self.timezone, |zone| zone.to_string()),
+            entry("sources", &self.sources, |sources| {
+                sources.as_ref().map_or_else(|| "(all)".to_string(), list)
+            }),
+            entry("paths.claude_data_dir", &self.claude_data_dir, path),
+            entry("paths.codex_home", &self.codex_home, path),
+            entry("paths.atuin_db", &self.atuin_db, optional_path),
+            entry("paths.zsh_history", &self.zsh_history, optional_path),
+            entry("paths.bash_history", &self.bash_history, optional_path),
+            entry("source_walk.exclude", &self.exclude, list),
+            entry("redaction.patterns", &self.redaction_patterns, list),
+            entry("gather.budget", &self.gather_budget, usize::to_string),
+        ];
+        for (name, limit) in &self.section_limits {
+            entries.push(entry(
+                &format!("gather.section_limits.{name}"),
+                limit,
+                usize::to_string,
+            ));
+        }
+        entries
+    }
+}
+
+/// One row of `devsql config show`.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ConfigEntry {
+    pub setting: String,
+    pub value: String,
+    /// `default`, `config file`, or the environment variable that set it
+    pub source: String,
+}
+
+fn listed(values: Option<Vec<String>>) -> Setting<Vec<String>> {
+    match values {
+        Some(values) => Setting::new(values, Origin::File),
+        None => Setting::new(Vec::new(), Origin::Default),
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .map(|home| home.join(rest))
+            .unwrap_or_else(|| PathBuf::from(path)),
+        None if path == "~" => dirs::home_dir().unwrap_or_else(|| PathBuf::from(path)),
+        None => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(text: &str, env: &[(&str, &str)]) -> Result<Config> {
+        Config::resolve(Path::new("config.toml"), Some(text), |name| {
+            env.iter()
+                .find(|(variable, _)| *variable == name)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn environment_overrides_the_file_which_overrides_defaults() {
+        let config = resolve(
+            r#"
+            repos = ["/src/api"]
+            timezone = "+05:30"
+            sources = ["Git", "code"]
+
+            [paths]
+            claude_data_dir = "/data/claude"
+            codex_home = "/data/codex"
+
+            [source_walk]
+            exclude = ["fixtures", "*.generated"]
+
+            [redaction]
+            patterns = ["ACME-[0-9]{6}"]
+
+            [gather]
+            budget = 2000
+            section_limits = { code_search = 20 }
+            "#,
+            &[("CODEX_HOME", "/env/codex")],
+        )
+        .expect("resolve");
+
+        assert_eq!(config.claude_data_dir.value, PathBuf::from("/data/claude"));
+        assert_eq!(config.claude_data_dir.origin, Origin::File);
+        assert_eq!(config.codex_home.value, PathBuf::from("/env/codex"));
+        assert_eq!(config.codex_home.origin, Origin::Env("CODEX_HOME"));
+        assert_eq!(config.zsh_history.value, None);
+        assert_eq!(config.zsh_history.origin, Origin::Default);
+        assert_eq!(config.repos.value, ["/src/api"]);
+        assert_eq!(config.timezone.value.to_string(), "+05:30");
+
+        assert!(config.source_enabled("GIT"));
+        assert!(!config.source_enabled("SHELL"));
+        assert!(config.excludes_dir("node_modules"));
+        assert!(config.excludes_dir("api.generated"));
+        assert!(!config.excludes_dir("src"));
+        assert_eq!(
+            config.redact("ticket ACME-123456, key sk-abcdefghijkl"),
+            "ticket <redacted>, key <redacted>"
+        );
+        assert_eq!(config.gather_budget.value, 2000);
+        assert_eq!(config.section_limit("code_search"), 20);
+        assert_eq!(config.section_limit("excerpts"), 5);
+
+        let entries = config.entries();
+        let codex = entries
+            .iter()
+            .find(|entry| entry.setting == "paths.codex_home")
+            .expect("codex_home");
+        assert_eq!(codex.source, "$CODEX_HOME");
+        let limit = entries
+            .iter()
+            .find(|entry| entry.setting == "gather.section_limits.code_search")
+            .expect("section limit");
+        assert_eq!(
+            (limit.value.as_str(), limit.source.as_str()),
+            ("20", "config file")
+        );
+    }
+
+    #[test]
+    fn unknown_keys_and_bad_values_are_errors() {
+        assert!(resolve("repo = [\"/src\"]", &[]).is_err());
+        assert!(resolve("timezone = \"Mars/Olympus\"", &[]).is_err());
+        assert!(resolve("[gather]\nsection_limits = { repo_state = 3 }", &[]).is_err());
+        assert!(resolve("[redaction]\npatterns = [\"(\"]", &[]).is_err());
+
+        let defaults = resolve("", &[]).expect("empty file");
+        assert!(defaults.source_enabled("FULL-TEXT"));
+        assert_eq!(defaults.timezone.value, Timezone::Local);
+    }
+}
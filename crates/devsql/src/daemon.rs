@@ -0,0 +1,898 @@
+//! `devsql daemon`: a background process keeping a persistent database of
+//! the Claude Code, Codex, shell, and worklog tables current, so queries
+//! copy rows from it instead of parsing every file again.
+//!
+//! Each [`Daemon::sync`] brings the database up to date:
+//!
+//! - JSONL files (Claude Code and Codex history, transcripts) are read from
+//!   the last complete record the previous sync stopped at, as the Codex
+//!   index reads its journals; a file whose first record changed or that
+//!   shrank is read again from the start.
+//! - Shell history and the worklog are reloaded whole when their files
+//!   change, replacing the old rows in one transaction.
+//! - The Codex index and the Git index of each configured repository are
+//!   synced when their journals or refs change, so a query's own sync
+//!   finds nothing to do.
+//!
+//! After every sync [`Daemon::run`] records a heartbeat. While it is fresh and
+//! the daemon reads the same directories as an engine, the engine's
+//! [`crate::UnifiedEngine::load_tables`] copies the [`HELD_TABLES`] from
+//! the database (see [`serving`]); set `DEVSQL_NO_DAEMON` to load them from
+//! their sources anyway.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use ccql::datasources::codex_journal::{visit_journal_records, CodexJournalFile, JournalState};
+use ccql::datasources::transcript::{discover_transcript_files, TranscriptFile};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{
+    params, Connection, OpenFlags, OptionalExtension, Transaction, TransactionBehavior,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use vcsql::TimeWindow;
+
+use crate::engine::{
+    self, HISTORY_COLUMNS, JHISTORY_COLUMNS, TOOL_CALL_COLUMNS, TRANSCRIPT_COLUMNS,
+};
+use crate::{index_cache, Error, Result};
+
+const SCHEMA_VERSION: i64 = 1;
+
+/// Skip the daemon's database when this is set to anything.
+pub const DISABLE_ENV: &str = "DEVSQL_NO_DAEMON";
+
+/// Seconds between syncs when `--interval` is not given.
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long past three intervals a heartbeat still counts as fresh, for a
+/// sync that reads many new files.
+const HEARTBEAT_GRACE: Duration = Duration::from_secs(10);
+
+/// Tables the daemon holds, with the order its rows are copied in: that of
+/// the files they were read from.
+pub const HELD_TABLES: [(&str, &str); 7] = [
+    ("history", "rowid"),
+    ("jhistory", "rowid"),
+    ("transcripts", "source_path, record_index, rowid"),
+    ("tool_calls", "source_path, record_index, rowid"),
+    ("shell_history", "rowid"),
+    ("work_tasks", "rowid"),
+    ("work_events", "rowid"),
+];
+
+/// Whether the daemon holds `table`.
+pub fn holds(table: &str) -> bool {
+    HELD_TABLES.iter().any(|(name, _)| *name == table)
+}
+
+/// The daemon's database, next to the worklog.
+pub fn db_path() -> PathBuf {
+    crate::worklog::home_dir().join("daemon.sqlite")
+}
+
+/// What one [`Daemon::sync`] did.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SyncStats {
+    /// JSONL files read, from the start or from their last complete record.
+    pub parsed_files: usize,
+    /// Of `parsed_files`, those read from their last complete record.
+    pub appended_files: usize,
+    pub unchanged_files: usize,
+    /// Files gone since the last sync, whose rows were deleted.
+    pub pruned_files: usize,
+    /// Files that could not be read, such as ones removed while the sync
+    /// ran; they are pruned and read again once they can be.
+    pub skipped_files: usize,
+    pub parsed_records: usize,
+    /// Tables reloaded whole because their files changed.
+    pub reloaded_tables: Vec<String>,
+    /// Codex journals the Codex index read.
+    pub codex_journals: usize,
+    /// Commits new to the Git indexes.
+    pub indexed_commits: usize,
+}
+
+/// The daemon's database and the directories it reads.
+pub struct Daemon {
+    conn: Connection,
+    path: PathBuf,
+    claude_data_dir: PathBuf,
+    codex_home: PathBuf,
+    repos: Vec<PathBuf>,
+}
+
+impl Daemon {
+    /// Open (or create) the database at [`db_path`].
+    pub fn open(
+        claude_data_dir: PathBuf,
+        codex_home: PathBuf,
+        repos: Vec<PathBuf>,
+    ) -> Result<Self> {
+        Self::open_at(&db_path(), claude_data_dir, codex_home, repos)
+    }
+
+    /// Open (or create) the database at `path`.
+    pub fn open_at(
+        path: &Path,
+        claude_data_dir: PathBuf,
+        codex_home: PathBuf,
+        repos: Vec<PathBuf>,
+    ) -> Result<Self> {
+        let conn = index_cache::open_versioned(path, SCHEMA_VERSION, create_schema)?;
+        Ok(Self {
+            conn,
+            path: path.to_path_buf(),
+            claude_data_dir,
+            codex_home,
+            repos,
+        })
+    }
+
+    /// Bring every table and index up to date once.
+    pub fn sync(&mut self) -> Result<SyncStats> {
+        let mut stats = SyncStats::default();
+        self.sync_records(&mut stats)?;
+        self.sync_reloaded(&mut stats)?;
+        self.sync_indexes(&mut stats)?;
+        Ok(stats)
+    }
+
+    /// Sync every `interval`, recording a heartbeat after each sync, until
+    /// `stop` receives a message or hangs up; then remove the heartbeat, so
+    /// queries read their sources again. A failed sync is logged and tried
+    /// again at the next interval, and records no heartbeat, so queries
+    /// stop reading the database if syncs keep failing.
+    pub fn run(&mut self, interval: Duration, stop: &mpsc::Receiver<()>) -> Result<()> {
+        loop {
+            if let Err(error) = self.sync().and_then(|_| self.record_heartbeat(interval)) {
+                eprintln!(
+                    "Sync failed, retrying in {}s: {error}",
+                    interval.as_secs_f64()
+                );
+            }
+            if !matches!(stop.recv_timeout(interval), Err(RecvTimeoutError::Timeout)) {
+                break;
+            }
+        }
+        self.conn
+            .execute("DELETE FROM daemon_meta WHERE key = 'synced_at'", [])?;
+        Ok(())
+    }
+
+    /// Append the records added to each JSONL file since the last sync.
+    fn sync_records(&mut self, stats: &mut SyncStats) -> Result<()> {
+        let transcripts = ccql::Config::new_with_codex_data_dir(
+            self.claude_data_dir.clone(),
+            self.codex_home.clone(),
+        )
+        .map(|config| discover_transcript_files(&config))
+        .unwrap_or_default();
+        self.sync_discovered(&transcripts, stats)
+    }
+
+    /// [`Self::sync_records`] with the transcripts already discovered. Files
+    /// that cannot be read are skipped, and pruned like removed ones.
+    fn sync_discovered(
+        &mut self,
+        transcripts: &[TranscriptFile],
+        stats: &mut SyncStats,
+    ) -> Result<()> {
+        let history = self.claude_data_dir.join("history.jsonl");
+        let jhistory = self.codex_home.join("history.jsonl");
+        let tx = self
+            .conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let mut seen = HashSet::new();
+        if history.is_file() {
+            let insert = engine::insert_sql("history", &HISTORY_COLUMNS);
+            let synced = sync_file(
+                &tx,
+                &history,
+                stats,
+                |tx| Ok(tx.execute_batch("DELETE FROM history")?),
+                |tx, _, entry| {
+                    tx.prepare_cached(&insert)?
+                        .execute(rusqlite::params_from_iter(engine::history_values(entry)))?;
+                    Ok(())
+                },
+            )?;
+            if synced {
+                seen.insert(path_text(&history));
+            }
+        }
+        if jhistory.is_file() {
+            let insert = engine::insert_sql("jhistory", &JHISTORY_COLUMNS);
+            let synced = sync_file(
+                &tx,
+                &jhistory,
+                stats,
+                |tx| Ok(tx.execute_batch("DELETE FROM jhistory")?),
+                |tx, _, entry| {
+                    let (_, values) = engine::jhistory_row(entry);
+                    tx.prepare_cached(&insert)?
+                        .execute(rusqlite::params_from_iter(values))?;
+                    Ok(())
+                },
+            )?;
+            if synced {
+                seen.insert(path_text(&jhistory));
+            }
+        }
+
+        let transcript_columns: Vec<&str> = TRANSCRIPT_COLUMNS
+            .iter()
+            .map(|(name, _)| *name)
+            .chain(["source_path", "record_index"])
+            .collect();
+        let insert_transcript = engine::insert_sql("transcripts", &transcript_columns);
+        let tool_call_columns: Vec<&str> = TOOL_CALL_COLUMNS
+            .iter()
+            .copied()
+            .chain(["record_index"])
+            .collect();
+        let insert_tool_call = engine::insert_sql("tool_calls", &tool_call_columns);
+        for file in transcripts {
+            let source_path = path_text(&file.path);
+            let synced = sync_file(
+                &tx,
+                &file.path,
+                stats,
+                |tx| delete_transcript_rows(tx, &source_path),
+                |tx, record_index, entry| {
+                    let mut values = engine::transcript_values(file, entry);
+                    values.extend([source_path.clone().into(), record_index.into()]);
+                    tx.prepare_cached(&insert_transcript)?
+                        .execute(rusqlite::params_from_iter(values))?;
+                    for (_, mut values) in engine::tool_call_rows(file, entry) {
+                        values.push(record_index.into());
+                        tx.prepare_cached(&insert_tool_call)?
+                            .execute(rusqlite::params_from_iter(values))?;
+                    }
+                    Ok(())
+                },
+            )?;
+            if synced {
+                seen.insert(source_path);
+            }
+        }
+
+        let known: Vec<String> = tx
+            .prepare("SELECT path FROM daemon_files")?
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        for path in known.into_iter().filter(|path| !seen.contains(path)) {
+            if path == path_text(&history) {
+                tx.execute_batch("DELETE FROM history")?;
+            } else if path == path_text(&jhistory) {
+                tx.execute_batch("DELETE FROM jhistory")?;
+            } else {
+                delete_transcript_rows(&tx, &path)?;
+            }
+            tx.execute("DELETE FROM daemon_files WHERE path = ?1", [&path])?;
+            stats.pruned_files += 1;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Reload shell history and the worklog when their files changed.
+    fn sync_reloaded(&mut self, stats: &mut SyncStats) -> Result<()> {
+        let shell_paths = crate::providers::shell_history::history_paths();
+        if let Some(stamp) = self.changed_stamp("shell_history", &shell_paths)? {
+            self.replace_tables(&["shell_history"], |scratch| {
+                crate::providers::shell_history::load(scratch, &TimeWindow::default())
+            })?;
+            self.set_meta("stamp:shell_history", &stamp)?;
+            self.set_meta("shell_history_paths", &paths_json(&shell_paths))?;
+            stats.reloaded_tables.push("shell_history".to_string());
+        }
+
+        let worklog = crate::worklog::db_path();
+        let mut wal = worklog.clone().into_os_string();
+        wal.push("-wal");
+        let inputs = [worklog.clone(), PathBuf::from(wal)];
+        if let Some(stamp) = self.changed_stamp("worklog", &inputs)? {
+            let tables = ["work_tasks", "work_events"];
+            self.replace_tables(&tables, |scratch| {
+                crate::worklog::Worklog::open_at(&worklog)?.materialize_into(
+                    scratch,
+                    &tables,
+                    &TimeWindow::default(),
+                )
+            })?;
+            self.set_meta("stamp:worklog", &stamp)?;
+            stats
+                .reloaded_tables
+                .extend(tables.iter().map(|table| table.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Sync the Codex index and each repository's Git index when their
+    /// journals or refs changed.
+    fn sync_indexes(&mut self, stats: &mut SyncStats) -> Result<()> {
+        let journals = [
+            self.codex_home.join("sessions"),
+            self.codex_home.join("archived_sessions"),
+        ];
+        if let Some(stamp) = self.changed_stamp("codex", &journals)? {
+            let mut index = crate::codex_index::CodexIndex::open(&self.codex_home)?;
+            stats.codex_journals += index.sync_since(None)?.parsed_journals;
+            self.set_meta("stamp:codex", &stamp)?;
+        }
+
+        for repo_path in self.repos.clone() {
+            let refs = crate::source::git_ref_paths(std::slice::from_ref(&repo_path));
+            let key = format!("git:{}", path_text(&repo_path));
+            let Some(stamp) = self.changed_stamp(&key, &refs)? else {
+                continue;
+            };
+            if let Ok(repo) = vcsql::GitRepo::open(&repo_path) {
+                let cache_path = crate::git_index::cache_path(Path::new(repo.path()));
+                let mut index = crate::git_index::GitIndex::open_at(&cache_path)?;
+                stats.indexed_commits += index.sync(&repo)?.1.indexed_commits;
+            }
+            self.set_meta(&format!("stamp:{key}"), &stamp)?;
+        }
+        Ok(())
+    }
+
+    /// Load `tables` into a scratch connection with `load`, then swap them
+    /// into the database in one transaction, so readers see either the old
+    /// rows or the new ones.
+    fn replace_tables(
+        &self,
+        tables: &[&str],
+        load: impl FnOnce(&mut Connection) -> Result<()>,
+    ) -> Result<()> {
+        let mut scratch = Connection::open_in_memory()?;
+        load(&mut scratch)?;
+        scratch.busy_timeout(Duration::from_secs(30))?;
+        scratch.execute("ATTACH DATABASE ?1 AS daemon", [path_text(&self.path)])?;
+        {
+            let tx = scratch.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            for table in tables {
+                tx.execute_batch(&format!("DROP TABLE IF EXISTS daemon.\"{table}\""))?;
+                crate::snapshot::copy_table(&tx, table, "daemon", false)?;
+            }
+            tx.commit()?;
+        }
+        scratch.execute_batch("DETACH DATABASE daemon")?;
+        Ok(())
+    }
+
+    /// The stamp of `paths` when it differs from the one recorded under
+    /// `key` at the last sync that read them.
+    fn changed_stamp(&self, key: &str, paths: &[PathBuf]) -> Result<Option<String>> {
+        let stamp = stamp_text(&crate::source::stamp(paths));
+        let recorded = get_meta(&self.conn, &format!("stamp:{key}"))?;
+        Ok((recorded.as_deref() != Some(stamp.as_str())).then_some(stamp))
+    }
+
+    fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO daemon_meta (key, value) VALUES (?1, ?2)",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    fn record_heartbeat(&self, interval: Duration) -> Result<()> {
+        let repos: Vec<PathBuf> = self.repos.iter().map(|repo| canonical(repo)).collect();
+        self.set_meta(
+            "claude_data_dir",
+            &path_text(&canonical(&self.claude_data_dir)),
+        )?;
+        self.set_meta("codex_home", &path_text(&canonical(&self.codex_home)))?;
+        self.set_meta("repos", &paths_json(&repos))?;
+        self.set_meta("pid", &std::process::id().to_string())?;
+        self.set_meta("interval_ms", &interval.as_millis().to_string())?;
+        self.set_meta(
+            "synced_at",
+            &Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        )
+    }
+}
+
+/// How far a JSONL file was read.
+struct FileState {
+    size: u64,
+    modified_ns: i64,
+    fingerprint: String,
+    last_complete_offset: u64,
+    last_record_index: i64,
+}
+
+/// [`sync_jsonl`] in a savepoint. When the file cannot be read, most often
+/// because it was removed after it was discovered, its changes are rolled
+/// back, the error is logged, and `false` is returned so the file is pruned.
+fn sync_file(
+    tx: &Transaction<'_>,
+    path: &Path,
+    stats: &mut SyncStats,
+    reset: impl FnOnce(&Transaction<'_>) -> Result<()>,
+    insert: impl FnMut(&Transaction<'_>, i64, &Value) -> Result<()>,
+) -> Result<bool> {
+    let before = stats.clone();
+    tx.execute_batch("SAVEPOINT sync_file")?;
+    match sync_jsonl(tx, path, stats, reset, insert) {
+        Ok(()) => {
+            tx.execute_batch("RELEASE sync_file")?;
+            Ok(true)
+        }
+        Err(Error::Io(error)) => {
+            tx.execute_batch("ROLLBACK TO sync_file; RELEASE sync_file")?;
+            eprintln!("Skipping {}: {error}", path.display());
+            *stats = before;
+            stats.skipped_files += 1;
+            Ok(false)
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// Insert the records of the JSONL file at `path` that the last sync did
+/// not read, calling `reset` to delete its rows first when it must be read
+/// from the start. A trailing record that does not parse yet is left for
+/// the next sync.
+fn sync_jsonl(
+    tx: &Transaction<'_>,
+    path: &Path,
+    stats: &mut SyncStats,
+    reset: impl FnOnce(&Transaction<'_>) -> Result<()>,
+    mut insert: impl FnMut(&Transaction<'_>, i64, &Value) -> Result<()>,
+) -> Result<()> {
+    let metadata = fs::metadata(path)?;
+    let size = metadata.len();
+    let modified_ns = crate::codex_index::modified_ns(&metadata);
+    let path_text = path_text(path);
+    let existing = tx
+        .query_row(
+            "SELECT size, modified_ns, leading_fingerprint, last_complete_offset,
+                    last_record_index
+             FROM daemon_files WHERE path = ?1",
+            [&path_text],
+            |row| {
+                Ok(FileState {
+                    size: row.get::<_, i64>(0)? as u64,
+                    modified_ns: row.get(1)?,
+                    fingerprint: row.get(2)?,
+                    last_complete_offset: row.get::<_, i64>(3)? as u64,
+                    last_record_index: row.get(4)?,
+                })
+            },
+        )
+        .optional()?;
+    if existing
+        .as_ref()
+        .is_some_and(|old| old.size == size && old.modified_ns == modified_ns)
+    {
+        stats.unchanged_files += 1;
+        return Ok(());
+    }
+
+    let file = CodexJournalFile {
+        path: path.to_path_buf(),
+        state: JournalState::Active,
+        compressed: false,
+    };
+    let fingerprint = crate::codex_index::leading_fingerprint(&file)?;
+    let (start_offset, start_index) = match &existing {
+        Some(old) if old.fingerprint == fingerprint && size >= old.size => {
+            stats.appended_files += 1;
+            (old.last_complete_offset, old.last_record_index + 1)
+        }
+        _ => {
+            reset(tx)?;
+            (0, 0)
+        }
+    };
+
+    crate::profile::file_read();
+    // An insert error is returned as itself, not as the I/O error that
+    // stops the visit, so it is not mistaken for an unreadable file.
+    let mut failed = None;
+    let visited = visit_journal_records(&file, start_offset, start_index, |record| {
+        if let Some(value) = &record.value {
+            stats.parsed_records += 1;
+            if let Err(error) = insert(tx, record.record_index, value) {
+                let message = error.to_string();
+                failed = Some(error);
+                return Err(io::Error::other(message));
+            }
+        }
+        Ok(())
+    });
+    if let Some(error) = failed {
+        return Err(error);
+    }
+    let progress = visited?;
+    let (mut last_complete_offset, mut last_record_index) =
+        (progress.last_complete_offset, progress.last_record_index);
+    if size > last_complete_offset {
+        // A last record without its newline is complete once it parses:
+        // the next record starts with the newline, an empty line skipped.
+        let mut tail = Vec::new();
+        let mut reader = fs::File::open(path)?;
+        io::Seek::seek(&mut reader, io::SeekFrom::Start(last_complete_offset))?;
+        io::Read::read_to_end(&mut reader, &mut tail)?;
+        if let Ok(value @ Value::Object(_)) = serde_json::from_slice::<Value>(&tail) {
+            last_record_index += 1;
+            last_complete_offset += tail.len() as u64;
+            stats.parsed_records += 1;
+            insert(tx, last_record_index, &value)?;
+        }
+    }
+    tx.execute(
+        "INSERT OR REPLACE INTO daemon_files
+             (path, size, modified_ns, leading_fingerprint, last_complete_offset,
+              last_record_index)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            path_text,
+            size as i64,
+            modified_ns,
+            fingerprint,
+            last_complete_offset as i64,
+            last_record_index,
+        ],
+    )?;
+    stats.parsed_files += 1;
+    Ok(())
+}
+
+fn delete_transcript_rows(tx: &Transaction<'_>, source_path: &str) -> Result<()> {
+    tx.execute(
+        "DELETE FROM transcripts WHERE source_path = ?1",
+        [source_path],
+    )?;
+    tx.execute(
+        "DELETE FROM tool_calls WHERE source_path = ?1",
+        [source_path],
+    )?;
+    Ok(())
+}
+
+fn create_schema(conn: &Connection) -> Result<()> {
+    let transcript_columns: Vec<String> = TRANSCRIPT_COLUMNS
+        .iter()
+        .map(|(name, ty)| format!("{name} {ty}"))
+        .collect();
+    let tool_call_columns: Vec<String> = TOOL_CALL_COLUMNS
+        .iter()
+        .map(|name| format!("{name} TEXT"))
+        .collect();
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS daemon_meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+         CREATE TABLE IF NOT EXISTS daemon_files (
+             path TEXT PRIMARY KEY,
+             size INTEGER NOT NULL,
+             modified_ns INTEGER NOT NULL,
+             leading_fingerprint TEXT NOT NULL,
+             last_complete_offset INTEGER NOT NULL,
+             last_record_index INTEGER NOT NULL
+         );
+         {history};
+         {jhistory};
+         CREATE TABLE IF NOT EXISTS transcripts (
+             rowid INTEGER PRIMARY KEY, {transcripts},
+             source_path TEXT NOT NULL, record_index INTEGER NOT NULL
+         );
+         CREATE INDEX IF NOT EXISTS transcripts_by_source
+             ON transcripts (source_path, record_index);
+         CREATE TABLE IF NOT EXISTS tool_calls (
+             rowid INTEGER PRIMARY KEY, {tool_calls}, record_index INTEGER NOT NULL
+         );
+         CREATE INDEX IF NOT EXISTS tool_calls_by_source
+             ON tool_calls (source_path, record_index);",
+        history = engine::HISTORY_TABLE,
+        jhistory = engine::JHISTORY_TABLE,
+        transcripts = transcript_columns.join(", "),
+        tool_calls = tool_call_columns.join(", "),
+    ))?;
+    Ok(())
+}
+
+fn get_meta(conn: &Connection, key: &str) -> Result<Option<String>> {
+    Ok(conn
+        .query_row(
+            "SELECT value FROM daemon_meta WHERE key = ?1",
+            [key],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// Where the daemon's database stands, read without syncing.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct Status {
+    pub path: String,
+    /// Whether the heartbeat is fresh.
+    pub running: bool,
+    pub pid: Option<u32>,
+    pub synced_at: Option<String>,
+    pub claude_data_dir: Option<String>,
+    pub codex_home: Option<String>,
+    pub repos: Vec<String>,
+    pub tables: Vec<TableRows>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct TableRows {
+    pub name: String,
+    pub rows: i64,
+}
+
+/// The status of the database at `path`, or `None` when there is none.
+pub fn status(path: &Path) -> Result<Option<Status>> {
+    let Some(conn) = open_read_only(path)? else {
+        return Ok(None);
+    };
+    let meta = |key: &str| get_meta(&conn, key);
+    let synced_at = meta("synced_at")?;
+    let interval = meta("interval_ms")?;
+    let mut tables = Vec::new();
+    for (name, _) in HELD_TABLES {
+        let rows = conn
+            .query_row(&format!("SELECT COUNT(*) FROM \"{name}\""), [], |row| {
+                row.get(0)
+            })
+            .unwrap_or(0);
+        tables.push(TableRows {
+            name: name.to_string(),
+            rows,
+        });
+    }
+    Ok(Some(Status {
+        path: path_text(path),
+        running: is_fresh(synced_at.as_deref(), interval.as_deref(), SystemTime::now()),
+        pid: meta("pid")?.and_then(|pid| pid.parse().ok()),
+        synced_at,
+        claude_data_dir: meta("claude_data_dir")?,
+        codex_home: meta("codex_home")?,
+        repos: meta("repos")?
+            .and_then(|repos| serde_json::from_str(&repos).ok())
+            .unwrap_or_default(),
+        tables,
+    }))
+}
+
+/// The daemon's database, when a running daemon reads `claude_data_dir`,
+/// `codex_home`, and the shell history files queries would read, and
+/// `DEVSQL_NO_DAEMON` is unset.
+pub(crate) fn serving(claude_data_dir: &Path, codex_home: &Path) -> Option<PathBuf> {
+    if std::env::var_os(DISABLE_ENV).is_some() {
+        return None;
+    }
+    let path = db_path();
+    let conn = open_read_only(&path).ok()??;
+    let meta = |key: &str| get_meta(&conn, key).ok().flatten();
+    let fresh = is_fresh(
+        meta("synced_at").as_deref(),
+        meta("interval_ms").as_deref(),
+        SystemTime::now(),
+    );
+    let shell_paths = paths_json(&crate::providers::shell_history::history_paths());
+    let matches = meta("claude_data_dir") == Some(path_text(&canonical(claude_data_dir)))
+        && meta("codex_home") == Some(path_text(&canonical(codex_home)))
+        && meta("shell_history_paths") == Some(shell_paths);
+    (fresh && matches).then_some(path)
+}
+
+fn open_read_only(path: &Path) -> Result<Option<Connection>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    Ok((version == SCHEMA_VERSION).then_some(conn))
+}
+
+/// Whether a heartbeat at `synced_at`, from a daemon syncing every
+/// `interval_ms`, is recent enough at `now` that the daemon is running.
+fn is_fresh(synced_at: Option<&str>, interval_ms: Option<&str>, now: SystemTime) -> bool {
+    let Some(synced_at) = synced_at.and_then(|time| DateTime::parse_from_rfc3339(time).ok()) else {
+        return false;
+    };
+    let interval = interval_ms
+        .and_then(|ms| ms.parse().ok())
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_INTERVAL);
+    let age = DateTime::<Utc>::from(now).signed_duration_since(synced_at);
+    age.to_std()
+        .map_or(true, |age| age <= interval * 3 + HEARTBEAT_GRACE)
+}
+
+fn stamp_text(stamp: &crate::source::InputStamp) -> String {
+    let modified = stamp
+        .modified
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_nanos());
+    format!("{}:{}:{modified}", stamp.files, stamp.bytes)
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn path_text(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+fn paths_json(paths: &[PathBuf]) -> String {
+    let paths: Vec<String> = paths.iter().map(|path| path_text(path)).collect();
+    serde_json::to_string(&paths).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().expect("parent")).expect("mkdir");
+        fs::write(path, contents).expect("write");
+    }
+
+    fn append(path: &Path, contents: &str) {
+        fs::OpenOptions::new()
+            .append(true)
+            .open(path)
+            .expect("open")
+            .write_all(contents.as_bytes())
+            .expect("append");
+    }
+
+    fn column(daemon: &Daemon, sql: &str) -> Vec<String> {
+        daemon
+            .conn
+            .prepare(sql)
+            .expect("prepare")
+            .query_map([], |row| row.get(0))
+            .expect("query")
+            .collect::<rusqlite::Result<_>>()
+            .expect("rows")
+    }
+
+    fn sync_records(daemon: &mut Daemon) -> SyncStats {
+        let mut stats = SyncStats::default();
+        daemon.sync_records(&mut stats).expect("sync");
+        stats
+    }
+
+    #[test]
+    fn appended_records_are_read_from_the_last_complete_one_and_rewrites_from_the_start() {
+        let temp = tempfile::tempdir().expect("temp");
+        let claude = temp.path().join("claude");
+        let transcript = claude.join("projects/-repo/session-1.jsonl");
+        write(
+            &claude.join("history.jsonl"),
+            "{\"display\":\"first\",\"timestamp\":1754402100000,\"project\":\"/repo\"}\n",
+        );
+        write(
+            &transcript,
+            concat!(
+                "{\"type\":\"user\",\"content\":\"hi\",\"timestamp\":\"2026-06-01T10:00:00Z\"}\n",
+                "{\"type\":\"assistant\",\"message\":{\"content\":[{\"type\":\"tool_use\",\"id\":\"t1\",\"name\":\"Bash\",\"input\":{\"command\":\"ls\"}}]},\"timestamp\":\"2026-06-01T10:00:01Z\"}\n",
+                // Still being written: left for the next sync.
+                "{\"type\":\"user\",\"content\":\"par"
+            ),
+        );
+        let mut daemon = Daemon::open_at(
+            &temp.path().join("daemon.sqlite"),
+            claude.clone(),
+            temp.path().join("codex"),
+            Vec::new(),
+        )
+        .expect("open");
+
+        let first = sync_records(&mut daemon);
+        assert_eq!(first.parsed_files, 2);
+        assert_eq!(first.parsed_records, 3);
+        assert_eq!(column(&daemon, "SELECT command FROM tool_calls"), ["ls"]);
+
+        let unchanged = sync_records(&mut daemon);
+        assert_eq!(unchanged.unchanged_files, 2);
+        assert_eq!(unchanged.parsed_records, 0);
+
+        append(
+            &transcript,
+            "tial\",\"timestamp\":\"2026-06-01T10:00:02Z\"}\n",
+        );
+        append(
+            &claude.join("history.jsonl"),
+            "{\"display\":\"second\",\"timestamp\":1754402200000,\"project\":\"/repo\"}\n",
+        );
+        let appended = sync_records(&mut daemon);
+        assert_eq!(appended.appended_files, 2);
+        assert_eq!(appended.parsed_records, 2);
+        assert_eq!(
+            column(
+                &daemon,
+                "SELECT content FROM transcripts ORDER BY record_index"
+            ),
+            ["hi", "", "partial"]
+        );
+        assert_eq!(
+            column(&daemon, "SELECT display FROM history ORDER BY rowid"),
+            ["first", "second"]
+        );
+
+        write(
+            &claude.join("history.jsonl"),
+            "{\"display\":\"rewritten\",\"timestamp\":1754402300000,\"project\":\"/repo\"}\n",
+        );
+        fs::remove_file(&transcript).expect("remove");
+        let rewritten = sync_records(&mut daemon);
+        assert_eq!(rewritten.appended_files, 0);
+        assert_eq!(rewritten.parsed_records, 1);
+        assert_eq!(rewritten.pruned_files, 1);
+        assert_eq!(
+            column(&daemon, "SELECT display FROM history"),
+            ["rewritten"]
+        );
+        assert!(column(&daemon, "SELECT content FROM transcripts").is_empty());
+        assert!(column(&daemon, "SELECT command FROM tool_calls").is_empty());
+    }
+
+    #[test]
+    fn a_file_removed_during_a_sync_is_skipped_and_pruned() {
+        let temp = tempfile::tempdir().expect("temp");
+        let claude = temp.path().join("claude");
+        let kept = claude.join("projects/-repo/session-1.jsonl");
+        let removed = claude.join("projects/-repo/session-2.jsonl");
+        let record =
+            "{\"type\":\"user\",\"content\":\"hi\",\"timestamp\":\"2026-06-01T10:00:00Z\"}\n";
+        write(&kept, record);
+        write(&removed, record);
+        let mut daemon = Daemon::open_at(
+            &temp.path().join("daemon.sqlite"),
+            claude.clone(),
+            temp.path().join("codex"),
+            Vec::new(),
+        )
+        .expect("open");
+        assert_eq!(sync_records(&mut daemon).parsed_files, 2);
+
+        // Removed between discovering the transcripts and reading them.
+        let config = ccql::Config::new_with_codex_data_dir(claude, temp.path().join("codex"))
+            .expect("config");
+        let transcripts = discover_transcript_files(&config);
+        fs::remove_file(&removed).expect("remove");
+        let mut stats = SyncStats::default();
+        daemon
+            .sync_discovered(&transcripts, &mut stats)
+            .expect("sync");
+        assert_eq!(stats.skipped_files, 1);
+        assert_eq!(stats.pruned_files, 1);
+        assert_eq!(stats.unchanged_files, 1);
+        assert_eq!(stats.parsed_records, 0);
+        assert_eq!(
+            column(&daemon, "SELECT source_path FROM transcripts"),
+            [path_text(&kept)]
+        );
+        assert_eq!(
+            column(&daemon, "SELECT path FROM daemon_files"),
+            [path_text(&kept)]
+        );
+    }
+
+    #[test]
+    fn heartbeat_is_fresh_for_three_intervals_and_the_grace_period() {
+        let now = SystemTime::now();
+        let at = |seconds_ago: u64| {
+            DateTime::<Utc>::from(now - Duration::from_secs(seconds_ago))
+                .to_rfc3339_opts(SecondsFormat::Millis, true)
+        };
+        assert!(is_fresh(Some(&at(15)), Some("2000"), now));
+        assert!(!is_fresh(Some(&at(17)), Some("2000"), now));
+        assert!(is_fresh(Some(&at(60)), Some("20000"), now));
+        assert!(!is_fresh(None, Some("2000"), now));
+    }
+}
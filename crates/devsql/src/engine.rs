@@ -1,24 +1,270 @@
 //! Unified query engine that combines ccql and vcsql data
 
+use crate::profile::{LoadStep, PlanStep, Profile};
 use crate::{Error, Result};
 use ccql::datasources::transcript::{
-    discover_transcript_files, flattened_usage_fields, SessionAggregate,
+    discover_transcript_files, flattened_usage_fields, SessionAggregate, TranscriptFile,
 };
 use chrono::DateTime;
-use rusqlite::{params, Connection};
+use futures::Stream;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, Connection, OpenFlags, Statement};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Instant;
+use vcsql::sql::{AttachedTable, Attachment};
+use vcsql::TimeWindow;
+
+/// Values bound to a query's parameters.
+///
+/// Positional values fill `?` and `?NNN` placeholders in order; named values
+/// fill `:name`, `@name`, and `$name` placeholders, keyed with or without the
+/// prefix. Strings, numbers, booleans, and null bind as the matching SQLite
+/// type; arrays and objects bind as their JSON text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum QueryParams {
+    #[default]
+    None,
+    Positional(Vec<Value>),
+    Named(serde_json::Map<String, Value>),
+}
+
+impl QueryParams {
+    /// Read parameters from JSON: an array is positional, an object is named,
+    /// and null means no parameters.
+    pub fn from_json(value: Value) -> Result<Self> {
+        match value {
+            Value::Null => Ok(Self::None),
+            Value::Array(values) => Ok(Self::Positional(values)),
+            Value::Object(values) => Ok(Self::Named(values)),
+            other => Err(Error::Query(format!(
+                "params must be a JSON array or object, got {other}"
+            ))),
+        }
+    }
+
+    /// Add a named value, turning `None` into `Named`.
+    pub fn with(self, name: &str, value: impl Into<Value>) -> Self {
+        let mut values = match self {
+            Self::Named(values) => values,
+            _ => serde_json::Map::new(),
+        };
+        values.insert(name.to_string(), value.into());
+        Self::Named(values)
+    }
+
+    fn bind(&self, stmt: &mut Statement<'_>) -> Result<()> {
+        let count = stmt.parameter_count();
+        match self {
+            Self::None if count == 0 => Ok(()),
+            Self::None => Err(Error::Query(format!(
+                "query has {count} parameter(s) but no params were given"
+            ))),
+            Self::Positional(values) => {
+                if values.len() != count {
+                    return Err(Error::Query(format!(
+                        "query has {count} parameter(s) but {} positional value(s) were given",
+                        values.len()
+                    )));
+                }
+                for (index, value) in values.iter().enumerate() {
+                    stmt.raw_bind_parameter(index + 1, sql_value(value))?;
+                }
+                Ok(())
+            }
+            Self::Named(values) => {
+                for index in 1..=count {
+                    let Some(name) = stmt.parameter_name(index) else {
+                        return Err(Error::Query(format!(
+                            "parameter {index} is positional but params are named"
+                        )));
+                    };
+                    let value = values
+                        .get(name)
+                        .or_else(|| values.get(&name[1..]))
+                        .ok_or_else(|| {
+                            Error::Query(format!("missing value for parameter {name}"))
+                        })?;
+                    stmt.raw_bind_parameter(index, sql_value(value))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn sql_value(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(i64::from(*b)),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => n.as_f64().map(SqlValue::Real).unwrap_or(SqlValue::Null),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+/// Escape `%`, `_`, and `\` in `term` and wrap it in `%` so it matches as a
+/// substring in `LIKE ... ESCAPE '\'`.
+pub fn like_contains(term: &str) -> String {
+    let mut pattern = String::with_capacity(term.len() + 2);
+    pattern.push('%');
+    for c in term.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+/// Build an FTS5 query matching rows that contain any of `terms`. Each term
+/// is quoted as a phrase, so FTS5 operators and punctuation in it are
+/// matched as text rather than parsed as query syntax.
+pub fn fts_any(terms: &[String]) -> String {
+    terms
+        .iter()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
+/// Full-text indexes built over loaded tables:
+/// (index, content table, indexed column, tokenizer).
+///
+/// `codex_messages_fts` is not listed: it persists in the Codex index cache
+/// and is kept current by that cache's triggers.
+pub(crate) const FTS_TABLES: &[(&str, &str, &str, &str)] = &[
+    (
+        "transcripts_fts",
+        "transcripts",
+        "content",
+        "porter unicode61",
+    ),
+    ("history_fts", "history", "display", "porter unicode61"),
+    ("shell_history_fts", "shell_history", "command", "unicode61"),
+    ("source_lines_fts", "source_lines", "content", "trigram"),
+];
+
+// --- Table definitions ---
+//
+// Each loader creates its table from one of these, and `crate::schema`
+// builds the table registry by running the same statements.
+
+pub(crate) const HISTORY_TABLE: &str = "CREATE TABLE IF NOT EXISTS history (
+    rowid INTEGER PRIMARY KEY,
+    display TEXT,
+    timestamp TEXT,
+    project TEXT
+)";
+
+pub(crate) const TOOL_CALLS_TABLE: &str = "CREATE TABLE IF NOT EXISTS tool_calls (
+    rowid INTEGER PRIMARY KEY,
+    tool_name TEXT,
+    input_json TEXT,
+    target TEXT,
+    source_id TEXT,
+    command TEXT,
+    session_id TEXT,
+    parent_session_id TEXT,
+    agent_id TEXT,
+    agent_role TEXT,
+    originator TEXT,
+    cwd TEXT,
+    source_path TEXT,
+    _project TEXT,
+    timestamp TEXT
+)";
+
+pub(crate) const SESSIONS_TABLE: &str = "CREATE TABLE IF NOT EXISTS sessions (
+    session_id TEXT,
+    project TEXT,
+    cwd TEXT,
+    git_branch TEXT,
+    version TEXT,
+    title TEXT,
+    first_timestamp TEXT,
+    last_timestamp TEXT,
+    user_message_count INTEGER,
+    assistant_message_count INTEGER,
+    subagent_count INTEGER,
+    total_input_tokens INTEGER,
+    total_output_tokens INTEGER,
+    total_cache_read_input_tokens INTEGER,
+    total_cache_creation_input_tokens INTEGER,
+    pr_url TEXT,
+    pr_number INTEGER
+)";
+
+pub(crate) const JHISTORY_TABLE: &str = "CREATE TABLE IF NOT EXISTS jhistory (
+    rowid INTEGER PRIMARY KEY,
+    session_id TEXT,
+    ts INTEGER,
+    text TEXT,
+    display TEXT,
+    timestamp INTEGER
+)";
+
+pub(crate) const CODEX_HISTORY_VIEW: &str =
+    "CREATE VIEW IF NOT EXISTS codex_history AS SELECT * FROM jhistory";
+
+pub(crate) const TODOS_TABLE: &str = "CREATE TABLE IF NOT EXISTS todos (
+    rowid INTEGER PRIMARY KEY,
+    content TEXT,
+    status TEXT
+)";
+
+pub(crate) const COMMAND_EVENTS_TABLE: &str = "CREATE TABLE command_events (
+    source TEXT,
+    channel TEXT,
+    actor TEXT,
+    provenance_quality TEXT,
+    provenance_reason TEXT,
+    source_id TEXT,
+    source_order INTEGER,
+    session_id TEXT,
+    parent_session_id TEXT,
+    agent_id TEXT,
+    agent_role TEXT,
+    originator TEXT,
+    tool_name TEXT,
+    timestamp TEXT,
+    duration_ms INTEGER,
+    exit_code INTEGER,
+    command TEXT,
+    cwd TEXT,
+    hostname TEXT,
+    source_path TEXT
+)";
+
+/// Rows yielded by [`UnifiedEngine::into_row_stream`].
+pub type RowStream = Pin<Box<dyn Stream<Item = Result<Value>> + Send>>;
+
+/// Rows a streamed query reads ahead of its consumer.
+pub const ROW_STREAM_BUFFER: usize = 256;
 
 /// Unified query engine that loads data from both Claude Code and Git
 pub struct UnifiedEngine {
     conn: Connection,
     claude_data_dir: PathBuf,
     codex_data_dir: PathBuf,
-    git_repo_path: PathBuf,
+    git_repo_paths: Vec<PathBuf>,
+    window: TimeWindow,
     codex_loaded: bool,
+    /// The result of a Codex index sync [`UnifiedEngine::load_tables`] ran
+    /// while other sources loaded, for `load_codex_tables` to use.
+    codex_synced: Option<Result<crate::codex_index::SyncStats>>,
+    /// Tables loaded through [`UnifiedEngine::load_tables`].
+    loaded: HashSet<String>,
+    profile: Option<Profile>,
 }
 
 impl UnifiedEngine {
@@ -33,8 +279,45 @@ impl UnifiedEngine {
         git_repo_path: PathBuf,
         codex_data_dir: PathBuf,
     ) -> Result<Self> {
-        let conn = Connection::open_in_memory()?;
+        Self::with_connection(
+            Connection::open_in_memory()?,
+            claude_data_dir,
+            git_repo_path,
+            codex_data_dir,
+        )
+    }
+
+    /// Open a snapshot written by [`crate::snapshot::export`], read-only.
+    /// Its tables stand in for every source: nothing is loaded from disk,
+    /// and a table the snapshot does not hold is not found.
+    pub fn open_snapshot(path: &Path) -> Result<Self> {
+        // URIs, so `--attach` can open its databases read-only too.
+        let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI;
+        let conn = Connection::open_with_flags(path, flags)
+            .map_err(|e| Error::Query(format!("Failed to open {}: {e}", path.display())))?;
+        if !table_exists(&conn, crate::snapshot::META_TABLE)? {
+            return Err(Error::Query(format!(
+                "{} is not a devsql snapshot",
+                path.display()
+            )));
+        }
+        let mut engine =
+            Self::with_connection(conn, PathBuf::new(), PathBuf::new(), PathBuf::new())?;
+        engine.git_repo_paths.clear();
+        engine.loaded = crate::source::sources()
+            .iter()
+            .flat_map(|source| source.tables())
+            .map(|table| table.name)
+            .collect();
+        Ok(engine)
+    }
 
+    fn with_connection(
+        conn: Connection,
+        claude_data_dir: PathBuf,
+        git_repo_path: PathBuf,
+        codex_data_dir: PathBuf,
+    ) -> Result<Self> {
         // Register custom DATE function that handles both epoch ms and ISO dates
         conn.create_scalar_function(
             "DATE",
@@ -45,16 +328,400 @@ impl UnifiedEngine {
                 Ok(normalize_date(&value))
             },
         )?;
+        vcsql::sql::functions::register(&conn).map_err(|e| Error::Vcsql(e.to_string()))?;
+        // Replaces vcsql's `redact` with one that also masks the configured
+        // patterns.
+        conn.create_scalar_function(
+            "redact",
+            1,
+            rusqlite::functions::FunctionFlags::SQLITE_DETERMINISTIC,
+            |ctx| {
+                Ok(match ctx.get_raw(0) {
+                    rusqlite::types::ValueRef::Text(text) => SqlValue::Text(
+                        crate::config::current().redact(&String::from_utf8_lossy(text)),
+                    ),
+                    value => SqlValue::from(value),
+                })
+            },
+        )?;
 
         Ok(Self {
             conn,
             claude_data_dir,
             codex_data_dir,
-            git_repo_path,
+            git_repo_paths: vec![git_repo_path],
+            window: TimeWindow::default(),
             codex_loaded: false,
+            codex_synced: None,
+            loaded: HashSet::new(),
+            profile: None,
         })
     }
 
+    /// Replace the repositories Git and code tables are loaded from. Rows
+    /// from each repository are told apart by their `repo` column.
+    pub fn set_git_repos(&mut self, repo_paths: Vec<PathBuf>) {
+        self.git_repo_paths = repo_paths;
+    }
+
+    /// Limit the tables loaded from now on to rows inside `window`.
+    ///
+    /// Each loader skips what it can without reading it: the Git walk stops
+    /// at the first commit before `since`, transcript, todo and Codex
+    /// journal files last written well before `since` are not opened, and
+    /// Atuin rows are filtered in its own query. Rows whose time is unknown
+    /// are left out of a bounded window. Code tables describe the working
+    /// tree and are not limited.
+    pub fn set_time_window(&mut self, window: TimeWindow) {
+        self.window = window;
+    }
+
+    /// The window set by [`Self::set_time_window`], for sources to honor.
+    pub fn time_window(&self) -> &TimeWindow {
+        &self.window
+    }
+
+    /// Repositories Git and code tables are loaded from.
+    pub fn repo_paths(&self) -> &[PathBuf] {
+        &self.git_repo_paths
+    }
+
+    /// Directory Claude Code tables are loaded from.
+    pub fn claude_data_dir(&self) -> &Path {
+        &self.claude_data_dir
+    }
+
+    /// Directory Codex tables are loaded from.
+    pub fn codex_data_dir(&self) -> &Path {
+        &self.codex_data_dir
+    }
+
+    /// Record table selection, load costs, and the Codex sync from now on
+    /// (see [`crate::profile`]). Enabling it again keeps what was recorded.
+    pub fn enable_profile(&mut self) {
+        self.profile.get_or_insert_with(Profile::default);
+    }
+
+    /// The profile recorded since [`Self::enable_profile`].
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    /// Mutable access to the profile, for callers adding the query's cost.
+    pub fn profile_mut(&mut self) -> Option<&mut Profile> {
+        self.profile.as_mut()
+    }
+
+    /// Record, when profiling, that `tables` were selected for `reason`.
+    /// Names no source provides are skipped.
+    pub fn note_selected(&mut self, tables: &[String], reason: &str) {
+        let Some(profile) = &mut self.profile else {
+            return;
+        };
+        for source in crate::source::sources() {
+            for table in source.tables() {
+                if tables.contains(&table.name) {
+                    profile.select(&table.name, source.name(), reason);
+                }
+            }
+        }
+    }
+
+    /// Whether `table` was loaded through [`Self::load_tables`] and not
+    /// unloaded since.
+    pub fn is_loaded(&self, table: &str) -> bool {
+        self.loaded.contains(table)
+    }
+
+    /// Load `tables`, and the tables they depend on first, through the
+    /// registered data sources (see [`crate::source`]). Each table is
+    /// loaded at most once per engine; names no source provides are
+    /// ignored, and a table from a source the config file does not enable
+    /// is an error.
+    pub fn load_tables(&mut self, tables: &[&str]) -> Result<()> {
+        let sources = crate::source::sources();
+        let catalog: Vec<(usize, crate::source::SourceTable)> = sources
+            .iter()
+            .enumerate()
+            .flat_map(|(index, source)| source.tables().into_iter().map(move |t| (index, t)))
+            .collect();
+
+        let mut order = Vec::new();
+        let mut visiting = Vec::new();
+        for table in tables {
+            self.plan_load(&catalog, table, &mut visiting, &mut order)?;
+        }
+        let config = crate::config::current();
+        for &entry in &order {
+            let (source, table) = &catalog[entry];
+            if !config.source_enabled(sources[*source].name()) {
+                return Err(Error::Config(format!(
+                    "{} comes from the {} source, which `sources` in {} does not enable",
+                    table.name,
+                    sources[*source].name(),
+                    config.path.display()
+                )));
+            }
+        }
+        if let Some(profile) = &mut self.profile {
+            for &entry in &order {
+                let table = &catalog[entry].1;
+                for dependency in &table.depends_on {
+                    if let Some((source, _)) = catalog.iter().find(|(_, t)| &t.name == dependency) {
+                        let reason = format!("required by {}", table.name);
+                        profile.select(dependency, sources[*source].name(), &reason);
+                    }
+                }
+            }
+        }
+
+        // A running daemon holds some tables already parsed; those are
+        // copied from its database rather than loaded from their sources.
+        let daemon = if self.window.is_unbounded()
+            && order
+                .iter()
+                .any(|&entry| crate::daemon::holds(&catalog[entry].1.name))
+        {
+            crate::daemon::serving(&self.claude_data_dir, &self.codex_data_dir)
+        } else {
+            None
+        };
+
+        // The Codex index syncs into its own database, so when a Codex table
+        // is planned it syncs on another thread while the sources before it
+        // load.
+        let codex_tables: Vec<&str> = codex_views(&self.window)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        let sync_codex = !self.codex_loaded
+            && order
+                .iter()
+                .any(|&entry| codex_tables.contains(&catalog[entry].1.name.as_str()));
+        let codex_home = self.codex_data_dir.clone();
+        let since = self.window.since;
+        let loaded = std::thread::scope(|scope| -> Result<()> {
+            let mut codex_sync = sync_codex.then(|| {
+                scope.spawn(move || {
+                    crate::codex_index::CodexIndex::open(&codex_home)?.sync_since(since)
+                })
+            });
+
+            // Hand each source its tables in one call, without reordering
+            // across a dependency on another source.
+            let mut start = 0;
+            while start < order.len() {
+                let source = catalog[order[start]].0;
+                let end = order[start..]
+                    .iter()
+                    .position(|&entry| catalog[entry].0 != source)
+                    .map_or(order.len(), |offset| start + offset);
+                let (held, names): (
+                    Vec<&crate::source::SourceTable>,
+                    Vec<&crate::source::SourceTable>,
+                ) = order[start..end]
+                    .iter()
+                    .map(|&entry| &catalog[entry].1)
+                    .partition(|table| daemon.is_some() && crate::daemon::holds(&table.name));
+                if let Some(path) = daemon.as_deref().filter(|_| !held.is_empty()) {
+                    let started = Instant::now();
+                    let changes = self.conn.total_changes();
+                    self.copy_from_daemon(path, &held)?;
+                    if let Some(profile) = &mut self.profile {
+                        profile.loads.push(LoadStep {
+                            source: "DAEMON".to_string(),
+                            tables: held.iter().map(|table| table.name.clone()).collect(),
+                            wall_ms: crate::profile::millis(started.elapsed()),
+                            rows_inserted: self.conn.total_changes() - changes,
+                            files_read: 0,
+                        });
+                    }
+                    self.loaded
+                        .extend(held.iter().map(|table| table.name.clone()));
+                }
+                if let Some(sync) = codex_sync.take_if(|_| {
+                    names
+                        .iter()
+                        .any(|table| codex_tables.contains(&table.name.as_str()))
+                }) {
+                    self.codex_synced = Some(
+                        sync.join()
+                            .unwrap_or_else(|panic| std::panic::resume_unwind(panic)),
+                    );
+                }
+                let names: Vec<&str> = names.iter().map(|table| table.name.as_str()).collect();
+                if names.is_empty() {
+                    start = end;
+                    continue;
+                }
+                let started = Instant::now();
+                let changes = self.conn.total_changes();
+                let files_read = crate::profile::files_read();
+                sources[source]
+                    .load(self, &names)
+                    .map_err(|error| Error::Load {
+                        category: sources[source].name().to_string(),
+                        error: Box::new(error),
+                    })?;
+                if let Some(profile) = &mut self.profile {
+                    profile.loads.push(LoadStep {
+                        source: sources[source].name().to_string(),
+                        tables: names.iter().map(|name| name.to_string()).collect(),
+                        wall_ms: crate::profile::millis(started.elapsed()),
+                        rows_inserted: self.conn.total_changes() - changes,
+                        files_read: crate::profile::files_read() - files_read,
+                    });
+                }
+                self.loaded
+                    .extend(names.iter().map(|name| name.to_string()));
+                start = end;
+            }
+
+            Ok(())
+        });
+        self.codex_synced = None;
+        loaded
+    }
+
+    /// Create `tables` from their schemas and fill them with the rows of
+    /// the daemon's database at `path` (see [`crate::daemon`]), in the order
+    /// their files hold them.
+    fn copy_from_daemon(
+        &mut self,
+        path: &Path,
+        tables: &[&crate::source::SourceTable],
+    ) -> Result<()> {
+        self.conn.execute(
+            "ATTACH DATABASE ?1 AS daemon",
+            [path.to_string_lossy().as_ref()],
+        )?;
+        let copied = (|| -> Result<()> {
+            let tx = self.conn.transaction()?;
+            for table in tables {
+                let order = crate::daemon::HELD_TABLES
+                    .iter()
+                    .find(|(name, _)| *name == table.name)
+                    .map_or("rowid", |(_, order)| *order);
+                drop_table_or_view(&tx, &table.name)?;
+                tx.execute_batch(&table.schema)?;
+                let held: HashSet<String> = tx
+                    .prepare("SELECT name FROM pragma_table_info(?1, 'daemon')")?
+                    .query_map([&table.name], |row| row.get(0))?
+                    .collect::<rusqlite::Result<_>>()?;
+                let columns: Vec<String> = tx
+                    .prepare("SELECT name FROM pragma_table_info(?1, 'main')")?
+                    .query_map([&table.name], |row| row.get::<_, String>(0))?
+                    .filter(|column| {
+                        column
+                            .as_ref()
+                            .map_or(true, |column| column != "rowid" && held.contains(column))
+                    })
+                    .map(|column| column.map(|column| format!("\"{column}\"")))
+                    .collect::<rusqlite::Result<_>>()?;
+                tx.execute_batch(&format!(
+                    "INSERT INTO main.\"{name}\" ({columns}) SELECT {columns} FROM daemon.\"{name}\" ORDER BY {order}",
+                    name = table.name,
+                    columns = columns.join(", "),
+                ))?;
+            }
+            tx.commit()?;
+            Ok(())
+        })();
+        self.conn.execute_batch("DETACH DATABASE daemon")?;
+        copied
+    }
+
+    /// Append `table`'s dependencies, then `table`, to `order` as indexes
+    /// into `catalog`, skipping tables already loaded or planned.
+    fn plan_load(
+        &self,
+        catalog: &[(usize, crate::source::SourceTable)],
+        table: &str,
+        visiting: &mut Vec<String>,
+        order: &mut Vec<usize>,
+    ) -> Result<()> {
+        let Some(entry) = catalog.iter().position(|(_, t)| t.name == table) else {
+            return Ok(());
+        };
+        if self.loaded.contains(table) || order.contains(&entry) {
+            return Ok(());
+        }
+        if visiting.iter().any(|name| name == table) {
+            return Err(Error::Query(format!(
+                "tables depend on each other in a cycle: {} -> {table}",
+                visiting.join(" -> ")
+            )));
+        }
+        visiting.push(table.to_string());
+        for dependency in &catalog[entry].1.depends_on {
+            self.plan_load(catalog, dependency, visiting, order)?;
+        }
+        visiting.pop();
+        order.push(entry);
+        Ok(())
+    }
+
+    /// Drop `tables`, and every loaded table that depends on them, so the
+    /// next [`Self::load_tables`] reads them from their sources again.
+    /// Returns the names dropped; tables that were not loaded are skipped.
+    ///
+    /// The Codex tables are views over one attached index, so dropping any
+    /// of them drops them all.
+    pub fn unload_tables(&mut self, tables: &[&str]) -> Result<Vec<String>> {
+        let catalog: Vec<crate::source::SourceTable> = crate::source::sources()
+            .iter()
+            .flat_map(|source| source.tables())
+            .collect();
+        let codex: Vec<&str> = codex_views(&self.window)
+            .into_iter()
+            .map(|(name, _)| name)
+            .chain(["codex_messages_fts"])
+            .collect();
+
+        let mut unload: Vec<String> = tables
+            .iter()
+            .filter(|table| self.loaded.contains(**table))
+            .map(|table| table.to_string())
+            .collect();
+        let mut next = 0;
+        while next < unload.len() {
+            let table = unload[next].clone();
+            let mut dependents: Vec<&str> = catalog
+                .iter()
+                .filter(|t| t.depends_on.contains(&table))
+                .map(|t| t.name.as_str())
+                .collect();
+            if codex.contains(&table.as_str()) {
+                dependents.extend(codex.iter().copied());
+            }
+            for dependent in dependents {
+                if self.loaded.contains(dependent) && !unload.iter().any(|t| t == dependent) {
+                    unload.push(dependent.to_string());
+                }
+            }
+            next += 1;
+        }
+
+        // Dependents were found last; drop them first.
+        for table in unload.iter().rev() {
+            if !codex.contains(&table.as_str()) {
+                drop_table_or_view(&self.conn, table)?;
+            }
+        }
+        if self.codex_loaded && unload.iter().any(|t| codex.contains(&t.as_str())) {
+            for (view, _) in codex_views(&self.window) {
+                self.conn
+                    .execute_batch(&format!("DROP VIEW IF EXISTS temp.{view}"))?;
+            }
+            self.conn.execute_batch("DETACH DATABASE codex_index")?;
+            self.codex_loaded = false;
+        }
+        for table in &unload {
+            self.loaded.remove(table);
+        }
+        Ok(unload)
+    }
+
     /// Load Claude Code tables needed for the query
     pub fn load_claude_tables(&mut self, tables: &[&str]) -> Result<()> {
         for table in tables {
@@ -72,39 +739,120 @@ impl UnifiedEngine {
                 | "codex_tool_executions"
                 | "codex_compactions"
                 | "codex_ingest_errors" => self.load_codex_tables()?,
+                "transcripts_fts" | "history_fts" | "codex_messages_fts" => {
+                    self.load_fts_tables(&[table])?
+                }
                 _ => {}
             }
         }
         Ok(())
     }
 
-    /// Load Git tables needed for the query
+    /// Load Git tables needed for the query.
+    ///
+    /// Every Git table is populated by vcsql's provider with the schema from
+    /// `vcsql::TABLES`, so the same SQL returns the same rows in both CLIs.
+    /// `commits`, `diffs`, and `diff_files` are read from the persistent Git
+    /// index, so only commits new since the last query are diffed and a
+    /// constraint on the commit id reads only that commit's rows.
     pub fn load_git_tables(&mut self, tables: &[&str]) -> Result<()> {
-        for table in tables {
-            match *table {
-                "commits" => self.load_commits()?,
-                "diffs" => self.load_diffs()?,
-                "diff_files" => self.load_diff_files()?,
-                "branches" => self.load_branches()?,
-                _ => {}
-            }
+        let (indexed, direct): (Vec<&str>, Vec<&str>) = tables.iter().partition(|table| {
+            crate::git_index::INDEXED_TABLES
+                .iter()
+                .any(|(name, _)| name == *table)
+        });
+        self.load_indexed_git_tables(&indexed)?;
+        for table in direct {
+            self.load_vcsql_table(table)?;
         }
         Ok(())
     }
 
     /// Load code analysis tables needed for the query
+    ///
+    /// `source_files` and `source_lines` are registered as virtual tables that
+    /// read files only when scanned, and only the files a `path` or
+    /// `file_path` constraint allows; the rest are loaded up front.
     pub fn load_code_tables(&mut self, tables: &[&str]) -> Result<()> {
-        crate::providers::load_all_code_tables(&self.conn, &self.git_repo_path, tables)
+        let (lazy, eager): (Vec<&str>, Vec<&str>) = tables
+            .iter()
+            .partition(|table| crate::providers::LAZY_TABLES.contains(table));
+        for table in lazy {
+            if table_exists(&self.conn, table)? {
+                continue;
+            }
+            if let Some(source) = crate::providers::lazy_table(table, &self.git_repo_paths) {
+                crate::lazy::register(&self.conn, table, source)?;
+            }
+        }
+        for repo_path in &self.git_repo_paths {
+            crate::providers::load_all_code_tables(&self.conn, repo_path, &eager)?;
+        }
+        if tables.contains(&"source_lines_fts") {
+            self.load_fts_tables(&["source_lines_fts"])?;
+        }
+        Ok(())
+    }
+
+    /// Build FTS5 indexes (`transcripts_fts`, `history_fts`,
+    /// `shell_history_fts`, `source_lines_fts`, `codex_messages_fts`),
+    /// loading each content table first if it is not loaded yet.
+    ///
+    /// Each index is an external-content table: its rowid is the content
+    /// table's rowid, so join back on `rowid` and rank with `bm25(<index>)`.
+    /// Names that are not full-text indexes are ignored.
+    pub fn load_fts_tables(&mut self, tables: &[&str]) -> Result<()> {
+        for table in tables {
+            if *table == "codex_messages_fts" {
+                self.load_codex_tables()?;
+                continue;
+            }
+            let Some(&(index, content, column, tokenize)) =
+                FTS_TABLES.iter().find(|(name, ..)| name == table)
+            else {
+                continue;
+            };
+            // Index lookups go by rowid, so the content is copied in rather
+            // than scanned lazily.
+            if crate::lazy::is_lazy(&self.conn, content)? {
+                self.conn.execute_batch(&format!("DROP TABLE {content}"))?;
+            }
+            if !table_exists(&self.conn, content)? {
+                match content {
+                    "shell_history" => self.load_shell_history()?,
+                    "source_lines" => {
+                        for repo_path in &self.git_repo_paths {
+                            crate::providers::load_all_code_tables(
+                                &self.conn,
+                                repo_path,
+                                &[content],
+                            )?;
+                        }
+                    }
+                    "transcripts" => self.materialize_transcripts()?,
+                    _ => self.load_claude_tables(&[content])?,
+                }
+            }
+            self.conn.execute_batch(&format!(
+                "DROP TABLE IF EXISTS {index};
+                 {};
+                 INSERT INTO {index} ({index}) VALUES ('rebuild');",
+                create_fts_sql(index, content, column, tokenize)
+            ))?;
+        }
+        Ok(())
     }
 
     /// Load the normalized Atuin, zsh, and bash history table.
     pub fn load_shell_history(&mut self) -> Result<()> {
-        crate::providers::shell_history::load(&mut self.conn)
+        crate::providers::shell_history::load(&mut self.conn, &self.window)
     }
 
     /// Load normalized shell and agent-issued command events with source-native provenance.
     pub fn load_command_events(&mut self) -> Result<()> {
-        self.load_shell_history()?;
+        if !table_exists(&self.conn, "shell_history")? {
+            self.load_shell_history()?;
+        }
         if !table_exists(&self.conn, "tool_calls")? {
             self.load_tool_calls()?;
         }
@@ -112,30 +860,9 @@ impl UnifiedEngine {
             self.load_codex_tables()?;
         }
 
-        self.conn.execute_batch(
+        self.conn.execute_batch(&format!(
             "DROP TABLE IF EXISTS command_events;
-             CREATE TABLE command_events (
-                source TEXT,
-                channel TEXT,
-                actor TEXT,
-                provenance_quality TEXT,
-                provenance_reason TEXT,
-                source_id TEXT,
-                source_order INTEGER,
-                session_id TEXT,
-                parent_session_id TEXT,
-                agent_id TEXT,
-                agent_role TEXT,
-                originator TEXT,
-                tool_name TEXT,
-                timestamp TEXT,
-                duration_ms INTEGER,
-                exit_code INTEGER,
-                command TEXT,
-                cwd TEXT,
-                hostname TEXT,
-                source_path TEXT
-             );
+             {COMMAND_EVENTS_TABLE};
              INSERT INTO command_events
              SELECT source, 'shell', 'unknown', 'unattributed',
                     'unattributed_shell_history', source_id, source_order,
@@ -160,8 +887,8 @@ impl UnifiedEngine {
              LEFT JOIN codex_threads AS thread
                ON thread.thread_id = execution.thread_id
              WHERE execution.tool_name IN ('exec_command', 'shell')
-               AND execution.cmd IS NOT NULL;",
-        )?;
+               AND execution.cmd IS NOT NULL;"
+        ))?;
 
         Ok(())
     }
@@ -172,18 +899,66 @@ impl UnifiedEngine {
             return Ok(());
         }
         let wl = crate::worklog::Worklog::open()?;
-        wl.materialize_into(&self.conn, tables)
+        wl.materialize_into(&self.conn, tables, &self.window)
     }
 
-    /// Access the underlying SQLite connection. Used by `gather` to
-    /// materialize section results as `gather_<section>` tables in the
-    /// connection used for rendering.
-    pub(crate) fn conn(&self) -> &Connection {
+    /// The SQLite connection queries run on. Data sources create their
+    /// tables here, and `gather` materializes section results as
+    /// `gather_<section>` tables in it.
+    pub fn conn(&self) -> &Connection {
         &self.conn
     }
 
+    /// Mutable access to the connection, for sources that load in a
+    /// transaction.
+    pub fn conn_mut(&mut self) -> &mut Connection {
+        &mut self.conn
+    }
+
+    /// Register `sql` as a temporary view named `name`, so later queries
+    /// can read it like a table. `name` must be a plain identifier.
+    pub fn create_view(&self, name: &str, sql: &str) -> Result<()> {
+        self.conn
+            .execute_batch(&format!("CREATE TEMP VIEW \"{name}\" AS {sql}"))?;
+        Ok(())
+    }
+
+    /// Load an external CSV, JSON, JSON Lines, or SQLite file as ad-hoc
+    /// tables (see [`vcsql::sql::attach`]). The name may not be one a data
+    /// source provides.
+    pub fn attach(&mut self, attachment: &Attachment) -> Result<Vec<AttachedTable>> {
+        if crate::source::provides(&attachment.name) {
+            return Err(Error::Query(format!(
+                "cannot attach as `{}`: it is a devsql table",
+                attachment.name
+            )));
+        }
+        vcsql::sql::attach(&self.conn, attachment).map_err(|e| Error::Vcsql(e.to_string()))
+    }
+
     /// Execute a SQL query and return results as JSON values
     pub fn query(&self, sql: &str) -> Result<Vec<Value>> {
+        self.query_with_params(sql, &QueryParams::None)
+    }
+
+    /// Execute a SQL query with bound parameters and return results as JSON
+    /// values. Every placeholder must have a value.
+    pub fn query_with_params(&self, sql: &str, params: &QueryParams) -> Result<Vec<Value>> {
+        let mut results = Vec::new();
+        self.query_each(sql, params, |row| {
+            results.push(row);
+            Ok(())
+        })?;
+        Ok(results)
+    }
+
+    /// Execute a SQL query with bound parameters, handing each row to
+    /// `visit` as SQLite produces it instead of collecting the result set.
+    /// An error from `visit` stops the query and is returned.
+    pub fn query_each<F>(&self, sql: &str, params: &QueryParams, mut visit: F) -> Result<()>
+    where
+        F: FnMut(Value) -> Result<()>,
+    {
         let mut stmt = self.conn.prepare(sql)?;
         let column_names: Vec<String> = stmt
             .column_names()
@@ -191,7 +966,9 @@ impl UnifiedEngine {
             .map(|s| s.to_string())
             .collect();
 
-        let rows = stmt.query_map([], |row| {
+        params.bind(&mut stmt)?;
+        let mut rows = stmt.raw_query();
+        while let Some(row) = rows.next()? {
             let mut obj = serde_json::Map::new();
             for (i, name) in column_names.iter().enumerate() {
                 // Try different types in order
@@ -208,43 +985,86 @@ impl UnifiedEngine {
                 };
                 obj.insert(name.clone(), value);
             }
-            Ok(Value::Object(obj))
-        })?;
+            visit(Value::Object(obj))?;
+        }
+        Ok(())
+    }
 
-        let results: Vec<Value> = rows.filter_map(|r| r.ok()).collect();
-        Ok(results)
+    /// The names of the columns `sql` returns, without running it.
+    pub fn column_names(&self, sql: &str) -> Result<Vec<String>> {
+        let stmt = self.conn.prepare(sql)?;
+        Ok(stmt
+            .column_names()
+            .into_iter()
+            .map(|s| s.to_string())
+            .collect())
+    }
+
+    /// SQLite's `EXPLAIN QUERY PLAN` for `sql`, with `params` bound as
+    /// [`Self::query_with_params`] would bind them. The query is not run.
+    pub fn explain(&self, sql: &str, params: &QueryParams) -> Result<Vec<PlanStep>> {
+        let mut stmt = self.conn.prepare(&format!("EXPLAIN QUERY PLAN {sql}"))?;
+        params.bind(&mut stmt)?;
+        let mut rows = stmt.raw_query();
+        let mut plan = Vec::new();
+        while let Some(row) = rows.next()? {
+            plan.push(PlanStep {
+                id: row.get(0)?,
+                parent: row.get(1)?,
+                detail: row.get(3)?,
+            });
+        }
+        Ok(plan)
+    }
+
+    /// Execute a SQL query on a worker thread that owns this engine and
+    /// stream its rows. At most [`ROW_STREAM_BUFFER`] rows are held at once,
+    /// so memory stays flat however large the result is. A query error ends
+    /// the stream with an `Err`; dropping the stream stops the query.
+    pub fn into_row_stream(self, sql: String, params: QueryParams) -> RowStream {
+        let (sender, receiver) = tokio::sync::mpsc::channel(ROW_STREAM_BUFFER);
+        std::thread::spawn(move || {
+            let result = self.query_each(&sql, &params, |row| {
+                sender
+                    .blocking_send(Ok(row))
+                    .map_err(|_| Error::Query("row stream closed".to_string()))
+            });
+            if let Err(error) = result {
+                // The receiver is gone if the stream was dropped; nothing to report.
+                let _ = sender.blocking_send(Err(error));
+            }
+        });
+        Box::pin(futures::stream::unfold(
+            receiver,
+            |mut receiver| async move { receiver.recv().await.map(|row| (row, receiver)) },
+        ))
     }
 
     // --- Table loaders ---
 
     fn load_history(&mut self) -> Result<()> {
         // Create history table
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS history (
-                rowid INTEGER PRIMARY KEY,
-                display TEXT,
-                timestamp TEXT,
-                project TEXT
-            )",
-            [],
-        )?;
+        self.conn.execute(HISTORY_TABLE, [])?;
 
         // Load from ccql's history.jsonl
         let history_path = self.claude_data_dir.join("history.jsonl");
         if history_path.exists() {
+            crate::profile::file_read();
             let content = std::fs::read_to_string(&history_path)?;
             for line in content.lines() {
                 if let Ok(entry) = serde_json::from_str::<Value>(line) {
-                    let display = entry.get("display").and_then(|v| v.as_str()).unwrap_or("");
-                    let timestamp = entry
-                        .get("timestamp")
-                        .map(|v| v.to_string())
-                        .unwrap_or_default();
-                    let project = entry.get("project").and_then(|v| v.as_str()).unwrap_or("");
-
+                    if !self.window.is_unbounded()
+                        && !entry
+                            .get("timestamp")
+                            .and_then(json_number_as_i64)
+                            .and_then(DateTime::from_timestamp_millis)
+                            .is_some_and(|time| self.window.contains(time))
+                    {
+                        continue;
+                    }
                     self.conn.execute(
-                        "INSERT INTO history (display, timestamp, project) VALUES (?1, ?2, ?3)",
-                        params![display, timestamp, project],
+                        &insert_sql("history", &HISTORY_COLUMNS),
+                        rusqlite::params_from_iter(history_values(&entry)),
                     )?;
                 }
             }
@@ -264,94 +1084,48 @@ impl UnifiedEngine {
     }
 
     fn load_transcripts(&mut self) -> Result<()> {
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS transcripts (
-                rowid INTEGER PRIMARY KEY,
-                type TEXT,
-                content TEXT,
-                tool_name TEXT,
-                session_id TEXT,
-                _source_file TEXT,
-                _session_id TEXT,
-                _project TEXT,
-                _agent_id TEXT,
-                timestamp TEXT,
-                model TEXT,
-                usage_input_tokens INTEGER,
-                usage_output_tokens INTEGER,
-                usage_cache_read_input_tokens INTEGER,
-                usage_cache_creation_input_tokens INTEGER,
-                usage_ephemeral_5m_input_tokens INTEGER,
-                usage_ephemeral_1h_input_tokens INTEGER,
-                usage_service_tier TEXT
-            )",
-            [],
-        )?;
+        if table_exists(&self.conn, "transcripts")? {
+            return Ok(());
+        }
+        let Some(config) = self.ccql_config() else {
+            self.conn.execute(&create_transcripts_sql(), [])?;
+            return Ok(());
+        };
+        crate::lazy::register(
+            &self.conn,
+            "transcripts",
+            Arc::new(LazyTranscripts {
+                config,
+                window: self.window,
+                columns: crate::lazy::columns(TRANSCRIPT_COLUMNS),
+            }),
+        )
+    }
 
+    /// Copy every transcript row into a real `transcripts` table, which
+    /// `transcripts_fts` needs for its rowid lookups.
+    fn materialize_transcripts(&mut self) -> Result<()> {
+        self.conn.execute(&create_transcripts_sql(), [])?;
         let Some(config) = self.ccql_config() else {
             return Ok(());
         };
 
         let tx = self.conn.transaction()?;
         {
-            let mut stmt = tx.prepare(
-                "INSERT INTO transcripts (type, content, tool_name, session_id,
-                    _source_file, _session_id, _project, _agent_id, timestamp,
-                    model, usage_input_tokens, usage_output_tokens,
-                    usage_cache_read_input_tokens, usage_cache_creation_input_tokens,
-                    usage_ephemeral_5m_input_tokens, usage_ephemeral_1h_input_tokens,
-                    usage_service_tier)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+            let names: Vec<&str> = TRANSCRIPT_COLUMNS.iter().map(|(name, _)| *name).collect();
+            let mut stmt = tx.prepare(&insert_sql("transcripts", &names))?;
+
+            let files = transcript_files(&config, &self.window);
+            crate::parallel::for_each_ordered(
+                &files,
+                |file| transcript_rows(file, &self.window),
+                |_, rows| {
+                    for (_, values) in rows {
+                        stmt.execute(rusqlite::params_from_iter(values))?;
+                    }
+                    Ok::<_, Error>(())
+                },
             )?;
-
-            for file in discover_transcript_files(&config) {
-                let content = match std::fs::read_to_string(&file.path) {
-                    Ok(c) => c,
-                    Err(_) => continue,
-                };
-
-                for line in content.lines() {
-                    let Ok(entry) = serde_json::from_str::<Value>(line) else {
-                        continue;
-                    };
-
-                    let msg_type = entry.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                    let msg_content = entry
-                        .get("content")
-                        .and_then(|v| v.as_str())
-                        .or_else(|| entry.get("message").and_then(|v| v.as_str()))
-                        .unwrap_or("");
-                    let tool_name = entry
-                        .get("tool_name")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("");
-                    let timestamp = entry.get("timestamp").and_then(|v| v.as_str());
-
-                    let usage: HashMap<&str, &Value> =
-                        flattened_usage_fields(&entry).into_iter().collect();
-                    let usage_int = |key: &str| usage.get(key).and_then(|v| v.as_i64());
-
-                    stmt.execute(params![
-                        msg_type,
-                        msg_content,
-                        tool_name,
-                        file.session_id,
-                        file.source_file,
-                        file.session_id,
-                        file.project,
-                        file.agent_id,
-                        timestamp,
-                        usage.get("model").and_then(|v| v.as_str()),
-                        usage_int("usage_input_tokens"),
-                        usage_int("usage_output_tokens"),
-                        usage_int("usage_cache_read_input_tokens"),
-                        usage_int("usage_cache_creation_input_tokens"),
-                        usage_int("usage_ephemeral_5m_input_tokens"),
-                        usage_int("usage_ephemeral_1h_input_tokens"),
-                        usage.get("usage_service_tier").and_then(|v| v.as_str()),
-                    ])?;
-                }
-            }
         }
         tx.commit()?;
 
@@ -359,26 +1133,7 @@ impl UnifiedEngine {
     }
 
     fn load_tool_calls(&mut self) -> Result<()> {
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS tool_calls (
-                rowid INTEGER PRIMARY KEY,
-                tool_name TEXT,
-                input_json TEXT,
-                target TEXT,
-                source_id TEXT,
-                command TEXT,
-                session_id TEXT,
-                parent_session_id TEXT,
-                agent_id TEXT,
-                agent_role TEXT,
-                originator TEXT,
-                cwd TEXT,
-                source_path TEXT,
-                _project TEXT,
-                timestamp TEXT
-            )",
-            [],
-        )?;
+        self.conn.execute(TOOL_CALLS_TABLE, [])?;
 
         let Some(config) = self.ccql_config() else {
             return Ok(());
@@ -386,49 +1141,31 @@ impl UnifiedEngine {
 
         let tx = self.conn.transaction()?;
         {
-            let mut stmt = tx.prepare(
-                "INSERT INTO tool_calls (
-                    tool_name, input_json, target, source_id, command, session_id,
-                    parent_session_id, agent_id, agent_role, originator, cwd,
-                    source_path, _project, timestamp
-                 ) VALUES (
-                    ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14
-                 )",
-            )?;
-
-            for file in discover_transcript_files(&config) {
-                let source_path = file.path.to_string_lossy().into_owned();
-                visit_jsonl_candidates(&file.path, &[b"\"tool_use\""], |entry| {
-                    let record_session_id = string_field(&entry, "sessionId")
-                        .or_else(|| string_field(&entry, "session_id"))
-                        .unwrap_or_else(|| file.session_id.clone());
-                    let parent_session_id = file.agent_id.as_ref().map(|_| file.session_id.clone());
-                    let agent_role = string_field(&entry, "agentName");
-                    let originator = string_field(&entry, "originator")
-                        .or_else(|| nested_string_field(&entry, "origin", "kind"))
-                        .or_else(|| string_field(&entry, "entrypoint"));
-                    let cwd = string_field(&entry, "cwd");
-                    for call in ccql::datasources::tool_calls::extract_tool_calls(&entry) {
-                        stmt.execute(params![
-                            call.tool_name,
-                            call.input_json,
-                            call.target,
-                            call.source_id,
-                            call.command,
-                            record_session_id,
-                            parent_session_id,
-                            file.agent_id,
-                            agent_role,
-                            originator,
-                            cwd,
-                            source_path,
-                            file.project,
-                            call.timestamp,
-                        ])?;
+            let mut stmt = tx.prepare(&insert_sql("tool_calls", &TOOL_CALL_COLUMNS))?;
+
+            let files = transcript_files(&config, &self.window);
+            let window = &self.window;
+            crate::parallel::for_each_ordered(
+                &files,
+                |file| {
+                    let mut rows = Vec::new();
+                    visit_jsonl_candidates(&file.path, &[b"\"tool_use\""], |entry| {
+                        rows.extend(tool_call_rows(file, &entry).into_iter().filter(
+                            |(timestamp, _)| {
+                                window.contains_rfc3339(timestamp.as_deref().unwrap_or_default())
+                            },
+                        ));
+                        Ok(())
+                    })
+                    .map(|()| rows)
+                },
+                |_, rows| {
+                    for (_, values) in rows? {
+                        stmt.execute(rusqlite::params_from_iter(values))?;
                     }
-                    Ok(())
-                })?;
-            }
+                    Ok::<_, Error>(())
+                },
+            )?;
         }
         tx.commit()?;
 
@@ -444,82 +1181,31 @@ impl UnifiedEngine {
             return Ok(());
         }
         let mut index = crate::codex_index::CodexIndex::open(&self.codex_data_dir)?;
-        index.sync()?;
+        let stats = match self.codex_synced.take() {
+            Some(synced) => synced?,
+            None => index.sync_since(self.window.since)?,
+        };
+        if let Some(profile) = &mut self.profile {
+            profile.codex_sync = Some(stats.into());
+        }
         let cache_path = index.cache_path().to_string_lossy().into_owned();
         drop(index);
 
         self.conn
             .execute("ATTACH DATABASE ?1 AS codex_index", [cache_path])?;
-        self.conn.execute_batch(
-            "
-            CREATE TEMP VIEW codex_threads AS
-              SELECT * FROM codex_index.codex_threads;
-            CREATE TEMP VIEW codex_events AS
-              SELECT * FROM codex_index.codex_events;
-            CREATE TEMP VIEW codex_messages AS
-              SELECT * FROM codex_index.codex_messages;
-            CREATE TEMP VIEW codex_tool_executions AS
-              SELECT * FROM codex_index.codex_tool_executions;
-            CREATE TEMP VIEW codex_compactions AS
-              SELECT * FROM codex_index.codex_compactions;
-            CREATE TEMP VIEW codex_ingest_errors AS
-              SELECT * FROM codex_index.codex_ingest_errors;
-            CREATE TEMP VIEW codex_tool_calls AS
-              SELECT
-                row_number() OVER (
-                  ORDER BY execution.thread_id, execution.call_record_index
-                ) AS rowid,
-                execution.tool_name,
-                execution.arguments_json,
-                execution.cmd,
-                execution.call_id AS source_id,
-                execution.thread_id AS session_id,
-                thread.parent_thread_id AS parent_session_id,
-                thread.agent_path AS agent_id,
-                thread.agent_role,
-                thread.originator,
-                COALESCE(execution.cwd, thread.cwd) AS cwd,
-                execution.source_path,
-                execution.called_at AS timestamp
-              FROM codex_index.codex_tool_executions AS execution
-              LEFT JOIN codex_index.codex_threads AS thread
-                ON thread.thread_id = execution.thread_id
-              WHERE execution.call_record_index IS NOT NULL;
-            ",
-        )?;
+        create_codex_views(&self.conn, &self.window)?;
         self.codex_loaded = true;
         Ok(())
     }
 
     fn load_sessions(&mut self) -> Result<()> {
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS sessions (
-                session_id TEXT,
-                project TEXT,
-                cwd TEXT,
-                git_branch TEXT,
-                version TEXT,
-                title TEXT,
-                first_timestamp TEXT,
-                last_timestamp TEXT,
-                user_message_count INTEGER,
-                assistant_message_count INTEGER,
-                subagent_count INTEGER,
-                total_input_tokens INTEGER,
-                total_output_tokens INTEGER,
-                total_cache_read_input_tokens INTEGER,
-                total_cache_creation_input_tokens INTEGER,
-                pr_url TEXT,
-                pr_number INTEGER
-            )",
-            [],
-        )?;
+        self.conn.execute(SESSIONS_TABLE, [])?;
 
         let Some(config) = self.ccql_config() else {
             return Ok(());
         };
 
-        let files = discover_transcript_files(&config);
+        let files = transcript_files(&config, &self.window);
 
         // Subagent file counts keyed by (project, parent session id)
         let mut subagent_counts: HashMap<(Option<String>, String), i64> = HashMap::new();
@@ -538,44 +1224,59 @@ impl UnifiedEngine {
                  (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
             )?;
 
-            for file in files.iter().filter(|f| f.agent_id.is_none()) {
-                let content = match std::fs::read_to_string(&file.path) {
-                    Ok(c) => c,
-                    Err(_) => continue,
-                };
-
-                let mut agg = SessionAggregate::default();
-                for line in content.lines() {
-                    if let Ok(json) = serde_json::from_str::<Value>(line) {
-                        agg.observe(&json);
+            let sessions: Vec<&TranscriptFile> =
+                files.iter().filter(|f| f.agent_id.is_none()).collect();
+            let window = &self.window;
+            crate::parallel::for_each_ordered(
+                &sessions,
+                |file| {
+                    crate::profile::file_read();
+                    let content = std::fs::read_to_string(&file.path).ok()?;
+
+                    let mut agg = SessionAggregate::default();
+                    for line in content.lines() {
+                        if let Ok(json) = serde_json::from_str::<Value>(line) {
+                            agg.observe(&json);
+                        }
                     }
-                }
+                    overlaps_window(
+                        window,
+                        agg.first_timestamp.as_deref(),
+                        agg.last_timestamp.as_deref(),
+                    )
+                    .then_some(agg)
+                },
+                |file, agg| {
+                    let Some(agg) = agg else {
+                        return Ok(());
+                    };
+                    let subagent_count = subagent_counts
+                        .get(&(file.project.clone(), file.session_id.clone()))
+                        .copied()
+                        .unwrap_or(0);
 
-                let subagent_count = subagent_counts
-                    .get(&(file.project.clone(), file.session_id.clone()))
-                    .copied()
-                    .unwrap_or(0);
-
-                stmt.execute(params![
-                    file.session_id,
-                    file.project,
-                    agg.cwd,
-                    agg.git_branch,
-                    agg.version,
-                    agg.title,
-                    agg.first_timestamp,
-                    agg.last_timestamp,
-                    agg.user_message_count,
-                    agg.assistant_message_count,
-                    subagent_count,
-                    agg.total_input_tokens,
-                    agg.total_output_tokens,
-                    agg.total_cache_read_input_tokens,
-                    agg.total_cache_creation_input_tokens,
-                    agg.pr_url,
-                    agg.pr_number,
-                ])?;
-            }
+                    stmt.execute(params![
+                        file.session_id,
+                        file.project,
+                        agg.cwd,
+                        agg.git_branch,
+                        agg.version,
+                        agg.title,
+                        agg.first_timestamp,
+                        agg.last_timestamp,
+                        agg.user_message_count,
+                        agg.assistant_message_count,
+                        subagent_count,
+                        agg.total_input_tokens,
+                        agg.total_output_tokens,
+                        agg.total_cache_read_input_tokens,
+                        agg.total_cache_creation_input_tokens,
+                        agg.pr_url,
+                        agg.pr_number,
+                    ])?;
+                    Ok::<_, Error>(())
+                },
+            )?;
         }
         tx.commit()?;
 
@@ -583,59 +1284,25 @@ impl UnifiedEngine {
     }
 
     fn load_jhistory(&mut self) -> Result<()> {
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS jhistory (
-                rowid INTEGER PRIMARY KEY,
-                session_id TEXT,
-                ts INTEGER,
-                text TEXT,
-                display TEXT,
-                timestamp INTEGER
-            )",
-            [],
-        )?;
-        self.conn.execute(
-            "CREATE VIEW IF NOT EXISTS codex_history AS SELECT * FROM jhistory",
-            [],
-        )?;
+        self.conn.execute(JHISTORY_TABLE, [])?;
+        self.conn.execute(CODEX_HISTORY_VIEW, [])?;
 
         let jhistory_path = self.codex_data_dir.join("history.jsonl");
         if !jhistory_path.exists() {
             return Ok(());
         }
 
+        crate::profile::file_read();
         let content = std::fs::read_to_string(&jhistory_path)?;
         for line in content.lines() {
             if let Ok(entry) = serde_json::from_str::<Value>(line) {
-                let text = entry
-                    .get("text")
-                    .or_else(|| entry.get("display"))
-                    .and_then(json_value_as_string)
-                    .unwrap_or_default();
-
-                let session_id = entry
-                    .get("session_id")
-                    .or_else(|| entry.get("sessionId"))
-                    .and_then(json_value_as_string)
-                    .unwrap_or_default();
-
-                let ts = entry
-                    .get("ts")
-                    .and_then(json_number_as_i64)
-                    .or_else(|| {
-                        entry
-                            .get("timestamp")
-                            .and_then(json_number_as_i64)
-                            .map(normalize_ts_seconds)
-                    })
-                    .unwrap_or(0);
-
-                let timestamp = ts.saturating_mul(1000);
-
+                let (ts, values) = jhistory_row(&entry);
+                if !self.window.is_unbounded() && !self.window.contains_seconds(ts) {
+                    continue;
+                }
                 self.conn.execute(
-                    "INSERT INTO jhistory (session_id, ts, text, display, timestamp)
-                     VALUES (?1, ?2, ?3, ?4, ?5)",
-                    params![session_id, ts, text.clone(), text, timestamp],
+                    &insert_sql("jhistory", &JHISTORY_COLUMNS),
+                    rusqlite::params_from_iter(values),
                 )?;
             }
         }
@@ -644,14 +1311,7 @@ impl UnifiedEngine {
     }
 
     fn load_todos(&mut self) -> Result<()> {
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS todos (
-                rowid INTEGER PRIMARY KEY,
-                content TEXT,
-                status TEXT
-            )",
-            [],
-        )?;
+        self.conn.execute(TODOS_TABLE, [])?;
 
         let todos_dir = self.claude_data_dir.join("todos");
         if !todos_dir.is_dir() {
@@ -662,13 +1322,19 @@ impl UnifiedEngine {
             Ok(e) => e,
             Err(_) => return Ok(()),
         };
+        let (since, _) = window_bounds(&self.window);
 
         for entry in entries.filter_map(|e| e.ok()) {
             let path = entry.path();
-            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            if path.extension().and_then(|e| e.to_str()) != Some("json")
+                || since
+                    .as_deref()
+                    .is_some_and(|since| written_before(&path, since))
+            {
                 continue;
             }
 
+            crate::profile::file_read();
             let content = match std::fs::read_to_string(&path) {
                 Ok(c) => c,
                 Err(_) => continue,
@@ -691,234 +1357,521 @@ impl UnifiedEngine {
         Ok(())
     }
 
-    fn load_commits(&mut self) -> Result<()> {
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS commits (
-                id TEXT PRIMARY KEY,
-                short_id TEXT,
-                author_name TEXT,
-                author_email TEXT,
-                authored_at TEXT,
-                summary TEXT,
-                message TEXT,
-                is_merge INTEGER
-            )",
-            [],
-        )?;
-
-        // Use git2 to load commits
-        if let Ok(repo) = git2::Repository::open(&self.git_repo_path) {
-            let mut revwalk = repo.revwalk().map_err(|e| Error::Vcsql(e.to_string()))?;
-            revwalk.push_head().ok();
-
-            for oid in revwalk.filter_map(|r| r.ok()) {
-                if let Ok(commit) = repo.find_commit(oid) {
-                    let id = commit.id().to_string();
-                    let short_id = &id[..7.min(id.len())];
-                    let author = commit.author();
-                    let author_name = author.name().unwrap_or("");
-                    let author_email = author.email().unwrap_or("");
-                    let time = commit.time();
-                    let authored_at = format_git_time(time.seconds());
-                    let summary = commit.summary().unwrap_or("");
-                    let message = commit.message().unwrap_or("");
-                    let is_merge = if commit.parent_count() > 1 { 1 } else { 0 };
-
-                    self.conn.execute(
-                        "INSERT OR IGNORE INTO commits VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-                        params![
-                            id,
-                            short_id,
-                            author_name,
-                            author_email,
-                            authored_at,
-                            summary,
-                            message,
-                            is_merge
-                        ],
-                    )?;
-                }
+    /// Sync the persistent Git index of each repository and copy the rows
+    /// reachable from its HEAD into `tables`.
+    fn load_indexed_git_tables(&mut self, tables: &[&str]) -> Result<()> {
+        let repo_paths = self
+            .open_git_repos()
+            .iter()
+            .map(|repo| PathBuf::from(repo.path()))
+            .collect();
+        let snapshot = Arc::new(crate::git_index::GitSnapshot::new(repo_paths, self.window));
+        for table in tables {
+            if table_exists(&self.conn, table)? {
+                continue;
+            }
+            if let Some(source) = crate::git_index::LazyGitTable::new(table, snapshot.clone())? {
+                crate::lazy::register(&self.conn, table, Arc::new(source))?;
             }
         }
+        Ok(())
+    }
+
+    /// Load one Git table through vcsql's provider. A path that is not a Git
+    /// repository yields an empty table rather than an error.
+    fn load_vcsql_table(&mut self, table: &str) -> Result<()> {
+        let Some(info) = vcsql::sql::get_table_info(table) else {
+            return Ok(());
+        };
+        let Some(provider) = vcsql::providers::provider_for(table) else {
+            return Ok(());
+        };
+        if table_exists(&self.conn, table)? {
+            return Ok(());
+        }
+        self.conn.execute(info.create_sql, [])?;
+
+        for mut repo in self.open_git_repos() {
+            let tx = self.conn.transaction()?;
+            provider
+                .populate(&tx, &mut repo)
+                .map_err(|e| Error::Vcsql(e.to_string()))?;
+            tx.commit()?;
+        }
 
         Ok(())
     }
 
-    fn load_diffs(&mut self) -> Result<()> {
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS diffs (
-                commit_id TEXT PRIMARY KEY,
-                files_changed INTEGER,
-                insertions INTEGER,
-                deletions INTEGER
-            )",
-            [],
-        )?;
+    /// Open each configured path that is inside a Git repository, once per
+    /// repository even when several paths resolve to the same one.
+    fn open_git_repos(&self) -> Vec<vcsql::GitRepo> {
+        let mut seen = std::collections::HashSet::new();
+        self.git_repo_paths
+            .iter()
+            .filter_map(|path| vcsql::GitRepo::open(path).ok())
+            .filter(|repo| seen.insert(repo.path().to_string()))
+            .map(|mut repo| {
+                repo.set_time_window(self.window);
+                repo
+            })
+            .collect()
+    }
+}
 
-        if let Ok(repo) = git2::Repository::open(&self.git_repo_path) {
-            let mut revwalk = repo.revwalk().map_err(|e| Error::Vcsql(e.to_string()))?;
-            revwalk.push_head().ok();
+/// Create the temp views over the attached `codex_index` database that
+/// Codex queries read, keeping only rows inside `window`.
+pub(crate) fn create_codex_views(conn: &Connection, window: &TimeWindow) -> Result<()> {
+    for (_, sql) in codex_views(window) {
+        conn.execute_batch(&sql)?;
+    }
+    Ok(())
+}
 
-            for oid in revwalk.filter_map(|r| r.ok()) {
-                let Ok(commit) = repo.find_commit(oid) else {
-                    continue;
-                };
+/// `(view, CREATE statement)` for each Codex view, in creation order.
+pub(crate) fn codex_views(window: &TimeWindow) -> Vec<(&'static str, String)> {
+    let predicate = |since_column: &str, until_column: &str| {
+        window_predicate(window, since_column, until_column)
+    };
+    let threads = predicate("last_event_at", "started_at");
+    let timestamp = predicate("timestamp", "timestamp");
+    let called_at = predicate("called_at", "called_at");
+    let execution_called_at = predicate("execution.called_at", "execution.called_at");
+    vec![
+        (
+            "codex_threads",
+            format!(
+                "CREATE TEMP VIEW codex_threads AS
+                   SELECT * FROM codex_index.codex_threads
+                   WHERE {threads}"
+            ),
+        ),
+        (
+            "codex_events",
+            format!(
+                "CREATE TEMP VIEW codex_events AS
+                   SELECT * FROM codex_index.codex_events WHERE {timestamp}"
+            ),
+        ),
+        (
+            "codex_messages",
+            format!(
+                "CREATE TEMP VIEW codex_messages AS
+                   SELECT * FROM codex_index.codex_messages WHERE {timestamp}"
+            ),
+        ),
+        (
+            "codex_tool_executions",
+            format!(
+                "CREATE TEMP VIEW codex_tool_executions AS
+                   SELECT * FROM codex_index.codex_tool_executions WHERE {called_at}"
+            ),
+        ),
+        (
+            "codex_compactions",
+            format!(
+                "CREATE TEMP VIEW codex_compactions AS
+                   SELECT * FROM codex_index.codex_compactions WHERE {timestamp}"
+            ),
+        ),
+        (
+            "codex_ingest_errors",
+            "CREATE TEMP VIEW codex_ingest_errors AS
+               SELECT * FROM codex_index.codex_ingest_errors"
+                .to_string(),
+        ),
+        (
+            "codex_tool_calls",
+            format!(
+                "CREATE TEMP VIEW codex_tool_calls AS
+                   SELECT
+                     row_number() OVER (
+                       ORDER BY execution.thread_id, execution.call_record_index
+                     ) AS rowid,
+                     execution.tool_name,
+                     execution.arguments_json,
+                     execution.cmd,
+                     execution.call_id AS source_id,
+                     execution.thread_id AS session_id,
+                     thread.parent_thread_id AS parent_session_id,
+                     thread.agent_path AS agent_id,
+                     thread.agent_role,
+                     thread.originator,
+                     COALESCE(execution.cwd, thread.cwd) AS cwd,
+                     execution.source_path,
+                     execution.called_at AS timestamp
+                   FROM codex_index.codex_tool_executions AS execution
+                   LEFT JOIN codex_index.codex_threads AS thread
+                     ON thread.thread_id = execution.thread_id
+                   WHERE execution.call_record_index IS NOT NULL
+                     AND {execution_called_at}"
+            ),
+        ),
+    ]
+}
 
-                let commit_tree = match commit.tree() {
-                    Ok(t) => t,
-                    Err(_) => continue,
-                };
+/// The external-content FTS5 table `index` over `content.column`.
+pub(crate) fn create_fts_sql(index: &str, content: &str, column: &str, tokenize: &str) -> String {
+    format!(
+        "CREATE VIRTUAL TABLE {index} USING fts5(
+           {column}, content = '{content}', content_rowid = 'rowid',
+           tokenize = '{tokenize}'
+         )"
+    )
+}
 
-                let parent_tree = if commit.parent_count() > 0 {
-                    commit.parent(0).ok().and_then(|p| p.tree().ok())
-                } else {
-                    None
-                };
+/// Columns of `transcripts`, besides its rowid.
+pub(crate) const TRANSCRIPT_COLUMNS: &[(&str, &str)] = &[
+    ("type", "TEXT"),
+    ("content", "TEXT"),
+    ("tool_name", "TEXT"),
+    ("session_id", "TEXT"),
+    ("_source_file", "TEXT"),
+    ("_session_id", "TEXT"),
+    ("_project", "TEXT"),
+    ("_agent_id", "TEXT"),
+    ("timestamp", "TEXT"),
+    ("model", "TEXT"),
+    ("usage_input_tokens", "INTEGER"),
+    ("usage_output_tokens", "INTEGER"),
+    ("usage_cache_read_input_tokens", "INTEGER"),
+    ("usage_cache_creation_input_tokens", "INTEGER"),
+    ("usage_ephemeral_5m_input_tokens", "INTEGER"),
+    ("usage_ephemeral_1h_input_tokens", "INTEGER"),
+    ("usage_service_tier", "TEXT"),
+];
+
+pub(crate) fn create_transcripts_sql() -> String {
+    let columns: Vec<String> = TRANSCRIPT_COLUMNS
+        .iter()
+        .map(|(name, ty)| format!("{name} {ty}"))
+        .collect();
+    format!(
+        "CREATE TABLE IF NOT EXISTS transcripts (rowid INTEGER PRIMARY KEY, {})",
+        columns.join(", ")
+    )
+}
 
-                let diff =
-                    match repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit_tree), None) {
-                        Ok(d) => d,
-                        Err(_) => continue,
-                    };
+/// The transcript files that can hold entries inside `window`, in path
+/// order.
+fn transcript_files(config: &ccql::Config, window: &TimeWindow) -> Vec<TranscriptFile> {
+    let mut files = discover_transcript_files(config);
+    if let (Some(since), _) = window_bounds(window) {
+        files.retain(|file| !written_before(&file.path, &since));
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files
+}
 
-                let stats = match diff.stats() {
-                    Ok(s) => s,
-                    Err(_) => continue,
-                };
+/// Columns of `history` filled from history.jsonl, in the order
+/// [`history_values`] gives values.
+pub(crate) const HISTORY_COLUMNS: [&str; 3] = ["display", "timestamp", "project"];
+
+/// The `history` row of one history.jsonl entry.
+pub(crate) fn history_values(entry: &Value) -> Vec<SqlValue> {
+    let display = entry.get("display").and_then(|v| v.as_str()).unwrap_or("");
+    let timestamp = entry
+        .get("timestamp")
+        .map(|v| v.to_string())
+        .unwrap_or_default();
+    let project = entry.get("project").and_then(|v| v.as_str()).unwrap_or("");
+    vec![
+        display.to_string().into(),
+        timestamp.into(),
+        project.to_string().into(),
+    ]
+}
 
-                let commit_id = commit.id().to_string();
-                self.conn.execute(
-                    "INSERT OR IGNORE INTO diffs VALUES (?1, ?2, ?3, ?4)",
-                    params![
-                        commit_id,
-                        stats.files_changed() as i64,
-                        stats.insertions() as i64,
-                        stats.deletions() as i64,
-                    ],
-                )?;
-            }
-        }
+/// Columns of `jhistory` filled from Codex's history.jsonl, in the order
+/// [`jhistory_row`] gives values.
+pub(crate) const JHISTORY_COLUMNS: [&str; 5] = ["session_id", "ts", "text", "display", "timestamp"];
+
+/// The `jhistory` row of one Codex history.jsonl entry, with its time in
+/// seconds.
+pub(crate) fn jhistory_row(entry: &Value) -> (i64, Vec<SqlValue>) {
+    let text = entry
+        .get("text")
+        .or_else(|| entry.get("display"))
+        .and_then(json_value_as_string)
+        .unwrap_or_default();
+
+    let session_id = entry
+        .get("session_id")
+        .or_else(|| entry.get("sessionId"))
+        .and_then(json_value_as_string)
+        .unwrap_or_default();
+
+    let ts = entry
+        .get("ts")
+        .and_then(json_number_as_i64)
+        .or_else(|| {
+            entry
+                .get("timestamp")
+                .and_then(json_number_as_i64)
+                .map(normalize_ts_seconds)
+        })
+        .unwrap_or(0);
 
-        Ok(())
-    }
+    let timestamp = ts.saturating_mul(1000);
+    (
+        ts,
+        vec![
+            session_id.into(),
+            ts.into(),
+            text.clone().into(),
+            text.into(),
+            timestamp.into(),
+        ],
+    )
+}
 
-    fn load_diff_files(&mut self) -> Result<()> {
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS diff_files (
-                commit_id TEXT,
-                path TEXT,
-                status TEXT,
-                insertions INTEGER,
-                deletions INTEGER
-            )",
-            [],
-        )?;
+/// `INSERT INTO table (columns) VALUES (?1, ...)`.
+pub(crate) fn insert_sql(table: &str, columns: &[&str]) -> String {
+    let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("?{i}")).collect();
+    format!(
+        "INSERT INTO {table} ({}) VALUES ({})",
+        columns.join(", "),
+        placeholders.join(", ")
+    )
+}
 
-        if let Ok(repo) = git2::Repository::open(&self.git_repo_path) {
-            let mut revwalk = repo.revwalk().map_err(|e| Error::Vcsql(e.to_string()))?;
-            revwalk.push_head().ok();
+/// `(line index, values)` for each entry of a transcript file inside
+/// `window`, with values in [`TRANSCRIPT_COLUMNS`] order.
+fn transcript_rows(file: &TranscriptFile, window: &TimeWindow) -> Vec<(i64, Vec<SqlValue>)> {
+    crate::profile::file_read();
+    let Ok(content) = std::fs::read_to_string(&file.path) else {
+        return Vec::new();
+    };
 
-            for oid in revwalk.filter_map(|r| r.ok()) {
-                let Ok(commit) = repo.find_commit(oid) else {
-                    continue;
-                };
+    let mut rows = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let Ok(entry) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        let timestamp = entry.get("timestamp").and_then(|v| v.as_str());
+        if !window.contains_rfc3339(timestamp.unwrap_or_default()) {
+            continue;
+        }
+        rows.push((index as i64, transcript_values(file, &entry)));
+    }
+    rows
+}
 
-                let commit_tree = match commit.tree() {
-                    Ok(t) => t,
-                    Err(_) => continue,
-                };
+/// The `transcripts` row of one entry of `file`, in [`TRANSCRIPT_COLUMNS`]
+/// order.
+pub(crate) fn transcript_values(file: &TranscriptFile, entry: &Value) -> Vec<SqlValue> {
+    let msg_type = entry.get("type").and_then(|v| v.as_str()).unwrap_or("");
+    let msg_content = entry
+        .get("content")
+        .and_then(|v| v.as_str())
+        .or_else(|| entry.get("message").and_then(|v| v.as_str()))
+        .unwrap_or("");
+    let tool_name = entry
+        .get("tool_name")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let timestamp = entry.get("timestamp").and_then(|v| v.as_str());
+
+    let usage: HashMap<&str, &Value> = flattened_usage_fields(entry).into_iter().collect();
+    let usage_int = |key: &str| usage.get(key).and_then(|v| v.as_i64());
+    let usage_text = |key: &str| usage.get(key).and_then(|v| v.as_str()).map(String::from);
+
+    vec![
+        msg_type.to_string().into(),
+        msg_content.to_string().into(),
+        tool_name.to_string().into(),
+        file.session_id.clone().into(),
+        file.source_file.clone().into(),
+        file.session_id.clone().into(),
+        file.project.clone().into(),
+        file.agent_id.clone().into(),
+        timestamp.map(String::from).into(),
+        usage_text("model").into(),
+        usage_int("usage_input_tokens").into(),
+        usage_int("usage_output_tokens").into(),
+        usage_int("usage_cache_read_input_tokens").into(),
+        usage_int("usage_cache_creation_input_tokens").into(),
+        usage_int("usage_ephemeral_5m_input_tokens").into(),
+        usage_int("usage_ephemeral_1h_input_tokens").into(),
+        usage_text("usage_service_tier").into(),
+    ]
+}
 
-                let parent_tree = if commit.parent_count() > 0 {
-                    commit.parent(0).ok().and_then(|p| p.tree().ok())
-                } else {
-                    None
-                };
+/// Columns of `tool_calls` filled from transcripts, in the order
+/// [`tool_call_rows`] gives values.
+pub(crate) const TOOL_CALL_COLUMNS: [&str; 14] = [
+    "tool_name",
+    "input_json",
+    "target",
+    "source_id",
+    "command",
+    "session_id",
+    "parent_session_id",
+    "agent_id",
+    "agent_role",
+    "originator",
+    "cwd",
+    "source_path",
+    "_project",
+    "timestamp",
+];
+
+/// The `tool_calls` rows of one entry of `file`, each with its timestamp
+/// and values in [`TOOL_CALL_COLUMNS`] order.
+pub(crate) fn tool_call_rows(
+    file: &TranscriptFile,
+    entry: &Value,
+) -> Vec<(Option<String>, Vec<SqlValue>)> {
+    let source_path = file.path.to_string_lossy().into_owned();
+    let record_session_id = string_field(entry, "sessionId")
+        .or_else(|| string_field(entry, "session_id"))
+        .unwrap_or_else(|| file.session_id.clone());
+    let parent_session_id = file.agent_id.as_ref().map(|_| file.session_id.clone());
+    let agent_role = string_field(entry, "agentName");
+    let originator = string_field(entry, "originator")
+        .or_else(|| nested_string_field(entry, "origin", "kind"))
+        .or_else(|| string_field(entry, "entrypoint"));
+    let cwd = string_field(entry, "cwd");
+    ccql::datasources::tool_calls::extract_tool_calls(entry)
+        .into_iter()
+        .map(|call| {
+            let values = vec![
+                call.tool_name.into(),
+                call.input_json.into(),
+                call.target.into(),
+                call.source_id.into(),
+                call.command.into(),
+                record_session_id.clone().into(),
+                parent_session_id.clone().into(),
+                file.agent_id.clone().into(),
+                agent_role.clone().into(),
+                originator.clone().into(),
+                cwd.clone().into(),
+                source_path.clone().into(),
+                file.project.clone().into(),
+                call.timestamp.clone().into(),
+            ];
+            (call.timestamp, values)
+        })
+        .collect()
+}
 
-                let diff =
-                    match repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit_tree), None) {
-                        Ok(d) => d,
-                        Err(_) => continue,
-                    };
+/// `transcripts` scanned on demand. A `session_id`, `_session_id`,
+/// `_source_file` or `_project` constraint skips the files of other
+/// sessions, and a lower bound on `timestamp` skips files last written
+/// before it (with a day of slack for clock skew and UTC offsets), since a
+/// transcript only gains entries as it is written.
+///
+/// Files and entries outside the engine's time window are never read.
+///
+/// Rowids combine the file's ordinal with the line index, so a row keeps its
+/// rowid however the table is filtered.
+struct LazyTranscripts {
+    config: ccql::Config,
+    window: TimeWindow,
+    columns: Vec<(String, String)>,
+}
 
-                let commit_id = commit.id().to_string();
-
-                for delta_idx in 0..diff.deltas().len() {
-                    let delta = diff.deltas().nth(delta_idx).unwrap();
-
-                    let path = delta
-                        .new_file()
-                        .path()
-                        .or_else(|| delta.old_file().path())
-                        .map(|p| p.to_string_lossy().to_string())
-                        .unwrap_or_default();
-
-                    let status = match delta.status() {
-                        git2::Delta::Added => "A",
-                        git2::Delta::Deleted => "D",
-                        git2::Delta::Modified => "M",
-                        git2::Delta::Renamed => "R",
-                        git2::Delta::Copied => "C",
-                        _ => "?",
-                    };
+impl crate::lazy::LazyTable for LazyTranscripts {
+    fn columns(&self) -> &[(String, String)] {
+        &self.columns
+    }
 
-                    // Get per-file line stats from Patch
-                    let (insertions, deletions) =
-                        if let Ok(Some(ref p)) = git2::Patch::from_diff(&diff, delta_idx) {
-                            let (_, adds, dels) = p.line_stats().unwrap_or((0, 0, 0));
-                            (adds as i64, dels as i64)
-                        } else {
-                            (0i64, 0i64)
-                        };
+    fn pushdown(&self) -> &[&'static str] {
+        &[
+            "session_id",
+            "_session_id",
+            "_source_file",
+            "_project",
+            "timestamp",
+        ]
+    }
 
-                    self.conn.execute(
-                        "INSERT INTO diff_files VALUES (?1, ?2, ?3, ?4, ?5)",
-                        params![commit_id, path, status, insertions, deletions],
-                    )?;
+    fn scan(
+        &self,
+        filter: &crate::lazy::Filter,
+        emit: &mut dyn FnMut(i64, Vec<SqlValue>) -> bool,
+    ) -> Result<()> {
+        let session = filter
+            .text_eq("session_id")
+            .or_else(|| filter.text_eq("_session_id"));
+        let source_file = filter.text_eq("_source_file");
+        let project = filter.text_eq("_project");
+        let since = filter.text_lower_bound("timestamp");
+
+        let files: Vec<(usize, TranscriptFile)> = transcript_files(&self.config, &self.window)
+            .into_iter()
+            .enumerate()
+            .filter(|(_, file)| {
+                session.is_none_or(|session| session == file.session_id)
+                    && source_file.is_none_or(|name| name == file.source_file)
+                    && project.is_none_or(|project| Some(project) == file.project.as_deref())
+                    && since.is_none_or(|since| !written_before(&file.path, since))
+            })
+            .collect();
+        let _stopped = crate::parallel::for_each_ordered(
+            &files,
+            |(_, file)| transcript_rows(file, &self.window),
+            |(ordinal, _), rows| {
+                for (index, values) in rows {
+                    if !emit((*ordinal as i64) << 32 | index, values) {
+                        return Err(());
+                    }
                 }
-            }
-        }
-
+                Ok(())
+            },
+        );
         Ok(())
     }
+}
 
-    fn load_branches(&mut self) -> Result<()> {
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS branches (
-                name TEXT PRIMARY KEY,
-                target TEXT,
-                is_head INTEGER,
-                is_remote INTEGER
-            )",
-            [],
-        )?;
+/// Whether `path` was last modified more than a day before the ISO-8601
+/// timestamp `bound`.
+fn written_before(path: &Path, bound: &str) -> bool {
+    let Some(modified) = std::fs::metadata(path).and_then(|m| m.modified()).ok() else {
+        return false;
+    };
+    let modified: DateTime<chrono::Utc> = modified.into();
+    let slack = modified + chrono::Duration::days(1);
+    slack.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string().as_str() < bound
+}
 
-        if let Ok(repo) = git2::Repository::open(&self.git_repo_path) {
-            if let Ok(branches) = repo.branches(None) {
-                for branch in branches.filter_map(|b| b.ok()) {
-                    let (branch, branch_type) = branch;
-                    let name = branch.name().ok().flatten().unwrap_or("");
-                    let target = branch
-                        .get()
-                        .target()
-                        .map(|t| t.to_string())
-                        .unwrap_or_default();
-                    let is_head = if branch.is_head() { 1 } else { 0 };
-                    let is_remote = if branch_type == git2::BranchType::Remote {
-                        1
-                    } else {
-                        0
-                    };
+/// The bounds of `window` as UTC timestamps in the format Claude and Codex
+/// write (`2026-01-05T09:00:00.000Z`), so they compare as text.
+pub(crate) fn window_bounds(window: &TimeWindow) -> (Option<String>, Option<String>) {
+    let text = |time: DateTime<chrono::Utc>| time.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+    (window.since.map(text), window.until.map(text))
+}
 
-                    self.conn.execute(
-                        "INSERT OR IGNORE INTO branches VALUES (?1, ?2, ?3, ?4)",
-                        params![name, target, is_head, is_remote],
-                    )?;
-                }
-            }
-        }
+/// A SQL condition keeping rows inside `window`: `since_column` at or after
+/// its start and `until_column` before its end.
+fn window_predicate(window: &TimeWindow, since_column: &str, until_column: &str) -> String {
+    let (since, until) = window_bounds(window);
+    let conditions: Vec<String> = since
+        .map(|since| format!("{since_column} >= '{since}'"))
+        .into_iter()
+        .chain(until.map(|until| format!("{until_column} < '{until}'")))
+        .collect();
+    if conditions.is_empty() {
+        "1".to_string()
+    } else {
+        conditions.join(" AND ")
+    }
+}
 
-        Ok(())
+/// Whether activity running from `first` to `last` (RFC 3339 timestamps)
+/// overlaps `window`. Unknown times overlap only an unbounded window.
+fn overlaps_window(window: &TimeWindow, first: Option<&str>, last: Option<&str>) -> bool {
+    if window.is_unbounded() {
+        return true;
     }
+    let parse = |text: Option<&str>| {
+        text.and_then(|text| DateTime::parse_from_rfc3339(text).ok())
+            .map(|time| time.with_timezone(&chrono::Utc))
+    };
+    let (Some(first), Some(last)) = (parse(first), parse(last)) else {
+        return false;
+    };
+    window.since.is_none_or(|since| last >= since) && window.until.is_none_or(|until| first < until)
 }
 
 /// Normalize dates from various formats to YYYY-MM-DD
@@ -950,18 +1903,8 @@ fn normalize_date(value: &str) -> String {
     value.to_string()
 }
 
-/// Format git timestamp to ISO date
-fn format_git_time(secs: i64) -> String {
-    DateTime::from_timestamp(secs, 0)
-        .map(|dt| dt.format("%Y-%m-%dT%H:%M:%SZ").to_string())
-        .unwrap_or_default()
-}
-
 fn default_codex_data_dir() -> PathBuf {
-    std::env::var_os("CODEX_HOME")
-        .map(PathBuf::from)
-        .or_else(|| dirs::home_dir().map(|p| p.join(".codex")))
-        .unwrap_or_else(|| PathBuf::from(".codex"))
+    crate::config::current().codex_home.value.clone()
 }
 
 fn json_number_as_i64(value: &Value) -> Option<i64> {
@@ -998,10 +1941,28 @@ fn table_exists(conn: &Connection, table_name: &str) -> Result<bool> {
     Ok(count > 0)
 }
 
+/// Drop the table or view called `name`, temporary or not, if it exists.
+fn drop_table_or_view(conn: &Connection, name: &str) -> Result<()> {
+    let kinds: Vec<String> = conn
+        .prepare(
+            "SELECT type FROM sqlite_temp_master WHERE name = ?1 AND type IN ('table', 'view')
+             UNION ALL
+             SELECT type FROM sqlite_master WHERE name = ?1 AND type IN ('table', 'view')",
+        )?
+        .query_map([name], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    for kind in kinds {
+        let kind = if kind == "view" { "VIEW" } else { "TABLE" };
+        conn.execute_batch(&format!("DROP {kind} \"{name}\""))?;
+    }
+    Ok(())
+}
+
 fn visit_jsonl_candidates<F>(path: &Path, markers: &[&[u8]], mut visit: F) -> Result<()>
 where
     F: FnMut(Value) -> Result<()>,
 {
+    crate::profile::file_read();
     let Ok(file) = File::open(path) else {
         return Ok(());
     };
@@ -1041,166 +2002,235 @@ fn nested_string_field(json: &Value, parent: &str, field: &str) -> Option<String
         .map(String::from)
 }
 
-fn query_mentions_table(query_upper: &str, table_name: &str) -> bool {
-    let table_upper = table_name.to_uppercase();
-    query_upper
-        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
-        .any(|token| token == table_upper)
-}
-
-/// Detect which tables are needed from a SQL query.
-///
-/// Returns a 5-tuple:
-/// (claude_tables, git_tables, code_tables, shell_tables, work_tables).
-pub type TableRequirements = (
-    Vec<String>,
-    Vec<String>,
-    Vec<String>,
-    Vec<String>,
-    Vec<String>,
-);
-
-pub fn detect_tables(query: &str) -> TableRequirements {
-    let query_upper = query.to_uppercase();
-
-    let claude_tables = [
-        "history",
-        "jhistory",
-        "codex_history",
-        "transcripts",
-        "sessions",
-        "todos",
-        "stats",
-        "tool_calls",
-        "codex_tool_calls",
-        "codex_threads",
-        "codex_events",
-        "codex_messages",
-        "codex_tool_executions",
-        "codex_compactions",
-        "codex_ingest_errors",
-    ];
-    let git_tables = [
-        "commits",
-        "commit_parents",
-        "branches",
-        "tags",
-        "refs",
-        "stashes",
-        "reflog",
-        "diffs",
-        "diff_files",
-        "blame",
-        "config",
-        "remotes",
-        "submodules",
-        "status",
-        "worktrees",
-        "hooks",
-        "notes",
-    ];
-    let code_tables = [
-        "source_files",
-        "source_lines",
-        "symbols",
-        "imports",
-        "ast_nodes",
-    ];
-    let shell_tables = ["shell_history", "command_events"];
-    let work_tables = ["work_tasks", "work_events"];
-
-    let needed_claude: Vec<String> = claude_tables
-        .iter()
-        .filter(|t| query_mentions_table(&query_upper, t))
-        .map(|s| s.to_string())
-        .collect();
-
-    let needed_git: Vec<String> = git_tables
-        .iter()
-        .filter(|t| query_mentions_table(&query_upper, t))
-        .map(|s| s.to_string())
-        .collect();
-
-    let needed_code: Vec<String> = code_tables
-        .iter()
-        .filter(|t| query_mentions_table(&query_upper, t))
-        .map(|s| s.to_string())
-        .collect();
-
-    let needed_shell: Vec<String> = shell_tables
-        .iter()
-        .filter(|t| query_mentions_table(&query_upper, t))
-        .map(|s| s.to_string())
-        .collect();
-
-    let needed_work: Vec<String> = work_tables
-        .iter()
-        .filter(|t| query_mentions_table(&query_upper, t))
-        .map(|s| s.to_string())
-        .collect();
-
-    (
-        needed_claude,
-        needed_git,
-        needed_code,
-        needed_shell,
-        needed_work,
-    )
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::io::Write;
 
     #[test]
-    fn detect_tables_handles_jhistory_without_history_false_positive() {
-        let (claude, _, _, _, _) = detect_tables("SELECT session_id, text FROM jhistory LIMIT 5");
+    fn like_contains_matches_wildcards_literally() {
+        let engine =
+            UnifiedEngine::new(PathBuf::from("/nonexistent"), PathBuf::from(".")).expect("engine");
+        let sql = "SELECT COUNT(*) AS hits FROM (SELECT '50% off' AS t UNION ALL SELECT '50 off') \
+                   WHERE t LIKE :pattern ESCAPE '\\'";
+        let rows = engine
+            .query_with_params(
+                sql,
+                &QueryParams::None.with("pattern", like_contains("50%")),
+            )
+            .expect("query");
+        assert_eq!(rows[0]["hits"], 1);
 
-        assert!(claude.contains(&"jhistory".to_string()));
-        assert!(!claude.contains(&"history".to_string()));
+        let rows = engine
+            .query_with_params(
+                sql,
+                &QueryParams::None.with("pattern", like_contains("a_b\\")),
+            )
+            .expect("query");
+        assert_eq!(rows[0]["hits"], 0);
     }
 
     #[test]
-    fn detect_tables_handles_codex_history_without_history_false_positive() {
-        let (claude, _, _, _, _) =
-            detect_tables("SELECT session_id, text FROM codex_history LIMIT 5");
+    fn shared_sql_functions_are_registered() {
+        let engine =
+            UnifiedEngine::new(PathBuf::from("/nonexistent"), PathBuf::from(".")).expect("engine");
+        let rows = engine
+            .query(
+                "WITH runs(started, ended) AS (VALUES \
+                   ('2026-01-01T10:00:00Z', '2026-01-01T10:00:02Z'), \
+                   ('2026-01-01T10:00:00Z', '2026-01-01T10:00:05Z'), \
+                   ('2026-01-01T10:00:00Z', '2026-01-01T10:01:00Z')) \
+                 SELECT median(duration_ms(started, ended)) AS median, \
+                        percentile(duration_ms(started, ended), 100) AS slowest FROM runs",
+            )
+            .expect("query");
+        assert_eq!(rows[0]["median"], 5000.0);
+        assert_eq!(rows[0]["slowest"], 60000.0);
+    }
 
-        assert!(claude.contains(&"codex_history".to_string()));
-        assert!(!claude.contains(&"history".to_string()));
+    #[test]
+    fn query_params_must_cover_every_placeholder() {
+        let engine =
+            UnifiedEngine::new(PathBuf::from("/nonexistent"), PathBuf::from(".")).expect("engine");
+        let positional = QueryParams::from_json(serde_json::json!([1])).expect("array");
+        assert!(engine
+            .query_with_params("SELECT ?, ?", &positional)
+            .is_err());
+        assert!(engine.query("SELECT :name").is_err());
+        assert!(QueryParams::from_json(serde_json::json!("text")).is_err());
+
+        let named = QueryParams::from_json(serde_json::json!({"@x": 2, "y": [1, 2]})).expect("map");
+        let rows = engine
+            .query_with_params("SELECT @x * 2 AS doubled, $y AS list", &named)
+            .expect("query");
+        assert_eq!(rows[0]["doubled"], 4);
+        assert_eq!(rows[0]["list"], "[1,2]");
     }
 
     #[test]
-    fn detect_tables_finds_code_tables() {
-        let (_, _, code, _, _) = detect_tables(
-            "SELECT * FROM source_files JOIN symbols ON source_files.path = symbols.file_path",
+    fn row_stream_yields_rows_then_the_query_error() {
+        use futures::StreamExt;
+
+        let engine =
+            UnifiedEngine::new(PathBuf::from("/nonexistent"), PathBuf::from(".")).expect("engine");
+        let sql =
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < :count) \
+                   SELECT i FROM n";
+        let rows: Vec<_> = futures::executor::block_on(
+            engine
+                .into_row_stream(
+                    sql.to_string(),
+                    QueryParams::None.with("count", ROW_STREAM_BUFFER as i64 * 3),
+                )
+                .collect(),
         );
+        assert_eq!(rows.len(), ROW_STREAM_BUFFER * 3);
+        assert_eq!(rows[0].as_ref().expect("row")["i"], 1);
+
+        // Rows produced before a failing one still arrive, then the error.
+        let engine =
+            UnifiedEngine::new(PathBuf::from("/nonexistent"), PathBuf::from(".")).expect("engine");
+        let rows: Vec<_> = futures::executor::block_on(
+            engine
+                .into_row_stream(
+                    "SELECT json_extract(doc, '$.n') AS n \
+                     FROM (SELECT '{\"n\": 1}' AS doc UNION ALL SELECT '{')"
+                        .to_string(),
+                    QueryParams::None,
+                )
+                .collect(),
+        );
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].as_ref().expect("row")["n"], 1);
+        assert!(rows[1].is_err());
+    }
 
-        assert!(code.contains(&"source_files".to_string()));
-        assert!(code.contains(&"symbols".to_string()));
-        assert!(!code.contains(&"source_lines".to_string()));
+    #[test]
+    fn fts_indexes_rank_history_with_bm25() {
+        let temp = tempfile::tempdir().expect("temp");
+        write(
+            &temp.path().join("history.jsonl"),
+            concat!(
+                "{\"display\":\"retry the flaky upload\",\"timestamp\":1}\n",
+                "{\"display\":\"retrying uploads, then retried the upload again\",\"timestamp\":2}\n",
+                "{\"display\":\"unrelated \\\"quoted\\\" prompt\",\"timestamp\":3}\n"
+            ),
+        );
+        let mut engine =
+            UnifiedEngine::new(temp.path().to_path_buf(), PathBuf::from(".")).expect("engine");
+        engine.load_fts_tables(&["history_fts"]).expect("load");
+
+        let terms = vec!["retry".to_string(), "upload".to_string()];
+        let rows = engine
+            .query_with_params(
+                "SELECT history.display, bm25(history_fts) AS bm25
+                 FROM history_fts JOIN history ON history.rowid = history_fts.rowid
+                 WHERE history_fts MATCH :fts ORDER BY bm25",
+                &QueryParams::None.with("fts", fts_any(&terms)),
+            )
+            .expect("match");
+        assert_eq!(rows.len(), 2, "stemming matches the inflected prompt");
+        assert!(rows[0]["display"].as_str().unwrap().starts_with("retrying"));
+        assert!(rows[0]["bm25"].as_f64().unwrap() < rows[1]["bm25"].as_f64().unwrap());
+
+        // Quotes inside a term are matched as text, not FTS5 syntax.
+        let quoted = fts_any(&["\"quoted".to_string()]);
+        assert_eq!(quoted, "\"\"\"quoted\"");
+        let rows = engine
+            .query_with_params(
+                "SELECT rowid FROM history_fts WHERE history_fts MATCH :fts",
+                &QueryParams::None.with("fts", quoted),
+            )
+            .expect("quoted match");
+        assert_eq!(rows.len(), 1);
     }
 
     #[test]
-    fn detect_tables_finds_work_tables() {
-        let (_, _, _, _, work) = detect_tables(
-            "SELECT * FROM work_events JOIN work_tasks ON work_events.task_id = work_tasks.id",
+    fn unload_tables_drops_dependents_so_a_reload_reads_the_source_again() {
+        let temp = tempfile::tempdir().expect("temp");
+        let history = temp.path().join("history.jsonl");
+        write(&history, "{\"display\":\"first\",\"timestamp\":1}\n");
+        let mut engine =
+            UnifiedEngine::new(temp.path().to_path_buf(), PathBuf::from(".")).expect("engine");
+        engine
+            .load_tables(&["history_fts", "jhistory"])
+            .expect("load");
+
+        write(
+            &history,
+            "{\"display\":\"first\",\"timestamp\":1}\n{\"display\":\"second\",\"timestamp\":2}\n",
         );
-        assert!(work.contains(&"work_events".to_string()));
-        assert!(work.contains(&"work_tasks".to_string()));
+        let unloaded = engine.unload_tables(&["history", "todos"]).expect("unload");
+        assert_eq!(unloaded, ["history", "history_fts"]);
+        assert!(engine.query("SELECT * FROM history_fts").is_err());
+        assert!(engine.query("SELECT * FROM codex_history").is_ok());
+
+        engine.load_tables(&["history_fts"]).expect("reload");
+        let rows = engine
+            .query("SELECT rowid FROM history_fts WHERE history_fts MATCH 'second'")
+            .expect("match");
+        assert_eq!(rows.len(), 1);
     }
 
     #[test]
-    fn detect_tables_finds_shell_history_without_history_false_positive() {
-        let (claude, _, _, shell, _) = detect_tables("SELECT command FROM shell_history LIMIT 5");
-        assert_eq!(shell, vec!["shell_history".to_string()]);
-        assert!(!claude.contains(&"history".to_string()));
+    fn git_tables_use_vcsql_schemas() {
+        let temp = tempfile::tempdir().expect("temp");
+        let repo = git2::Repository::init(temp.path()).expect("git init");
+        std::fs::write(temp.path().join("README.md"), "# demo\n").expect("write");
+        let mut index = repo.index().expect("index");
+        index
+            .add_path(std::path::Path::new("README.md"))
+            .expect("add");
+        index.write().expect("index write");
+        let tree = repo
+            .find_tree(index.write_tree().expect("tree"))
+            .expect("find tree");
+        let sig = git2::Signature::now("Test", "test@example.com").expect("sig");
+        let head = repo
+            .commit(Some("HEAD"), &sig, &sig, "Initial commit", &tree, &[])
+            .expect("commit");
+        let object = repo.find_object(head, None).expect("object");
+        repo.tag_lightweight("v1.0.0", &object, false).expect("tag");
+
+        let mut engine = UnifiedEngine::new(temp.path().to_path_buf(), temp.path().to_path_buf())
+            .expect("engine");
+        let delegated: Vec<&str> = vcsql::TABLES.iter().map(|table| table.name).collect();
+        engine.load_git_tables(&delegated).expect("load");
+
+        for name in &delegated {
+            let info = vcsql::sql::get_table_info(name).expect("table info");
+            let expected: Vec<&str> = info.columns.iter().map(|c| c.name).collect();
+            let mut stmt = engine
+                .conn()
+                .prepare(&format!("SELECT * FROM {name}"))
+                .expect("prepare");
+            assert_eq!(stmt.column_names(), expected, "{name} columns");
+            stmt.raw_query().next().expect("step");
+        }
+
+        let tags = engine
+            .query("SELECT name, target_id FROM tags")
+            .expect("tags");
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0]["name"], serde_json::json!("v1.0.0"));
+        assert_eq!(tags[0]["target_id"], serde_json::json!(head.to_string()));
+        let blame = engine
+            .query("SELECT path, line_content FROM blame")
+            .expect("blame");
+        assert_eq!(blame[0]["line_content"], serde_json::json!("# demo"));
     }
 
     #[test]
-    fn detect_tables_finds_command_events() {
-        let (_, _, _, shell, _) = detect_tables("SELECT actor, command FROM command_events");
-        assert_eq!(shell, vec!["command_events".to_string()]);
+    fn git_tables_are_empty_outside_a_repository() {
+        let temp = tempfile::tempdir().expect("temp");
+        let mut engine = UnifiedEngine::new(temp.path().to_path_buf(), temp.path().to_path_buf())
+            .expect("engine");
+        engine.load_git_tables(&["tags", "reflog"]).expect("load");
+        assert!(engine.query("SELECT * FROM tags").expect("tags").is_empty());
+        assert!(engine
+            .query("SELECT * FROM reflog")
+            .expect("reflog")
+            .is_empty());
     }
 
     #[test]
@@ -1214,6 +2244,81 @@ mod tests {
         std::fs::write(path, contents).expect("write");
     }
 
+    fn plan(engine: &UnifiedEngine, sql: &str) -> String {
+        engine
+            .query(&format!("EXPLAIN QUERY PLAN {sql}"))
+            .expect("plan")
+            .iter()
+            .map(|row| row["detail"].as_str().unwrap_or_default().to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn lazy_tables_push_session_and_path_constraints_to_the_scan() {
+        let temp = tempfile::tempdir().expect("temp");
+        let root = temp.path();
+        for (session, text) in [("sess-a", "alpha"), ("sess-b", "beta")] {
+            write(
+                &root.join("projects/-app").join(format!("{session}.jsonl")),
+                &format!(
+                    r#"{{"type":"user","content":"{text}","timestamp":"2026-06-01T10:00:00.000Z"}}"#
+                ),
+            );
+        }
+        write(&root.join("src/main.rs"), "fn main() {}\n\nmain();\n");
+        write(&root.join("src/lib.rs"), "pub mod a;\n");
+
+        let mut engine =
+            UnifiedEngine::new(root.to_path_buf(), root.to_path_buf()).expect("engine");
+        engine
+            .load_claude_tables(&["transcripts"])
+            .expect("transcripts");
+        engine
+            .load_code_tables(&["source_files", "source_lines"])
+            .expect("code");
+
+        let sql = "SELECT content FROM transcripts WHERE session_id = 'sess-b'";
+        assert!(plan(&engine, sql).contains("VIRTUAL TABLE INDEX 0:3="));
+        let rows = engine.query(sql).expect("session");
+        assert_eq!(rows, vec![serde_json::json!({"content": "beta"})]);
+        let later = engine
+            .query("SELECT COUNT(*) AS n FROM transcripts WHERE timestamp >= '2026-06-01'")
+            .expect("range");
+        assert_eq!(later[0]["n"], 2);
+
+        let sql = "SELECT f.line_count, l.line_number, l.is_blank \
+                   FROM source_files f JOIN source_lines l ON l.file_path = f.path \
+                   WHERE f.path = 'src/main.rs'";
+        assert!(plan(&engine, sql).contains("VIRTUAL TABLE INDEX 0:0="));
+        let rows = engine.query(sql).expect("join");
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[1]["is_blank"], 1);
+
+        // A row keeps its rowid however the table is filtered.
+        let all = engine
+            .query("SELECT rowid FROM source_lines WHERE content = 'main();'")
+            .expect("all");
+        let pinned = engine
+            .query("SELECT rowid FROM source_lines WHERE file_path = 'src/main.rs' AND line_number = 3")
+            .expect("pinned");
+        assert_eq!(all, pinned);
+    }
+
+    #[test]
+    fn transcript_files_are_skipped_when_last_written_well_before_the_bound() {
+        let temp = tempfile::tempdir().expect("temp");
+        let path = temp.path().join("old.jsonl");
+        write(&path, "{}");
+        let file = File::options().write(true).open(&path).expect("open");
+        let written = DateTime::parse_from_rfc3339("2026-01-10T12:00:00Z").expect("time");
+        file.set_modified(written.into()).expect("mtime");
+
+        assert!(written_before(&path, "2026-01-12"));
+        assert!(!written_before(&path, "2026-01-11T06:00:00.000Z"));
+        assert!(!written_before(&temp.path().join("missing.jsonl"), "2030"));
+    }
+
     #[test]
     fn loads_modern_projects_layout_with_usage_and_sessions() {
         let temp = tempfile::tempdir().expect("temp");
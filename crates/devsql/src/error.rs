@@ -19,6 +19,21 @@ pub enum Error {
     #[error("vcsql error: {0}")]
     Vcsql(String),
 
+    #[error("Repository error: {0}")]
+    Repo(String),
+
     #[error("Query error: {0}")]
     Query(String),
+
+    #[error("Saved query error: {0}")]
+    SavedQuery(String),
+
+    #[error("Config error: {0}")]
+    Config(String),
+
+    #[error("Server error: {0}")]
+    Serve(String),
+
+    #[error("Failed to load {category} tables: {error}")]
+    Load { category: String, error: Box<Error> },
 }
@@ -0,0 +1,570 @@
+//! Persistent, incremental cache of the per-commit Git tables (`commits`,
+//! `diffs`, `diff_files`), keyed by repository path.
+//!
+//! Rows derived from a commit never change once written, so a sync only
+//! computes diffs for commits the cache has not seen yet. Queries read the
+//! rows of commits currently reachable from HEAD, in revwalk order, so
+//! rewritten history never leaks into results.
+
+use crate::index_cache;
+use crate::lazy::{Filter, LazyTable};
+use crate::{Error, Result};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{Connection, OptionalExtension, TransactionBehavior};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use vcsql::{GitRepo, TimeWindow};
+
+const SCHEMA_VERSION: i64 = 1;
+
+/// Commits processed per write transaction, so an interrupted first index of
+/// a large history keeps the work already done.
+const BATCH_SIZE: usize = 500;
+
+/// Tables served from the cache, with the column holding each row's commit.
+pub(crate) const INDEXED_TABLES: [(&str, &str); 3] = [
+    ("commits", "id"),
+    ("diffs", "commit_id"),
+    ("diff_files", "commit_id"),
+];
+
+/// `(name, columns)` of the indexes the cache keeps on `table`, for a copy
+/// of its rows to recreate.
+pub(crate) fn snapshot_indexes(table: &str) -> Vec<(String, Vec<String>)> {
+    let by_commit = INDEXED_TABLES
+        .iter()
+        .filter(|(name, _)| *name == table)
+        .map(|(name, column)| (format!("{name}_by_commit"), vec![column.to_string()]));
+    let pushdown = PUSHDOWN_COLUMNS
+        .iter()
+        .filter(|(name, _)| *name == table)
+        .map(|(name, column)| (format!("{name}_by_{column}"), vec![column.to_string()]));
+    by_commit.chain(pushdown).collect()
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct GitSyncStats {
+    pub reachable_commits: usize,
+    pub indexed_commits: usize,
+}
+
+/// Cache file of the repository at `repo_path`.
+pub(crate) fn cache_path(repo_path: &Path) -> PathBuf {
+    index_cache::cache_path_for("git-index", repo_path)
+}
+
+pub(crate) struct GitIndex {
+    conn: Connection,
+}
+
+impl GitIndex {
+    pub(crate) fn open_at(cache_path: &Path) -> Result<Self> {
+        let conn = index_cache::open_versioned(cache_path, SCHEMA_VERSION, create_schema)?;
+        Ok(Self { conn })
+    }
+
+    /// Index every commit reachable from HEAD that the cache does not hold
+    /// yet. Returns the reachable commit ids in revwalk order.
+    pub(crate) fn sync(&mut self, repo: &GitRepo) -> Result<(Vec<String>, GitSyncStats)> {
+        let reachable = repo
+            .walk_commits()
+            .and_then(|commits| {
+                commits
+                    .map(|commit| commit.map(|commit| commit.id()))
+                    .collect::<vcsql::Result<Vec<_>>>()
+            })
+            .map_err(|e| Error::Vcsql(e.to_string()))?;
+
+        let mut stats = GitSyncStats {
+            reachable_commits: reachable.len(),
+            ..GitSyncStats::default()
+        };
+        let indexed = self.indexed_commit_ids()?;
+        let pending: Vec<git2::Oid> = reachable
+            .iter()
+            .filter(|oid| !indexed.contains(&oid.to_string()))
+            .copied()
+            .collect();
+
+        let providers: Vec<Box<dyn vcsql::providers::CommitProvider>> = INDEXED_TABLES
+            .iter()
+            .filter_map(|(table, _)| vcsql::providers::commit_provider_for(table))
+            .collect();
+
+        for batch in pending.chunks(BATCH_SIZE) {
+            let tx = self
+                .conn
+                .transaction_with_behavior(TransactionBehavior::Immediate)?;
+            for oid in batch {
+                let commit_id = oid.to_string();
+                // Another process may have indexed this commit while we
+                // waited for the write lock.
+                let already_indexed = tx
+                    .query_row(
+                        "SELECT 1 FROM indexed_commits WHERE commit_id = ?1",
+                        [&commit_id],
+                        |_| Ok(()),
+                    )
+                    .optional()?
+                    .is_some();
+                if already_indexed {
+                    continue;
+                }
+                let commit = repo
+                    .inner()
+                    .find_commit(*oid)
+                    .map_err(|e| Error::Vcsql(e.to_string()))?;
+                for provider in &providers {
+                    provider
+                        .populate_commit(&tx, repo, &commit)
+                        .map_err(|e| Error::Vcsql(e.to_string()))?;
+                }
+                tx.execute(
+                    "INSERT INTO indexed_commits (commit_id) VALUES (?1)",
+                    [&commit_id],
+                )?;
+                stats.indexed_commits += 1;
+            }
+            tx.commit()?;
+        }
+
+        Ok((reachable.iter().map(|oid| oid.to_string()).collect(), stats))
+    }
+
+    #[cfg(test)]
+    pub(crate) fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    fn indexed_commit_ids(&self) -> Result<HashSet<String>> {
+        let mut statement = self.conn.prepare("SELECT commit_id FROM indexed_commits")?;
+        let rows = statement.query_map([], |row| row.get::<_, String>(0))?;
+        Ok(rows.collect::<std::result::Result<HashSet<_>, _>>()?)
+    }
+}
+
+/// Rows a scan reads from a cache per lock, so a scan that stops early never
+/// reads, or holds in memory, more than one chunk past the rows it wanted.
+const SCAN_CHUNK_ROWS: usize = 256;
+
+/// Columns of the indexed tables that scans can be narrowed by, besides the
+/// commit column.
+const PUSHDOWN_COLUMNS: [(&str, &str); 1] = [("diff_files", "path")];
+
+/// The caches of a set of repositories, synced the first time any of the
+/// tables sharing it is scanned. Each cache connection holds the revwalk
+/// of its repository, limited to the time window, in `temp.git_walk`.
+pub(crate) struct GitSnapshot {
+    /// `(repository path, cache path)` of each repository.
+    repos: Vec<(PathBuf, PathBuf)>,
+    window: TimeWindow,
+    caches: Mutex<Option<Vec<Connection>>>,
+}
+
+impl GitSnapshot {
+    pub(crate) fn new(repo_paths: Vec<PathBuf>, window: TimeWindow) -> Self {
+        let repos = repo_paths
+            .into_iter()
+            .map(|path| {
+                let cache_path = cache_path(&path);
+                (path, cache_path)
+            })
+            .collect();
+        Self {
+            repos,
+            window,
+            caches: Mutex::new(None),
+        }
+    }
+
+    fn with_caches<T>(&self, f: impl FnOnce(&[Connection]) -> Result<T>) -> Result<T> {
+        let mut caches = self.caches.lock().unwrap_or_else(|e| e.into_inner());
+        if caches.is_none() {
+            *caches = Some(self.sync()?);
+        }
+        f(caches.as_deref().unwrap_or_default())
+    }
+
+    /// Sync each repository's index, in parallel, and return its
+    /// connection, in repository order.
+    fn sync(&self) -> Result<Vec<Connection>> {
+        let mut caches = Vec::new();
+        crate::parallel::for_each_ordered(
+            &self.repos,
+            |(repo_path, cache_path)| self.sync_repo(repo_path, cache_path),
+            |_, cache| {
+                caches.push(cache?);
+                Ok::<_, Error>(())
+            },
+        )?;
+        Ok(caches)
+    }
+
+    fn sync_repo(&self, repo_path: &Path, cache_path: &Path) -> Result<Connection> {
+        let mut repo = GitRepo::open(repo_path).map_err(|e| Error::Vcsql(e.to_string()))?;
+        repo.set_time_window(self.window);
+        let mut index = GitIndex::open_at(cache_path)?;
+        let (commit_ids, _) = index.sync(&repo)?;
+        index.conn.execute_batch(
+            "CREATE TEMP TABLE git_walk (ordinal INTEGER PRIMARY KEY, commit_id TEXT NOT NULL);
+             CREATE INDEX temp.git_walk_by_commit ON git_walk (commit_id);",
+        )?;
+        {
+            let mut insert = index
+                .conn
+                .prepare("INSERT INTO git_walk (ordinal, commit_id) VALUES (?1, ?2)")?;
+            for (ordinal, commit_id) in commit_ids.iter().enumerate() {
+                insert.execute(rusqlite::params![ordinal as i64, commit_id])?;
+            }
+        }
+        Ok(index.conn)
+    }
+}
+
+/// An indexed table read straight from the repositories' caches rather than
+/// copied into the query connection. Rows are those of commits reachable
+/// from HEAD, in revwalk order; a constraint on the commit column (or on
+/// `diff_files.path`) is answered from the cache's indexes, so only the
+/// matching commits' rows are read.
+pub(crate) struct LazyGitTable {
+    table: &'static str,
+    commit_column: &'static str,
+    pushdown: Vec<&'static str>,
+    columns: Vec<(String, String)>,
+    snapshot: Arc<GitSnapshot>,
+    #[cfg(test)]
+    rows_read: std::sync::atomic::AtomicUsize,
+}
+
+impl LazyGitTable {
+    /// The lazily scanned form of `table`, if it is one of [`INDEXED_TABLES`].
+    pub(crate) fn new(table: &str, snapshot: Arc<GitSnapshot>) -> Result<Option<Self>> {
+        let Some(&(table, commit_column)) = INDEXED_TABLES.iter().find(|(name, _)| *name == table)
+        else {
+            return Ok(None);
+        };
+        let info = vcsql::sql::get_table_info(table)
+            .ok_or_else(|| Error::Vcsql(format!("unknown table {table}")))?;
+        let pushdown = std::iter::once(commit_column)
+            .chain(
+                PUSHDOWN_COLUMNS
+                    .iter()
+                    .filter(|(name, _)| *name == table)
+                    .map(|(_, column)| *column),
+            )
+            .collect();
+        Ok(Some(Self {
+            table,
+            commit_column,
+            pushdown,
+            columns: crate::lazy::columns_of(info.create_sql)?,
+            snapshot,
+            #[cfg(test)]
+            rows_read: Default::default(),
+        }))
+    }
+}
+
+impl LazyTable for LazyGitTable {
+    fn columns(&self) -> &[(String, String)] {
+        &self.columns
+    }
+
+    fn pushdown(&self) -> &[&'static str] {
+        &self.pushdown
+    }
+
+    fn scan(
+        &self,
+        filter: &Filter,
+        emit: &mut dyn FnMut(i64, Vec<SqlValue>) -> bool,
+    ) -> Result<()> {
+        let mut sql = format!(
+            "SELECT w.ordinal, t.rowid, t.* FROM main.{table} AS t
+             JOIN temp.git_walk AS w ON w.commit_id = t.{commit_column}
+             WHERE w.ordinal >= ?1 AND (w.ordinal > ?1 OR t.rowid > ?2)",
+            table = self.table,
+            commit_column = self.commit_column,
+        );
+        let mut params: Vec<SqlValue> = vec![SqlValue::Null, SqlValue::Null];
+        for column in &self.pushdown {
+            if let Some(value) = filter.text_eq(column) {
+                params.push(SqlValue::Text(value.to_string()));
+                sql.push_str(&format!(" AND t.{column} = ?{}", params.len()));
+            }
+        }
+        sql.push_str(&format!(
+            " ORDER BY w.ordinal, t.rowid LIMIT {SCAN_CHUNK_ROWS}"
+        ));
+
+        // The caches are shared by every scan of the snapshot, so rows are
+        // read under the lock a chunk at a time, resuming after the last row
+        // read, and emitted after it.
+        let repos = self.snapshot.with_caches(|caches| Ok(caches.len()))?;
+        for i in 0..repos {
+            let mut after = (-1, i64::MIN);
+            loop {
+                params[0] = SqlValue::Integer(after.0);
+                params[1] = SqlValue::Integer(after.1);
+                let chunk = self.snapshot.with_caches(|caches| {
+                    let mut stmt = caches[i].prepare_cached(&sql)?;
+                    let width = stmt.column_count();
+                    let mut found = Vec::new();
+                    let mut rows = stmt.query(rusqlite::params_from_iter(&params))?;
+                    while let Some(row) = rows.next()? {
+                        let ordinal: i64 = row.get(0)?;
+                        let rowid: i64 = row.get(1)?;
+                        let values = (2..width)
+                            .map(|j| row.get::<_, SqlValue>(j))
+                            .collect::<rusqlite::Result<Vec<_>>>()?;
+                        found.push((ordinal, rowid, values));
+                    }
+                    Ok(found)
+                })?;
+                #[cfg(test)]
+                self.rows_read
+                    .fetch_add(chunk.len(), std::sync::atomic::Ordering::Relaxed);
+                let complete = chunk.len() < SCAN_CHUNK_ROWS;
+                for (ordinal, rowid, values) in chunk {
+                    after = (ordinal, rowid);
+                    if !emit(rowid * repos as i64 + i as i64, values) {
+                        return Ok(());
+                    }
+                }
+                if complete {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Every pushdown column is indexed in the caches.
+    fn eq_cost(&self, _column: &str) -> f64 {
+        1_000.0
+    }
+}
+
+fn create_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS index_meta (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS indexed_commits (
+            commit_id TEXT PRIMARY KEY
+        );
+        ",
+    )?;
+    conn.execute(
+        "INSERT OR REPLACE INTO index_meta (key, value) VALUES ('schema_version', ?1)",
+        [SCHEMA_VERSION.to_string()],
+    )?;
+    for (table, commit_column) in INDEXED_TABLES {
+        let info = vcsql::sql::get_table_info(table)
+            .ok_or_else(|| Error::Vcsql(format!("unknown table {table}")))?;
+        conn.execute(info.create_sql, [])?;
+        conn.execute(
+            &format!("CREATE INDEX IF NOT EXISTS {table}_by_commit ON {table} ({commit_column})"),
+            [],
+        )?;
+    }
+    for (table, column) in PUSHDOWN_COLUMNS {
+        conn.execute(
+            &format!("CREATE INDEX IF NOT EXISTS {table}_by_{column} ON {table} ({column})"),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(repo: &git2::Repository, name: &str, message: &str) -> git2::Oid {
+        std::fs::write(repo.workdir().expect("workdir").join(name), message).expect("write");
+        let mut index = repo.index().expect("index");
+        index.add_path(Path::new(name)).expect("add");
+        index.write().expect("write index");
+        let tree = repo
+            .find_tree(index.write_tree().expect("tree"))
+            .expect("find tree");
+        let sig = git2::Signature::now("Test", "test@example.com").expect("sig");
+        let parents: Vec<git2::Commit> = repo
+            .head()
+            .ok()
+            .and_then(|head| head.peel_to_commit().ok())
+            .into_iter()
+            .collect();
+        let parents: Vec<&git2::Commit> = parents.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+            .expect("commit")
+    }
+
+    fn count(index: &GitIndex, table: &str) -> i64 {
+        index
+            .connection()
+            .query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+                row.get(0)
+            })
+            .expect("count")
+    }
+
+    #[test]
+    fn second_sync_only_indexes_commits_added_since_the_last_one() {
+        let temp = tempfile::tempdir().expect("temp");
+        let repo_dir = temp.path().join("repo");
+        let repo = git2::Repository::init(&repo_dir).expect("git init");
+        commit(&repo, "a.txt", "first");
+        commit(&repo, "b.txt", "second");
+
+        let cache = temp.path().join("cache/git.sqlite");
+        let git_repo = GitRepo::open(&repo_dir).expect("open");
+        let mut index = GitIndex::open_at(&cache).expect("open index");
+        let (order, first) = index.sync(&git_repo).expect("first sync");
+        assert_eq!(first.indexed_commits, 2);
+        assert_eq!(order.len(), 2);
+
+        let (_, unchanged) = index.sync(&git_repo).expect("unchanged sync");
+        assert_eq!(unchanged.indexed_commits, 0);
+        assert_eq!(unchanged.reachable_commits, 2);
+
+        let third = commit(&repo, "c.txt", "third");
+        let (order, moved) = index.sync(&git_repo).expect("sync after HEAD moved");
+        assert_eq!(moved.indexed_commits, 1);
+        assert_eq!(order[0], third.to_string());
+        assert_eq!(count(&index, "commits"), 3);
+        assert_eq!(count(&index, "diff_files"), 3);
+    }
+
+    #[test]
+    fn scanned_rows_follow_the_current_history_only() {
+        let temp = tempfile::tempdir().expect("temp");
+        let repo_dir = temp.path().join("repo");
+        let repo = git2::Repository::init(&repo_dir).expect("git init");
+        let first = commit(&repo, "a.txt", "first");
+        commit(&repo, "b.txt", "abandoned");
+
+        let cache = temp.path().join("cache/git.sqlite");
+        let git_repo = GitRepo::open(&repo_dir).expect("open");
+        let mut index = GitIndex::open_at(&cache).expect("open index");
+        index.sync(&git_repo).expect("first sync");
+
+        let target = repo.find_object(first, None).expect("first commit");
+        repo.reset(&target, git2::ResetType::Hard, None)
+            .expect("reset");
+        assert_eq!(count(&index, "commits"), 2);
+        drop(index);
+
+        let snapshot = Arc::new(GitSnapshot {
+            repos: vec![(repo_dir.clone(), cache.clone())],
+            window: TimeWindow::default(),
+            caches: Mutex::new(None),
+        });
+        let conn = Connection::open_in_memory().expect("memory");
+        for table in ["commits", "diff_files"] {
+            let source = LazyGitTable::new(table, snapshot.clone())
+                .expect("columns")
+                .expect("indexed table");
+            crate::lazy::register(&conn, table, Arc::new(source)).expect("register");
+        }
+        let summaries: Vec<String> = conn
+            .prepare("SELECT summary FROM commits")
+            .expect("prepare")
+            .query_map([], |row| row.get(0))
+            .expect("query")
+            .collect::<std::result::Result<_, _>>()
+            .expect("rows");
+        assert_eq!(summaries, vec!["first".to_string()]);
+
+        let paths: Vec<String> = conn
+            .prepare("SELECT path FROM diff_files WHERE commit_id = ?1")
+            .expect("prepare")
+            .query_map([first.to_string()], |row| row.get(0))
+            .expect("query")
+            .collect::<std::result::Result<_, _>>()
+            .expect("rows");
+        assert_eq!(paths, vec!["a.txt".to_string()]);
+        let abandoned: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM diff_files WHERE path = 'b.txt'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("abandoned");
+        assert_eq!(abandoned, 0, "unreachable commits stay hidden");
+    }
+
+    #[test]
+    fn a_limited_query_stops_reading_after_the_chunk_it_needs() {
+        let temp = tempfile::tempdir().expect("temp");
+        let repo_dir = temp.path().join("repo");
+        let repo = git2::Repository::init(&repo_dir).expect("git init");
+        let files = SCAN_CHUNK_ROWS * 8;
+        let mut index = repo.index().expect("index");
+        for n in 1..files {
+            let name = format!("file{n}.txt");
+            std::fs::write(repo_dir.join(&name), "content").expect("write");
+            index.add_path(Path::new(&name)).expect("add");
+        }
+        index.write().expect("write index");
+        commit(&repo, "file0.txt", "many files");
+
+        let snapshot = Arc::new(GitSnapshot {
+            repos: vec![(repo_dir.clone(), temp.path().join("cache/git.sqlite"))],
+            window: TimeWindow::default(),
+            caches: Mutex::new(None),
+        });
+        let source = Arc::new(
+            LazyGitTable::new("diff_files", snapshot)
+                .expect("columns")
+                .expect("indexed table"),
+        );
+        let conn = Connection::open_in_memory().expect("memory");
+        crate::lazy::register(&conn, "diff_files", source.clone()).expect("register");
+
+        let total: i64 = conn
+            .query_row("SELECT COUNT(*) FROM diff_files", [], |row| row.get(0))
+            .expect("count");
+        assert_eq!(total, files as i64, "a full scan reads every chunk");
+
+        source
+            .rows_read
+            .store(0, std::sync::atomic::Ordering::Relaxed);
+        let first: String = conn
+            .query_row("SELECT path FROM diff_files LIMIT 1", [], |row| row.get(0))
+            .expect("first row");
+        assert!(first.starts_with("file"));
+        let read = source.rows_read.load(std::sync::atomic::Ordering::Relaxed);
+        assert!(
+            read > 0 && read <= 2 * SCAN_CHUNK_ROWS,
+            "read {read} of {files} rows for LIMIT 1"
+        );
+    }
+
+    #[test]
+    fn schema_version_change_rebuilds_the_disposable_cache() {
+        let temp = tempfile::tempdir().expect("temp");
+        let cache = temp.path().join("cache/git.sqlite");
+        let index = GitIndex::open_at(&cache).expect("open");
+        index
+            .connection()
+            .execute(
+                "INSERT INTO indexed_commits (commit_id) VALUES ('stale')",
+                [],
+            )
+            .expect("stale row");
+        index
+            .connection()
+            .pragma_update(None, "user_version", 99)
+            .expect("version");
+        drop(index);
+
+        let rebuilt = GitIndex::open_at(&cache).expect("reopen");
+        assert_eq!(count(&rebuilt, "indexed_commits"), 0);
+    }
+}
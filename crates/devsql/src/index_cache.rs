@@ -0,0 +1,158 @@
+//! Shared plumbing for devsql's disposable on-disk SQLite caches.
+//!
+//! Each cache is rebuildable from its source: a corrupt file or a
+//! `user_version` other than the caller's schema version is deleted and
+//! recreated rather than migrated. Connections use WAL so concurrent devsql
+//! processes can read while one of them syncs.
+
+use crate::Result;
+use rusqlite::{Connection, ErrorCode};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Cache file for `source` under the user's cache directory, named by the
+/// SHA-256 of its canonical path (e.g. `~/.cache/devsql/<kind>/<digest>.sqlite`).
+pub(crate) fn cache_path_for(kind: &str, source: &Path) -> PathBuf {
+    let cache_root = dirs::cache_dir()
+        .unwrap_or_else(|| std::env::temp_dir().join("devsql-cache"))
+        .join("devsql")
+        .join(kind);
+    let canonical_source = source
+        .canonicalize()
+        .unwrap_or_else(|_| source.to_path_buf());
+    let digest = Sha256::digest(canonical_source.to_string_lossy().as_bytes());
+    cache_root.join(format!("{digest:x}.sqlite"))
+}
+
+/// Open the cache at `cache_path`, recreating it when it is corrupt or was
+/// written by a different `schema_version`, then apply `create_schema`.
+pub(crate) fn open_versioned(
+    cache_path: &Path,
+    schema_version: i64,
+    create_schema: impl Fn(&Connection) -> Result<()>,
+) -> Result<Connection> {
+    if let Some(parent) = cache_path.parent() {
+        fs::create_dir_all(parent)?;
+        set_private_directory_permissions(parent)?;
+    }
+    let mut conn = match open_cache_connection(cache_path) {
+        Ok(conn) => conn,
+        Err(error) if is_corrupt_cache_error(&error) => {
+            remove_cache_files(cache_path)?;
+            open_cache_connection(cache_path)?
+        }
+        Err(error) => return Err(error),
+    };
+    let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version != 0 && version != schema_version {
+        drop(conn);
+        remove_cache_files(cache_path)?;
+        conn = open_cache_connection(cache_path)?;
+    }
+    create_schema(&conn)?;
+    conn.pragma_update(None, "user_version", schema_version)?;
+    Ok(conn)
+}
+
+fn open_cache_connection(cache_path: &Path) -> Result<Connection> {
+    let conn = Connection::open(cache_path)?;
+    set_private_file_permissions(cache_path)?;
+    conn.pragma_update(None, "foreign_keys", "ON")?;
+    conn.busy_timeout(Duration::from_secs(30))?;
+    ensure_wal_mode(&conn)?;
+    set_private_file_permissions(cache_path)?;
+    set_cache_sidecar_permissions(cache_path)?;
+    Ok(conn)
+}
+
+fn ensure_wal_mode(conn: &Connection) -> Result<()> {
+    let started = Instant::now();
+    loop {
+        let result = (|| -> rusqlite::Result<()> {
+            let mode: String = conn.pragma_query_value(None, "journal_mode", |row| row.get(0))?;
+            if !mode.eq_ignore_ascii_case("wal") {
+                conn.pragma_update(None, "journal_mode", "WAL")?;
+            }
+            Ok(())
+        })();
+
+        match result {
+            Ok(()) => return Ok(()),
+            Err(error)
+                if is_busy_sql_error(&error) && started.elapsed() < Duration::from_secs(30) =>
+            {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Err(error) => return Err(error.into()),
+        }
+    }
+}
+
+fn is_busy_sql_error(error: &rusqlite::Error) -> bool {
+    matches!(
+        error,
+        rusqlite::Error::SqliteFailure(code, _)
+            if matches!(code.code, ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+    )
+}
+
+fn is_corrupt_cache_error(error: &crate::Error) -> bool {
+    matches!(
+        error,
+        crate::Error::Sql(rusqlite::Error::SqliteFailure(code, _))
+            if matches!(code.code, ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase)
+    )
+}
+
+pub(crate) fn remove_cache_files(cache_path: &Path) -> Result<()> {
+    for path in [
+        cache_path.to_path_buf(),
+        PathBuf::from(format!("{}-wal", cache_path.to_string_lossy())),
+        PathBuf::from(format!("{}-shm", cache_path.to_string_lossy())),
+    ] {
+        match fs::remove_file(path) {
+            Ok(()) => {}
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error.into()),
+        }
+    }
+    Ok(())
+}
+
+fn set_cache_sidecar_permissions(cache_path: &Path) -> Result<()> {
+    for path in [
+        PathBuf::from(format!("{}-wal", cache_path.to_string_lossy())),
+        PathBuf::from(format!("{}-shm", cache_path.to_string_lossy())),
+    ] {
+        if path.exists() {
+            set_private_file_permissions(&path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_private_directory_permissions(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o700))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_private_directory_permissions(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_private_file_permissions(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_private_file_permissions(_path: &Path) -> Result<()> {
+    Ok(())
+}
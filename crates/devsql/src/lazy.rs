@@ -0,0 +1,671 @@
+//! Lazily scanned virtual tables with constraint pushdown.
+//!
+//! A [`LazyTable`] is registered with SQLite as a virtual table instead of
+//! being copied into the connection before the query runs. Each scan receives
+//! the `WHERE` constraints on the table's pushdown columns, so a source can
+//! skip whole files, sessions, or commits that cannot match. Constraints are
+//! never marked as consumed: SQLite still checks every row a scan returns, so
+//! a source only has to avoid skipping rows that might match.
+//!
+//! A cursor's first scan runs on its own thread and streams rows to SQLite
+//! through a bounded channel, so reading a whole table never holds it in
+//! memory. SQLite scans the inner table of a join again for every outer row;
+//! those rescans are cached per constraint set for the life of the statement,
+//! and an unconstrained rescan, or more than [`CACHED_RESCANS`] distinct
+//! ones, reads the table once and answers the rest from memory.
+
+use crate::{Error, Result};
+use rusqlite::ffi;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::vtab::{
+    read_only_module, Context, CreateVTab, IndexConstraintOp, IndexInfo, VTab, VTabConnection,
+    VTabCursor, VTabKind, Values,
+};
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::os::raw::c_int;
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+
+/// A table whose rows are produced on demand for each scan.
+pub(crate) trait LazyTable: Send + Sync {
+    /// `(name, declared type)` of each column, in order.
+    fn columns(&self) -> &[(String, String)];
+
+    /// Columns whose constraints are passed to [`LazyTable::scan`].
+    fn pushdown(&self) -> &[&'static str];
+
+    /// Emit `(rowid, values)` for every row that may satisfy `filter`, and
+    /// return once `emit` returns `false` because no more rows are wanted.
+    ///
+    /// Scans run on their own thread, possibly alongside another scan of the
+    /// same table, so `emit` must not be called while holding a lock another
+    /// scan needs.
+    fn scan(&self, filter: &Filter, emit: &mut dyn FnMut(i64, Vec<SqlValue>) -> bool)
+        -> Result<()>;
+
+    /// Planner cost of a scan that pins `column` by equality, against
+    /// [`FULL_SCAN_COST`] for one with no usable constraint. Most sources
+    /// still walk every file to find the ones that match, so by default only
+    /// the reads they skip are saved.
+    fn eq_cost(&self, _column: &str) -> f64 {
+        FULL_SCAN_COST / 10.0
+    }
+}
+
+/// Register `table` as the virtual table `name` on `conn`.
+pub(crate) fn register(conn: &Connection, name: &str, table: Arc<dyn LazyTable>) -> Result<()> {
+    let module = format!("devsql_{name}");
+    conn.create_module(&module, read_only_module::<LazyVTab>(), Some(table))?;
+    conn.execute_batch(&format!("CREATE VIRTUAL TABLE {name} USING {module}"))?;
+    Ok(())
+}
+
+/// Whether `name` is a table registered through [`register`].
+pub(crate) fn is_lazy(conn: &Connection, name: &str) -> Result<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master
+         WHERE type = 'table' AND name = ?1 AND sql LIKE 'CREATE VIRTUAL TABLE % USING devsql\\_%' ESCAPE '\\'",
+        [name],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+/// Build a column list for [`LazyTable::columns`].
+pub(crate) fn columns(columns: &[(&str, &str)]) -> Vec<(String, String)> {
+    columns
+        .iter()
+        .map(|(name, ty)| (name.to_string(), ty.to_string()))
+        .collect()
+}
+
+/// The column list of the table `create_sql` defines.
+pub(crate) fn columns_of(create_sql: &str) -> Result<Vec<(String, String)>> {
+    let scratch = Connection::open_in_memory()?;
+    scratch.execute(create_sql, [])?;
+    let table: String =
+        scratch.query_row("SELECT name FROM sqlite_master", [], |row| row.get(0))?;
+    let mut stmt = scratch.prepare("SELECT name, type FROM pragma_table_info(?1) ORDER BY cid")?;
+    let columns = stmt
+        .query_map([table], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(columns)
+}
+
+/// Comparison operators a scan can be handed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Op {
+    Eq,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Op {
+    fn from_constraint(op: IndexConstraintOp) -> Option<Self> {
+        match op {
+            IndexConstraintOp::SQLITE_INDEX_CONSTRAINT_EQ => Some(Self::Eq),
+            IndexConstraintOp::SQLITE_INDEX_CONSTRAINT_GT => Some(Self::Gt),
+            IndexConstraintOp::SQLITE_INDEX_CONSTRAINT_GE => Some(Self::Ge),
+            IndexConstraintOp::SQLITE_INDEX_CONSTRAINT_LT => Some(Self::Lt),
+            IndexConstraintOp::SQLITE_INDEX_CONSTRAINT_LE => Some(Self::Le),
+            _ => None,
+        }
+    }
+
+    fn code(self) -> char {
+        match self {
+            Self::Eq => '=',
+            Self::Gt => '>',
+            Self::Ge => 'g',
+            Self::Lt => '<',
+            Self::Le => 'l',
+        }
+    }
+
+    fn from_code(code: char) -> Option<Self> {
+        [Self::Eq, Self::Gt, Self::Ge, Self::Lt, Self::Le]
+            .into_iter()
+            .find(|op| op.code() == code)
+    }
+}
+
+/// The pushed-down constraints of one scan.
+#[derive(Debug, Default)]
+pub(crate) struct Filter {
+    constraints: Vec<(String, Op, SqlValue)>,
+}
+
+impl Filter {
+    /// The text `column` must equal, when the query pins it.
+    pub(crate) fn text_eq(&self, column: &str) -> Option<&str> {
+        self.constraints
+            .iter()
+            .find_map(|(name, op, value)| match value {
+                SqlValue::Text(text) if name == column && *op == Op::Eq => Some(text.as_str()),
+                _ => None,
+            })
+    }
+
+    /// The greatest text lower bound (`>`, `>=` or `=`) on `column`.
+    pub(crate) fn text_lower_bound(&self, column: &str) -> Option<&str> {
+        self.constraints
+            .iter()
+            .filter_map(|(name, op, value)| match value {
+                SqlValue::Text(text)
+                    if name == column && matches!(op, Op::Eq | Op::Gt | Op::Ge) =>
+                {
+                    Some(text.as_str())
+                }
+                _ => None,
+            })
+            .max()
+    }
+}
+
+/// Planner cost of a scan with no usable constraint.
+pub(crate) const FULL_SCAN_COST: f64 = 1_000_000.0;
+
+/// Planner cost of a scan with a pushed-down range constraint, which skips
+/// only the files that cannot reach the bound.
+const RANGE_SCAN_COST: f64 = FULL_SCAN_COST / 5.0;
+
+/// Distinct constrained rescans one cursor answers by scanning the source
+/// again before it reads the whole table once instead.
+const CACHED_RESCANS: usize = 4;
+
+/// Rows a streaming scan may produce ahead of SQLite reading them.
+const STREAMED_ROWS: usize = 256;
+
+/// `(rowid, values)` of one row.
+type Row = (i64, Vec<SqlValue>);
+
+#[repr(C)]
+struct LazyVTab {
+    /// Base class. Must be first.
+    base: ffi::sqlite3_vtab,
+    table: Arc<dyn LazyTable>,
+}
+
+impl LazyVTab {
+    fn column_name(&self, column: c_int) -> Option<&str> {
+        if column < 0 {
+            return Some("rowid");
+        }
+        let (name, _) = self.table.columns().get(column as usize)?;
+        Some(name)
+    }
+}
+
+unsafe impl<'vtab> VTab<'vtab> for LazyVTab {
+    type Aux = Arc<dyn LazyTable>;
+    type Cursor = LazyCursor;
+
+    fn connect(
+        _db: &mut VTabConnection,
+        aux: Option<&Self::Aux>,
+        _args: &[&[u8]],
+    ) -> rusqlite::Result<(String, Self)> {
+        let table = aux
+            .cloned()
+            .ok_or_else(|| rusqlite::Error::ModuleError("lazy table has no source".into()))?;
+        let columns: Vec<String> = table
+            .columns()
+            .iter()
+            .map(|(name, ty)| format!("\"{name}\" {ty}"))
+            .collect();
+        let schema = format!("CREATE TABLE x({})", columns.join(", "));
+        Ok((
+            schema,
+            Self {
+                base: ffi::sqlite3_vtab::default(),
+                table,
+            },
+        ))
+    }
+
+    fn best_index(&self, info: &mut IndexInfo) -> rusqlite::Result<()> {
+        let pushdown = self.table.pushdown();
+        let mut plan = String::new();
+        let mut argv_index = 0;
+        let mut cost = FULL_SCAN_COST;
+        for (constraint, mut usage) in info.constraints_and_usages() {
+            if !constraint.is_usable() {
+                continue;
+            }
+            let Some(op) = Op::from_constraint(constraint.operator()) else {
+                continue;
+            };
+            let column = constraint.column();
+            if !self
+                .column_name(column)
+                .is_some_and(|name| pushdown.contains(&name))
+            {
+                continue;
+            }
+            argv_index += 1;
+            usage.set_argv_index(argv_index);
+            plan.push_str(&format!("{column}{},", op.code()));
+            cost = cost.min(match (op, self.column_name(column)) {
+                (Op::Eq, Some("rowid")) => 1.0,
+                (Op::Eq, Some(name)) => self.table.eq_cost(name),
+                _ => RANGE_SCAN_COST,
+            });
+        }
+        info.set_idx_str(&plan);
+        info.set_estimated_cost(cost);
+        info.set_estimated_rows(cost as i64);
+        Ok(())
+    }
+
+    fn open(&'vtab mut self) -> rusqlite::Result<LazyCursor> {
+        Ok(LazyCursor {
+            base: ffi::sqlite3_vtab_cursor::default(),
+            table: Arc::clone(&self.table),
+            scans: 0,
+            rescans: HashMap::new(),
+            materialized: None,
+            rows: Rows::Done,
+        })
+    }
+}
+
+impl CreateVTab<'_> for LazyVTab {
+    const KIND: VTabKind = VTabKind::Default;
+}
+
+#[repr(C)]
+struct LazyCursor {
+    /// Base class. Must be first.
+    base: ffi::sqlite3_vtab_cursor,
+    table: Arc<dyn LazyTable>,
+    /// `filter` calls so far: only the first streams.
+    scans: usize,
+    /// Rows of each constrained rescan, keyed by its constraints.
+    rescans: HashMap<String, Arc<Vec<Row>>>,
+    /// The whole table, once rescans made reading it worthwhile.
+    materialized: Option<Materialized>,
+    rows: Rows,
+}
+
+impl LazyCursor {
+    fn row(&self) -> Option<&Row> {
+        match &self.rows {
+            Rows::Done => None,
+            Rows::Streamed(stream) => stream.current.as_ref(),
+            Rows::Replayed {
+                rows,
+                selection,
+                position,
+            } => match selection {
+                Some(selection) => rows.get(*selection.get(*position)?),
+                None => rows.get(*position),
+            },
+        }
+    }
+
+    /// Every row that may satisfy `filter`, read on this thread.
+    fn collect(&self, filter: &Filter) -> rusqlite::Result<Vec<Row>> {
+        let mut rows = Vec::new();
+        self.table
+            .scan(filter, &mut |rowid, values| {
+                rows.push((rowid, values));
+                true
+            })
+            .map_err(scan_error)?;
+        Ok(rows)
+    }
+}
+
+/// Where a cursor's rows come from.
+enum Rows {
+    Done,
+    Streamed(Stream),
+    /// Rows read by an earlier scan, optionally only those at `selection`.
+    Replayed {
+        rows: Arc<Vec<Row>>,
+        selection: Option<Vec<usize>>,
+        position: usize,
+    },
+}
+
+/// A scan running on its own thread.
+struct Stream {
+    receiver: Option<mpsc::Receiver<Result<Row>>>,
+    scan: Option<JoinHandle<()>>,
+    current: Option<Row>,
+}
+
+impl Stream {
+    fn start(table: Arc<dyn LazyTable>, filter: Filter) -> rusqlite::Result<Self> {
+        let (sender, receiver) = mpsc::sync_channel(STREAMED_ROWS);
+        let scan = std::thread::spawn(move || {
+            let scanned = table.scan(&filter, &mut |rowid, values| {
+                sender.send(Ok((rowid, values))).is_ok()
+            });
+            if let Err(e) = scanned {
+                let _ = sender.send(Err(e));
+            }
+        });
+        let mut stream = Self {
+            receiver: Some(receiver),
+            scan: Some(scan),
+            current: None,
+        };
+        stream.advance()?;
+        Ok(stream)
+    }
+
+    fn advance(&mut self) -> rusqlite::Result<()> {
+        self.current = None;
+        let Some(receiver) = &self.receiver else {
+            return Ok(());
+        };
+        match receiver.recv() {
+            Ok(Ok(row)) => {
+                self.current = Some(row);
+                Ok(())
+            }
+            Ok(Err(e)) => {
+                self.stop()?;
+                Err(scan_error(e))
+            }
+            Err(_) => self.stop(),
+        }
+    }
+
+    /// Tell the scan no more rows are wanted and wait for its thread.
+    fn stop(&mut self) -> rusqlite::Result<()> {
+        self.receiver = None;
+        match self.scan.take().map(JoinHandle::join) {
+            Some(Err(_)) => Err(rusqlite::Error::ModuleError(
+                "lazy table scan panicked".into(),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Drop for Stream {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// Every row of a table, with equality indexes built the first time a
+/// rescan pins each column.
+struct Materialized {
+    rows: Arc<Vec<Row>>,
+    indexes: HashMap<c_int, HashMap<String, Vec<usize>>>,
+}
+
+impl Materialized {
+    /// Replay the rows that may satisfy `constraints`.
+    fn replay(&mut self, constraints: &[(c_int, Op, SqlValue)]) -> Rows {
+        let selection = constraints.iter().find_map(|(column, op, value)| {
+            let key = index_key(value).filter(|_| *op == Op::Eq)?;
+            let rows = &self.rows;
+            let index = self.indexes.entry(*column).or_insert_with(|| {
+                let mut index: HashMap<String, Vec<usize>> = HashMap::new();
+                for (position, (rowid, values)) in rows.iter().enumerate() {
+                    let value = if *column < 0 {
+                        Some(SqlValue::Integer(*rowid))
+                    } else {
+                        values.get(*column as usize).cloned()
+                    };
+                    if let Some(key) = value.as_ref().and_then(index_key) {
+                        index.entry(key).or_default().push(position);
+                    }
+                }
+                index
+            });
+            Some(index.get(&key).cloned().unwrap_or_default())
+        });
+        Rows::Replayed {
+            rows: Arc::clone(&self.rows),
+            selection,
+            position: 0,
+        }
+    }
+}
+
+/// The key equal values share in a [`Materialized`] index. Text that reads
+/// as a number has none, since SQLite may compare it to a number as equal,
+/// so a constraint on it replays every row.
+fn index_key(value: &SqlValue) -> Option<String> {
+    match value {
+        SqlValue::Integer(n) => Some(n.to_string()),
+        SqlValue::Real(r) if r.fract() == 0.0 && r.abs() < 1e15 => Some((*r as i64).to_string()),
+        SqlValue::Real(r) => Some(r.to_string()),
+        SqlValue::Text(text) if text.trim().parse::<f64>().is_err() => Some(text.clone()),
+        _ => None,
+    }
+}
+
+fn scan_error(error: Error) -> rusqlite::Error {
+    match error {
+        Error::Sql(e) => e,
+        other => rusqlite::Error::ModuleError(other.to_string()),
+    }
+}
+
+unsafe impl VTabCursor for LazyCursor {
+    fn filter(
+        &mut self,
+        _idx_num: c_int,
+        idx_str: Option<&str>,
+        args: &Values<'_>,
+    ) -> rusqlite::Result<()> {
+        let mut constraints = Vec::new();
+        let plan = idx_str.unwrap_or_default().split_terminator(',');
+        for (i, entry) in plan.enumerate() {
+            let Some(code) = entry.chars().last() else {
+                continue;
+            };
+            let column: c_int = entry[..entry.len() - code.len_utf8()]
+                .parse()
+                .map_err(|_| rusqlite::Error::ModuleError(format!("bad scan plan {entry}")))?;
+            if let Some(op) = Op::from_code(code) {
+                constraints.push((column, op, args.get::<SqlValue>(i)?));
+            }
+        }
+        let filter = Filter {
+            constraints: constraints
+                .iter()
+                .map(|(column, op, value)| {
+                    let name = if *column < 0 {
+                        "rowid".to_string()
+                    } else {
+                        self.table.columns()[*column as usize].0.clone()
+                    };
+                    (name, *op, value.clone())
+                })
+                .collect(),
+        };
+
+        // Stop a scan still streaming before starting the next.
+        self.rows = Rows::Done;
+        self.scans += 1;
+        if self.scans == 1 {
+            self.rows = Rows::Streamed(Stream::start(Arc::clone(&self.table), filter)?);
+            return Ok(());
+        }
+        if self.materialized.is_none()
+            && (filter.constraints.is_empty() || self.rescans.len() >= CACHED_RESCANS)
+        {
+            self.materialized = Some(Materialized {
+                rows: Arc::new(self.collect(&Filter::default())?),
+                indexes: HashMap::new(),
+            });
+            self.rescans.clear();
+        }
+        if let Some(materialized) = &mut self.materialized {
+            self.rows = materialized.replay(&constraints);
+            return Ok(());
+        }
+
+        let key = format!("{:?}", filter.constraints);
+        let rows = match self.rescans.get(&key) {
+            Some(rows) => Arc::clone(rows),
+            None => {
+                let rows = Arc::new(self.collect(&filter)?);
+                self.rescans.insert(key, Arc::clone(&rows));
+                rows
+            }
+        };
+        self.rows = Rows::Replayed {
+            rows,
+            selection: None,
+            position: 0,
+        };
+        Ok(())
+    }
+
+    fn next(&mut self) -> rusqlite::Result<()> {
+        match &mut self.rows {
+            Rows::Done => Ok(()),
+            Rows::Streamed(stream) => stream.advance(),
+            Rows::Replayed { position, .. } => {
+                *position += 1;
+                Ok(())
+            }
+        }
+    }
+
+    fn eof(&self) -> bool {
+        self.row().is_none()
+    }
+
+    fn column(&self, ctx: &mut Context, i: c_int) -> rusqlite::Result<()> {
+        let value = self
+            .row()
+            .and_then(|(_, values)| values.get(i as usize))
+            .unwrap_or(&SqlValue::Null);
+        ctx.set_result(value)
+    }
+
+    fn rowid(&self) -> rusqlite::Result<i64> {
+        Ok(self.row().map_or(0, |(rowid, _)| *rowid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    struct Numbers {
+        columns: Vec<(String, String)>,
+        scans: Mutex<Vec<Option<String>>>,
+    }
+
+    impl LazyTable for Numbers {
+        fn columns(&self) -> &[(String, String)] {
+            &self.columns
+        }
+
+        fn pushdown(&self) -> &[&'static str] {
+            &["label"]
+        }
+
+        fn scan(
+            &self,
+            filter: &Filter,
+            emit: &mut dyn FnMut(i64, Vec<SqlValue>) -> bool,
+        ) -> Result<()> {
+            let pinned = filter.text_eq("label");
+            self.scans.lock().unwrap().push(pinned.map(String::from));
+            for n in 1..=100 {
+                let label = format!("#{n}");
+                if pinned.is_none_or(|pinned| pinned == label)
+                    && !emit(n, vec![n.into(), label.into()])
+                {
+                    break;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    fn numbers() -> Arc<Numbers> {
+        Arc::new(Numbers {
+            columns: columns(&[("n", "INTEGER"), ("label", "TEXT")]),
+            scans: Mutex::new(Vec::new()),
+        })
+    }
+
+    #[test]
+    fn scans_receive_equality_constraints_and_sqlite_rechecks_rows() {
+        let conn = Connection::open_in_memory().expect("memory");
+        let numbers = numbers();
+        register(&conn, "numbers", numbers.clone()).expect("register");
+        assert!(is_lazy(&conn, "numbers").expect("lookup"));
+
+        let n: i64 = conn
+            .query_row("SELECT n FROM numbers WHERE label = '#42'", [], |row| {
+                row.get(0)
+            })
+            .expect("pinned");
+        assert_eq!(n, 42);
+
+        let count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM numbers WHERE n > 90 AND label != '#95'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("range");
+        assert_eq!(count, 9);
+        assert_eq!(
+            *numbers.scans.lock().unwrap(),
+            vec![Some("#42".to_string()), None]
+        );
+    }
+
+    #[test]
+    fn joins_read_the_inner_table_a_bounded_number_of_times() {
+        let conn = Connection::open_in_memory().expect("memory");
+        let numbers = numbers();
+        register(&conn, "numbers", numbers.clone()).expect("register");
+
+        let pairs: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM numbers a JOIN numbers b ON b.label = a.label",
+                [],
+                |row| row.get(0),
+            )
+            .expect("self-join");
+        assert_eq!(pairs, 100);
+        let scans = numbers.scans.lock().unwrap().len();
+        // One scan of the outer table, then for the inner one its first
+        // scan, the cached rescans, and the scan it is materialized from.
+        assert!(scans <= 3 + CACHED_RESCANS, "{scans} scans");
+
+        let pairs: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM numbers a JOIN numbers b ON b.n > a.n",
+                [],
+                |row| row.get(0),
+            )
+            .expect("unconstrained join");
+        assert_eq!(pairs, 4950);
+        let scans = numbers.scans.lock().unwrap().len() - scans;
+        assert_eq!(scans, 3, "one scan of each side, then one to materialize");
+    }
+
+    #[test]
+    fn a_stream_stops_once_sqlite_needs_no_more_rows() {
+        let conn = Connection::open_in_memory().expect("memory");
+        register(&conn, "numbers", numbers()).expect("register");
+        let first: Vec<i64> = conn
+            .prepare("SELECT n FROM numbers LIMIT 3")
+            .expect("prepare")
+            .query_map([], |row| row.get(0))
+            .expect("query")
+            .collect::<rusqlite::Result<_>>()
+            .expect("rows");
+        assert_eq!(first, vec![1, 2, 3]);
+    }
+}
@@ -3,11 +3,24 @@
 //! This crate combines AI coding history, Git, source code, and shell history
 //! in a unified query interface.
 
+pub mod cli;
 mod codex_index;
+pub mod config;
+pub mod daemon;
 pub mod engine;
 pub mod error;
+mod git_index;
+mod index_cache;
+mod lazy;
+mod parallel;
+pub mod profile;
 pub mod providers;
-mod redaction;
+pub mod repos;
+pub mod saved;
+pub mod schema;
+pub mod server;
+pub mod snapshot;
+pub mod source;
 pub mod tools;
 pub mod worklog;
 
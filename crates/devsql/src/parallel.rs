@@ -0,0 +1,192 @@
+//! Parallel parsing for loaders.
+//!
+//! Loaders read many independent files — transcripts, Codex journals, source
+//! files — but write them into a single SQLite connection. [`for_each_ordered`]
+//! parses the files on a pool of scoped threads and hands each result to one
+//! writer on the caller's thread in input order, so the rows a load inserts,
+//! and the rowids they get, never depend on thread scheduling.
+//!
+//! The pool has one thread per available core; `DEVSQL_THREADS` overrides
+//! that, and `DEVSQL_THREADS=1` parses everything on the caller's thread.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Condvar, Mutex};
+
+/// Environment variable that sets how many threads parse in parallel.
+const THREADS_ENV: &str = "DEVSQL_THREADS";
+
+/// How many results each thread may parse ahead of the writer, which bounds
+/// the parsed results held in memory while a slow item is still parsing.
+const LEAD_PER_THREAD: usize = 4;
+
+/// Threads a load parses with: `DEVSQL_THREADS` if set to a positive number,
+/// otherwise the available parallelism.
+pub(crate) fn threads() -> usize {
+    std::env::var(THREADS_ENV)
+        .ok()
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .filter(|&threads| threads > 0)
+        .unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|threads| threads.get())
+                .unwrap_or(1)
+        })
+}
+
+/// Parse each of `items` with `parse` on a pool of threads and pass each item
+/// and its result to `write`, on the caller's thread, in the order of `items`.
+///
+/// The first error `write` returns stops the pool and is returned.
+pub(crate) fn for_each_ordered<T, R, E, P, W>(items: &[T], parse: P, write: W) -> Result<(), E>
+where
+    T: Sync,
+    R: Send,
+    P: Fn(&T) -> R + Sync,
+    W: FnMut(&T, R) -> Result<(), E>,
+{
+    for_each_ordered_with(items, || (), |(), item| parse(item), write)
+}
+
+/// [`for_each_ordered`] with per-thread state made by `init`, such as a
+/// parser that is costly to create for every item.
+pub(crate) fn for_each_ordered_with<T, S, R, E, I, P, W>(
+    items: &[T],
+    init: I,
+    parse: P,
+    mut write: W,
+) -> Result<(), E>
+where
+    T: Sync,
+    R: Send,
+    I: Fn() -> S + Sync,
+    P: Fn(&mut S, &T) -> R + Sync,
+    W: FnMut(&T, R) -> Result<(), E>,
+{
+    let workers = threads().min(items.len());
+    if workers <= 1 {
+        let mut state = init();
+        for item in items {
+            write(item, parse(&mut state, item))?;
+        }
+        return Ok(());
+    }
+
+    let lead = workers * LEAD_PER_THREAD;
+    let next = AtomicUsize::new(0);
+    let stopped = AtomicBool::new(false);
+    // Items written so far; a thread waits before parsing an item more than
+    // `lead` places past it.
+    let written = Mutex::new(0usize);
+    let advanced = Condvar::new();
+
+    std::thread::scope(|scope| {
+        let (sender, receiver) = mpsc::sync_channel::<(usize, R)>(lead);
+        for _ in 0..workers {
+            let sender = sender.clone();
+            let (next, stopped, written, advanced) = (&next, &stopped, &written, &advanced);
+            let (init, parse) = (&init, &parse);
+            scope.spawn(move || {
+                let mut state = init();
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    if index >= items.len() {
+                        return;
+                    }
+                    {
+                        let mut written = written.lock().unwrap_or_else(|e| e.into_inner());
+                        while index >= *written + lead && !stopped.load(Ordering::Relaxed) {
+                            written = advanced.wait(written).unwrap_or_else(|e| e.into_inner());
+                        }
+                    }
+                    if stopped.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    let parsed = parse(&mut state, &items[index]);
+                    if sender.send((index, parsed)).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        let mut pending = BTreeMap::new();
+        let mut expected = 0;
+        let mut result = Ok(());
+        'receive: for (index, parsed) in &receiver {
+            pending.insert(index, parsed);
+            while let Some(parsed) = pending.remove(&expected) {
+                if let Err(e) = write(&items[expected], parsed) {
+                    result = Err(e);
+                    break 'receive;
+                }
+                expected += 1;
+                *written.lock().unwrap_or_else(|e| e.into_inner()) = expected;
+                advanced.notify_all();
+            }
+        }
+        if result.is_err() {
+            stopped.store(true, Ordering::Relaxed);
+            // Hold the lock while notifying so no thread can miss the stop
+            // between checking it and waiting.
+            let _written = written.lock().unwrap_or_else(|e| e.into_inner());
+            advanced.notify_all();
+        }
+        // Unblock threads waiting to send, then let the scope join them.
+        drop(receiver);
+        result
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results_are_written_in_input_order_whatever_the_parse_time() {
+        let items: Vec<u64> = (0..200).collect();
+        let mut written = Vec::new();
+        for_each_ordered(
+            &items,
+            |&item| {
+                // Early items take longest, so later ones finish first.
+                std::thread::sleep(std::time::Duration::from_micros((200 - item) * 20));
+                item * 2
+            },
+            |_, parsed| {
+                written.push(parsed);
+                Ok::<_, ()>(())
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            written,
+            items.iter().map(|item| item * 2).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn a_write_error_stops_the_pool() {
+        let items: Vec<u64> = (0..10_000).collect();
+        let parsed = AtomicUsize::new(0);
+        let mut written = 0;
+        let result = for_each_ordered(
+            &items,
+            |&item| {
+                parsed.fetch_add(1, Ordering::Relaxed);
+                item
+            },
+            |&item, _| {
+                if item == 10 {
+                    return Err(item);
+                }
+                written += 1;
+                Ok(())
+            },
+        );
+        assert_eq!(result, Err(10));
+        assert_eq!(written, 10);
+        assert!(parsed.load(Ordering::Relaxed) < items.len());
+    }
+}
@@ -0,0 +1,134 @@
+//! Query profiles.
+//!
+//! An engine with profiling enabled ([`UnifiedEngine::enable_profile`])
+//! records which tables a query selected and why, what each data source
+//! cost to load, and what the Codex index sync did. `devsql query
+//! --profile` adds the query's own cost and SQLite's plan and writes the
+//! report to stderr; `--explain` returns it instead of running the query.
+//!
+//! [`UnifiedEngine::enable_profile`]: crate::UnifiedEngine::enable_profile
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::codex_index::SyncStats;
+
+static FILES_READ: AtomicU64 = AtomicU64::new(0);
+
+/// Count one source file opened by a loader or lazy table.
+pub(crate) fn file_read() {
+    FILES_READ.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Source files opened so far by this process. Loads report the change
+/// across them, so reads by another engine running at the same time are
+/// counted too.
+pub fn files_read() -> u64 {
+    FILES_READ.load(Ordering::Relaxed)
+}
+
+/// Everything recorded while answering one query.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Profile {
+    /// Tables selected for loading, in the order they were selected.
+    pub tables: Vec<TableSelection>,
+    /// One step per data source call, in load order.
+    pub loads: Vec<LoadStep>,
+    /// What the Codex index sync did, when a Codex table was loaded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub codex_sync: Option<CodexSync>,
+    /// SQLite's `EXPLAIN QUERY PLAN` for the query.
+    pub query_plan: Vec<PlanStep>,
+    /// The query's own cost; absent when it was explained but not run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: Option<QueryStep>,
+}
+
+impl Profile {
+    /// Record that `table`, provided by `source`, was selected for `reason`.
+    /// A table keeps the first reason it was selected for.
+    pub fn select(&mut self, table: &str, source: &str, reason: &str) {
+        if self.tables.iter().any(|selected| selected.table == table) {
+            return;
+        }
+        self.tables.push(TableSelection {
+            table: table.to_string(),
+            source: source.to_string(),
+            reason: reason.to_string(),
+        });
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TableSelection {
+    pub table: String,
+    /// Category of the data source providing it, such as `GIT`.
+    pub source: String,
+    /// Why it was loaded: read by the query, by a saved query the query
+    /// reads, or by another table's loader.
+    pub reason: String,
+}
+
+/// One data source loading some of its tables.
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadStep {
+    pub source: String,
+    pub tables: Vec<String>,
+    pub wall_ms: f64,
+    /// Rows inserted into the engine's connection. Lazy virtual tables
+    /// insert nothing here; their reads show up under the query.
+    pub rows_inserted: u64,
+    pub files_read: u64,
+}
+
+/// The Codex index sync behind the Codex tables.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct CodexSync {
+    /// Journals last written before `--since`, left unread.
+    pub skipped_journals: usize,
+    /// Journals whose size and mtime match the index.
+    pub unchanged_journals: usize,
+    /// Journals that grew, read from their last complete record.
+    pub appended_journals: usize,
+    /// New or rewritten journals, read from the start.
+    pub reparsed_journals: usize,
+    pub parsed_records: usize,
+    pub pruned_threads: usize,
+}
+
+impl From<SyncStats> for CodexSync {
+    fn from(stats: SyncStats) -> Self {
+        Self {
+            skipped_journals: stats.skipped_journals,
+            unchanged_journals: stats.unchanged_journals,
+            appended_journals: stats.appended_journals,
+            reparsed_journals: stats.parsed_journals - stats.appended_journals,
+            parsed_records: stats.parsed_records,
+            pruned_threads: stats.pruned_threads,
+        }
+    }
+}
+
+/// One row of `EXPLAIN QUERY PLAN`; `parent` is the `id` of the step it
+/// is nested under, or 0.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanStep {
+    pub id: i64,
+    pub parent: i64,
+    pub detail: String,
+}
+
+/// Running the query itself, including files read by lazy tables.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct QueryStep {
+    pub wall_ms: f64,
+    pub rows: usize,
+    pub files_read: u64,
+}
+
+/// `duration` in milliseconds, to the microsecond.
+pub fn millis(duration: Duration) -> f64 {
+    (duration.as_micros() as f64) / 1000.0
+}
@@ -1,10 +1,254 @@
 #![cfg(feature = "tree-sitter-ast")]
 
+//! Provider for the `ast_nodes` table.
+//!
+//! Materializes the significant nodes of each parsed file (see
+//! `is_significant_node`) with their position, nesting, and a text excerpt,
+//! so structural questions can be answered in SQL. `parent_id` points at the
+//! nearest significant ancestor and `depth` counts significant ancestors.
+
 use crate::Result;
-use rusqlite::Connection;
+use rusqlite::{params, Connection, Statement};
 use std::path::Path;
+use tree_sitter::Node;
+
+use super::tree_sitter::{definition_name, is_significant_node, TsLanguageKind, TsParser};
+use super::{detect_language, walk_source_files, FileInfo};
+
+const MAX_FILE_SIZE: u64 = 1_048_576; // 1 MB
+
+/// Longest excerpt stored in `text`, in bytes.
+const MAX_EXCERPT_BYTES: usize = 200;
+
+const INSERT_SQL: &str = r#"
+INSERT INTO ast_nodes (
+    id,
+    parent_id,
+    file_path,
+    kind,
+    name,
+    start_line,
+    start_column,
+    end_line,
+    end_column,
+    start_byte,
+    end_byte,
+    depth,
+    text,
+    language,
+    repo
+) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+"#;
+
+/// A significant node, with ids counted from 0 within its file.
+struct NodeRow {
+    id: i64,
+    parent_id: Option<i64>,
+    kind: &'static str,
+    name: Option<String>,
+    start_line: i64,
+    start_column: i64,
+    end_line: i64,
+    end_column: i64,
+    start_byte: i64,
+    end_byte: i64,
+    depth: i64,
+    text: String,
+}
+
+pub fn load(conn: &Connection, repo_path: &Path, repo: &str) -> Result<()> {
+    conn.execute_batch(super::AST_NODES_TABLE)?;
+    let files = walk_source_files(repo_path);
+
+    conn.execute_batch("BEGIN")?;
+    let mut stmt = conn.prepare(INSERT_SQL)?;
+    let mut next_id: i64 = conn.query_row(
+        "SELECT COALESCE(MAX(id), 0) + 1 FROM ast_nodes",
+        [],
+        |row| row.get(0),
+    )?;
+
+    crate::parallel::for_each_ordered_with(
+        &files,
+        TsParser::new,
+        |parser, file| file_nodes(parser, repo_path, file),
+        |file, nodes| {
+            let Some((language, rows)) = nodes else {
+                return Ok(());
+            };
+            let file_ids = rows.len() as i64;
+            for row in rows {
+                insert_node(&mut stmt, &row, next_id, &file.path, language, repo)?;
+            }
+            next_id += file_ids;
+            Ok::<_, crate::Error>(())
+        },
+    )?;
+
+    drop(stmt);
+    conn.execute_batch("COMMIT")?;
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_ast_nodes_kind ON ast_nodes(kind);
+         CREATE INDEX IF NOT EXISTS idx_ast_nodes_parent ON ast_nodes(parent_id);
+         CREATE INDEX IF NOT EXISTS idx_ast_nodes_file ON ast_nodes(file_path, start_byte);",
+    )?;
+    Ok(())
+}
+
+/// The language and significant nodes of one source file, if it can be
+/// parsed.
+fn file_nodes(
+    parser: &mut TsParser,
+    repo_path: &Path,
+    file: &FileInfo,
+) -> Option<(&'static str, Vec<NodeRow>)> {
+    if file.size > MAX_FILE_SIZE {
+        return None;
+    }
+    let language = detect_language(&file.extension);
+    let lang_kind = TsLanguageKind::from_name(language)?;
+    let abs_path = repo_path.join(&file.path);
+    crate::profile::file_read();
+    let source = std::fs::read_to_string(&abs_path).ok()?;
+    let tree = parser.parse(language, &source)?;
+    Some((
+        language,
+        significant_nodes(lang_kind, tree.root_node(), &source),
+    ))
+}
+
+/// Depth-first walk that collects significant nodes in source order.
+fn significant_nodes(language: TsLanguageKind, root: Node, source: &str) -> Vec<NodeRow> {
+    let mut rows = Vec::new();
+    // (node, nearest significant ancestor id, significant depth)
+    let mut stack: Vec<(Node, Option<i64>, i64)> = vec![(root, None, 0)];
+    while let Some((node, parent_id, depth)) = stack.pop() {
+        let (child_parent, child_depth) = if is_significant_node(language, node.kind()) {
+            let id = rows.len() as i64;
+            rows.push(node_row(id, parent_id, depth, node, source));
+            (Some(id), depth + 1)
+        } else {
+            (parent_id, depth)
+        };
+
+        let mut cursor = node.walk();
+        let children: Vec<Node> = node.named_children(&mut cursor).collect();
+        for child in children.into_iter().rev() {
+            stack.push((child, child_parent, child_depth));
+        }
+    }
+    rows
+}
 
-/// Placeholder loader until tree-sitter-backed AST extraction is implemented.
-pub fn load(_conn: &Connection, _repo_path: &Path) -> Result<()> {
+fn node_row(id: i64, parent_id: Option<i64>, depth: i64, node: Node, source: &str) -> NodeRow {
+    let start = node.start_position();
+    let end = node.end_position();
+    let name = definition_name(node)
+        .and_then(|name| name.utf8_text(source.as_bytes()).ok())
+        .map(String::from);
+    NodeRow {
+        id,
+        parent_id,
+        kind: node.kind(),
+        name,
+        start_line: (start.row + 1) as i64,
+        start_column: (start.column + 1) as i64,
+        end_line: (end.row + 1) as i64,
+        end_column: (end.column + 1) as i64,
+        start_byte: node.start_byte() as i64,
+        end_byte: node.end_byte() as i64,
+        depth,
+        text: excerpt(source, node.start_byte(), node.end_byte()).to_string(),
+    }
+}
+
+/// Insert a node of `file_path`, shifting its file-local ids by `first_id`.
+fn insert_node(
+    stmt: &mut Statement<'_>,
+    row: &NodeRow,
+    first_id: i64,
+    file_path: &str,
+    language: &str,
+    repo: &str,
+) -> Result<()> {
+    stmt.execute(params![
+        row.id + first_id,
+        row.parent_id.map(|parent_id| parent_id + first_id),
+        file_path,
+        row.kind,
+        row.name,
+        row.start_line,
+        row.start_column,
+        row.end_line,
+        row.end_column,
+        row.start_byte,
+        row.end_byte,
+        row.depth,
+        row.text,
+        language,
+        repo,
+    ])?;
     Ok(())
 }
+
+/// The node's source text, cut at `MAX_EXCERPT_BYTES` on a char boundary.
+fn excerpt(source: &str, start: usize, end: usize) -> &str {
+    let mut end = end.min(start + MAX_EXCERPT_BYTES).min(source.len());
+    while !source.is_char_boundary(end) {
+        end -= 1;
+    }
+    source.get(start..end).unwrap_or("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_nodes_link_to_their_nearest_significant_ancestor() {
+        let temp = tempfile::tempdir().expect("temp");
+        std::fs::write(
+            temp.path().join("lib.rs"),
+            "impl Widget {\n    async fn load(&self) {\n        unsafe { fetch().await; }\n    }\n}\n",
+        )
+        .expect("write");
+        let conn = Connection::open_in_memory().expect("memory");
+        load(&conn, temp.path(), "demo").expect("load");
+
+        let unsafe_in_impl: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM ast_nodes u
+                 JOIN ast_nodes i ON i.file_path = u.file_path AND i.kind = 'impl_item'
+                  AND u.start_byte >= i.start_byte AND u.end_byte <= i.end_byte
+                 WHERE u.kind = 'unsafe_block'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("unsafe");
+        assert_eq!(unsafe_in_impl, 1);
+
+        let (name, depth, parent_kind, line): (String, i64, String, i64) = conn
+            .query_row(
+                "SELECT f.name, f.depth, p.kind, f.start_line FROM ast_nodes f
+                 JOIN ast_nodes p ON p.id = f.parent_id
+                 WHERE f.kind = 'function_item'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .expect("function");
+        assert_eq!(name, "load");
+        assert_eq!(depth, 1);
+        assert_eq!(parent_kind, "impl_item");
+        assert_eq!(line, 2);
+    }
+
+    #[test]
+    fn excerpts_stop_on_a_char_boundary() {
+        let source = "é".repeat(MAX_EXCERPT_BYTES);
+        let text = excerpt(&source, 0, source.len());
+        assert_eq!(text.len(), MAX_EXCERPT_BYTES);
+        let source = format!("a{source}");
+        let text = excerpt(&source, 0, source.len());
+        assert_eq!(text.len(), MAX_EXCERPT_BYTES - 1);
+    }
+}
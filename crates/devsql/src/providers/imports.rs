@@ -4,25 +4,13 @@ use crate::Result;
 use rusqlite::{params, Connection, Statement};
 use std::path::Path;
 
-use super::{detect_language, walk_source_files, LineIndex};
 use super::tree_sitter::{import_query, TsLanguageKind, TsParser};
+use super::{detect_language, walk_source_files, FileInfo, LineIndex};
 use streaming_iterator::StreamingIterator;
 use tree_sitter::{Node, QueryCursor, Tree};
 
 const MAX_FILE_SIZE: u64 = 1_048_576; // 1 MB
 
-const CREATE_TABLE_SQL: &str = r#"
-CREATE TABLE IF NOT EXISTS imports (
-    file_path TEXT,
-    line_number INTEGER,
-    module TEXT,
-    name TEXT,
-    alias TEXT,
-    kind TEXT,
-    is_default INTEGER,
-    is_wildcard INTEGER
-)"#;
-
 const INSERT_SQL: &str = r#"
 INSERT INTO imports (
     file_path,
@@ -32,67 +20,71 @@ INSERT INTO imports (
     alias,
     kind,
     is_default,
-    is_wildcard
-) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+    is_wildcard,
+    repo
+) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
 "#;
 
-pub fn load(conn: &Connection, repo_path: &Path) -> Result<()> {
+pub fn load(conn: &Connection, repo_path: &Path, repo: &str) -> Result<()> {
     ensure_table(conn)?;
 
     let files = walk_source_files(repo_path);
     conn.execute_batch("BEGIN")?;
     let mut stmt = conn.prepare(INSERT_SQL)?;
-    let mut parser = TsParser::new();
-
-    for file in files {
-        if file.size > MAX_FILE_SIZE {
-            continue;
-        }
 
-        let language = detect_language(&file.extension);
-        let Some(lang_kind) = TsLanguageKind::from_name(language) else {
-            continue;
-        };
-
-        // Currently only TypeScript/JavaScript imports are implemented.
-        if !matches!(
-            lang_kind,
-            TsLanguageKind::TypeScript | TsLanguageKind::Tsx | TsLanguageKind::JavaScript | TsLanguageKind::Jsx
-        ) {
-            continue;
-        }
+    crate::parallel::for_each_ordered_with(
+        &files,
+        TsParser::new,
+        |parser, file| file_imports(parser, repo_path, file),
+        |_, rows| {
+            for row in rows {
+                insert_row(&mut stmt, &row, repo)?;
+            }
+            Ok::<_, crate::Error>(())
+        },
+    )?;
 
-        let abs_path = repo_path.join(&file.path);
-        let Ok(contents) = std::fs::read_to_string(&abs_path) else {
-            continue;
-        };
+    drop(stmt);
+    conn.execute_batch("COMMIT")?;
+    create_indexes(conn)?;
+    Ok(())
+}
 
-        let line_index = LineIndex::new(&contents);
-        let Some(tree) = parser.parse(language, &contents) else {
-            continue;
-        };
+/// The imports of one source file.
+fn file_imports(parser: &mut TsParser, repo_path: &Path, file: &FileInfo) -> Vec<ImportRow> {
+    if file.size > MAX_FILE_SIZE {
+        return Vec::new();
+    }
 
-        let rows = extract_imports_from_tree(
-            lang_kind,
-            &tree,
-            &line_index,
-            &contents,
-            &file.path,
-        );
+    let language = detect_language(&file.extension);
+    let Some(lang_kind) = TsLanguageKind::from_name(language) else {
+        return Vec::new();
+    };
 
-        for row in rows {
-            insert_row(&mut stmt, &row)?;
-        }
+    // Rust, Python and Go imports are not implemented yet.
+    if matches!(
+        lang_kind,
+        TsLanguageKind::Rust | TsLanguageKind::Python | TsLanguageKind::Go
+    ) {
+        return Vec::new();
     }
 
-    drop(stmt);
-    conn.execute_batch("COMMIT")?;
-    create_indexes(conn)?;
-    Ok(())
+    let abs_path = repo_path.join(&file.path);
+    crate::profile::file_read();
+    let Ok(contents) = std::fs::read_to_string(&abs_path) else {
+        return Vec::new();
+    };
+
+    let line_index = LineIndex::new(&contents);
+    let Some(tree) = parser.parse(language, &contents) else {
+        return Vec::new();
+    };
+
+    extract_imports_from_tree(lang_kind, &tree, &line_index, &contents, &file.path)
 }
 
 fn ensure_table(conn: &Connection) -> Result<()> {
-    conn.execute_batch(CREATE_TABLE_SQL)?;
+    conn.execute_batch(super::IMPORTS_TABLE)?;
     Ok(())
 }
 
@@ -115,7 +107,7 @@ struct ImportRow {
     is_wildcard: bool,
 }
 
-fn insert_row(stmt: &mut Statement<'_>, row: &ImportRow) -> Result<()> {
+fn insert_row(stmt: &mut Statement<'_>, row: &ImportRow, repo: &str) -> Result<()> {
     stmt.execute(params![
         row.file_path,
         row.line_number,
@@ -125,6 +117,7 @@ fn insert_row(stmt: &mut Statement<'_>, row: &ImportRow) -> Result<()> {
         row.kind,
         if row.is_default { 1 } else { 0 },
         if row.is_wildcard { 1 } else { 0 },
+        repo,
     ])?;
     Ok(())
 }
@@ -141,28 +134,155 @@ fn extract_imports_from_tree(
     match language {
         TsLanguageKind::TypeScript | TsLanguageKind::Tsx => {
             rows.extend(extract_ts_like_imports(
-                language,
-                tree,
-                lines,
-                source,
-                file_path,
+                language, tree, lines, source, file_path,
             ));
         }
         TsLanguageKind::JavaScript | TsLanguageKind::Jsx => {
             rows.extend(extract_ts_like_imports(
-                language,
-                tree,
-                lines,
-                source,
-                file_path,
+                language, tree, lines, source, file_path,
+            ));
+        }
+        TsLanguageKind::Rust | TsLanguageKind::Python | TsLanguageKind::Go => {}
+        #[cfg(any(
+            feature = "lang-java",
+            feature = "lang-c",
+            feature = "lang-cpp",
+            feature = "lang-ruby",
+            feature = "lang-csharp",
+            feature = "lang-kotlin"
+        ))]
+        _ => {
+            rows.extend(extract_declared_imports(
+                language, tree, lines, source, file_path,
             ));
         }
-        _ => {}
     }
 
     rows
 }
 
+#[cfg(any(
+    feature = "lang-java",
+    feature = "lang-c",
+    feature = "lang-cpp",
+    feature = "lang-ruby",
+    feature = "lang-csharp",
+    feature = "lang-kotlin"
+))]
+/// Java, C#, and Kotlin imports, C/C++ includes, and Ruby requires: one row per
+/// declaration, with `name` set when a single member is imported.
+fn extract_declared_imports(
+    language: TsLanguageKind,
+    tree: &Tree,
+    lines: &LineIndex<'_>,
+    source: &str,
+    file_path: &str,
+) -> Vec<ImportRow> {
+    let Some(query) = import_query(language) else {
+        return Vec::new();
+    };
+
+    let mut rows = Vec::new();
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(query, tree.root_node(), source.as_bytes());
+
+    while {
+        matches.advance();
+        matches.get().is_some()
+    } {
+        let matched = matches.get().unwrap();
+        for capture in matched.captures {
+            let capture_name = &query.capture_names()[capture.index as usize];
+            let Some(kind) = capture_name.strip_prefix("import.") else {
+                continue;
+            };
+            if let Some(row) = parse_declared_import(capture.node, kind, source, lines, file_path) {
+                rows.push(row);
+            }
+        }
+    }
+
+    rows
+}
+
+#[cfg(any(
+    feature = "lang-java",
+    feature = "lang-c",
+    feature = "lang-cpp",
+    feature = "lang-ruby",
+    feature = "lang-csharp",
+    feature = "lang-kotlin"
+))]
+fn parse_declared_import(
+    node: Node,
+    kind: &str,
+    source: &str,
+    lines: &LineIndex<'_>,
+    file_path: &str,
+) -> Option<ImportRow> {
+    let children = named_children(node);
+    let mut alias = None;
+    let mut is_wildcard = false;
+    let path = match node.kind() {
+        // `import java.util.List;`, `import static a.B.c;`, `import a.b.*;`
+        "import_declaration" => {
+            is_wildcard = children.iter().any(|child| child.kind() == "asterisk");
+            children
+                .iter()
+                .find(|child| matches!(child.kind(), "scoped_identifier" | "identifier"))
+                .map(|child| node_text(*child, source))?
+        }
+        // `#include <vector>` or `#include "config.h"`
+        "preproc_include" => {
+            let path = node_text(node.child_by_field_name("path")?, source);
+            path.trim_matches(['"', '<', '>']).to_string()
+        }
+        // `require "json"`
+        "call" => {
+            let arguments = node.child_by_field_name("arguments")?;
+            let first = named_children(arguments).into_iter().next()?;
+            string_literal_value(first, source)
+        }
+        // `import a.b.C`, `import a.b.*`, or `import a.b.C as D`
+        "import" => {
+            is_wildcard = node_text(node, source).trim_end().ends_with('*');
+            alias = children
+                .get(1)
+                .filter(|child| child.kind() == "identifier")
+                .map(|child| node_text(*child, source));
+            children.first().map(|child| node_text(*child, source))?
+        }
+        // `using System.Text;` or `using Json = Newtonsoft.Json;`
+        "using_directive" => {
+            alias = node
+                .child_by_field_name("name")
+                .map(|name| node_text(name, source));
+            children.last().map(|child| node_text(*child, source))?
+        }
+        _ => return None,
+    };
+
+    // A Java or Kotlin import names its last segment; wildcards name the
+    // package.
+    let (module, name) = match (node.kind(), path.rsplit_once('.')) {
+        ("import_declaration" | "import", Some((module, name))) if !is_wildcard => {
+            (module.to_string(), Some(name.to_string()))
+        }
+        _ => (path, None),
+    };
+
+    Some(ImportRow {
+        file_path: file_path.to_string(),
+        line_number: lines.line_for_byte(node.start_byte()) as i64,
+        module,
+        name,
+        alias,
+        kind: kind.to_string(),
+        is_default: false,
+        is_wildcard,
+    })
+}
+
 fn extract_ts_like_imports(
     language: TsLanguageKind,
     tree: &Tree,
@@ -217,14 +337,7 @@ fn parse_ts_import_statement(
     let mut entries = 0usize;
     for child in named_children(node) {
         if child.kind() == "import_clause" {
-            entries += parse_ts_import_clause(
-                child,
-                source,
-                file_path,
-                line_number,
-                &module,
-                rows,
-            );
+            entries += parse_ts_import_clause(child, source, file_path, line_number, &module, rows);
         }
     }
 
@@ -268,7 +381,10 @@ fn parse_ts_import_clause(
                 });
             }
             "named_imports" => {
-                for spec in named_children(child).into_iter().filter(|n| n.kind() == "import_specifier") {
+                for spec in named_children(child)
+                    .into_iter()
+                    .filter(|n| n.kind() == "import_specifier")
+                {
                     let spec_name = spec
                         .child_by_field_name("name")
                         .map(|n| clean_identifier(node_text(n, source)));
@@ -338,7 +454,10 @@ fn parse_ts_export_statement(
     for child in named_children(node) {
         match child.kind() {
             "export_clause" => {
-                for spec in named_children(child).into_iter().filter(|n| n.kind() == "export_specifier") {
+                for spec in named_children(child)
+                    .into_iter()
+                    .filter(|n| n.kind() == "export_specifier")
+                {
                     let export_name = spec
                         .child_by_field_name("name")
                         .map(|n| clean_identifier(node_text(n, source)));
@@ -436,3 +555,160 @@ fn node_text(node: Node, source: &str) -> String {
     let end = node.end_byte();
     source[start..end].trim().to_string()
 }
+
+#[cfg(all(
+    test,
+    any(
+        feature = "lang-java",
+        feature = "lang-c",
+        feature = "lang-cpp",
+        feature = "lang-ruby",
+        feature = "lang-csharp",
+        feature = "lang-kotlin"
+    )
+))]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "lang-java")]
+    #[test]
+    fn java_imports_split_the_member_from_the_package() {
+        let source = "import java.util.List;\nimport static org.junit.Assert.assertEquals;\nimport java.io.*;\n";
+        let tree = TsParser::new().parse("Java", source).expect("parse");
+        let rows = extract_imports_from_tree(
+            TsLanguageKind::Java,
+            &tree,
+            &LineIndex::new(source),
+            source,
+            "App.java",
+        );
+        let summary: Vec<(i64, &str, Option<&str>, bool)> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.line_number,
+                    row.module.as_str(),
+                    row.name.as_deref(),
+                    row.is_wildcard,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, "java.util", Some("List"), false),
+                (2, "org.junit.Assert", Some("assertEquals"), false),
+                (3, "java.io", None, true),
+            ]
+        );
+    }
+
+    #[cfg(feature = "lang-kotlin")]
+    #[test]
+    fn kotlin_imports_keep_their_alias() {
+        let source = "package app\n\nimport kotlin.collections.List\nimport app.engine.*\nimport app.json.Parser as JsonParser\n";
+        let tree = TsParser::new().parse("Kotlin", source).expect("parse");
+        let rows = extract_imports_from_tree(
+            TsLanguageKind::Kotlin,
+            &tree,
+            &LineIndex::new(source),
+            source,
+            "App.kt",
+        );
+        let summary: Vec<(&str, Option<&str>, Option<&str>, bool)> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.module.as_str(),
+                    row.name.as_deref(),
+                    row.alias.as_deref(),
+                    row.is_wildcard,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("kotlin.collections", Some("List"), None, false),
+                ("app.engine", None, None, true),
+                ("app.json", Some("Parser"), Some("JsonParser"), false),
+            ]
+        );
+    }
+
+    #[cfg(any(
+        feature = "lang-c",
+        feature = "lang-cpp",
+        feature = "lang-ruby",
+        feature = "lang-csharp"
+    ))]
+    fn imports(
+        language: &str,
+        kind: TsLanguageKind,
+        source: &str,
+    ) -> Vec<(String, String, Option<String>)> {
+        let tree = TsParser::new().parse(language, source).expect("parse");
+        extract_imports_from_tree(kind, &tree, &LineIndex::new(source), source, "file")
+            .into_iter()
+            .map(|row| (row.kind, row.module, row.alias))
+            .collect()
+    }
+
+    #[cfg(feature = "lang-c")]
+    #[test]
+    fn c_includes_drop_their_delimiters() {
+        assert_eq!(
+            imports(
+                "C",
+                TsLanguageKind::C,
+                "#include <stdio.h>\n#include \"util/log.h\"\n"
+            ),
+            vec![
+                ("include".to_string(), "stdio.h".to_string(), None),
+                ("include".to_string(), "util/log.h".to_string(), None),
+            ]
+        );
+    }
+
+    #[cfg(feature = "lang-cpp")]
+    #[test]
+    fn cpp_includes_drop_their_delimiters() {
+        assert_eq!(
+            imports(
+                "C++",
+                TsLanguageKind::Cpp,
+                "#include <vector>\n#include \"engine/config.hpp\"\n"
+            ),
+            vec![
+                ("include".to_string(), "vector".to_string(), None),
+                ("include".to_string(), "engine/config.hpp".to_string(), None),
+            ]
+        );
+    }
+
+    #[cfg(feature = "lang-ruby")]
+    #[test]
+    fn ruby_requires_are_imports() {
+        assert_eq!(
+            imports("Ruby", TsLanguageKind::Ruby, "require 'json'\nputs 'hi'\n"),
+            vec![("require".to_string(), "json".to_string(), None)]
+        );
+    }
+
+    #[cfg(feature = "lang-csharp")]
+    #[test]
+    fn csharp_using_aliases_keep_the_alias() {
+        assert_eq!(
+            imports(
+                "C#",
+                TsLanguageKind::CSharp,
+                "using Json = Newtonsoft.Json;\n"
+            ),
+            vec![(
+                "using".to_string(),
+                "Newtonsoft.Json".to_string(),
+                Some("Json".to_string())
+            )]
+        );
+    }
+}
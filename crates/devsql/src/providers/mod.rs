@@ -2,6 +2,8 @@
 //!
 //! Provides `source_files`, `source_lines`, and `symbols` tables by walking
 //! the repository tree, reading files, and extracting symbols via regex.
+//! With the `tree-sitter-ast` feature, `imports`, `ast_nodes`, and
+//! `symbol_refs` come from tree-sitter parses.
 
 use crate::Result;
 
@@ -12,6 +14,8 @@ pub mod imports;
 pub mod shell_history;
 pub mod source_files;
 pub mod source_lines;
+#[cfg(feature = "tree-sitter-ast")]
+pub mod symbol_refs;
 pub mod symbols;
 #[cfg(feature = "tree-sitter-ast")]
 pub mod tree_sitter;
@@ -19,7 +23,7 @@ pub mod tree_sitter;
 use rusqlite::Connection;
 use std::fs;
 use std::io::Read;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Metadata about a discovered source file.
 pub struct FileInfo {
@@ -195,9 +199,10 @@ pub fn should_skip_dir(name: &str) -> bool {
 }
 
 /// Walk a repository directory and collect metadata for all non-binary source
-/// files. Uses the `ignore` crate to automatically respect .gitignore rules
-/// and skip .git directories, with `should_skip_dir()` as an additional filter
-/// for common non-gitignored directories (node_modules, target, etc.).
+/// files, in path order. Uses the `ignore` crate to automatically respect .gitignore rules
+/// and skip .git directories, with `should_skip_dir()` and the configured
+/// `source_walk.exclude` patterns as an additional filter for common
+/// non-gitignored directories (node_modules, target, etc.).
 pub fn walk_source_files(repo_path: &Path) -> Vec<FileInfo> {
     let mut files = Vec::new();
 
@@ -207,7 +212,7 @@ pub fn walk_source_files(repo_path: &Path) -> Vec<FileInfo> {
         .filter_entry(|entry| {
             if entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
                 if let Some(name) = entry.file_name().to_str() {
-                    return !should_skip_dir(name);
+                    return !crate::config::current().excludes_dir(name);
                 }
             }
             true
@@ -270,21 +275,156 @@ pub fn walk_source_files(repo_path: &Path) -> Vec<FileInfo> {
         });
     }
 
+    files.sort_by(|a, b| a.path.cmp(&b.path));
     files
 }
 
+/// Read the source files of each `(repo_path, repo_label)` with `read`, in
+/// parallel, and visit each file with what was read in path order, until
+/// `visit` returns `false`. The ordinal counts every file of every
+/// repository, so it is the same whether or not `only` restricts the visit
+/// to the files at that path.
+pub(crate) fn visit_repo_files<R, P, F>(
+    repos: &[(PathBuf, String)],
+    only: Option<&str>,
+    read: P,
+    mut visit: F,
+) where
+    R: Send,
+    P: Fn(&Path, &FileInfo) -> R + Sync,
+    F: FnMut(i64, &str, &FileInfo, R) -> bool,
+{
+    // (ordinal, index into `repos`, file)
+    let mut files = Vec::new();
+    let mut ordinal = 0;
+    for (index, (repo_path, _)) in repos.iter().enumerate() {
+        for file_info in walk_source_files(repo_path) {
+            ordinal += 1;
+            if only.is_none_or(|path| path == file_info.path) {
+                files.push((ordinal, index, file_info));
+            }
+        }
+    }
+    let _stopped = crate::parallel::for_each_ordered(
+        &files,
+        |(_, index, file_info)| read(&repos[*index].0, file_info),
+        |(ordinal, index, file_info), read| {
+            if visit(*ordinal, &repos[*index].1, file_info, read) {
+                Ok(())
+            } else {
+                Err(())
+            }
+        },
+    );
+}
+
+// Tables extracted with tree-sitter. Their definitions live here rather than
+// in the feature-gated modules so `crate::schema` can describe them in every
+// build.
+
+pub(crate) const SYMBOL_REFS_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS symbol_refs (
+    file_path TEXT,
+    line_number INTEGER,
+    column_number INTEGER,
+    caller TEXT,
+    caller_line INTEGER,
+    name TEXT,
+    kind TEXT,
+    language TEXT,
+    repo TEXT
+)"#;
+
+pub(crate) const IMPORTS_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS imports (
+    file_path TEXT,
+    line_number INTEGER,
+    module TEXT,
+    name TEXT,
+    alias TEXT,
+    kind TEXT,
+    is_default INTEGER,
+    is_wildcard INTEGER,
+    repo TEXT
+)"#;
+
+pub(crate) const AST_NODES_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS ast_nodes (
+    id INTEGER PRIMARY KEY,
+    parent_id INTEGER,
+    file_path TEXT,
+    kind TEXT,
+    name TEXT,
+    start_line INTEGER,
+    start_column INTEGER,
+    end_line INTEGER,
+    end_column INTEGER,
+    start_byte INTEGER,
+    end_byte INTEGER,
+    depth INTEGER,
+    text TEXT,
+    language TEXT,
+    repo TEXT
+)"#;
+
+/// Code tables served as [`crate::lazy`] virtual tables instead of being
+/// loaded up front.
+pub(crate) const LAZY_TABLES: [&str; 2] = ["source_files", "source_lines"];
+
+/// The lazily scanned form of `table` over `repo_paths`, if it has one.
+pub(crate) fn lazy_table(
+    table: &str,
+    repo_paths: &[PathBuf],
+) -> Option<std::sync::Arc<dyn crate::lazy::LazyTable>> {
+    let repos = || {
+        repo_paths
+            .iter()
+            .map(|path| (path.clone(), repo_label(path)))
+            .collect()
+    };
+    match table {
+        "source_files" => Some(std::sync::Arc::new(source_files::LazySourceFiles::new(
+            repos(),
+        ))),
+        "source_lines" => Some(std::sync::Arc::new(source_lines::LazySourceLines::new(
+            repos(),
+        ))),
+        _ => None,
+    }
+}
+
+/// The `repo` value for rows loaded from `repo_path`: the working-directory
+/// path vcsql records in the Git tables, so code and Git rows join on `repo`.
+/// Paths outside a Git repository are labelled with the path itself.
+pub fn repo_label(repo_path: &Path) -> String {
+    vcsql::GitRepo::open(repo_path)
+        .map(|repo| repo.path().to_string())
+        .unwrap_or_else(|_| repo_path.display().to_string())
+}
+
 /// Load one or more code tables using the existing per-table providers.
 /// This is a compatibility shim while the single-pass loader is under construction.
 pub fn load_all_code_tables(conn: &Connection, repo_path: &Path, tables: &[&str]) -> Result<()> {
+    let repo = repo_label(repo_path);
     for table in tables {
         match *table {
-            "source_files" => source_files::load(conn, repo_path)?,
-            "source_lines" => source_lines::load(conn, repo_path)?,
-            "symbols" => symbols::load(conn, repo_path)?,
+            "source_files" => source_files::load(conn, repo_path, &repo)?,
+            "source_lines" => source_lines::load(conn, repo_path, &repo)?,
+            "symbols" => symbols::load(conn, repo_path, &repo)?,
+            #[cfg(feature = "tree-sitter-ast")]
+            "imports" => imports::load(conn, repo_path, &repo)?,
             #[cfg(feature = "tree-sitter-ast")]
-            "imports" => imports::load(conn, repo_path)?,
+            "ast_nodes" => ast_nodes::load(conn, repo_path, &repo)?,
             #[cfg(feature = "tree-sitter-ast")]
-            "ast_nodes" => ast_nodes::load(conn, repo_path)?,
+            "symbol_refs" => symbol_refs::load(conn, repo_path, &repo)?,
+            // Without tree-sitter these tables are created empty, so queries
+            // against them return no rows instead of failing.
+            #[cfg(not(feature = "tree-sitter-ast"))]
+            "imports" => conn.execute_batch(IMPORTS_TABLE)?,
+            #[cfg(not(feature = "tree-sitter-ast"))]
+            "ast_nodes" => conn.execute_batch(AST_NODES_TABLE)?,
+            #[cfg(not(feature = "tree-sitter-ast"))]
+            "symbol_refs" => conn.execute_batch(SYMBOL_REFS_TABLE)?,
             _ => {}
         }
     }
@@ -3,10 +3,11 @@
 use chrono::{DateTime, SecondsFormat, Utc};
 use rusqlite::{params, Connection, OpenFlags};
 use std::path::{Path, PathBuf};
+use vcsql::TimeWindow;
 
 use crate::Result;
 
-const CREATE_TABLE: &str = "CREATE TABLE shell_history (
+pub(crate) const CREATE_TABLE: &str = "CREATE TABLE shell_history (
     source TEXT NOT NULL,
     source_id TEXT,
     source_order INTEGER NOT NULL,
@@ -35,17 +36,19 @@ struct ShellEntry {
     history_path: String,
 }
 
-/// Rebuild `shell_history` from every available read-only source.
+/// Rebuild `shell_history` from every available read-only source, keeping
+/// only commands run inside `window`. With a bounded window, commands
+/// without a recorded time (plain zsh and bash histories) are left out.
 ///
 /// Shell histories are optional developer-local data. A missing, unreadable, or
 /// incompatible source contributes no rows instead of failing the full query.
-pub fn load(conn: &mut Connection) -> Result<()> {
+pub fn load(conn: &mut Connection, window: &TimeWindow) -> Result<()> {
     conn.execute("DROP TABLE IF EXISTS shell_history", [])?;
     conn.execute(CREATE_TABLE, [])?;
 
     let mut entries = Vec::new();
     if let Some(path) = atuin_history_path() {
-        entries.extend(read_atuin(&path));
+        entries.extend(read_atuin(&path, window));
     }
     if let Some(path) = zsh_history_path() {
         entries.extend(read_zsh(&path));
@@ -53,6 +56,14 @@ pub fn load(conn: &mut Connection) -> Result<()> {
     if let Some(path) = bash_history_path() {
         entries.extend(read_bash(&path));
     }
+    if !window.is_unbounded() {
+        entries.retain(|entry| {
+            entry
+                .timestamp
+                .as_deref()
+                .is_some_and(|timestamp| window.contains_rfc3339(timestamp))
+        });
+    }
 
     let tx = conn.transaction()?;
     {
@@ -82,12 +93,26 @@ pub fn load(conn: &mut Connection) -> Result<()> {
     Ok(())
 }
 
-fn read_atuin(path: &Path) -> Vec<ShellEntry> {
+/// The history files [`load`] reads that exist.
+pub fn history_paths() -> Vec<PathBuf> {
+    [
+        atuin_history_path(),
+        zsh_history_path(),
+        bash_history_path(),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|path| path.exists())
+    .collect()
+}
+
+fn read_atuin(path: &Path, window: &TimeWindow) -> Vec<ShellEntry> {
     if !path.is_file() {
         return Vec::new();
     }
 
     let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+    crate::profile::file_read();
     let Ok(source) = Connection::open_with_flags(path, flags) else {
         return Vec::new();
     };
@@ -95,13 +120,16 @@ fn read_atuin(path: &Path) -> Vec<ShellEntry> {
         "SELECT id, timestamp, duration, exit, command, cwd, session, hostname
          FROM history
          WHERE deleted_at IS NULL
+           AND (?1 IS NULL OR timestamp >= ?1)
+           AND (?2 IS NULL OR timestamp < ?2)
          ORDER BY timestamp, id",
     ) else {
         return Vec::new();
     };
 
+    let nanos = |time: Option<DateTime<Utc>>| time.and_then(|time| time.timestamp_nanos_opt());
     let history_path = path.to_string_lossy().into_owned();
-    let Ok(rows) = statement.query_map([], |row| {
+    let Ok(rows) = statement.query_map(params![nanos(window.since), nanos(window.until)], |row| {
         let timestamp_ns: i64 = row.get(1)?;
         let duration_ns: i64 = row.get(2)?;
         Ok(ShellEntry {
@@ -271,6 +299,7 @@ fn native_entry(
 }
 
 fn read_lossy(path: &Path) -> Option<String> {
+    crate::profile::file_read();
     std::fs::read(path)
         .ok()
         .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
@@ -289,8 +318,8 @@ fn timestamp_from_seconds(seconds: i64) -> Option<String> {
 }
 
 fn atuin_history_path() -> Option<PathBuf> {
-    if let Some(path) = env_path("DEVSQL_ATUIN_DB") {
-        return Some(path);
+    if let Some(path) = &crate::config::current().atuin_db.value {
+        return Some(path.clone());
     }
 
     if let Some(path) = atuin_config_path().and_then(|config| configured_atuin_db(&config)) {
@@ -326,8 +355,8 @@ fn configured_atuin_db(config_path: &Path) -> Option<PathBuf> {
 }
 
 fn zsh_history_path() -> Option<PathBuf> {
-    if let Some(path) = env_path("DEVSQL_ZSH_HISTORY") {
-        return Some(path);
+    if let Some(path) = &crate::config::current().zsh_history.value {
+        return Some(path.clone());
     }
     if active_shell_is("zsh") {
         if let Some(path) = env_path("HISTFILE") {
@@ -339,8 +368,8 @@ fn zsh_history_path() -> Option<PathBuf> {
 }
 
 fn bash_history_path() -> Option<PathBuf> {
-    if let Some(path) = env_path("DEVSQL_BASH_HISTORY") {
-        return Some(path);
+    if let Some(path) = &crate::config::current().bash_history.value {
+        return Some(path.clone());
     }
     if active_shell_is("bash") {
         if let Some(path) = env_path("HISTFILE") {
@@ -1,26 +1,31 @@
 //! Provider for the `source_files` table.
 
+use crate::lazy::{self, Filter, LazyTable};
 use crate::Result;
+use rusqlite::types::Value as SqlValue;
 use rusqlite::{params, Connection};
 use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use super::{detect_language, walk_source_files};
+use super::{detect_language, visit_repo_files, walk_source_files};
+
+/// Schema of the materialized `source_files` table.
+pub(crate) const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS source_files (
+    path TEXT,
+    name TEXT,
+    extension TEXT,
+    directory TEXT,
+    size_bytes INTEGER,
+    line_count INTEGER,
+    modified_at TEXT,
+    language TEXT,
+    repo TEXT,
+    PRIMARY KEY (repo, path)
+)";
 
 /// Create and populate the `source_files` table.
-pub fn load(conn: &Connection, repo_path: &Path) -> Result<()> {
-    conn.execute_batch(
-        "CREATE TABLE IF NOT EXISTS source_files (
-            path TEXT PRIMARY KEY,
-            name TEXT,
-            extension TEXT,
-            directory TEXT,
-            size_bytes INTEGER,
-            line_count INTEGER,
-            modified_at TEXT,
-            language TEXT
-        )",
-    )?;
+pub fn load(conn: &Connection, repo_path: &Path, repo: &str) -> Result<()> {
+    conn.execute_batch(CREATE_TABLE)?;
 
     let files = walk_source_files(repo_path);
 
@@ -28,26 +33,30 @@ pub fn load(conn: &Connection, repo_path: &Path) -> Result<()> {
 
     let mut stmt = conn.prepare(
         "INSERT OR IGNORE INTO source_files
-         (path, name, extension, directory, size_bytes, line_count, modified_at, language)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+         (path, name, extension, directory, size_bytes, line_count, modified_at, language, repo)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
     )?;
 
-    for file_info in &files {
-        let abs_path = repo_path.join(&file_info.path);
-        let line_count = count_lines(&abs_path);
-        let language = detect_language(&file_info.extension);
-
-        stmt.execute(params![
-            file_info.path,
-            file_info.name,
-            file_info.extension,
-            file_info.directory,
-            file_info.size as i64,
-            line_count as i64,
-            file_info.modified_at,
-            language,
-        ])?;
-    }
+    crate::parallel::for_each_ordered(
+        &files,
+        |file_info| count_lines(&repo_path.join(&file_info.path)),
+        |file_info, line_count| {
+            let language = detect_language(&file_info.extension);
+
+            stmt.execute(params![
+                file_info.path,
+                file_info.name,
+                file_info.extension,
+                file_info.directory,
+                file_info.size as i64,
+                line_count as i64,
+                file_info.modified_at,
+                language,
+                repo,
+            ])?;
+            Ok::<_, crate::Error>(())
+        },
+    )?;
 
     drop(stmt);
     conn.execute_batch("COMMIT")?;
@@ -58,9 +67,75 @@ pub fn load(conn: &Connection, repo_path: &Path) -> Result<()> {
 /// Count lines in a file using a buffered reader without holding the entire
 /// file contents in memory.
 fn count_lines(path: &Path) -> usize {
+    crate::profile::file_read();
     let Ok(file) = std::fs::File::open(path) else {
         return 0;
     };
     let reader = BufReader::new(file);
     reader.lines().count()
 }
+
+/// `source_files` scanned on demand: a `path` constraint counts the lines of
+/// only that file rather than of every file in each repository.
+pub(crate) struct LazySourceFiles {
+    repos: Vec<(PathBuf, String)>,
+    columns: Vec<(String, String)>,
+}
+
+impl LazySourceFiles {
+    pub(crate) fn new(repos: Vec<(PathBuf, String)>) -> Self {
+        Self {
+            repos,
+            columns: lazy::columns(&[
+                ("path", "TEXT"),
+                ("name", "TEXT"),
+                ("extension", "TEXT"),
+                ("directory", "TEXT"),
+                ("size_bytes", "INTEGER"),
+                ("line_count", "INTEGER"),
+                ("modified_at", "TEXT"),
+                ("language", "TEXT"),
+                ("repo", "TEXT"),
+            ]),
+        }
+    }
+}
+
+impl LazyTable for LazySourceFiles {
+    fn columns(&self) -> &[(String, String)] {
+        &self.columns
+    }
+
+    fn pushdown(&self) -> &[&'static str] {
+        &["path"]
+    }
+
+    fn scan(
+        &self,
+        filter: &Filter,
+        emit: &mut dyn FnMut(i64, Vec<SqlValue>) -> bool,
+    ) -> Result<()> {
+        visit_repo_files(
+            &self.repos,
+            filter.text_eq("path"),
+            |repo_path, file_info| count_lines(&repo_path.join(&file_info.path)),
+            |ordinal, repo, file_info, line_count| {
+                emit(
+                    ordinal,
+                    vec![
+                        file_info.path.clone().into(),
+                        file_info.name.clone().into(),
+                        file_info.extension.clone().into(),
+                        file_info.directory.clone().into(),
+                        (file_info.size as i64).into(),
+                        (line_count as i64).into(),
+                        file_info.modified_at.clone().into(),
+                        detect_language(&file_info.extension).to_string().into(),
+                        repo.to_string().into(),
+                    ],
+                )
+            },
+        );
+        Ok(())
+    }
+}
@@ -1,26 +1,30 @@
 //! Provider for the `source_lines` table.
 
+use crate::lazy::{self, Filter, LazyTable};
 use crate::Result;
+use rusqlite::types::Value as SqlValue;
 use rusqlite::{params, Connection};
 use std::path::Path;
 
-use super::walk_source_files;
+use super::{visit_repo_files, walk_source_files, FileInfo};
 
 /// Maximum file size (in bytes) to ingest line-by-line. Files larger than this
 /// are skipped to keep the in-memory database manageable.
 const MAX_FILE_SIZE: u64 = 1_048_576; // 1 MB
 
+/// Schema of the materialized `source_lines` table.
+pub(crate) const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS source_lines (
+    file_path TEXT,
+    line_number INTEGER,
+    content TEXT,
+    is_blank INTEGER,
+    repo TEXT,
+    PRIMARY KEY (repo, file_path, line_number)
+)";
+
 /// Create and populate the `source_lines` table.
-pub fn load(conn: &Connection, repo_path: &Path) -> Result<()> {
-    conn.execute_batch(
-        "CREATE TABLE IF NOT EXISTS source_lines (
-            file_path TEXT,
-            line_number INTEGER,
-            content TEXT,
-            is_blank INTEGER,
-            PRIMARY KEY (file_path, line_number)
-        )",
-    )?;
+pub fn load(conn: &Connection, repo_path: &Path, repo: &str) -> Result<()> {
+    conn.execute_batch(CREATE_TABLE)?;
 
     let files = walk_source_files(repo_path);
 
@@ -28,27 +32,20 @@ pub fn load(conn: &Connection, repo_path: &Path) -> Result<()> {
 
     let mut stmt = conn.prepare(
         "INSERT OR IGNORE INTO source_lines
-         (file_path, line_number, content, is_blank)
-         VALUES (?1, ?2, ?3, ?4)",
+         (file_path, line_number, content, is_blank, repo)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
     )?;
 
-    for file_info in &files {
-        if file_info.size > MAX_FILE_SIZE {
-            continue;
-        }
-
-        let abs_path = repo_path.join(&file_info.path);
-        let Ok(bytes) = std::fs::read(&abs_path) else {
-            continue;
-        };
-        let content = String::from_utf8_lossy(&bytes);
-
-        for (i, line) in content.lines().enumerate() {
-            let line_number = (i + 1) as i64;
-            let is_blank: i64 = if line.trim().is_empty() { 1 } else { 0 };
-            stmt.execute(params![file_info.path, line_number, line, is_blank])?;
-        }
-    }
+    crate::parallel::for_each_ordered(
+        &files,
+        |file_info| file_lines(repo_path, file_info),
+        |file_info, lines| {
+            for (line_number, line, is_blank) in lines {
+                stmt.execute(params![file_info.path, line_number, line, is_blank, repo])?;
+            }
+            Ok::<_, crate::Error>(())
+        },
+    )?;
 
     drop(stmt);
     conn.execute_batch("COMMIT")?;
@@ -60,3 +57,86 @@ pub fn load(conn: &Connection, repo_path: &Path) -> Result<()> {
 
     Ok(())
 }
+
+/// `(line_number, content, is_blank)` for each line of a file, or none for
+/// files over [`MAX_FILE_SIZE`] or that can no longer be read.
+fn file_lines(repo_path: &Path, file_info: &FileInfo) -> Vec<(i64, String, i64)> {
+    if file_info.size > MAX_FILE_SIZE {
+        return Vec::new();
+    }
+    crate::profile::file_read();
+    let Ok(bytes) = std::fs::read(repo_path.join(&file_info.path)) else {
+        return Vec::new();
+    };
+    let content = String::from_utf8_lossy(&bytes);
+    content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let is_blank: i64 = if line.trim().is_empty() { 1 } else { 0 };
+            ((i + 1) as i64, line.to_string(), is_blank)
+        })
+        .collect()
+}
+
+/// `source_lines` scanned on demand: a `file_path` constraint reads only
+/// that file rather than every file in each repository.
+///
+/// Rowids combine the file's ordinal with the line number, so a row keeps
+/// its rowid however the table is filtered.
+pub(crate) struct LazySourceLines {
+    repos: Vec<(std::path::PathBuf, String)>,
+    columns: Vec<(String, String)>,
+}
+
+impl LazySourceLines {
+    pub(crate) fn new(repos: Vec<(std::path::PathBuf, String)>) -> Self {
+        Self {
+            repos,
+            columns: lazy::columns(&[
+                ("file_path", "TEXT"),
+                ("line_number", "INTEGER"),
+                ("content", "TEXT"),
+                ("is_blank", "INTEGER"),
+                ("repo", "TEXT"),
+            ]),
+        }
+    }
+}
+
+impl LazyTable for LazySourceLines {
+    fn columns(&self) -> &[(String, String)] {
+        &self.columns
+    }
+
+    fn pushdown(&self) -> &[&'static str] {
+        &["file_path"]
+    }
+
+    fn scan(
+        &self,
+        filter: &Filter,
+        emit: &mut dyn FnMut(i64, Vec<SqlValue>) -> bool,
+    ) -> Result<()> {
+        visit_repo_files(
+            &self.repos,
+            filter.text_eq("file_path"),
+            file_lines,
+            |ordinal, repo, file_info, lines| {
+                lines.into_iter().all(|(line_number, line, is_blank)| {
+                    emit(
+                        ordinal << 20 | line_number,
+                        vec![
+                            file_info.path.clone().into(),
+                            line_number.into(),
+                            line.into(),
+                            is_blank.into(),
+                            repo.to_string().into(),
+                        ],
+                    )
+                })
+            },
+        );
+        Ok(())
+    }
+}
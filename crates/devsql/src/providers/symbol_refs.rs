@@ -0,0 +1,373 @@
+#![cfg(feature = "tree-sitter-ast")]
+
+//! Provider for the `symbol_refs` table: call sites, type uses, and imported
+//! names, each attributed to the innermost enclosing definition.
+//!
+//! `caller` and `caller_line` match `symbols.name` and `symbols.line_start`
+//! of that definition; both are NULL for top-level references.
+
+use crate::Result;
+use rusqlite::{params, Connection, Statement};
+use std::path::Path;
+use streaming_iterator::StreamingIterator;
+use tree_sitter::{Node, QueryCursor, Tree};
+
+use super::tree_sitter::{
+    definition_name, is_definition_node, reference_query, TsLanguageKind, TsParser,
+};
+use super::{detect_language, walk_source_files, FileInfo};
+
+const MAX_FILE_SIZE: u64 = 1_048_576; // 1 MB
+
+const INSERT_SQL: &str = r#"
+INSERT INTO symbol_refs (
+    file_path,
+    line_number,
+    column_number,
+    caller,
+    caller_line,
+    name,
+    kind,
+    language,
+    repo
+) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+"#;
+
+struct RefRow {
+    line_number: i64,
+    column_number: i64,
+    caller: Option<String>,
+    caller_line: Option<i64>,
+    name: String,
+    kind: &'static str,
+}
+
+pub fn load(conn: &Connection, repo_path: &Path, repo: &str) -> Result<()> {
+    conn.execute_batch(super::SYMBOL_REFS_TABLE)?;
+    let files = walk_source_files(repo_path);
+
+    conn.execute_batch("BEGIN")?;
+    let mut stmt = conn.prepare(INSERT_SQL)?;
+
+    crate::parallel::for_each_ordered_with(
+        &files,
+        TsParser::new,
+        |parser, file| file_refs(parser, repo_path, file),
+        |file, refs| {
+            let Some((language, rows)) = refs else {
+                return Ok(());
+            };
+            for row in rows {
+                insert_row(&mut stmt, &row, &file.path, language, repo)?;
+            }
+            Ok::<_, crate::Error>(())
+        },
+    )?;
+
+    drop(stmt);
+    conn.execute_batch("COMMIT")?;
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_symbol_refs_name ON symbol_refs(name);
+         CREATE INDEX IF NOT EXISTS idx_symbol_refs_caller ON symbol_refs(file_path, caller);",
+    )?;
+    Ok(())
+}
+
+/// The language and references of one source file, if it can be parsed.
+fn file_refs(
+    parser: &mut TsParser,
+    repo_path: &Path,
+    file: &FileInfo,
+) -> Option<(&'static str, Vec<RefRow>)> {
+    if file.size > MAX_FILE_SIZE {
+        return None;
+    }
+    let language = detect_language(&file.extension);
+    let lang_kind = TsLanguageKind::from_name(language)?;
+    let abs_path = repo_path.join(&file.path);
+    crate::profile::file_read();
+    let source = std::fs::read_to_string(&abs_path).ok()?;
+    let tree = parser.parse(language, &source)?;
+    Some((language, extract_refs_from_tree(lang_kind, &tree, &source)))
+}
+
+fn insert_row(
+    stmt: &mut Statement<'_>,
+    row: &RefRow,
+    file_path: &str,
+    language: &str,
+    repo: &str,
+) -> Result<()> {
+    stmt.execute(params![
+        file_path,
+        row.line_number,
+        row.column_number,
+        row.caller,
+        row.caller_line,
+        row.name,
+        row.kind,
+        language,
+        repo,
+    ])?;
+    Ok(())
+}
+
+fn extract_refs_from_tree(language: TsLanguageKind, tree: &Tree, source: &str) -> Vec<RefRow> {
+    let query = reference_query(language);
+    let mut rows = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(query, tree.root_node(), source.as_bytes());
+
+    while let Some(matched) = matches.next() {
+        for capture in matched.captures {
+            let node = capture.node;
+            // A node can match several patterns (a struct literal's name is
+            // also a type identifier); the first pattern wins.
+            if !seen.insert(node.id()) || is_definition_name(language, node) {
+                continue;
+            }
+            let capture_name = &query.capture_names()[capture.index as usize];
+            let kind = match capture_name.rsplit('.').next() {
+                Some("call") => "call",
+                Some("type") => "type",
+                Some("import") => "import",
+                _ => continue,
+            };
+            let name = reference_name(node, kind, source);
+            if name.is_empty() {
+                continue;
+            }
+            let (caller, caller_line) = match enclosing_definition(language, node, source) {
+                Some((name, line)) => (Some(name), Some(line)),
+                None => (None, None),
+            };
+            let start = node.start_position();
+            rows.push(RefRow {
+                line_number: (start.row + 1) as i64,
+                column_number: (start.column + 1) as i64,
+                caller,
+                caller_line,
+                name,
+                kind,
+            });
+        }
+    }
+
+    rows.sort_by_key(|row| (row.line_number, row.column_number));
+    rows
+}
+
+/// The referenced name. Go imports, C includes, and Ruby requires are paths,
+/// referenced by their last segment (without a header extension).
+fn reference_name(node: Node, kind: &str, source: &str) -> String {
+    let text = node.utf8_text(source.as_bytes()).unwrap_or("");
+    if kind == "import"
+        && matches!(
+            node.kind(),
+            "interpreted_string_literal"
+                | "string_literal"
+                | "system_lib_string"
+                | "string_content"
+        )
+    {
+        let path = text.trim_matches(['"', '<', '>']);
+        let last = path.rsplit('/').next().unwrap_or(path);
+        return last
+            .strip_suffix(".h")
+            .or_else(|| last.strip_suffix(".hpp"))
+            .unwrap_or(last)
+            .to_string();
+    }
+    text.to_string()
+}
+
+/// True when `node` is the name a definition introduces rather than a use.
+/// The type an `impl` block names is a use.
+fn is_definition_name(language: TsLanguageKind, node: Node) -> bool {
+    node.parent().is_some_and(|parent| {
+        is_definition_node(language, parent.kind())
+            && parent.kind() != "impl_item"
+            && definition_name(parent).is_some_and(|name| name.id() == node.id())
+    })
+}
+
+/// Name and 1-based start line of the innermost named definition around `node`.
+fn enclosing_definition(
+    language: TsLanguageKind,
+    node: Node,
+    source: &str,
+) -> Option<(String, i64)> {
+    let mut current = node.parent();
+    while let Some(candidate) = current {
+        if is_definition_node(language, candidate.kind()) {
+            let name_node = definition_name(candidate);
+            if let Some(name) = name_node.and_then(|n| n.utf8_text(source.as_bytes()).ok()) {
+                return Some((
+                    name.to_string(),
+                    (candidate.start_position().row + 1) as i64,
+                ));
+            }
+        }
+        current = candidate.parent();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn refs(language: &str, source: &str) -> Vec<RefRow> {
+        let kind = TsLanguageKind::from_name(language).expect("language");
+        let tree = TsParser::new().parse(language, source).expect("parse");
+        extract_refs_from_tree(kind, &tree, source)
+    }
+
+    #[test]
+    fn rust_references_are_attributed_to_their_enclosing_definition() {
+        let source = "use crate::engine::Engine;\n\
+                      struct Config { engine: Engine }\n\
+                      impl Config {\n    fn run(&self) { helper(); self.engine.start(); }\n}\n";
+        let rows = refs("Rust", source);
+        let summary: Vec<(&str, &str, Option<&str>)> = rows
+            .iter()
+            .map(|row| (row.kind, row.name.as_str(), row.caller.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("import", "Engine", None),
+                ("type", "Engine", Some("Config")),
+                ("type", "Config", Some("Config")),
+                ("call", "helper", Some("run")),
+                ("call", "start", Some("run")),
+            ]
+        );
+        assert_eq!(rows[3].caller_line, Some(4));
+    }
+
+    #[test]
+    fn python_and_go_calls_and_imports() {
+        let python = refs(
+            "Python",
+            "from app.models import User\ndef load() -> User:\n    return fetch(User)\n",
+        );
+        let python: Vec<(&str, &str)> = python.iter().map(|r| (r.kind, r.name.as_str())).collect();
+        assert_eq!(
+            python,
+            vec![("import", "User"), ("type", "User"), ("call", "fetch")]
+        );
+
+        let go = refs(
+            "Go",
+            "package main\nimport \"net/http\"\nfunc main() { http.ListenAndServe(addr, nil) }\n",
+        );
+        let go: Vec<(&str, &str)> = go.iter().map(|r| (r.kind, r.name.as_str())).collect();
+        assert_eq!(go, vec![("import", "http"), ("call", "ListenAndServe")]);
+    }
+
+    #[cfg(all(feature = "lang-java", feature = "lang-cpp"))]
+    #[test]
+    fn java_and_cpp_references() {
+        let java = refs(
+            "Java",
+            "import app.model.User;\nclass Api {\n    User load() { return repo.find(new Query()); }\n}\n",
+        );
+        let java: Vec<(&str, &str, Option<&str>)> = java
+            .iter()
+            .map(|r| (r.kind, r.name.as_str(), r.caller.as_deref()))
+            .collect();
+        assert_eq!(
+            java,
+            vec![
+                ("import", "User", None),
+                ("type", "User", Some("load")),
+                ("call", "find", Some("load")),
+                ("call", "Query", Some("load")),
+            ]
+        );
+
+        let cpp = refs(
+            "C++",
+            "#include <billing/invoice.h>\nvoid Ledger::post(Invoice& inv) { inv.validate(); audit::log(inv); }\n",
+        );
+        let cpp: Vec<(&str, &str, Option<&str>)> = cpp
+            .iter()
+            .map(|r| (r.kind, r.name.as_str(), r.caller.as_deref()))
+            .collect();
+        assert_eq!(
+            cpp,
+            vec![
+                ("import", "invoice", None),
+                ("type", "Invoice", Some("Ledger::post")),
+                ("call", "validate", Some("Ledger::post")),
+                ("call", "log", Some("Ledger::post")),
+            ]
+        );
+    }
+
+    #[cfg(feature = "lang-kotlin")]
+    #[test]
+    fn kotlin_references() {
+        let kotlin = refs(
+            "Kotlin",
+            "import app.model.User\nclass Api : Base() {\n    fun load(id: Long): User = repo.find(Query(id))\n}\n",
+        );
+        let kotlin: Vec<(&str, &str, Option<&str>)> = kotlin
+            .iter()
+            .map(|r| (r.kind, r.name.as_str(), r.caller.as_deref()))
+            .collect();
+        assert_eq!(
+            kotlin,
+            vec![
+                ("import", "User", None),
+                ("type", "Base", Some("Api")),
+                ("type", "Long", Some("load")),
+                ("type", "User", Some("load")),
+                ("call", "find", Some("load")),
+                ("call", "Query", Some("load")),
+            ]
+        );
+    }
+
+    #[cfg(all(feature = "lang-ruby", feature = "lang-csharp"))]
+    #[test]
+    fn ruby_and_csharp_references() {
+        let ruby = refs(
+            "Ruby",
+            "require \"json\"\nclass Report\n  def render\n    JSON.dump(rows)\n  end\nend\n",
+        );
+        let ruby: Vec<(&str, &str, Option<&str>)> = ruby
+            .iter()
+            .map(|r| (r.kind, r.name.as_str(), r.caller.as_deref()))
+            .collect();
+        assert_eq!(
+            ruby,
+            vec![
+                ("call", "require", None),
+                ("import", "json", None),
+                ("type", "JSON", Some("render")),
+                ("call", "dump", Some("render")),
+            ]
+        );
+
+        let csharp = refs(
+            "C#",
+            "using System.Text;\nclass Greeter : IGreeter {\n    string Hello(Person p) { return Format(p); }\n}\n",
+        );
+        let csharp: Vec<(&str, &str, Option<&str>)> = csharp
+            .iter()
+            .map(|r| (r.kind, r.name.as_str(), r.caller.as_deref()))
+            .collect();
+        assert_eq!(
+            csharp,
+            vec![
+                ("import", "System.Text", None),
+                ("type", "IGreeter", Some("Greeter")),
+                ("type", "Person", Some("Hello")),
+                ("call", "Format", Some("Hello")),
+            ]
+        );
+    }
+}
@@ -6,12 +6,12 @@ use rusqlite::{params, Connection, Statement};
 use std::path::Path;
 use std::sync::LazyLock;
 
-use super::{detect_language, walk_source_files};
 #[cfg(feature = "tree-sitter-ast")]
 use super::LineIndex;
+use super::{detect_language, walk_source_files, FileInfo};
 
 #[cfg(feature = "tree-sitter-ast")]
-use super::tree_sitter::{symbol_query, TsLanguageKind, TsParser};
+use super::tree_sitter::{definition_name, symbol_query, TsLanguageKind, TsParser};
 #[cfg(feature = "tree-sitter-ast")]
 use std::collections::HashMap;
 #[cfg(feature = "tree-sitter-ast")]
@@ -22,7 +22,7 @@ use tree_sitter::{Node, QueryCursor, Tree};
 /// Maximum file size (in bytes) to scan for symbols.
 const MAX_FILE_SIZE: u64 = 1_048_576; // 1 MB
 
-const CREATE_SYMBOLS_TABLE: &str = r#"
+pub(crate) const CREATE_SYMBOLS_TABLE: &str = r#"
 CREATE TABLE IF NOT EXISTS symbols (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
     file_path TEXT,
@@ -35,7 +35,8 @@ CREATE TABLE IF NOT EXISTS symbols (
     parent_id INTEGER,
     parameters TEXT,
     return_type TEXT,
-    language TEXT
+    language TEXT,
+    repo TEXT
 )"#;
 
 const INSERT_SYMBOL_SQL: &str = r#"
@@ -51,8 +52,9 @@ INSERT INTO symbols (
     parent_id,
     parameters,
     return_type,
-    language
-) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+    language,
+    repo
+) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
 "#;
 
 struct SymbolRow {
@@ -70,61 +72,66 @@ struct SymbolRow {
     language: String,
 }
 
-pub fn load(conn: &Connection, repo_path: &Path) -> Result<()> {
+pub fn load(conn: &Connection, repo_path: &Path, repo: &str) -> Result<()> {
     #[cfg(feature = "tree-sitter-ast")]
     {
-        load_with_tree_sitter(conn, repo_path)
+        load_with_tree_sitter(conn, repo_path, repo)
     }
     #[cfg(not(feature = "tree-sitter-ast"))]
     {
-        load_with_regex(conn, repo_path)
+        load_with_regex(conn, repo_path, repo)
     }
 }
 
 #[cfg(feature = "tree-sitter-ast")]
-fn load_with_tree_sitter(conn: &Connection, repo_path: &Path) -> Result<()> {
+fn load_with_tree_sitter(conn: &Connection, repo_path: &Path, repo: &str) -> Result<()> {
     ensure_table(conn)?;
     let files = walk_source_files(repo_path);
 
     conn.execute_batch("BEGIN")?;
     let mut insert_stmt = conn.prepare(INSERT_SYMBOL_SQL)?;
-    let mut parser = TsParser::new();
-    let mut next_id: i64 = 1;
+    let mut next_id = next_symbol_id(conn)?;
 
-    for file_info in &files {
-        if file_info.size > MAX_FILE_SIZE {
-            continue;
-        }
-        let language = detect_language(&file_info.extension);
-        let abs_path = repo_path.join(&file_info.path);
-        let Ok(content) = std::fs::read_to_string(&abs_path) else {
-            continue;
-        };
-        let line_index = LineIndex::new(&content);
-        let mut rows = Vec::new();
-
-        if let Some(lang_kind) = TsLanguageKind::from_name(language) {
-            if let Some(tree) = parser.parse(language, &content) {
-                rows = extract_symbols_from_tree(
-                    lang_kind,
-                    &tree,
-                    &line_index,
+    crate::parallel::for_each_ordered_with(
+        &files,
+        TsParser::new,
+        |parser, file_info| {
+            let mut file_ids = 0;
+            let Some((language, content)) = read_source(repo_path, file_info) else {
+                return (Vec::new(), file_ids);
+            };
+            let line_index = LineIndex::new(&content);
+            let mut rows = Vec::new();
+
+            if let Some(lang_kind) = TsLanguageKind::from_name(language) {
+                if let Some(tree) = parser.parse(language, &content) {
+                    rows = extract_symbols_from_tree(
+                        lang_kind,
+                        &tree,
+                        &line_index,
+                        &content,
+                        &file_info.path,
+                        language,
+                        &mut file_ids,
+                    );
+                }
+            }
+
+            if rows.is_empty() {
+                rows = extract_symbols_with_regex(
+                    language,
                     &content,
                     &file_info.path,
                     language,
-                    &mut next_id,
+                    &mut file_ids,
                 );
             }
-        }
-
-        if rows.is_empty() {
-            rows = extract_symbols_with_regex(language, &content, &file_info.path, language, &mut next_id);
-        }
-
-        for row in rows {
-            insert_symbol(&mut insert_stmt, &row)?;
-        }
-    }
+            (rows, file_ids)
+        },
+        |_, (rows, file_ids)| {
+            insert_file_symbols(&mut insert_stmt, rows, file_ids, &mut next_id, repo)
+        },
+    )?;
 
     drop(insert_stmt);
     conn.execute_batch("COMMIT")?;
@@ -133,28 +140,34 @@ fn load_with_tree_sitter(conn: &Connection, repo_path: &Path) -> Result<()> {
 }
 
 #[cfg(not(feature = "tree-sitter-ast"))]
-fn load_with_regex(conn: &Connection, repo_path: &Path) -> Result<()> {
+fn load_with_regex(conn: &Connection, repo_path: &Path, repo: &str) -> Result<()> {
     ensure_table(conn)?;
     let files = walk_source_files(repo_path);
 
     conn.execute_batch("BEGIN")?;
     let mut insert_stmt = conn.prepare(INSERT_SYMBOL_SQL)?;
-    let mut next_id: i64 = 1;
+    let mut next_id = next_symbol_id(conn)?;
 
-    for file_info in &files {
-        if file_info.size > MAX_FILE_SIZE {
-            continue;
-        }
-        let language = detect_language(&file_info.extension);
-        let abs_path = repo_path.join(&file_info.path);
-        let Ok(content) = std::fs::read_to_string(&abs_path) else {
-            continue;
-        };
-        let rows = extract_symbols_with_regex(language, &content, &file_info.path, language, &mut next_id);
-        for row in rows {
-            insert_symbol(&mut insert_stmt, &row)?;
-        }
-    }
+    crate::parallel::for_each_ordered(
+        &files,
+        |file_info| {
+            let mut file_ids = 0;
+            let Some((language, content)) = read_source(repo_path, file_info) else {
+                return (Vec::new(), file_ids);
+            };
+            let rows = extract_symbols_with_regex(
+                language,
+                &content,
+                &file_info.path,
+                language,
+                &mut file_ids,
+            );
+            (rows, file_ids)
+        },
+        |_, (rows, file_ids)| {
+            insert_file_symbols(&mut insert_stmt, rows, file_ids, &mut next_id, repo)
+        },
+    )?;
 
     drop(insert_stmt);
     conn.execute_batch("COMMIT")?;
@@ -162,6 +175,36 @@ fn load_with_regex(conn: &Connection, repo_path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// The language and content of a source file small enough to parse.
+fn read_source(repo_path: &Path, file_info: &FileInfo) -> Option<(&'static str, String)> {
+    if file_info.size > MAX_FILE_SIZE {
+        return None;
+    }
+    let language = detect_language(&file_info.extension);
+    crate::profile::file_read();
+    let content = std::fs::read_to_string(repo_path.join(&file_info.path)).ok()?;
+    Some((language, content))
+}
+
+/// Insert one file's symbols, extracted in parallel with ids counted from 0
+/// within the file, after shifting their ids past the `file_ids` ids already
+/// assigned from `next_id`.
+fn insert_file_symbols(
+    stmt: &mut Statement<'_>,
+    rows: Vec<SymbolRow>,
+    file_ids: i64,
+    next_id: &mut i64,
+    repo: &str,
+) -> Result<()> {
+    for mut row in rows {
+        row.id += *next_id;
+        row.parent_id = row.parent_id.map(|parent_id| parent_id + *next_id);
+        insert_symbol(stmt, &row, repo)?;
+    }
+    *next_id += file_ids;
+    Ok(())
+}
+
 fn ensure_table(conn: &Connection) -> Result<()> {
     conn.execute_batch(CREATE_SYMBOLS_TABLE)?;
     Ok(())
@@ -176,7 +219,17 @@ fn create_indexes(conn: &Connection) -> Result<()> {
     Ok(())
 }
 
-fn insert_symbol(stmt: &mut Statement<'_>, row: &SymbolRow) -> Result<()> {
+/// Ids continue after rows already loaded from other repositories so
+/// `parent_id` references stay unique.
+fn next_symbol_id(conn: &Connection) -> Result<i64> {
+    Ok(
+        conn.query_row("SELECT COALESCE(MAX(id), 0) + 1 FROM symbols", [], |row| {
+            row.get(0)
+        })?,
+    )
+}
+
+fn insert_symbol(stmt: &mut Statement<'_>, row: &SymbolRow, repo: &str) -> Result<()> {
     stmt.execute(params![
         row.id,
         row.file_path,
@@ -190,6 +243,7 @@ fn insert_symbol(stmt: &mut Statement<'_>, row: &SymbolRow) -> Result<()> {
         row.parameters,
         row.return_type,
         row.language,
+        repo,
     ])?;
     Ok(())
 }
@@ -268,15 +322,9 @@ fn extract_symbols_from_tree(
 
 #[cfg(feature = "tree-sitter-ast")]
 fn extract_name(node: Node, source: &str) -> String {
-    if let Some(name_node) = node.child_by_field_name("name") {
-        return node_text(name_node, source);
-    }
-    if node.kind() == "impl_item" {
-        if let Some(target) = node.child_by_field_name("type") {
-            return node_text(target, source);
-        }
-    }
-    String::new()
+    definition_name(node)
+        .map(|name| node_text(name, source))
+        .unwrap_or_default()
 }
 
 #[cfg(feature = "tree-sitter-ast")]
@@ -299,6 +347,8 @@ fn normalize_kind(language: TsLanguageKind, key: &str, node: Node) -> String {
         }
         (TsLanguageKind::Rust, "impl") => "impl".to_string(),
         (TsLanguageKind::Rust, other) => other.to_string(),
+        #[cfg(feature = "lang-kotlin")]
+        (TsLanguageKind::Kotlin, "class") => kotlin_class_kind(node).to_string(),
         (TsLanguageKind::Python, "decorated") => {
             if let Some(target) = decorated_target(node) {
                 return normalize_kind(language, target.kind(), target);
@@ -317,9 +367,42 @@ fn normalize_kind(language: TsLanguageKind, key: &str, node: Node) -> String {
     }
 }
 
+/// Kotlin declares interfaces and enums with `class_declaration` too: an
+/// `interface` keyword or an `enum` class modifier tells them apart.
+#[cfg(feature = "lang-kotlin")]
+fn kotlin_class_kind(node: Node) -> &'static str {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        match child.kind() {
+            "interface" => return "interface",
+            "modifiers" => {
+                let mut cursor = child.walk();
+                let is_enum = child.named_children(&mut cursor).any(|modifier| {
+                    modifier.kind() == "class_modifier"
+                        && modifier.child(0).is_some_and(|word| word.kind() == "enum")
+                });
+                if is_enum {
+                    return "enum";
+                }
+            }
+            _ => {}
+        }
+    }
+    "class"
+}
+
 #[cfg(feature = "tree-sitter-ast")]
 fn signature_text(node: Node, source: &str) -> String {
-    if let Some(body) = node.child_by_field_name("body") {
+    // Kotlin bodies are children without a field.
+    let body = node.child_by_field_name("body").or_else(|| {
+        node.named_children(&mut node.walk()).find(|child| {
+            matches!(
+                child.kind(),
+                "class_body" | "enum_class_body" | "function_body"
+            )
+        })
+    });
+    if let Some(body) = body {
         let start = node.start_byte();
         let end = body.start_byte();
         return source[start..end].trim().to_string();
@@ -333,10 +416,24 @@ fn visibility_text(language: TsLanguageKind, node: Node, source: &str) -> String
         return node_text(vis, source);
     }
     match language {
+        TsLanguageKind::Rust => {
+            let mut cursor = node.walk();
+            let visibility = node
+                .named_children(&mut cursor)
+                .find(|child| child.kind() == "visibility_modifier")
+                .map(|vis| node_text(vis, source))
+                .unwrap_or_default();
+            visibility
+        }
         TsLanguageKind::Go => {
             if let Some(name) = node.child_by_field_name("name") {
                 let text = node_text(name, source);
-                if text.chars().next().map(|c| c.is_uppercase()).unwrap_or(false) {
+                if text
+                    .chars()
+                    .next()
+                    .map(|c| c.is_uppercase())
+                    .unwrap_or(false)
+                {
                     "export".to_string()
                 } else {
                     "private".to_string()
@@ -345,14 +442,51 @@ fn visibility_text(language: TsLanguageKind, node: Node, source: &str) -> String
                 String::new()
             }
         }
+        #[cfg(feature = "lang-java")]
+        TsLanguageKind::Java => modifier_visibility(node, source),
+        #[cfg(feature = "lang-csharp")]
+        TsLanguageKind::CSharp => modifier_visibility(node, source),
+        #[cfg(feature = "lang-kotlin")]
+        TsLanguageKind::Kotlin => modifier_visibility(node, source),
         _ => String::new(),
     }
 }
 
+/// The access modifier among a Java, C#, or Kotlin declaration's modifiers.
+#[cfg(any(
+    feature = "lang-java",
+    feature = "lang-csharp",
+    feature = "lang-kotlin"
+))]
+fn modifier_visibility(node: Node, source: &str) -> String {
+    let mut cursor = node.walk();
+    let modifiers: Vec<String> = node
+        .named_children(&mut cursor)
+        .filter(|child| matches!(child.kind(), "modifiers" | "modifier"))
+        .map(|child| node_text(child, source))
+        .collect();
+    modifiers
+        .iter()
+        .flat_map(|text| text.split_whitespace())
+        .find(|word| matches!(*word, "public" | "protected" | "private" | "internal"))
+        .unwrap_or_default()
+        .to_string()
+}
+
 #[cfg(feature = "tree-sitter-ast")]
 fn parameters_text(language: TsLanguageKind, node: Node, source: &str) -> String {
     let field_candidates = match language {
         TsLanguageKind::Go => &["parameters", "signature"][..],
+        // Kotlin's parameters have no field, and its type parameters come
+        // first.
+        #[cfg(feature = "lang-kotlin")]
+        TsLanguageKind::Kotlin => {
+            return node
+                .named_children(&mut node.walk())
+                .find(|child| child.kind() == "function_value_parameters")
+                .map(|parameters| node_text(parameters, source))
+                .unwrap_or_default();
+        }
         _ => &["parameters"][..],
     };
     for field in field_candidates {
@@ -360,6 +494,14 @@ fn parameters_text(language: TsLanguageKind, node: Node, source: &str) -> String
             return node_text(p, source);
         }
     }
+    // C and C++ functions keep their parameters on the function declarator.
+    let mut declarator = node.child_by_field_name("declarator");
+    while let Some(current) = declarator {
+        if let Some(p) = current.child_by_field_name("parameters") {
+            return node_text(p, source);
+        }
+        declarator = current.child_by_field_name("declarator");
+    }
     // TypeScript/JavaScript use `formal_parameters`.
     if let Some(formal) = node
         .named_children(&mut node.walk())
@@ -379,7 +521,10 @@ fn return_type_text(language: TsLanguageKind, node: Node, source: &str) -> Strin
             }
             String::new()
         }
-        TsLanguageKind::TypeScript | TsLanguageKind::Tsx | TsLanguageKind::JavaScript | TsLanguageKind::Jsx => {
+        TsLanguageKind::TypeScript
+        | TsLanguageKind::Tsx
+        | TsLanguageKind::JavaScript
+        | TsLanguageKind::Jsx => {
             if let Some(ret) = node.child_by_field_name("return_type") {
                 return node_text(ret, source);
             }
@@ -394,9 +539,56 @@ fn return_type_text(language: TsLanguageKind, node: Node, source: &str) -> Strin
             }
             String::new()
         }
+        #[cfg(feature = "lang-java")]
+        TsLanguageKind::Java => field_text(node, "type", source),
+        #[cfg(feature = "lang-c")]
+        TsLanguageKind::C => field_text(node, "type", source),
+        #[cfg(feature = "lang-cpp")]
+        TsLanguageKind::Cpp => field_text(node, "type", source),
+        #[cfg(feature = "lang-ruby")]
+        TsLanguageKind::Ruby => String::new(),
+        #[cfg(feature = "lang-csharp")]
+        TsLanguageKind::CSharp => field_text(node, "returns", source),
+        // The type after a function's parameters, which has no field.
+        #[cfg(feature = "lang-kotlin")]
+        TsLanguageKind::Kotlin => {
+            let mut cursor = node.walk();
+            let mut children = node.named_children(&mut cursor);
+            if node.kind() != "function_declaration"
+                || !children.any(|child| child.kind() == "function_value_parameters")
+            {
+                return String::new();
+            }
+            children
+                .next()
+                .filter(|child| {
+                    matches!(
+                        child.kind(),
+                        "user_type"
+                            | "nullable_type"
+                            | "non_nullable_type"
+                            | "function_type"
+                            | "parenthesized_type"
+                    )
+                })
+                .map(|child| node_text(child, source))
+                .unwrap_or_default()
+        }
     }
 }
 
+#[cfg(any(
+    feature = "lang-java",
+    feature = "lang-c",
+    feature = "lang-cpp",
+    feature = "lang-csharp"
+))]
+fn field_text(node: Node, field: &str, source: &str) -> String {
+    node.child_by_field_name(field)
+        .map(|child| node_text(child, source))
+        .unwrap_or_default()
+}
+
 #[cfg(feature = "tree-sitter-ast")]
 fn parent_symbol_id(node: Node, map: &HashMap<usize, i64>) -> Option<i64> {
     let mut current = node.parent();
@@ -462,6 +654,11 @@ pub fn extract_symbols(language: &str, content: &str) -> Vec<SymbolMatch> {
         "TypeScript" | "TSX" | "JavaScript" | "JSX" => extract_ts_js(content),
         "Python" => extract_python(content),
         "Go" => extract_go(content),
+        "Java" => extract_class_based(content, &RE_JAVA_TYPE, &RE_JAVA_METHOD),
+        "C#" => extract_class_based(content, &RE_CSHARP_TYPE, &RE_CSHARP_METHOD),
+        "C" | "C Header" | "C++" | "C++ Header" => extract_c_family(content),
+        "Ruby" => extract_ruby(content),
+        "Kotlin" => extract_kotlin(content),
         _ => Vec::new(),
     }
 }
@@ -471,7 +668,10 @@ fn line_number_at(content: &str, byte_offset: usize) -> usize {
 }
 
 fn line_text(content: &str, line_num: usize) -> &str {
-    content.lines().nth(line_num.saturating_sub(1)).unwrap_or("")
+    content
+        .lines()
+        .nth(line_num.saturating_sub(1))
+        .unwrap_or("")
 }
 
 // --- Regex fallback extractors (unchanged logic) ---
@@ -504,7 +704,11 @@ fn extract_rust(content: &str) -> Vec<SymbolMatch> {
         if kind == "impl" {
             continue;
         }
-        let kind_str = if kind == "macro_rules!" { "macro" } else { kind };
+        let kind_str = if kind == "macro_rules!" {
+            "macro"
+        } else {
+            kind
+        };
         let name = caps.get(5).map(|m| m.as_str()).unwrap_or("");
 
         rows.push(SymbolMatch {
@@ -584,7 +788,12 @@ fn extract_go(content: &str) -> Vec<SymbolMatch> {
         let line_start = line_number_at(content, full_match.start());
         let signature = line_text(content, line_start).trim().to_string();
         let name = caps.get(2).map(|m| m.as_str()).unwrap_or("");
-        let visibility = if name.chars().next().map(|c| c.is_uppercase()).unwrap_or(false) {
+        let visibility = if name
+            .chars()
+            .next()
+            .map(|c| c.is_uppercase())
+            .unwrap_or(false)
+        {
             "export"
         } else {
             ""
@@ -600,3 +809,413 @@ fn extract_go(content: &str) -> Vec<SymbolMatch> {
     }
     rows
 }
+
+/// Words that look like a name followed by `(...) {` but open a statement.
+const STATEMENT_KEYWORDS: &[&str] = &[
+    "if",
+    "else",
+    "for",
+    "foreach",
+    "while",
+    "do",
+    "switch",
+    "catch",
+    "try",
+    "return",
+    "new",
+    "throw",
+    "synchronized",
+    "using",
+    "lock",
+    "fixed",
+    "case",
+    "await",
+    "yield",
+    "sizeof",
+];
+
+fn access_modifier(modifiers: &str) -> String {
+    modifiers
+        .split_whitespace()
+        .find(|word| matches!(*word, "public" | "protected" | "private" | "internal"))
+        .unwrap_or_default()
+        .to_string()
+}
+
+static RE_JAVA_TYPE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?m)^[ \t]*((?:(?:public|protected|private|static|final|abstract|sealed|non-sealed|strictfp)\s+)*)(class|interface|enum|record|@interface)\s+(\w+)",
+    )
+    .expect("Java type regex")
+});
+
+static RE_JAVA_METHOD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?m)^[ \t]*((?:(?:public|protected|private|static|final|abstract|synchronized|native|default|strictfp)\s+)*)(?:<[^>\n]*>\s+)?(?:([\w.]+(?:<[^;{}()]*>)?(?:\[\])*)\s+)?(\w+)\s*\([^;{}]*?\)\s*(?:throws\s+[\w.,\s]+?)?\s*\{",
+    )
+    .expect("Java method regex")
+});
+
+static RE_CSHARP_TYPE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?m)^[ \t]*((?:(?:public|protected|private|internal|static|sealed|abstract|partial|readonly|ref|unsafe)\s+)*)(class|interface|struct|enum|record)\s+(\w+)",
+    )
+    .expect("C# type regex")
+});
+
+static RE_CSHARP_METHOD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?m)^[ \t]*((?:(?:public|protected|private|internal|static|virtual|override|abstract|sealed|async|extern|unsafe|new|partial|readonly)\s+)*)(?:([\w.]+(?:<[^;{}()]*>)?(?:\[\])*\??)\s+)?(\w+)(?:<[^>\n]*>)?\s*\([^;{}]*?\)\s*(?:where\s+[^{;]+?)?\s*(?:\{|=>)",
+    )
+    .expect("C# method regex")
+});
+
+/// Java and C#: type declarations (modifiers, kind, name) and methods
+/// (modifiers, return type, name).
+fn extract_class_based(content: &str, types: &Regex, methods: &Regex) -> Vec<SymbolMatch> {
+    let mut rows = Vec::new();
+    for caps in types.captures_iter(content) {
+        let full_match = caps.get(0).unwrap();
+        let line_start = line_number_at(content, full_match.start());
+        let kind = match caps.get(2).map(|m| m.as_str()).unwrap_or("unknown") {
+            "@interface" => "interface",
+            other => other,
+        };
+        rows.push(SymbolMatch {
+            name: caps.get(3).map(|m| m.as_str()).unwrap_or("").to_string(),
+            kind: kind.to_string(),
+            line_start,
+            line_end: line_start,
+            signature: line_text(content, line_start).trim().to_string(),
+            visibility: access_modifier(caps.get(1).map(|m| m.as_str()).unwrap_or("")),
+        });
+    }
+    for caps in methods.captures_iter(content) {
+        let name = caps.get(3).map(|m| m.as_str()).unwrap_or("");
+        let return_type = caps.get(2).map(|m| m.as_str()).unwrap_or("");
+        if STATEMENT_KEYWORDS.contains(&name) || STATEMENT_KEYWORDS.contains(&return_type) {
+            continue;
+        }
+        let full_match = caps.get(0).unwrap();
+        let line_start = line_number_at(content, full_match.start());
+        rows.push(SymbolMatch {
+            name: name.to_string(),
+            kind: "method".to_string(),
+            line_start,
+            line_end: line_start,
+            signature: line_text(content, line_start).trim().to_string(),
+            visibility: access_modifier(caps.get(1).map(|m| m.as_str()).unwrap_or("")),
+        });
+    }
+    rows.sort_by_key(|row| row.line_start);
+    rows
+}
+
+static RE_C_TYPE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?m)^[ \t]*(?:typedef\s+)?(struct|union|enum|class|namespace)\s+(?:\w+\s+)*?(\w+)\s*(?::[^;{]*)?\{",
+    )
+    .expect("C type regex")
+});
+
+static RE_C_FUNCTION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?m)^[ \t]*(?:(?:static|inline|extern|virtual|constexpr|explicit|friend)\s+)*(?:[\w:<>,*&]+[\s*&]+)+?((?:\w+::)*~?\w+)\s*\([^;{}]*\)\s*(?:const\s*)?(?:noexcept\s*)?(?:override\s*)?(?:final\s*)?\{",
+    )
+    .expect("C function regex")
+});
+
+/// C and C++: aggregate types, namespaces, and function definitions. Bodies
+/// are required so declarations in headers are not reported twice.
+fn extract_c_family(content: &str) -> Vec<SymbolMatch> {
+    let mut rows = Vec::new();
+    for caps in RE_C_TYPE.captures_iter(content) {
+        let full_match = caps.get(0).unwrap();
+        let line_start = line_number_at(content, full_match.start());
+        let kind = match caps.get(1).map(|m| m.as_str()).unwrap_or("unknown") {
+            "namespace" => "module",
+            other => other,
+        };
+        rows.push(SymbolMatch {
+            name: caps.get(2).map(|m| m.as_str()).unwrap_or("").to_string(),
+            kind: kind.to_string(),
+            line_start,
+            line_end: line_start,
+            signature: line_text(content, line_start).trim().to_string(),
+            visibility: String::new(),
+        });
+    }
+    for caps in RE_C_FUNCTION.captures_iter(content) {
+        let name = caps.get(1).map(|m| m.as_str()).unwrap_or("");
+        if STATEMENT_KEYWORDS.contains(&name) {
+            continue;
+        }
+        let name_start = caps.get(1).unwrap().start();
+        let line_start = line_number_at(content, name_start);
+        rows.push(SymbolMatch {
+            name: name.to_string(),
+            kind: "function".to_string(),
+            line_start,
+            line_end: line_start,
+            signature: line_text(content, line_start).trim().to_string(),
+            visibility: String::new(),
+        });
+    }
+    rows.sort_by_key(|row| row.line_start);
+    rows
+}
+
+static RE_RUBY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^[ \t]*(def|class|module)\s+((?:self\.)?[\w:]+[?!=]?)").expect("Ruby regex")
+});
+
+fn extract_ruby(content: &str) -> Vec<SymbolMatch> {
+    let mut rows = Vec::new();
+    for caps in RE_RUBY.captures_iter(content) {
+        let full_match = caps.get(0).unwrap();
+        let line_start = line_number_at(content, full_match.start());
+        let kind = match caps.get(1).map(|m| m.as_str()).unwrap_or("unknown") {
+            "def" => "method",
+            other => other,
+        };
+        rows.push(SymbolMatch {
+            name: caps.get(2).map(|m| m.as_str()).unwrap_or("").to_string(),
+            kind: kind.to_string(),
+            line_start,
+            line_end: line_start,
+            signature: line_text(content, line_start).trim().to_string(),
+            visibility: String::new(),
+        });
+    }
+    rows
+}
+
+static RE_KOTLIN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?m)^[ \t]*((?:(?:public|private|protected|internal|open|abstract|final|override|data|sealed|enum|annotation|inner|value|inline|suspend|operator|infix|tailrec|external|expect|actual)\s+)*)(fun|class|interface|object)\s+(?:<[^>\n]*>\s*)?(?:[\w.<>]+\.)?(\w+)",
+    )
+    .expect("Kotlin regex")
+});
+
+fn extract_kotlin(content: &str) -> Vec<SymbolMatch> {
+    let mut rows = Vec::new();
+    for caps in RE_KOTLIN.captures_iter(content) {
+        let full_match = caps.get(0).unwrap();
+        let line_start = line_number_at(content, full_match.start());
+        let modifiers = caps.get(1).map(|m| m.as_str()).unwrap_or("");
+        let kind = match caps.get(2).map(|m| m.as_str()).unwrap_or("unknown") {
+            "fun" => "function",
+            "class" if modifiers.split_whitespace().any(|word| word == "enum") => "enum",
+            other => other,
+        };
+        rows.push(SymbolMatch {
+            name: caps.get(3).map(|m| m.as_str()).unwrap_or("").to_string(),
+            kind: kind.to_string(),
+            line_start,
+            line_end: line_start,
+            signature: line_text(content, line_start).trim().to_string(),
+            visibility: access_modifier(modifiers),
+        });
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(rows: &[SymbolMatch]) -> Vec<(&str, &str, &str)> {
+        rows.iter()
+            .map(|row| {
+                (
+                    row.kind.as_str(),
+                    row.name.as_str(),
+                    row.visibility.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn regex_fallback_covers_java_cpp_and_kotlin() {
+        let java = "package app;\n\
+                    public class OrderService {\n\
+                    \x20   private final Repo repo;\n\
+                    \x20   public OrderService(Repo repo) {\n\
+                    \x20       if (repo == null) {\n\
+                    \x20           throw new IllegalArgumentException();\n\
+                    \x20       }\n\
+                    \x20   }\n\
+                    \x20   protected Map<String, Order> byId(List<String> ids) throws IOException {\n\
+                    \x20       return repo.load(ids);\n\
+                    \x20   }\n\
+                    }\n";
+        assert_eq!(
+            summary(&extract_symbols("Java", java)),
+            vec![
+                ("class", "OrderService", "public"),
+                ("method", "OrderService", "public"),
+                ("method", "byId", "protected"),
+            ]
+        );
+
+        let cpp = "namespace billing {\n\
+                   class Invoice : public Document {\n\
+                   };\n\
+                   static int\n\
+                   total(const Invoice& invoice) {\n\
+                   \x20   while (true) {\n\
+                   \x20   }\n\
+                   }\n\
+                   void Invoice::print() const {\n\
+                   }\n\
+                   }\n";
+        assert_eq!(
+            summary(&extract_symbols("C++", cpp)),
+            vec![
+                ("module", "billing", ""),
+                ("class", "Invoice", ""),
+                ("function", "total", ""),
+                ("function", "Invoice::print", ""),
+            ]
+        );
+
+        let kotlin = "data class User(val id: Long)\n\
+                      enum class Role { ADMIN }\n\
+                      internal suspend fun <T> List<T>.firstPage(): List<T> = take(10)\n";
+        assert_eq!(
+            summary(&extract_symbols("Kotlin", kotlin)),
+            vec![
+                ("class", "User", ""),
+                ("enum", "Role", ""),
+                ("function", "firstPage", "internal"),
+            ]
+        );
+    }
+
+    #[cfg(feature = "lang-java")]
+    #[test]
+    fn java_grammar_reports_members_with_their_parent() {
+        let source = "public class Cart {\n    private int size() { return 0; }\n}\n";
+        let tree = TsParser::new().parse("Java", source).expect("parse");
+        let mut next_id = 1;
+        let rows = extract_symbols_from_tree(
+            TsLanguageKind::Java,
+            &tree,
+            &LineIndex::new(source),
+            source,
+            "Cart.java",
+            "Java",
+            &mut next_id,
+        );
+        let summary: Vec<(&str, &str, &str, &str, Option<i64>)> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.kind.as_str(),
+                    row.name.as_str(),
+                    row.visibility.as_str(),
+                    row.return_type.as_str(),
+                    row.parent_id,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("class", "Cart", "public", "", None),
+                ("method", "size", "private", "int", Some(1)),
+            ]
+        );
+    }
+
+    #[cfg(feature = "lang-kotlin")]
+    #[test]
+    fn kotlin_grammar_tells_interfaces_and_enums_from_classes() {
+        let source = "interface Shape\n\
+                      enum class Role { ADMIN }\n\
+                      typealias Names = List<String>\n\
+                      object Registry {\n\
+                      \x20   internal fun <T> find(key: T, limit: Int): Shape? = null\n\
+                      }\n";
+        let tree = TsParser::new().parse("Kotlin", source).expect("parse");
+        let mut next_id = 1;
+        let rows = extract_symbols_from_tree(
+            TsLanguageKind::Kotlin,
+            &tree,
+            &LineIndex::new(source),
+            source,
+            "Registry.kt",
+            "Kotlin",
+            &mut next_id,
+        );
+        let summary: Vec<(&str, &str, &str, &str, &str)> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.kind.as_str(),
+                    row.name.as_str(),
+                    row.visibility.as_str(),
+                    row.parameters.as_str(),
+                    row.return_type.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("interface", "Shape", "", "", ""),
+                ("enum", "Role", "", "", ""),
+                ("type_alias", "Names", "", "", ""),
+                ("object", "Registry", "", "", ""),
+                (
+                    "function",
+                    "find",
+                    "internal",
+                    "(key: T, limit: Int)",
+                    "Shape?"
+                ),
+            ]
+        );
+        assert_eq!(
+            rows[4].signature,
+            "internal fun <T> find(key: T, limit: Int): Shape?"
+        );
+        assert_eq!(rows[4].parent_id, Some(rows[3].id));
+    }
+
+    #[cfg(feature = "lang-cpp")]
+    #[test]
+    fn cpp_functions_are_named_through_their_declarator() {
+        let source =
+            "struct Point { int x; };\nint* Point::origin(int scale) { return nullptr; }\n";
+        let tree = TsParser::new().parse("C++", source).expect("parse");
+        let mut next_id = 1;
+        let rows = extract_symbols_from_tree(
+            TsLanguageKind::Cpp,
+            &tree,
+            &LineIndex::new(source),
+            source,
+            "point.cpp",
+            "C++",
+            &mut next_id,
+        );
+        let summary: Vec<(&str, &str, &str)> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.kind.as_str(),
+                    row.name.as_str(),
+                    row.parameters.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("struct", "Point", ""),
+                ("function", "Point::origin", "(int scale)"),
+            ]
+        );
+    }
+}
@@ -5,7 +5,7 @@
 use std::collections::HashSet;
 use std::sync::LazyLock;
 
-use tree_sitter::{Language, Parser, Query, Tree};
+use tree_sitter::{Language, Node, Parser, Query, Tree};
 
 /// Wrapper around the tree-sitter parser with multi-language dispatch.
 #[derive(Default)]
@@ -28,7 +28,8 @@ impl TsParser {
     }
 }
 
-/// Supported tree-sitter languages.
+/// Supported tree-sitter languages. Grammars beyond the core set are each
+/// behind a `lang-*` feature.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum TsLanguageKind {
     Rust,
@@ -38,6 +39,18 @@ pub enum TsLanguageKind {
     Jsx,
     Python,
     Go,
+    #[cfg(feature = "lang-java")]
+    Java,
+    #[cfg(feature = "lang-c")]
+    C,
+    #[cfg(feature = "lang-cpp")]
+    Cpp,
+    #[cfg(feature = "lang-ruby")]
+    Ruby,
+    #[cfg(feature = "lang-csharp")]
+    CSharp,
+    #[cfg(feature = "lang-kotlin")]
+    Kotlin,
 }
 
 impl TsLanguageKind {
@@ -50,6 +63,18 @@ impl TsLanguageKind {
             "JSX" => Some(Self::Jsx),
             "Python" => Some(Self::Python),
             "Go" => Some(Self::Go),
+            #[cfg(feature = "lang-java")]
+            "Java" => Some(Self::Java),
+            #[cfg(feature = "lang-c")]
+            "C" | "C Header" => Some(Self::C),
+            #[cfg(feature = "lang-cpp")]
+            "C++" | "C++ Header" => Some(Self::Cpp),
+            #[cfg(feature = "lang-ruby")]
+            "Ruby" => Some(Self::Ruby),
+            #[cfg(feature = "lang-csharp")]
+            "C#" => Some(Self::CSharp),
+            #[cfg(feature = "lang-kotlin")]
+            "Kotlin" => Some(Self::Kotlin),
             _ => None,
         }
     }
@@ -63,6 +88,18 @@ impl TsLanguageKind {
             Self::Jsx => tree_sitter_javascript::LANGUAGE,
             Self::Python => tree_sitter_python::LANGUAGE,
             Self::Go => tree_sitter_go::LANGUAGE,
+            #[cfg(feature = "lang-java")]
+            Self::Java => tree_sitter_java::LANGUAGE,
+            #[cfg(feature = "lang-c")]
+            Self::C => tree_sitter_c::LANGUAGE,
+            #[cfg(feature = "lang-cpp")]
+            Self::Cpp => tree_sitter_cpp::LANGUAGE,
+            #[cfg(feature = "lang-ruby")]
+            Self::Ruby => tree_sitter_ruby::LANGUAGE,
+            #[cfg(feature = "lang-csharp")]
+            Self::CSharp => tree_sitter_c_sharp::LANGUAGE,
+            #[cfg(feature = "lang-kotlin")]
+            Self::Kotlin => tree_sitter_kotlin_ng::LANGUAGE,
         }
         .into()
     }
@@ -76,6 +113,18 @@ pub fn is_significant_node(lang: TsLanguageKind, kind: &str) -> bool {
         TsLanguageKind::JavaScript | TsLanguageKind::Jsx => &*JS_SIGNIFICANT_KINDS,
         TsLanguageKind::Python => &*PY_SIGNIFICANT_KINDS,
         TsLanguageKind::Go => &*GO_SIGNIFICANT_KINDS,
+        #[cfg(feature = "lang-java")]
+        TsLanguageKind::Java => &*JAVA_SIGNIFICANT_KINDS,
+        #[cfg(feature = "lang-c")]
+        TsLanguageKind::C => &*C_SIGNIFICANT_KINDS,
+        #[cfg(feature = "lang-cpp")]
+        TsLanguageKind::Cpp => &*CPP_SIGNIFICANT_KINDS,
+        #[cfg(feature = "lang-ruby")]
+        TsLanguageKind::Ruby => &*RUBY_SIGNIFICANT_KINDS,
+        #[cfg(feature = "lang-csharp")]
+        TsLanguageKind::CSharp => &*CSHARP_SIGNIFICANT_KINDS,
+        #[cfg(feature = "lang-kotlin")]
+        TsLanguageKind::Kotlin => &*KOTLIN_SIGNIFICANT_KINDS,
     };
     set.contains(kind)
 }
@@ -106,6 +155,10 @@ static RUST_SIGNIFICANT_KINDS: LazyLock<HashSet<&'static str>> = LazyLock::new(|
         "let_declaration",
         // Calls
         "call_expression",
+        "await_expression",
+        "closure_expression",
+        // Blocks
+        "unsafe_block",
         // Literals
         "string_literal",
     ])
@@ -134,6 +187,7 @@ static TS_SIGNIFICANT_KINDS: LazyLock<HashSet<&'static str>> = LazyLock::new(||
         "catch_clause",
         "throw_statement",
         "call_expression",
+        "await_expression",
         "new_expression",
         "string",
     ])
@@ -158,6 +212,7 @@ static JS_SIGNIFICANT_KINDS: LazyLock<HashSet<&'static str>> = LazyLock::new(||
         "catch_clause",
         "throw_statement",
         "call_expression",
+        "await_expression",
         "new_expression",
         "string",
     ])
@@ -181,6 +236,7 @@ static PY_SIGNIFICANT_KINDS: LazyLock<HashSet<&'static str>> = LazyLock::new(||
         "return_statement",
         "raise_statement",
         "call",
+        "await",
         "attribute",
         "string",
     ])
@@ -208,6 +264,175 @@ static GO_SIGNIFICANT_KINDS: LazyLock<HashSet<&'static str>> = LazyLock::new(||
     ])
 });
 
+#[cfg(feature = "lang-java")]
+static JAVA_SIGNIFICANT_KINDS: LazyLock<HashSet<&'static str>> = LazyLock::new(|| {
+    HashSet::from([
+        "class_declaration",
+        "interface_declaration",
+        "enum_declaration",
+        "record_declaration",
+        "method_declaration",
+        "constructor_declaration",
+        "field_declaration",
+        "import_declaration",
+        "local_variable_declaration",
+        "if_statement",
+        "for_statement",
+        "enhanced_for_statement",
+        "while_statement",
+        "switch_expression",
+        "return_statement",
+        "try_statement",
+        "catch_clause",
+        "throw_statement",
+        "method_invocation",
+        "object_creation_expression",
+        "lambda_expression",
+        "string_literal",
+    ])
+});
+
+#[cfg(feature = "lang-c")]
+static C_SIGNIFICANT_KINDS: LazyLock<HashSet<&'static str>> = LazyLock::new(|| {
+    HashSet::from([
+        "function_definition",
+        "struct_specifier",
+        "union_specifier",
+        "enum_specifier",
+        "type_definition",
+        "declaration",
+        "preproc_include",
+        "preproc_def",
+        "preproc_function_def",
+        "if_statement",
+        "for_statement",
+        "while_statement",
+        "do_statement",
+        "switch_statement",
+        "return_statement",
+        "goto_statement",
+        "call_expression",
+        "string_literal",
+    ])
+});
+
+#[cfg(feature = "lang-cpp")]
+static CPP_SIGNIFICANT_KINDS: LazyLock<HashSet<&'static str>> = LazyLock::new(|| {
+    HashSet::from([
+        "function_definition",
+        "class_specifier",
+        "struct_specifier",
+        "union_specifier",
+        "enum_specifier",
+        "namespace_definition",
+        "template_declaration",
+        "type_definition",
+        "alias_declaration",
+        "declaration",
+        "preproc_include",
+        "preproc_def",
+        "if_statement",
+        "for_statement",
+        "for_range_loop",
+        "while_statement",
+        "switch_statement",
+        "return_statement",
+        "try_statement",
+        "catch_clause",
+        "throw_statement",
+        "call_expression",
+        "new_expression",
+        "delete_expression",
+        "lambda_expression",
+        "string_literal",
+    ])
+});
+
+#[cfg(feature = "lang-ruby")]
+static RUBY_SIGNIFICANT_KINDS: LazyLock<HashSet<&'static str>> = LazyLock::new(|| {
+    HashSet::from([
+        "method",
+        "singleton_method",
+        "class",
+        "module",
+        "singleton_class",
+        "assignment",
+        "if",
+        "unless",
+        "case",
+        "while",
+        "until",
+        "for",
+        "return",
+        "begin",
+        "rescue",
+        "call",
+        "block",
+        "do_block",
+        "lambda",
+        "string",
+    ])
+});
+
+#[cfg(feature = "lang-csharp")]
+static CSHARP_SIGNIFICANT_KINDS: LazyLock<HashSet<&'static str>> = LazyLock::new(|| {
+    HashSet::from([
+        "namespace_declaration",
+        "file_scoped_namespace_declaration",
+        "class_declaration",
+        "interface_declaration",
+        "struct_declaration",
+        "record_declaration",
+        "enum_declaration",
+        "method_declaration",
+        "constructor_declaration",
+        "property_declaration",
+        "field_declaration",
+        "using_directive",
+        "local_declaration_statement",
+        "if_statement",
+        "for_statement",
+        "foreach_statement",
+        "while_statement",
+        "switch_statement",
+        "return_statement",
+        "try_statement",
+        "catch_clause",
+        "throw_statement",
+        "invocation_expression",
+        "object_creation_expression",
+        "await_expression",
+        "lambda_expression",
+        "string_literal",
+    ])
+});
+
+#[cfg(feature = "lang-kotlin")]
+static KOTLIN_SIGNIFICANT_KINDS: LazyLock<HashSet<&'static str>> = LazyLock::new(|| {
+    HashSet::from([
+        "class_declaration",
+        "object_declaration",
+        "companion_object",
+        "function_declaration",
+        "secondary_constructor",
+        "property_declaration",
+        "type_alias",
+        "import",
+        "if_expression",
+        "when_expression",
+        "for_statement",
+        "while_statement",
+        "do_while_statement",
+        "return_expression",
+        "try_expression",
+        "catch_block",
+        "throw_expression",
+        "call_expression",
+        "lambda_literal",
+        "string_literal",
+    ])
+});
+
 const RUST_SYMBOL_QUERY_SRC: &str = r#"
 (function_item) @function.definition
 (struct_item) @struct.definition
@@ -248,6 +473,66 @@ const GO_SYMBOL_QUERY_SRC: &str = r#"
 (type_declaration) @type.definition
 "#;
 
+#[cfg(feature = "lang-java")]
+const JAVA_SYMBOL_QUERY_SRC: &str = r#"
+(class_declaration) @class.definition
+(interface_declaration) @interface.definition
+(enum_declaration) @enum.definition
+(record_declaration) @record.definition
+(method_declaration) @method.definition
+(constructor_declaration) @constructor.definition
+"#;
+
+#[cfg(feature = "lang-c")]
+const C_SYMBOL_QUERY_SRC: &str = r#"
+(function_definition) @function.definition
+(struct_specifier name: (_) body: (_)) @struct.definition
+(union_specifier name: (_) body: (_)) @union.definition
+(enum_specifier name: (_) body: (_)) @enum.definition
+(type_definition) @type_alias.definition
+"#;
+
+#[cfg(feature = "lang-cpp")]
+const CPP_SYMBOL_QUERY_SRC: &str = r#"
+(function_definition) @function.definition
+(class_specifier name: (_) body: (_)) @class.definition
+(struct_specifier name: (_) body: (_)) @struct.definition
+(union_specifier name: (_) body: (_)) @union.definition
+(enum_specifier name: (_) body: (_)) @enum.definition
+(namespace_definition name: (_)) @module.definition
+(type_definition) @type_alias.definition
+(alias_declaration) @type_alias.definition
+"#;
+
+#[cfg(feature = "lang-ruby")]
+const RUBY_SYMBOL_QUERY_SRC: &str = r#"
+(method) @method.definition
+(singleton_method) @method.definition
+(class) @class.definition
+(module) @module.definition
+"#;
+
+#[cfg(feature = "lang-csharp")]
+const CSHARP_SYMBOL_QUERY_SRC: &str = r#"
+(namespace_declaration) @module.definition
+(file_scoped_namespace_declaration) @module.definition
+(class_declaration) @class.definition
+(interface_declaration) @interface.definition
+(struct_declaration) @struct.definition
+(record_declaration) @record.definition
+(enum_declaration) @enum.definition
+(method_declaration) @method.definition
+(constructor_declaration) @constructor.definition
+"#;
+
+#[cfg(feature = "lang-kotlin")]
+const KOTLIN_SYMBOL_QUERY_SRC: &str = r#"
+(class_declaration) @class.definition
+(object_declaration) @object.definition
+(function_declaration) @function.definition
+(type_alias) @type_alias.definition
+"#;
+
 const RUST_IMPORT_QUERY_SRC: &str = r#"
 (use_declaration) @import.use
 "#;
@@ -271,6 +556,139 @@ const GO_IMPORT_QUERY_SRC: &str = r#"
 (import_declaration) @import.declaration
 "#;
 
+#[cfg(feature = "lang-java")]
+const JAVA_IMPORT_QUERY_SRC: &str = r#"
+(import_declaration) @import.declaration
+"#;
+
+#[cfg(any(feature = "lang-c", feature = "lang-cpp"))]
+const C_IMPORT_QUERY_SRC: &str = r#"
+(preproc_include) @import.include
+"#;
+
+#[cfg(feature = "lang-ruby")]
+const RUBY_IMPORT_QUERY_SRC: &str = r#"
+((call
+   method: (identifier) @_method
+   arguments: (argument_list . (string))) @import.require
+ (#any-of? @_method "require" "require_relative" "load"))
+"#;
+
+#[cfg(feature = "lang-csharp")]
+const CSHARP_IMPORT_QUERY_SRC: &str = r#"
+(using_directive) @import.using
+"#;
+
+#[cfg(feature = "lang-kotlin")]
+const KOTLIN_IMPORT_QUERY_SRC: &str = r#"
+(import) @import.declaration
+"#;
+
+const RUST_REFERENCE_QUERY_SRC: &str = r#"
+(call_expression function: (identifier) @reference.call)
+(call_expression function: (field_expression field: (field_identifier) @reference.call))
+(call_expression function: (scoped_identifier name: (identifier) @reference.call))
+(struct_expression name: (type_identifier) @reference.call)
+(type_identifier) @reference.type
+(use_declaration argument: (identifier) @reference.import)
+(use_declaration argument: (scoped_identifier name: (identifier) @reference.import))
+(use_list (identifier) @reference.import)
+(use_list (scoped_identifier name: (identifier) @reference.import))
+(use_as_clause path: (identifier) @reference.import)
+(use_as_clause path: (scoped_identifier name: (identifier) @reference.import))
+"#;
+
+const TS_REFERENCE_QUERY_SRC: &str = r#"
+(call_expression function: (identifier) @reference.call)
+(call_expression function: (member_expression property: (property_identifier) @reference.call))
+(new_expression constructor: (identifier) @reference.call)
+(type_identifier) @reference.type
+(import_specifier name: (identifier) @reference.import)
+(import_clause (identifier) @reference.import)
+(namespace_import (identifier) @reference.import)
+"#;
+
+const JS_REFERENCE_QUERY_SRC: &str = r#"
+(call_expression function: (identifier) @reference.call)
+(call_expression function: (member_expression property: (property_identifier) @reference.call))
+(new_expression constructor: (identifier) @reference.call)
+(import_specifier name: (identifier) @reference.import)
+(import_clause (identifier) @reference.import)
+(namespace_import (identifier) @reference.import)
+"#;
+
+const PY_REFERENCE_QUERY_SRC: &str = r#"
+(call function: (identifier) @reference.call)
+(call function: (attribute attribute: (identifier) @reference.call))
+(type (identifier) @reference.type)
+(import_from_statement name: (dotted_name (identifier) @reference.import .))
+(import_from_statement name: (aliased_import name: (dotted_name (identifier) @reference.import .)))
+"#;
+
+const GO_REFERENCE_QUERY_SRC: &str = r#"
+(call_expression function: (identifier) @reference.call)
+(call_expression function: (selector_expression field: (field_identifier) @reference.call))
+(type_identifier) @reference.type
+(import_spec path: (interpreted_string_literal) @reference.import)
+"#;
+
+#[cfg(feature = "lang-java")]
+const JAVA_REFERENCE_QUERY_SRC: &str = r#"
+(method_invocation name: (identifier) @reference.call)
+(object_creation_expression type: (type_identifier) @reference.call)
+(type_identifier) @reference.type
+(import_declaration (scoped_identifier name: (identifier) @reference.import))
+"#;
+
+#[cfg(feature = "lang-c")]
+const C_REFERENCE_QUERY_SRC: &str = r#"
+(call_expression function: (identifier) @reference.call)
+(call_expression function: (field_expression field: (field_identifier) @reference.call))
+(type_identifier) @reference.type
+(preproc_include path: (_) @reference.import)
+"#;
+
+#[cfg(feature = "lang-cpp")]
+const CPP_REFERENCE_QUERY_SRC: &str = r#"
+(call_expression function: (identifier) @reference.call)
+(call_expression function: (field_expression field: (field_identifier) @reference.call))
+(call_expression function: (qualified_identifier name: (identifier) @reference.call))
+(new_expression type: (type_identifier) @reference.call)
+(type_identifier) @reference.type
+(preproc_include path: (_) @reference.import)
+"#;
+
+#[cfg(feature = "lang-ruby")]
+const RUBY_REFERENCE_QUERY_SRC: &str = r#"
+(call method: (identifier) @reference.call)
+(constant) @reference.type
+((call
+   method: (identifier) @_method
+   arguments: (argument_list . (string (string_content) @reference.import)))
+ (#any-of? @_method "require" "require_relative"))
+"#;
+
+#[cfg(feature = "lang-csharp")]
+const CSHARP_REFERENCE_QUERY_SRC: &str = r#"
+(invocation_expression function: (identifier) @reference.call)
+(invocation_expression function: (member_access_expression name: (identifier) @reference.call))
+(object_creation_expression type: (identifier) @reference.call)
+(base_list (identifier) @reference.type)
+(parameter type: (identifier) @reference.type)
+(variable_declaration type: (identifier) @reference.type)
+(method_declaration returns: (identifier) @reference.type)
+(using_directive (qualified_name) @reference.import)
+(using_directive (identifier) @reference.import)
+"#;
+
+#[cfg(feature = "lang-kotlin")]
+const KOTLIN_REFERENCE_QUERY_SRC: &str = r#"
+(call_expression . (identifier) @reference.call)
+(call_expression . (navigation_expression (identifier) @reference.call .))
+(user_type (identifier) @reference.type)
+(import (qualified_identifier (identifier) @reference.import .))
+"#;
+
 static RUST_SYMBOL_QUERY: LazyLock<Query> = LazyLock::new(|| {
     let language = TsLanguageKind::Rust.ts_language();
     Query::new(&language, RUST_SYMBOL_QUERY_SRC).expect("rust symbol query")
@@ -321,6 +739,144 @@ static GO_IMPORT_QUERY: LazyLock<Query> = LazyLock::new(|| {
     Query::new(&language, GO_IMPORT_QUERY_SRC).expect("go import query")
 });
 
+static RUST_REFERENCE_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::Rust.ts_language();
+    Query::new(&language, RUST_REFERENCE_QUERY_SRC).expect("rust reference query")
+});
+
+static TS_REFERENCE_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::TypeScript.ts_language();
+    Query::new(&language, TS_REFERENCE_QUERY_SRC).expect("ts reference query")
+});
+
+static TSX_REFERENCE_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::Tsx.ts_language();
+    Query::new(&language, TS_REFERENCE_QUERY_SRC).expect("tsx reference query")
+});
+
+static JS_REFERENCE_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::JavaScript.ts_language();
+    Query::new(&language, JS_REFERENCE_QUERY_SRC).expect("js reference query")
+});
+
+static PY_REFERENCE_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::Python.ts_language();
+    Query::new(&language, PY_REFERENCE_QUERY_SRC).expect("py reference query")
+});
+
+static GO_REFERENCE_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::Go.ts_language();
+    Query::new(&language, GO_REFERENCE_QUERY_SRC).expect("go reference query")
+});
+
+#[cfg(feature = "lang-java")]
+static JAVA_SYMBOL_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::Java.ts_language();
+    Query::new(&language, JAVA_SYMBOL_QUERY_SRC).expect("java symbol query")
+});
+
+#[cfg(feature = "lang-c")]
+static C_SYMBOL_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::C.ts_language();
+    Query::new(&language, C_SYMBOL_QUERY_SRC).expect("c symbol query")
+});
+
+#[cfg(feature = "lang-cpp")]
+static CPP_SYMBOL_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::Cpp.ts_language();
+    Query::new(&language, CPP_SYMBOL_QUERY_SRC).expect("cpp symbol query")
+});
+
+#[cfg(feature = "lang-ruby")]
+static RUBY_SYMBOL_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::Ruby.ts_language();
+    Query::new(&language, RUBY_SYMBOL_QUERY_SRC).expect("ruby symbol query")
+});
+
+#[cfg(feature = "lang-csharp")]
+static CSHARP_SYMBOL_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::CSharp.ts_language();
+    Query::new(&language, CSHARP_SYMBOL_QUERY_SRC).expect("c# symbol query")
+});
+
+#[cfg(feature = "lang-kotlin")]
+static KOTLIN_SYMBOL_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::Kotlin.ts_language();
+    Query::new(&language, KOTLIN_SYMBOL_QUERY_SRC).expect("kotlin symbol query")
+});
+
+#[cfg(feature = "lang-java")]
+static JAVA_IMPORT_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::Java.ts_language();
+    Query::new(&language, JAVA_IMPORT_QUERY_SRC).expect("java import query")
+});
+
+#[cfg(feature = "lang-c")]
+static C_IMPORT_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::C.ts_language();
+    Query::new(&language, C_IMPORT_QUERY_SRC).expect("c import query")
+});
+
+#[cfg(feature = "lang-cpp")]
+static CPP_IMPORT_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::Cpp.ts_language();
+    Query::new(&language, C_IMPORT_QUERY_SRC).expect("cpp import query")
+});
+
+#[cfg(feature = "lang-ruby")]
+static RUBY_IMPORT_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::Ruby.ts_language();
+    Query::new(&language, RUBY_IMPORT_QUERY_SRC).expect("ruby import query")
+});
+
+#[cfg(feature = "lang-csharp")]
+static CSHARP_IMPORT_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::CSharp.ts_language();
+    Query::new(&language, CSHARP_IMPORT_QUERY_SRC).expect("c# import query")
+});
+
+#[cfg(feature = "lang-kotlin")]
+static KOTLIN_IMPORT_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::Kotlin.ts_language();
+    Query::new(&language, KOTLIN_IMPORT_QUERY_SRC).expect("kotlin import query")
+});
+
+#[cfg(feature = "lang-java")]
+static JAVA_REFERENCE_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::Java.ts_language();
+    Query::new(&language, JAVA_REFERENCE_QUERY_SRC).expect("java reference query")
+});
+
+#[cfg(feature = "lang-c")]
+static C_REFERENCE_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::C.ts_language();
+    Query::new(&language, C_REFERENCE_QUERY_SRC).expect("c reference query")
+});
+
+#[cfg(feature = "lang-cpp")]
+static CPP_REFERENCE_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::Cpp.ts_language();
+    Query::new(&language, CPP_REFERENCE_QUERY_SRC).expect("cpp reference query")
+});
+
+#[cfg(feature = "lang-ruby")]
+static RUBY_REFERENCE_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::Ruby.ts_language();
+    Query::new(&language, RUBY_REFERENCE_QUERY_SRC).expect("ruby reference query")
+});
+
+#[cfg(feature = "lang-csharp")]
+static CSHARP_REFERENCE_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::CSharp.ts_language();
+    Query::new(&language, CSHARP_REFERENCE_QUERY_SRC).expect("c# reference query")
+});
+
+#[cfg(feature = "lang-kotlin")]
+static KOTLIN_REFERENCE_QUERY: LazyLock<Query> = LazyLock::new(|| {
+    let language = TsLanguageKind::Kotlin.ts_language();
+    Query::new(&language, KOTLIN_REFERENCE_QUERY_SRC).expect("kotlin reference query")
+});
+
 /// Return the query that surfaces symbol definitions for the requested language.
 pub fn symbol_query(lang: TsLanguageKind) -> Option<&'static Query> {
     match lang {
@@ -329,6 +885,18 @@ pub fn symbol_query(lang: TsLanguageKind) -> Option<&'static Query> {
         TsLanguageKind::JavaScript | TsLanguageKind::Jsx => Some(&*JS_SYMBOL_QUERY),
         TsLanguageKind::Python => Some(&*PY_SYMBOL_QUERY),
         TsLanguageKind::Go => Some(&*GO_SYMBOL_QUERY),
+        #[cfg(feature = "lang-java")]
+        TsLanguageKind::Java => Some(&*JAVA_SYMBOL_QUERY),
+        #[cfg(feature = "lang-c")]
+        TsLanguageKind::C => Some(&*C_SYMBOL_QUERY),
+        #[cfg(feature = "lang-cpp")]
+        TsLanguageKind::Cpp => Some(&*CPP_SYMBOL_QUERY),
+        #[cfg(feature = "lang-ruby")]
+        TsLanguageKind::Ruby => Some(&*RUBY_SYMBOL_QUERY),
+        #[cfg(feature = "lang-csharp")]
+        TsLanguageKind::CSharp => Some(&*CSHARP_SYMBOL_QUERY),
+        #[cfg(feature = "lang-kotlin")]
+        TsLanguageKind::Kotlin => Some(&*KOTLIN_SYMBOL_QUERY),
     }
 }
 
@@ -340,5 +908,150 @@ pub fn import_query(lang: TsLanguageKind) -> Option<&'static Query> {
         TsLanguageKind::JavaScript | TsLanguageKind::Jsx => Some(&*JS_IMPORT_QUERY),
         TsLanguageKind::Python => Some(&*PY_IMPORT_QUERY),
         TsLanguageKind::Go => Some(&*GO_IMPORT_QUERY),
+        #[cfg(feature = "lang-java")]
+        TsLanguageKind::Java => Some(&*JAVA_IMPORT_QUERY),
+        #[cfg(feature = "lang-c")]
+        TsLanguageKind::C => Some(&*C_IMPORT_QUERY),
+        #[cfg(feature = "lang-cpp")]
+        TsLanguageKind::Cpp => Some(&*CPP_IMPORT_QUERY),
+        #[cfg(feature = "lang-ruby")]
+        TsLanguageKind::Ruby => Some(&*RUBY_IMPORT_QUERY),
+        #[cfg(feature = "lang-csharp")]
+        TsLanguageKind::CSharp => Some(&*CSHARP_IMPORT_QUERY),
+        #[cfg(feature = "lang-kotlin")]
+        TsLanguageKind::Kotlin => Some(&*KOTLIN_IMPORT_QUERY),
+    }
+}
+
+/// Return the query that surfaces references (`@reference.call`,
+/// `@reference.type`, `@reference.import`) for a language.
+pub fn reference_query(lang: TsLanguageKind) -> &'static Query {
+    match lang {
+        TsLanguageKind::Rust => &RUST_REFERENCE_QUERY,
+        TsLanguageKind::TypeScript => &TS_REFERENCE_QUERY,
+        TsLanguageKind::Tsx => &TSX_REFERENCE_QUERY,
+        TsLanguageKind::JavaScript | TsLanguageKind::Jsx => &JS_REFERENCE_QUERY,
+        TsLanguageKind::Python => &PY_REFERENCE_QUERY,
+        TsLanguageKind::Go => &GO_REFERENCE_QUERY,
+        #[cfg(feature = "lang-java")]
+        TsLanguageKind::Java => &JAVA_REFERENCE_QUERY,
+        #[cfg(feature = "lang-c")]
+        TsLanguageKind::C => &C_REFERENCE_QUERY,
+        #[cfg(feature = "lang-cpp")]
+        TsLanguageKind::Cpp => &CPP_REFERENCE_QUERY,
+        #[cfg(feature = "lang-ruby")]
+        TsLanguageKind::Ruby => &RUBY_REFERENCE_QUERY,
+        #[cfg(feature = "lang-csharp")]
+        TsLanguageKind::CSharp => &CSHARP_REFERENCE_QUERY,
+        #[cfg(feature = "lang-kotlin")]
+        TsLanguageKind::Kotlin => &KOTLIN_REFERENCE_QUERY,
+    }
+}
+
+/// Return true if `kind` is a definition whose name identifies the caller of
+/// the references nested inside it.
+pub fn is_definition_node(lang: TsLanguageKind, kind: &str) -> bool {
+    match lang {
+        TsLanguageKind::Rust => matches!(
+            kind,
+            "function_item"
+                | "function_signature_item"
+                | "struct_item"
+                | "enum_item"
+                | "trait_item"
+                | "impl_item"
+                | "type_item"
+                | "mod_item"
+                | "const_item"
+                | "static_item"
+        ),
+        TsLanguageKind::TypeScript
+        | TsLanguageKind::Tsx
+        | TsLanguageKind::JavaScript
+        | TsLanguageKind::Jsx => matches!(
+            kind,
+            "function_declaration"
+                | "method_definition"
+                | "class_declaration"
+                | "interface_declaration"
+                | "type_alias_declaration"
+                | "enum_declaration"
+        ),
+        TsLanguageKind::Python => matches!(kind, "function_definition" | "class_definition"),
+        TsLanguageKind::Go => matches!(
+            kind,
+            "function_declaration" | "method_declaration" | "type_spec"
+        ),
+        #[cfg(feature = "lang-java")]
+        TsLanguageKind::Java => matches!(
+            kind,
+            "class_declaration"
+                | "interface_declaration"
+                | "enum_declaration"
+                | "record_declaration"
+                | "method_declaration"
+                | "constructor_declaration"
+        ),
+        #[cfg(feature = "lang-c")]
+        TsLanguageKind::C => matches!(
+            kind,
+            "function_definition"
+                | "struct_specifier"
+                | "union_specifier"
+                | "enum_specifier"
+                | "type_definition"
+        ),
+        #[cfg(feature = "lang-cpp")]
+        TsLanguageKind::Cpp => matches!(
+            kind,
+            "function_definition"
+                | "class_specifier"
+                | "struct_specifier"
+                | "union_specifier"
+                | "enum_specifier"
+                | "namespace_definition"
+                | "type_definition"
+                | "alias_declaration"
+        ),
+        #[cfg(feature = "lang-ruby")]
+        TsLanguageKind::Ruby => {
+            matches!(kind, "method" | "singleton_method" | "class" | "module")
+        }
+        #[cfg(feature = "lang-csharp")]
+        TsLanguageKind::CSharp => matches!(
+            kind,
+            "namespace_declaration"
+                | "file_scoped_namespace_declaration"
+                | "class_declaration"
+                | "interface_declaration"
+                | "struct_declaration"
+                | "record_declaration"
+                | "enum_declaration"
+                | "method_declaration"
+                | "constructor_declaration"
+        ),
+        #[cfg(feature = "lang-kotlin")]
+        TsLanguageKind::Kotlin => matches!(
+            kind,
+            "class_declaration" | "object_declaration" | "function_declaration" | "type_alias"
+        ),
+    }
+}
+
+/// The node naming a definition: its `name` field, the implemented type of a
+/// Rust `impl` or the alias a Kotlin `typealias` declares, or, for C and C++
+/// functions and typedefs, the identifier at the end of the `declarator`
+/// chain.
+pub fn definition_name(node: Node) -> Option<Node> {
+    if let Some(name) = node.child_by_field_name("name") {
+        return Some(name);
+    }
+    if matches!(node.kind(), "impl_item" | "type_alias") {
+        return node.child_by_field_name("type");
+    }
+    let mut declarator = node.child_by_field_name("declarator")?;
+    while let Some(inner) = declarator.child_by_field_name("declarator") {
+        declarator = inner;
     }
+    Some(declarator)
 }
@@ -0,0 +1,130 @@
+//! Resolve the repositories a query runs against.
+//!
+//! Repositories come from repeated `--repo` values, glob patterns such as
+//! `~/src/*`, and workspace files that list one repository root per line.
+
+use crate::{Error, Result};
+use std::path::{Path, PathBuf};
+
+/// Expand `specs` and the optional workspace file into repository paths, in
+/// the order given and without duplicates. With neither, the `repos` listed
+/// in the config file are used, and without those the current directory is
+/// the only repository.
+///
+/// A spec containing `*`, `?`, or `[` is a glob and expands to the matching
+/// directories. Workspace files skip blank lines and `#` comments, and
+/// resolve relative entries against the file's own directory.
+pub fn resolve_repo_paths(specs: &[String], workspace: Option<&Path>) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for spec in specs {
+        expand_spec(spec, None, &mut paths)?;
+    }
+    if let Some(workspace) = workspace {
+        let contents = std::fs::read_to_string(workspace).map_err(|e| {
+            Error::Repo(format!(
+                "cannot read workspace file {}: {e}",
+                workspace.display()
+            ))
+        })?;
+        let base = workspace.parent().unwrap_or_else(|| Path::new("."));
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            expand_spec(line, Some(base), &mut paths)?;
+        }
+    }
+    if specs.is_empty() && workspace.is_none() {
+        for spec in &crate::config::current().repos.value {
+            expand_spec(spec, None, &mut paths)?;
+        }
+    }
+    if paths.is_empty() {
+        paths.push(std::env::current_dir()?);
+    }
+
+    let mut unique = Vec::with_capacity(paths.len());
+    for path in paths {
+        if !unique.contains(&path) {
+            unique.push(path);
+        }
+    }
+    Ok(unique)
+}
+
+fn expand_spec(spec: &str, base: Option<&Path>, paths: &mut Vec<PathBuf>) -> Result<()> {
+    let path = expand_home(spec);
+    let path = match base {
+        Some(base) if path.is_relative() => base.join(path),
+        _ => path,
+    };
+    if path == Path::new(".") {
+        paths.push(std::env::current_dir()?);
+        return Ok(());
+    }
+    if !spec.contains(['*', '?', '[']) {
+        paths.push(path);
+        return Ok(());
+    }
+
+    let pattern = path.to_string_lossy();
+    let matches = glob::glob(&pattern)
+        .map_err(|e| Error::Repo(format!("invalid repository pattern {spec}: {e}")))?;
+    let mut found: Vec<PathBuf> = matches
+        .filter_map(|entry| entry.ok())
+        .filter(|path| path.is_dir())
+        .collect();
+    if found.is_empty() {
+        return Err(Error::Repo(format!(
+            "repository pattern {spec} matched no directories"
+        )));
+    }
+    found.sort();
+    paths.extend(found);
+    Ok(())
+}
+
+fn expand_home(spec: &str) -> PathBuf {
+    match spec.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .map(|home| home.join(rest))
+            .unwrap_or_else(|| PathBuf::from(spec)),
+        None => PathBuf::from(spec),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs_and_workspace_entries_expand_in_order_without_duplicates() {
+        let temp = tempfile::tempdir().expect("temp");
+        for name in ["api", "billing", "web"] {
+            std::fs::create_dir(temp.path().join(name)).expect("mkdir");
+        }
+        std::fs::write(temp.path().join("notes.txt"), "not a repo").expect("write");
+        let workspace = temp.path().join("services.workspace");
+        std::fs::write(&workspace, "# services\nweb\n\napi\n").expect("write");
+
+        let pattern = temp.path().join("b*").to_string_lossy().to_string();
+        let paths = resolve_repo_paths(&[pattern], Some(&workspace)).expect("resolve");
+
+        assert_eq!(
+            paths,
+            vec![
+                temp.path().join("billing"),
+                temp.path().join("web"),
+                temp.path().join("api"),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_glob_without_matching_directories_is_an_error() {
+        let temp = tempfile::tempdir().expect("temp");
+        let pattern = temp.path().join("*").to_string_lossy().to_string();
+        assert!(resolve_repo_paths(&[pattern], None).is_err());
+    }
+}
@@ -0,0 +1,550 @@
+//! Saved queries: a team library of `.sql` files in `~/.devsql/queries`
+//! (override the root with `DEVSQL_HOME`).
+//!
+//! Each file holds one query named after the file. Leading comment lines
+//! describe it and declare its named parameters, optionally with a default:
+//!
+//! ```sql
+//! -- description: Sessions with the commits made while they ran
+//! -- param repo: Repository to report on
+//! -- param days = 7: How many days back to look
+//! SELECT ... WHERE c.repo = :repo AND c.authored_at >= date('now', '-' || :days || ' days')
+//! ```
+//!
+//! `devsql run <name> --param k=v` runs one. Any other query can also read
+//! a saved query as a table: it is registered as a temporary view with its
+//! parameters filled in from the caller's named params or their defaults.
+
+use crate::source;
+use crate::{Error, Result};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+/// One query from the library.
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedQuery {
+    /// File name without `.sql`; also the view and command name
+    pub name: String,
+    pub description: String,
+    pub params: Vec<SavedParam>,
+    /// The file's SQL, front matter included, without a trailing `;`
+    pub sql: String,
+    pub path: PathBuf,
+}
+
+/// A named parameter declared with `-- param name [= default]: description`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedParam {
+    pub name: String,
+    pub description: String,
+    pub default: Option<Value>,
+}
+
+/// A saved query as registered for another query to read: its SQL with
+/// every parameter replaced by a literal.
+#[derive(Debug, Clone)]
+pub struct View {
+    pub name: String,
+    pub sql: String,
+}
+
+/// Directory holding the saved-query library.
+pub fn library_dir() -> PathBuf {
+    crate::worklog::home_dir().join("queries")
+}
+
+/// Every saved query in the library, sorted by name. A missing directory is
+/// an empty library; a file that does not parse is an error naming it.
+pub fn library() -> Result<Vec<SavedQuery>> {
+    load_dir(&library_dir())
+}
+
+fn load_dir(dir: &Path) -> Result<Vec<SavedQuery>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut queries: Vec<SavedQuery> = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_none_or(|extension| extension != "sql") {
+            continue;
+        }
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default()
+            .to_string();
+        let text = std::fs::read_to_string(&path)?;
+        let query = SavedQuery::parse(&name, &text, path.clone())
+            .map_err(|e| Error::SavedQuery(format!("{}: {e}", path.display())))?;
+        if let Some(other) = queries
+            .iter()
+            .find(|other| other.name.eq_ignore_ascii_case(&name))
+        {
+            return Err(Error::SavedQuery(format!(
+                "{} and {} define the same query",
+                other.path.display(),
+                path.display()
+            )));
+        }
+        queries.push(query);
+    }
+    queries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(queries)
+}
+
+impl SavedQuery {
+    /// Parse a library file. Front matter is the run of `--` comment lines
+    /// (and blank lines) before the first line of SQL; comments other than
+    /// `description:` and `param` are left alone.
+    pub fn parse(name: &str, text: &str, path: PathBuf) -> std::result::Result<Self, String> {
+        if !is_identifier(name) {
+            return Err(format!(
+                "'{name}' is not a valid query name; use letters, digits, and underscores"
+            ));
+        }
+        let mut description = String::new();
+        let mut params: Vec<SavedParam> = Vec::new();
+        for line in text.lines().map(str::trim) {
+            let Some(comment) = line.strip_prefix("--") else {
+                if line.is_empty() {
+                    continue;
+                }
+                break;
+            };
+            let comment = comment.trim();
+            if let Some(text) = comment.strip_prefix("description:") {
+                description = text.trim().to_string();
+            } else if let Some(declaration) = comment.strip_prefix("param ") {
+                let param = parse_param(declaration)?;
+                if params.iter().any(|other| other.name == param.name) {
+                    return Err(format!("parameter '{}' is declared twice", param.name));
+                }
+                params.push(param);
+            }
+        }
+        let sql = text.trim_end().trim_end_matches(';').trim_end().to_string();
+        if sql.lines().all(|line| {
+            let line = line.trim();
+            line.is_empty() || line.starts_with("--")
+        }) {
+            return Err("the file has no SQL".to_string());
+        }
+        Ok(Self {
+            name: name.to_string(),
+            description,
+            params,
+            sql,
+            path,
+        })
+    }
+
+    /// Values for every declared parameter: the given ones, then defaults.
+    /// A required parameter without a value, or a value for a parameter the
+    /// query does not declare, is an error.
+    pub fn bind(
+        &self,
+        mut given: Map<String, Value>,
+    ) -> std::result::Result<Map<String, Value>, String> {
+        if let Some(unknown) = given
+            .keys()
+            .find(|name| !self.params.iter().any(|param| &param.name == *name))
+        {
+            let declared: Vec<&str> = self.params.iter().map(|p| p.name.as_str()).collect();
+            return Err(format!(
+                "saved query '{}' has no parameter '{unknown}' (parameters: {})",
+                self.name,
+                if declared.is_empty() {
+                    "none".to_string()
+                } else {
+                    declared.join(", ")
+                }
+            ));
+        }
+        for param in &self.params {
+            if given.contains_key(&param.name) {
+                continue;
+            }
+            let Some(default) = &param.default else {
+                return Err(format!(
+                    "saved query '{}' needs parameter '{}'",
+                    self.name, param.name
+                ));
+            };
+            given.insert(param.name.clone(), default.clone());
+        }
+        Ok(given)
+    }
+
+    /// The query as a view body, taking each declared parameter from
+    /// `params` (other entries are ignored) or its default.
+    fn view_sql(&self, params: &Map<String, Value>) -> std::result::Result<String, String> {
+        let given = params
+            .iter()
+            .filter(|(name, _)| self.params.iter().any(|param| &param.name == *name))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        let values = self.bind(given)?;
+        inline_params(&self.sql, &values)
+            .map_err(|e| format!("saved query '{}' cannot be used as a view: {e}", self.name))
+    }
+}
+
+/// Parse `name [= default]: description`.
+fn parse_param(declaration: &str) -> std::result::Result<SavedParam, String> {
+    let (head, description) = declaration.split_once(':').unwrap_or((declaration, ""));
+    let (name, default) = match head.split_once('=') {
+        Some((name, default)) => (name.trim(), Some(parse_value(default.trim()))),
+        None => (head.trim(), None),
+    };
+    if !is_identifier(name) {
+        return Err(format!("'{name}' is not a valid parameter name"));
+    }
+    Ok(SavedParam {
+        name: name.to_string(),
+        description: description.trim().to_string(),
+        default,
+    })
+}
+
+/// Parse a `--param` assignment such as `days=7`.
+pub fn parse_assignment(assignment: &str) -> std::result::Result<(String, Value), String> {
+    let (name, value) = assignment
+        .split_once('=')
+        .ok_or_else(|| format!("--param '{assignment}' must look like name=value"))?;
+    let name = name.trim().trim_start_matches([':', '@', '$']);
+    if !is_identifier(name) {
+        return Err(format!("'{name}' is not a valid parameter name"));
+    }
+    Ok((name.to_string(), parse_value(value)))
+}
+
+/// Read a parameter value from text: a whole number or a decimal when it
+/// reads as one, otherwise the text itself. Quotes force text, so `'0042'`
+/// stays a string.
+pub fn parse_value(text: &str) -> Value {
+    for quote in ['\'', '"'] {
+        if let Some(inner) = text
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return Value::String(inner.to_string());
+        }
+    }
+    if let Ok(integer) = text.parse::<i64>() {
+        if integer.to_string() == text {
+            return Value::from(integer);
+        }
+    }
+    match text.parse::<f64>() {
+        Ok(real) if real.is_finite() && text.contains('.') && !text.starts_with(['+', '.']) => {
+            Value::from(real)
+        }
+        _ => Value::String(text.to_string()),
+    }
+}
+
+/// The saved queries `query` reads, directly or through other saved
+/// queries, in the order their views must be created. Names of devsql's
+/// own tables always mean the table, and names that match nothing are left
+/// for SQLite to report.
+pub fn views(query: &str, params: &Map<String, Value>) -> Result<Vec<View>> {
+    if saved_names(query).is_empty() {
+        // Skip reading the library for the common case.
+        return Ok(Vec::new());
+    }
+    resolve_views(&library()?, query, params)
+}
+
+fn resolve_views(
+    library: &[SavedQuery],
+    query: &str,
+    params: &Map<String, Value>,
+) -> Result<Vec<View>> {
+    let mut views = Vec::new();
+    let mut visiting = Vec::new();
+    for name in saved_names(query) {
+        add_view(library, &name, params, &mut views, &mut visiting)?;
+    }
+    Ok(views)
+}
+
+fn add_view(
+    library: &[SavedQuery],
+    name: &str,
+    params: &Map<String, Value>,
+    views: &mut Vec<View>,
+    visiting: &mut Vec<String>,
+) -> Result<()> {
+    let Some(saved) = library
+        .iter()
+        .find(|query| query.name.eq_ignore_ascii_case(name))
+    else {
+        return Ok(());
+    };
+    if views.iter().any(|view| view.name == saved.name) {
+        return Ok(());
+    }
+    if visiting.contains(&saved.name) {
+        visiting.push(saved.name.clone());
+        return Err(Error::SavedQuery(format!(
+            "saved queries refer to each other in a cycle: {}",
+            visiting.join(" -> ")
+        )));
+    }
+    visiting.push(saved.name.clone());
+    let sql = saved.view_sql(params).map_err(Error::SavedQuery)?;
+    for dependency in saved_names(&sql) {
+        add_view(library, &dependency, params, views, visiting)?;
+    }
+    visiting.pop();
+    views.push(View {
+        name: saved.name.clone(),
+        sql,
+    });
+    Ok(())
+}
+
+/// Tables `query` reads that devsql does not load itself, sorted.
+fn saved_names(query: &str) -> Vec<String> {
+    let mut names: Vec<String> = vcsql::sql::referenced_tables(query)
+        .into_iter()
+        .filter(|name| !source::provides(name))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Replace each `:name`, `@name`, or `$name` parameter in `sql` with the
+/// SQL literal for its value. Strings, quoted identifiers, and comments are
+/// copied untouched. Views cannot hold parameters, so this is how a saved
+/// query's parameters reach its view.
+fn inline_params(sql: &str, values: &Map<String, Value>) -> std::result::Result<String, String> {
+    let bytes = sql.as_bytes();
+    let mut out = String::with_capacity(sql.len());
+    let mut copied = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let next = bytes.get(i + 1).copied();
+        match bytes[i] {
+            quote @ (b'\'' | b'"' | b'`') => i = skip_past(bytes, i + 1, &[quote]),
+            b'[' => i = skip_past(bytes, i + 1, b"]"),
+            b'-' if next == Some(b'-') => i = skip_past(bytes, i + 2, b"\n"),
+            b'/' if next == Some(b'*') => i = skip_past(bytes, i + 2, b"*/"),
+            b'?' => return Err("positional (?) parameters cannot be named; use :name".into()),
+            b':' | b'@' | b'$'
+                if next.is_some_and(|c| c.is_ascii_alphabetic() || c == b'_')
+                    && (i == 0 || !is_identifier_byte(bytes[i - 1])) =>
+            {
+                let end = (i + 1..bytes.len())
+                    .find(|&j| !is_identifier_byte(bytes[j]))
+                    .unwrap_or(bytes.len());
+                let name = &sql[i + 1..end];
+                let value = values
+                    .get(name)
+                    .ok_or_else(|| format!("parameter :{name} is not declared"))?;
+                out.push_str(&sql[copied..i]);
+                out.push_str(&literal(value));
+                copied = end;
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    out.push_str(&sql[copied..]);
+    Ok(out)
+}
+
+/// Index just past the first `close` at or after `from`, or the end.
+fn skip_past(bytes: &[u8], from: usize, close: &[u8]) -> usize {
+    bytes[from.min(bytes.len())..]
+        .windows(close.len())
+        .position(|window| window == close)
+        .map_or(bytes.len(), |at| from + at + close.len())
+}
+
+fn literal(value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Bool(b) => i64::from(*b).to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => format!("'{}'", s.replace('\'', "''")),
+        other => format!("'{}'", other.to_string().replace('\'', "''")),
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.bytes()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == b'_')
+        && name.bytes().all(is_identifier_byte)
+}
+
+fn is_identifier_byte(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn write_library(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::TempDir::new().expect("temp");
+        for (name, text) in files {
+            std::fs::write(dir.path().join(name), text).expect("write");
+        }
+        dir
+    }
+
+    fn object(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().expect("object")
+    }
+
+    #[test]
+    fn front_matter_declares_description_and_parameters() {
+        let text = "-- description: Commits by author\n\
+                    -- Any other comment is kept as SQL.\n\
+                    -- param author: Author email\n\
+                    -- param days = 7: Days back\n\
+                    -- param label = 'all': Label\n\
+                    SELECT * FROM commits WHERE author_email = :author;\n";
+        let query = SavedQuery::parse("by_author", text, PathBuf::new()).expect("parse");
+        assert_eq!(query.description, "Commits by author");
+        let params: Vec<(&str, Option<&Value>)> = query
+            .params
+            .iter()
+            .map(|p| (p.name.as_str(), p.default.as_ref()))
+            .collect();
+        assert_eq!(
+            params,
+            [
+                ("author", None),
+                ("days", Some(&json!(7))),
+                ("label", Some(&json!("all")))
+            ]
+        );
+        assert_eq!(query.params[0].description, "Author email");
+        assert!(query.sql.ends_with(":author"));
+
+        assert!(SavedQuery::parse("bad-name", "SELECT 1", PathBuf::new()).is_err());
+        assert!(SavedQuery::parse("empty", "-- description: x\n", PathBuf::new()).is_err());
+        assert!(
+            SavedQuery::parse("twice", "-- param a\n-- param a\nSELECT :a", PathBuf::new())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn bind_fills_defaults_and_rejects_missing_or_unknown_parameters() {
+        let query = SavedQuery::parse(
+            "q",
+            "-- param a\n-- param b = 2\nSELECT :a, :b",
+            PathBuf::new(),
+        )
+        .expect("parse");
+        assert_eq!(
+            Value::Object(query.bind(object(json!({"a": "x"}))).expect("bind")),
+            json!({"a": "x", "b": 2})
+        );
+        let missing = query.bind(Map::new()).unwrap_err();
+        assert!(missing.contains("needs parameter 'a'"), "{missing}");
+        let unknown = query.bind(object(json!({"a": 1, "c": 1}))).unwrap_err();
+        assert!(unknown.contains("no parameter 'c'"), "{unknown}");
+    }
+
+    #[test]
+    fn parameter_values_read_as_numbers_unless_quoted_or_padded() {
+        assert_eq!(parse_value("7"), json!(7));
+        assert_eq!(parse_value("-1.5"), json!(-1.5));
+        assert_eq!(parse_value("0042"), json!("0042"));
+        assert_eq!(parse_value("'7'"), json!("7"));
+        assert_eq!(parse_value("me@example.com"), json!("me@example.com"));
+        assert_eq!(parse_value("nan"), json!("nan"));
+        assert_eq!(
+            parse_assignment(":days=30").expect("assignment"),
+            ("days".to_string(), json!(30))
+        );
+        assert!(parse_assignment("days").is_err());
+    }
+
+    #[test]
+    fn inlining_replaces_parameters_outside_strings_and_comments() {
+        let values = object(json!({"who": "O'Neil", "n": 3}));
+        let sql = "SELECT ':who', \":who\", [:who], a$b -- :who\n\
+                   /* @who */ FROM t WHERE x = :who AND y < @n AND z = $n";
+        assert_eq!(
+            inline_params(sql, &values).expect("inline"),
+            "SELECT ':who', \":who\", [:who], a$b -- :who\n\
+             /* @who */ FROM t WHERE x = 'O''Neil' AND y < 3 AND z = 3"
+        );
+        assert!(inline_params("SELECT ?", &values).is_err());
+        assert!(inline_params("SELECT :other", &values).is_err());
+    }
+
+    #[test]
+    fn views_resolve_through_other_saved_queries_in_dependency_order() {
+        let dir = write_library(&[
+            (
+                "recent.sql",
+                "-- param days = 7\nSELECT id FROM commits WHERE authored_at > :days",
+            ),
+            (
+                "recent_count.sql",
+                "SELECT COUNT(*) AS n FROM recent JOIN history",
+            ),
+            ("a.sql", "SELECT * FROM b"),
+            ("b.sql", "SELECT * FROM a"),
+            ("notes.txt", "not a query"),
+        ]);
+        let library = load_dir(dir.path()).expect("library");
+        let names: Vec<&str> = library.iter().map(|q| q.name.as_str()).collect();
+        assert_eq!(names, ["a", "b", "recent", "recent_count"]);
+
+        let params = object(json!({"days": 30, "unrelated": 1}));
+        let views = resolve_views(
+            &library,
+            "SELECT * FROM recent_count, commits, no_such_table",
+            &params,
+        )
+        .expect("views");
+        let resolved: Vec<(&str, &str)> = views
+            .iter()
+            .map(|view| (view.name.as_str(), view.sql.as_str()))
+            .collect();
+        assert_eq!(
+            resolved,
+            [
+                (
+                    "recent",
+                    "-- param days = 7\nSELECT id FROM commits WHERE authored_at > 30"
+                ),
+                (
+                    "recent_count",
+                    "SELECT COUNT(*) AS n FROM recent JOIN history"
+                ),
+            ]
+        );
+
+        let cycle = resolve_views(&library, "SELECT * FROM a", &Map::new()).unwrap_err();
+        assert!(cycle.to_string().contains("a -> b -> a"), "{cycle}");
+    }
+
+    #[test]
+    fn views_run_against_the_engine() {
+        let engine = crate::UnifiedEngine::new(PathBuf::from("/nonexistent"), PathBuf::from("."))
+            .expect("engine");
+        let view = View {
+            name: "numbers".to_string(),
+            sql: "SELECT 1 AS n UNION ALL SELECT 2".to_string(),
+        };
+        engine.create_view(&view.name, &view.sql).expect("view");
+        let rows = engine
+            .query("SELECT SUM(n) AS total FROM numbers")
+            .expect("query");
+        assert_eq!(rows[0]["total"], 3);
+    }
+}
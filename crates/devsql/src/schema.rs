@@ -0,0 +1,1069 @@
+//! Registry of every table devsql can query.
+//!
+//! Column names and types are not written down here: [`tables`] runs the
+//! `CREATE` statement each [data source](crate::source) declares in a scratch
+//! connection and reads the columns back with `pragma_table_info`, so the
+//! registry always matches what a query sees. This module adds only what SQL
+//! cannot say about the built-in tables: descriptions, where rows come from,
+//! and the cargo feature a table needs. Git tables take their descriptions
+//! from vcsql's [`vcsql::sql::TableInfo`]; registered sources describe their
+//! own tables.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::source;
+use crate::Result;
+
+/// Category headings, in the order tables are listed.
+pub const CATEGORIES: &[&str] = &[
+    "CLAUDE",
+    "CODEX",
+    "GIT",
+    "CODE",
+    "SHELL",
+    "WORKLOG",
+    "FULL-TEXT",
+];
+
+/// A queryable table or view.
+#[derive(Debug, Clone, Serialize)]
+pub struct TableSchema {
+    pub name: String,
+    /// One of [`CATEGORIES`].
+    pub category: String,
+    pub description: String,
+    /// Files, directories, or databases the rows are read from.
+    pub sources: Vec<String>,
+    /// Cargo feature the table needs, if any.
+    pub feature: Option<String>,
+    /// Whether this build can load the table (false if `feature` is off).
+    pub available: bool,
+    pub columns: Vec<ColumnSchema>,
+}
+
+/// A column of a [`TableSchema`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ColumnSchema {
+    pub name: String,
+    /// Declared SQLite type; empty for computed view columns and FTS5 columns.
+    pub sql_type: String,
+    pub nullable: bool,
+    pub description: String,
+}
+
+/// A documented example query.
+#[derive(Debug, Clone)]
+pub struct Example {
+    /// One of [`CATEGORIES`].
+    pub category: &'static str,
+    pub description: &'static str,
+    pub sql: &'static str,
+}
+
+/// Categories of the built-in tables, then the name of each registered
+/// source.
+pub fn categories() -> Vec<String> {
+    let mut categories: Vec<String> = CATEGORIES.iter().map(|c| c.to_string()).collect();
+    for source in source::registered() {
+        if !categories.iter().any(|c| c == source.name()) {
+            categories.push(source.name().to_string());
+        }
+    }
+    categories
+}
+
+/// Describes every table, grouped in [`categories`] order.
+pub fn tables() -> Result<Vec<TableSchema>> {
+    let conn = scratch_connection()?;
+    let mut tables = Vec::new();
+    for category in CATEGORIES {
+        if *category == "GIT" {
+            for info in vcsql::TABLES {
+                tables.push(git_table(&conn, info)?);
+            }
+            continue;
+        }
+        for doc in DOCS.iter().filter(|doc| doc.category == *category) {
+            tables.push(documented_table(&conn, doc)?);
+        }
+    }
+    for source in source::registered() {
+        for table in source.tables() {
+            if tables.iter().any(|t| t.name == table.name) {
+                continue;
+            }
+            let columns = introspect(&conn, &table.name)?
+                .into_iter()
+                .map(|(name, sql_type, not_null)| ColumnSchema {
+                    name,
+                    sql_type,
+                    nullable: !not_null,
+                    description: String::new(),
+                })
+                .collect();
+            tables.push(TableSchema {
+                name: table.name,
+                category: source.name().to_string(),
+                description: table.description,
+                sources: Vec::new(),
+                feature: None,
+                available: true,
+                columns,
+            });
+        }
+    }
+    Ok(tables)
+}
+
+/// Description of a built-in table, or `""` if it has none.
+pub(crate) fn description(name: &str) -> &'static str {
+    DOCS.iter()
+        .find(|doc| doc.name == name)
+        .map_or("", |doc| doc.description)
+}
+
+/// Describes the table called `name`, if there is one.
+pub fn table(name: &str) -> Result<Option<TableSchema>> {
+    Ok(tables()?.into_iter().find(|table| table.name == name))
+}
+
+/// An in-memory connection holding every table, view, and index devsql
+/// creates, all empty.
+pub(crate) fn scratch_connection() -> Result<Connection> {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    let conn = Connection::open_in_memory()?;
+
+    // The Codex views read from the attached index cache; a shared in-memory
+    // database with the cache's schema stands in for it.
+    let codex_uri = format!(
+        "file:devsql-schema-{}-{}?mode=memory&cache=shared",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    );
+    let codex = Connection::open(&codex_uri)?;
+    crate::codex_index::create_schema(&codex)?;
+    conn.execute("ATTACH DATABASE ?1 AS codex_index", [&codex_uri])?;
+
+    let mut created: Vec<String> = Vec::new();
+    for source in source::sources() {
+        for table in source.tables() {
+            // A table a registered source redeclares keeps the first schema.
+            if table.schema.is_empty() || created.contains(&table.name) {
+                continue;
+            }
+            conn.execute_batch(&table.schema)?;
+            created.push(table.name);
+        }
+    }
+    Ok(conn)
+}
+
+/// `(name, declared type, not null)` for each column of `table`.
+fn introspect(conn: &Connection, table: &str) -> Result<Vec<(String, String, bool)>> {
+    let mut stmt =
+        conn.prepare("SELECT name, type, \"notnull\" FROM pragma_table_info(?1) ORDER BY cid")?;
+    let columns = stmt
+        .query_map([table], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(columns)
+}
+
+fn documented_table(conn: &Connection, doc: &TableDoc) -> Result<TableSchema> {
+    let columns = introspect(conn, doc.name)?
+        .into_iter()
+        .map(|(name, sql_type, not_null)| ColumnSchema {
+            description: doc
+                .columns
+                .iter()
+                .find(|(column, _)| *column == name)
+                .map(|(_, description)| description.to_string())
+                .unwrap_or_default(),
+            name,
+            sql_type,
+            nullable: !not_null,
+        })
+        .collect();
+    Ok(TableSchema {
+        name: doc.name.to_string(),
+        category: doc.category.to_string(),
+        description: doc.description.to_string(),
+        sources: doc
+            .sources
+            .iter()
+            .map(|source| source.to_string())
+            .collect(),
+        feature: doc.feature.map(String::from),
+        available: doc.feature.is_none() || cfg!(feature = "tree-sitter-ast"),
+        columns,
+    })
+}
+
+fn git_table(conn: &Connection, info: &vcsql::sql::TableInfo) -> Result<TableSchema> {
+    let columns = introspect(conn, info.name)?
+        .into_iter()
+        .map(|(name, sql_type, not_null)| ColumnSchema {
+            description: info
+                .columns
+                .iter()
+                .find(|column| column.name == name)
+                .map(|column| column.description.to_string())
+                .unwrap_or_default(),
+            name,
+            sql_type,
+            nullable: !not_null,
+        })
+        .collect();
+    Ok(TableSchema {
+        name: info.name.to_string(),
+        category: "GIT".to_string(),
+        description: info.description.to_string(),
+        sources: vec![GIT_SOURCE.to_string()],
+        feature: None,
+        available: true,
+        columns,
+    })
+}
+
+const GIT_SOURCE: &str =
+    "Git repositories named by --repo / --workspace (default: the current one)";
+const CODE_SOURCE: &str = "Working tree of each --repo / --workspace repository";
+const TRANSCRIPT_SOURCES: &[&str] = &[
+    "~/.claude/projects/<slug>/**/*.jsonl",
+    "~/.claude/transcripts/*.jsonl",
+];
+const CODEX_SOURCES: &[&str] = &[
+    "~/.codex/sessions/**/rollout-*.jsonl[.zst]",
+    "~/.codex/archived_sessions/**/rollout-*.jsonl[.zst]",
+    "~/.cache/devsql/codex-index/<digest>.sqlite (incremental cache)",
+];
+const TREE_SITTER: Option<&str> = Some("tree-sitter-ast");
+
+/// What the `CREATE` statement of a table cannot say about it.
+struct TableDoc {
+    name: &'static str,
+    category: &'static str,
+    description: &'static str,
+    sources: &'static [&'static str],
+    feature: Option<&'static str>,
+    /// `(column, description)` for every column.
+    columns: &'static [(&'static str, &'static str)],
+}
+
+static DOCS: &[TableDoc] = &[
+    // CLAUDE
+    TableDoc {
+        name: "history",
+        category: "CLAUDE",
+        description: "Prompts typed into Claude Code",
+        sources: &["~/.claude/history.jsonl"],
+        feature: None,
+        columns: &[
+            ("rowid", "Row number, joined on by history_fts"),
+            ("display", "Prompt text"),
+            ("timestamp", "Epoch milliseconds"),
+            ("project", "Working directory the prompt was typed in"),
+        ],
+    },
+    TableDoc {
+        name: "transcripts",
+        category: "CLAUDE",
+        description: "One row per Claude Code transcript entry, subagents included",
+        sources: TRANSCRIPT_SOURCES,
+        feature: None,
+        columns: &[
+            ("rowid", "Stable row number, joined on by transcripts_fts"),
+            (
+                "type",
+                "Entry type (user, assistant, tool_use, tool_result, ...)",
+            ),
+            ("content", "Message text or tool payload"),
+            ("tool_name", "Tool called by a tool_use entry"),
+            ("session_id", "Session id recorded on the entry"),
+            ("_source_file", "Transcript file the entry was read from"),
+            ("_session_id", "Session id taken from the file name"),
+            (
+                "_project",
+                "Project slug directory; NULL for legacy transcripts",
+            ),
+            (
+                "_agent_id",
+                "Subagent id; NULL outside subagent transcripts",
+            ),
+            ("timestamp", "RFC 3339 time of the entry"),
+            ("model", "Model that produced an assistant entry"),
+            ("usage_input_tokens", "Input tokens billed for the response"),
+            ("usage_output_tokens", "Output tokens of the response"),
+            (
+                "usage_cache_read_input_tokens",
+                "Input tokens read from the prompt cache",
+            ),
+            (
+                "usage_cache_creation_input_tokens",
+                "Input tokens written to the prompt cache",
+            ),
+            (
+                "usage_ephemeral_5m_input_tokens",
+                "Cache writes with a 5 minute lifetime",
+            ),
+            (
+                "usage_ephemeral_1h_input_tokens",
+                "Cache writes with a 1 hour lifetime",
+            ),
+            ("usage_service_tier", "Service tier the response ran on"),
+        ],
+    },
+    TableDoc {
+        name: "sessions",
+        category: "CLAUDE",
+        description: "One row per Claude Code session, aggregated from its transcript",
+        sources: &["~/.claude/projects/<slug>/*.jsonl"],
+        feature: None,
+        columns: &[
+            ("session_id", "Session id (the transcript file name)"),
+            (
+                "project",
+                "Project slug directory; NULL for legacy transcripts",
+            ),
+            ("cwd", "Working directory of the session"),
+            ("git_branch", "Git branch checked out during the session"),
+            ("version", "Claude Code version"),
+            ("title", "Session summary or first prompt"),
+            ("first_timestamp", "Time of the first entry"),
+            ("last_timestamp", "Time of the last entry"),
+            ("user_message_count", "User messages in the session"),
+            (
+                "assistant_message_count",
+                "Assistant messages in the session",
+            ),
+            (
+                "subagent_count",
+                "Subagent transcripts started by the session",
+            ),
+            ("total_input_tokens", "Sum of usage_input_tokens"),
+            ("total_output_tokens", "Sum of usage_output_tokens"),
+            (
+                "total_cache_read_input_tokens",
+                "Sum of usage_cache_read_input_tokens",
+            ),
+            (
+                "total_cache_creation_input_tokens",
+                "Sum of usage_cache_creation_input_tokens",
+            ),
+            ("pr_url", "Pull request linked from the session"),
+            ("pr_number", "Number of the linked pull request"),
+        ],
+    },
+    TableDoc {
+        name: "tool_calls",
+        category: "CLAUDE",
+        description: "Tool calls made by Claude Code, one row per call",
+        sources: TRANSCRIPT_SOURCES,
+        feature: None,
+        columns: &[
+            ("rowid", "Row number"),
+            ("tool_name", "Tool called (Bash, Read, Edit, Agent, ...)"),
+            ("input_json", "Tool input as JSON"),
+            (
+                "target",
+                "Command's first line for Bash, subagent type for Agent, else file_path",
+            ),
+            ("source_id", "Tool use id"),
+            ("command", "Full command of a Bash call"),
+            ("session_id", "Session the call was made in"),
+            ("parent_session_id", "Parent session of a subagent call"),
+            ("agent_id", "Subagent id; NULL in top-level sessions"),
+            ("agent_role", "Subagent name"),
+            ("originator", "Client that started the session"),
+            ("cwd", "Working directory of the call"),
+            ("source_path", "Transcript file the call was read from"),
+            ("_project", "Project slug directory"),
+            ("timestamp", "RFC 3339 time of the call"),
+        ],
+    },
+    TableDoc {
+        name: "todos",
+        category: "CLAUDE",
+        description: "Claude Code todo items",
+        sources: &["~/.claude/todos/*.json"],
+        feature: None,
+        columns: &[
+            ("rowid", "Row number"),
+            ("content", "Todo text"),
+            ("status", "pending, in_progress, or completed"),
+        ],
+    },
+    // CODEX
+    TableDoc {
+        name: "jhistory",
+        category: "CODEX",
+        description: "Prompts typed into Codex CLI",
+        sources: &["~/.codex/history.jsonl ($CODEX_HOME)"],
+        feature: None,
+        columns: &[
+            ("rowid", "Row number"),
+            ("session_id", "Codex session id"),
+            ("ts", "Epoch seconds"),
+            ("text", "Prompt text"),
+            ("display", "Prompt text (same as text)"),
+            ("timestamp", "Epoch milliseconds"),
+        ],
+    },
+    TableDoc {
+        name: "codex_history",
+        category: "CODEX",
+        description: "Alias of jhistory",
+        sources: &["~/.codex/history.jsonl ($CODEX_HOME)"],
+        feature: None,
+        columns: &[
+            ("rowid", "Row number"),
+            ("session_id", "Codex session id"),
+            ("ts", "Epoch seconds"),
+            ("text", "Prompt text"),
+            ("display", "Prompt text (same as text)"),
+            ("timestamp", "Epoch milliseconds"),
+        ],
+    },
+    TableDoc {
+        name: "codex_threads",
+        category: "CODEX",
+        description: "One row per Codex thread (session journal)",
+        sources: CODEX_SOURCES,
+        feature: None,
+        columns: &[
+            ("thread_id", "Thread id"),
+            ("parent_thread_id", "Thread that spawned this one"),
+            (
+                "parent_record_index",
+                "Record in the parent thread that spawned it",
+            ),
+            ("source_kind", "How the thread was started"),
+            ("source_json", "Raw session source metadata"),
+            ("agent_path", "Subagent path; NULL for top-level threads"),
+            ("agent_role", "Subagent role"),
+            ("originator", "Client that started the thread"),
+            ("cwd", "Working directory"),
+            ("git_branch", "Git branch checked out"),
+            ("cli_version", "Codex CLI version"),
+            ("state", "active or archived"),
+            ("compressed", "1 if the journal is zstd-compressed"),
+            ("journal_path", "Journal file"),
+            ("started_at", "Time of the first record"),
+            ("last_event_at", "Time of the last record"),
+            ("first_user_text", "First user message"),
+            ("event_count", "Records in the journal"),
+            ("user_message_count", "User messages"),
+            ("assistant_message_count", "Assistant messages"),
+            ("tool_call_count", "Tool calls"),
+            ("compaction_count", "Context compactions"),
+        ],
+    },
+    TableDoc {
+        name: "codex_events",
+        category: "CODEX",
+        description: "Every record of every Codex journal",
+        sources: CODEX_SOURCES,
+        feature: None,
+        columns: &[
+            ("thread_id", "Thread the record belongs to"),
+            ("record_index", "Position of the record in the journal"),
+            ("timestamp", "RFC 3339 time of the record"),
+            (
+                "record_type",
+                "Journal record type (response_item, event_msg, ...)",
+            ),
+            ("payload_type", "Payload type within the record"),
+            ("role", "Message role, for message records"),
+            ("call_id", "Tool call id, for tool records"),
+            ("source_path", "Journal file"),
+        ],
+    },
+    TableDoc {
+        name: "codex_messages",
+        category: "CODEX",
+        description: "User and assistant messages in Codex threads",
+        sources: CODEX_SOURCES,
+        feature: None,
+        columns: &[
+            ("thread_id", "Thread the message belongs to"),
+            ("record_index", "Position of the record in the journal"),
+            ("timestamp", "RFC 3339 time of the message"),
+            ("role", "user, assistant, or developer"),
+            ("text", "Message text, indexed by codex_messages_fts"),
+            ("content_json", "Raw message content"),
+            (
+                "is_canonical",
+                "1 for response items, 0 for event echoes of them",
+            ),
+            ("source_path", "Journal file"),
+        ],
+    },
+    TableDoc {
+        name: "codex_tool_executions",
+        category: "CODEX",
+        description: "Codex tool calls paired with their output",
+        sources: CODEX_SOURCES,
+        feature: None,
+        columns: &[
+            ("thread_id", "Thread the call was made in"),
+            ("call_id", "Tool call id"),
+            ("call_record_index", "Record of the call"),
+            ("output_record_index", "Record of the output"),
+            (
+                "tool_name",
+                "Tool called (exec_command, shell, apply_patch, ...)",
+            ),
+            ("arguments_json", "Call arguments as JSON"),
+            ("cmd", "Command run by shell tools"),
+            ("output_text", "Tool output"),
+            ("called_at", "Time of the call"),
+            ("completed_at", "Time of the output"),
+            ("cwd", "Working directory of the call"),
+            ("source_path", "Journal file"),
+        ],
+    },
+    TableDoc {
+        name: "codex_tool_calls",
+        category: "CODEX",
+        description: "Codex tool calls in the same shape as tool_calls",
+        sources: CODEX_SOURCES,
+        feature: None,
+        columns: &[
+            ("rowid", "Row number"),
+            ("tool_name", "Tool called"),
+            ("arguments_json", "Call arguments as JSON"),
+            ("cmd", "Command run by shell tools"),
+            ("source_id", "Tool call id"),
+            ("session_id", "Thread the call was made in"),
+            ("parent_session_id", "Parent thread of a subagent call"),
+            ("agent_id", "Subagent path"),
+            ("agent_role", "Subagent role"),
+            ("originator", "Client that started the thread"),
+            ("cwd", "Working directory of the call"),
+            ("source_path", "Journal file"),
+            ("timestamp", "Time of the call"),
+        ],
+    },
+    TableDoc {
+        name: "codex_compactions",
+        category: "CODEX",
+        description: "Context compactions in Codex threads",
+        sources: CODEX_SOURCES,
+        feature: None,
+        columns: &[
+            ("thread_id", "Thread that was compacted"),
+            ("record_index", "Position of the record in the journal"),
+            ("timestamp", "Time of the compaction"),
+            ("window_id", "Context window started by the compaction"),
+            ("previous_window_id", "Context window it replaced"),
+            ("first_window_id", "First context window of the thread"),
+            ("window_number", "Ordinal of the new window"),
+            ("summary_text", "Summary carried into the new window"),
+            ("source_path", "Journal file"),
+        ],
+    },
+    TableDoc {
+        name: "codex_ingest_errors",
+        category: "CODEX",
+        description: "Journal records the Codex index could not parse",
+        sources: CODEX_SOURCES,
+        feature: None,
+        columns: &[
+            ("journal_path", "Journal file"),
+            ("thread_id", "Thread, if known"),
+            ("record_index", "Record that failed, if known"),
+            ("error_kind", "Kind of failure"),
+            ("message", "Error message"),
+            ("observed_at", "When the failure was recorded"),
+        ],
+    },
+    // CODE
+    TableDoc {
+        name: "source_files",
+        category: "CODE",
+        description: "Source files in the working tree",
+        sources: &[CODE_SOURCE],
+        feature: None,
+        columns: &[
+            ("path", "Path relative to the repository root"),
+            ("name", "File name"),
+            ("extension", "Extension without the dot"),
+            ("directory", "Directory relative to the repository root"),
+            ("size_bytes", "File size"),
+            ("line_count", "Lines in the file"),
+            ("modified_at", "Last modification time"),
+            ("language", "Language detected from the extension"),
+            ("repo", "Repository the file belongs to"),
+        ],
+    },
+    TableDoc {
+        name: "source_lines",
+        category: "CODE",
+        description: "Lines of text source files up to 1 MB",
+        sources: &[CODE_SOURCE],
+        feature: None,
+        columns: &[
+            ("file_path", "Path relative to the repository root"),
+            ("line_number", "1-based line number"),
+            ("content", "Line text, indexed by source_lines_fts"),
+            ("is_blank", "1 if the line is only whitespace"),
+            ("repo", "Repository the file belongs to"),
+        ],
+    },
+    TableDoc {
+        name: "symbols",
+        category: "CODE",
+        description:
+            "Definitions (functions, types, modules, ...); regex-based without tree-sitter-ast",
+        sources: &[CODE_SOURCE],
+        feature: None,
+        columns: &[
+            ("id", "Symbol id"),
+            ("file_path", "Path relative to the repository root"),
+            ("name", "Symbol name"),
+            (
+                "kind",
+                "fn, function, struct, class, trait, ... (varies by language)",
+            ),
+            ("line_start", "First line of the definition"),
+            ("line_end", "Last line of the definition"),
+            ("signature", "Declaration line"),
+            (
+                "visibility",
+                "pub, export, private, ... where the language has one",
+            ),
+            ("parent_id", "id of the enclosing symbol"),
+            ("parameters", "Parameter list"),
+            ("return_type", "Declared return type"),
+            ("language", "Source language"),
+            ("repo", "Repository the file belongs to"),
+        ],
+    },
+    TableDoc {
+        name: "symbol_refs",
+        category: "CODE",
+        description: "Call sites, type uses, and imported names with their enclosing definition",
+        sources: &[CODE_SOURCE],
+        feature: TREE_SITTER,
+        columns: &[
+            ("file_path", "Path relative to the repository root"),
+            ("line_number", "Line of the reference"),
+            ("column_number", "Column of the reference"),
+            ("caller", "Name of the enclosing definition"),
+            ("caller_line", "First line of the enclosing definition"),
+            ("name", "Referenced name"),
+            ("kind", "call, type, or import"),
+            ("language", "Source language"),
+            ("repo", "Repository the file belongs to"),
+        ],
+    },
+    TableDoc {
+        name: "imports",
+        category: "CODE",
+        description: "Import and use statements",
+        sources: &[CODE_SOURCE],
+        feature: TREE_SITTER,
+        columns: &[
+            ("file_path", "Path relative to the repository root"),
+            ("line_number", "Line of the statement"),
+            ("module", "Imported module or path"),
+            ("name", "Imported name"),
+            ("alias", "Local alias"),
+            ("kind", "Import form (use, import, from, include, ...)"),
+            ("is_default", "1 for default imports"),
+            ("is_wildcard", "1 for glob imports"),
+            ("repo", "Repository the file belongs to"),
+        ],
+    },
+    TableDoc {
+        name: "ast_nodes",
+        category: "CODE",
+        description: "Significant syntax nodes from tree-sitter parses",
+        sources: &[CODE_SOURCE],
+        feature: TREE_SITTER,
+        columns: &[
+            ("id", "Node id"),
+            ("parent_id", "id of the nearest materialized ancestor"),
+            ("file_path", "Path relative to the repository root"),
+            ("kind", "tree-sitter node kind"),
+            ("name", "Name of the node, if it declares one"),
+            ("start_line", "First line"),
+            ("start_column", "First column"),
+            ("end_line", "Last line"),
+            ("end_column", "Last column"),
+            ("start_byte", "Start byte offset"),
+            ("end_byte", "End byte offset"),
+            ("depth", "Depth in the syntax tree"),
+            ("text", "Source excerpt, at most 200 bytes"),
+            ("language", "Source language"),
+            ("repo", "Repository the file belongs to"),
+        ],
+    },
+    // SHELL
+    TableDoc {
+        name: "shell_history",
+        category: "SHELL",
+        description: "Commands from Atuin, zsh, and bash history",
+        sources: &[
+            "Atuin history.db ($DEVSQL_ATUIN_DB, Atuin config, or ~/.local/share/atuin)",
+            "~/.zsh_history ($DEVSQL_ZSH_HISTORY, $HISTFILE, $ZDOTDIR)",
+            "~/.bash_history ($DEVSQL_BASH_HISTORY, $HISTFILE)",
+        ],
+        feature: None,
+        columns: &[
+            ("source", "atuin, zsh, or bash"),
+            ("source_id", "Id in the source history, if it has one"),
+            ("source_order", "Position in the source history"),
+            ("timestamp", "RFC 3339 start time; NULL for plain histories"),
+            ("duration_ms", "Run time"),
+            ("exit_code", "Exit status"),
+            ("command", "Command line, indexed by shell_history_fts"),
+            ("cwd", "Working directory"),
+            ("session_id", "Shell session id"),
+            ("hostname", "Host the command ran on"),
+            ("history_path", "History file the command was read from"),
+        ],
+    },
+    TableDoc {
+        name: "command_events",
+        category: "SHELL",
+        description: "Shell commands and agent-issued commands with their provenance",
+        sources: &[
+            "shell_history",
+            "tool_calls (Bash)",
+            "codex_tool_executions (exec_command, shell)",
+        ],
+        feature: None,
+        columns: &[
+            ("source", "atuin, zsh, bash, claude, or codex"),
+            ("channel", "shell or agent_tool"),
+            ("actor", "agent, or unknown for shell history"),
+            (
+                "provenance_quality",
+                "exact for agent calls, unattributed for shell history",
+            ),
+            ("provenance_reason", "Why provenance is not exact"),
+            ("source_id", "Id in the source"),
+            ("source_order", "Position in the source"),
+            (
+                "session_id",
+                "Shell session, Claude session, or Codex thread",
+            ),
+            ("parent_session_id", "Parent session of a subagent command"),
+            ("agent_id", "Subagent id or path"),
+            ("agent_role", "Subagent role"),
+            ("originator", "Client that started the session"),
+            ("tool_name", "Agent tool that ran the command"),
+            ("timestamp", "Time the command ran"),
+            ("duration_ms", "Run time, from shell history"),
+            ("exit_code", "Exit status, from shell history"),
+            ("command", "Command line"),
+            ("cwd", "Working directory"),
+            ("hostname", "Host, from shell history"),
+            ("source_path", "File the command was read from"),
+        ],
+    },
+    // WORKLOG
+    TableDoc {
+        name: "work_tasks",
+        category: "WORKLOG",
+        description: "Tasks recorded with `devsql work`",
+        sources: &["~/.devsql/worklog.sqlite ($DEVSQL_HOME)"],
+        feature: None,
+        columns: &[
+            ("id", "Task id"),
+            ("title", "Task title"),
+            ("project", "Project the task belongs to"),
+            ("cwd", "Working directory it was started in"),
+            ("agent", "Agent that started it"),
+            ("status", "doing, done, blocked, or dropped"),
+            ("created_at", "When the task was started"),
+            ("updated_at", "When the task last changed"),
+            ("session_id", "Agent session that started it"),
+            ("source", "Who recorded the task"),
+        ],
+    },
+    TableDoc {
+        name: "work_events",
+        category: "WORKLOG",
+        description: "Timeline events recorded with `devsql work`",
+        sources: &["~/.devsql/worklog.sqlite ($DEVSQL_HOME)"],
+        feature: None,
+        columns: &[
+            ("id", "Event id"),
+            ("task_id", "Task the event belongs to"),
+            ("ts", "RFC 3339 time of the event"),
+            (
+                "local_date",
+                "Calendar day of the event in the configured timezone",
+            ),
+            ("kind", "start, update, done, blocked, or note"),
+            ("title", "Event title"),
+            ("body", "Event details"),
+            ("project", "Project"),
+            ("cwd", "Working directory"),
+            ("agent", "Agent that recorded it"),
+            ("session_id", "Agent session"),
+            ("meta_json", "Extra metadata as JSON"),
+        ],
+    },
+    // FULL-TEXT
+    TableDoc {
+        name: "transcripts_fts",
+        category: "FULL-TEXT",
+        description: "Porter-stemmed FTS5 index over transcripts.content; join on rowid",
+        sources: &["transcripts"],
+        feature: None,
+        columns: &[(
+            "content",
+            "Indexed text; filter with MATCH, rank with bm25()",
+        )],
+    },
+    TableDoc {
+        name: "history_fts",
+        category: "FULL-TEXT",
+        description: "Porter-stemmed FTS5 index over history.display; join on rowid",
+        sources: &["history"],
+        feature: None,
+        columns: &[(
+            "display",
+            "Indexed text; filter with MATCH, rank with bm25()",
+        )],
+    },
+    TableDoc {
+        name: "codex_messages_fts",
+        category: "FULL-TEXT",
+        description: "Porter-stemmed FTS5 index over codex_messages.text, kept in the Codex index",
+        sources: &["codex_messages"],
+        feature: None,
+        columns: &[("text", "Indexed text; filter with MATCH, rank with bm25()")],
+    },
+    TableDoc {
+        name: "shell_history_fts",
+        category: "FULL-TEXT",
+        description: "FTS5 index over shell_history.command; join on rowid",
+        sources: &["shell_history"],
+        feature: None,
+        columns: &[(
+            "command",
+            "Indexed text; filter with MATCH, rank with bm25()",
+        )],
+    },
+    TableDoc {
+        name: "source_lines_fts",
+        category: "FULL-TEXT",
+        description: "Trigram FTS5 index over source_lines.content (substrings of 3+ characters)",
+        sources: &["source_lines"],
+        feature: None,
+        columns: &[(
+            "content",
+            "Indexed text; filter with MATCH, rank with bm25()",
+        )],
+    },
+];
+
+/// Example queries shown by `devsql examples`, at least one per category.
+pub static EXAMPLES: &[Example] = &[
+    Example {
+        category: "CLAUDE",
+        description: "Recent Claude prompts",
+        sql: "SELECT display, project\nFROM history\nORDER BY timestamp DESC\nLIMIT 10",
+    },
+    Example {
+        category: "CLAUDE",
+        description: "Top sessions by cache-read tokens",
+        sql: "SELECT title, project, total_cache_read_input_tokens\nFROM sessions\nORDER BY total_cache_read_input_tokens DESC\nLIMIT 10",
+    },
+    Example {
+        category: "CLAUDE",
+        description: "Daily output tokens by model",
+        sql: "SELECT date(timestamp) AS day, model, SUM(usage_output_tokens) AS output_tokens\nFROM transcripts\nWHERE type = 'assistant' AND usage_output_tokens IS NOT NULL\nGROUP BY day, model\nORDER BY day DESC",
+    },
+    Example {
+        category: "CLAUDE",
+        description: "Most used tools",
+        sql: "SELECT tool_name, COUNT(*) AS uses\nFROM tool_calls\nGROUP BY tool_name\nORDER BY uses DESC",
+    },
+    Example {
+        category: "CODEX",
+        description: "Recent Codex prompts",
+        sql: "SELECT datetime(timestamp/1000, 'unixepoch') AS time, display\nFROM jhistory\nORDER BY timestamp DESC\nLIMIT 10",
+    },
+    Example {
+        category: "CODEX",
+        description: "Codex threads with the most tool calls",
+        sql: "SELECT thread_id, cwd, first_user_text, tool_call_count\nFROM codex_threads\nORDER BY tool_call_count DESC\nLIMIT 10",
+    },
+    Example {
+        category: "GIT",
+        description: "Commits by author",
+        sql: "SELECT author_name, COUNT(*) AS commits\nFROM commits\nGROUP BY author_name\nORDER BY commits DESC",
+    },
+    Example {
+        category: "GIT",
+        description: "Most changed files",
+        sql: "SELECT path, COUNT(DISTINCT commit_id) AS commits, SUM(insertions) AS added\nFROM diff_files\nGROUP BY path\nORDER BY commits DESC\nLIMIT 10",
+    },
+    Example {
+        category: "GIT",
+        description: "Commits correlated with Claude prompts",
+        sql: "SELECT date(c.authored_at) AS day, COUNT(DISTINCT c.id) AS commits,\n  COUNT(DISTINCT h.timestamp) AS prompts\nFROM commits c\nLEFT JOIN history h ON date(c.authored_at) = date(datetime(h.timestamp/1000, 'unixepoch'))\nGROUP BY day\nORDER BY day DESC\nLIMIT 14",
+    },
+    Example {
+        category: "CODE",
+        description: "Codebase overview by language",
+        sql: "SELECT language, COUNT(*) AS files, SUM(line_count) AS total_lines\nFROM source_files\nGROUP BY language\nORDER BY total_lines DESC",
+    },
+    Example {
+        category: "CODE",
+        description: "Public Rust functions",
+        sql: "SELECT name, file_path, line_start, signature\nFROM symbols\nWHERE kind = 'fn' AND visibility = 'pub'\nORDER BY file_path, line_start",
+    },
+    Example {
+        category: "CODE",
+        description: "Most called names (needs tree-sitter-ast)",
+        sql: "SELECT name, COUNT(*) AS calls\nFROM symbol_refs\nWHERE kind = 'call'\nGROUP BY name\nORDER BY calls DESC\nLIMIT 20",
+    },
+    Example {
+        category: "SHELL",
+        description: "Most run commands",
+        sql: "SELECT command, COUNT(*) AS runs\nFROM shell_history\nGROUP BY command\nORDER BY runs DESC\nLIMIT 20",
+    },
+    Example {
+        category: "SHELL",
+        description: "Commands run by agents versus by hand",
+        sql: "SELECT source, channel, COUNT(*) AS commands\nFROM command_events\nGROUP BY source, channel",
+    },
+    Example {
+        category: "WORKLOG",
+        description: "Open tasks",
+        sql: "SELECT title, project, updated_at\nFROM work_tasks\nWHERE status = 'doing'\nORDER BY updated_at DESC",
+    },
+    Example {
+        category: "WORKLOG",
+        description: "Events per day",
+        sql: "SELECT local_date, COUNT(*) AS events\nFROM work_events\nGROUP BY local_date\nORDER BY local_date DESC",
+    },
+    Example {
+        category: "FULL-TEXT",
+        description: "Best matching Claude prompts",
+        sql: "SELECT h.display, bm25(history_fts) AS score\nFROM history_fts\nJOIN history h ON h.rowid = history_fts.rowid\nWHERE history_fts MATCH 'migration'\nORDER BY score\nLIMIT 10",
+    },
+    Example {
+        category: "FULL-TEXT",
+        description: "Lines containing a substring",
+        sql: "SELECT l.file_path, l.line_number, l.content\nFROM source_lines_fts\nJOIN source_lines l ON l.rowid = source_lines_fts.rowid\nWHERE source_lines_fts MATCH 'unwrap'\nLIMIT 20",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lazy::LazyTable;
+    use crate::{engine, providers};
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn every_table_devsql_creates_is_described() {
+        let conn = scratch_connection().expect("scratch");
+        let registered: BTreeSet<String> = tables()
+            .expect("tables")
+            .into_iter()
+            .map(|table| table.name)
+            .collect();
+        let mut stmt = conn
+            .prepare(
+                "SELECT name FROM sqlite_master
+                 WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite%'
+                 UNION SELECT name FROM sqlite_temp_master WHERE type = 'view'",
+            )
+            .expect("prepare");
+        let created: BTreeSet<String> = stmt
+            .query_map([], |row| row.get(0))
+            .expect("query")
+            .collect::<rusqlite::Result<_>>()
+            .expect("names");
+        let fts_shadow = |name: &str| {
+            engine::FTS_TABLES
+                .iter()
+                .any(|(index, ..)| name.starts_with(&format!("{index}_")))
+        };
+        let undescribed: Vec<_> = created
+            .iter()
+            .filter(|name| !registered.contains(*name) && !fts_shadow(name))
+            .collect();
+        assert!(undescribed.is_empty(), "{undescribed:?}");
+
+        for name in &registered {
+            assert_eq!(
+                crate::source::tables_in(&format!("SELECT * FROM {name}")),
+                vec![name.clone()],
+                "{name} is not loaded by queries"
+            );
+        }
+    }
+
+    #[test]
+    fn every_column_has_a_description_and_no_description_is_stale() {
+        let builtin = tables()
+            .expect("tables")
+            .into_iter()
+            .filter(|table| CATEGORIES.contains(&table.category.as_str()));
+        for table in builtin {
+            assert!(!table.columns.is_empty(), "{} has no columns", table.name);
+            for column in &table.columns {
+                assert!(
+                    !column.description.is_empty(),
+                    "{}.{} is not described",
+                    table.name,
+                    column.name
+                );
+            }
+        }
+        for doc in DOCS {
+            let table = table(doc.name).expect("table").expect("registered");
+            for (column, _) in doc.columns {
+                assert!(
+                    table.columns.iter().any(|c| c.name == *column),
+                    "{}.{column} is described but does not exist",
+                    doc.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn lazy_tables_expose_the_registered_columns() {
+        let registered = |name: &str| -> Vec<(String, String)> {
+            table(name)
+                .expect("table")
+                .expect("registered")
+                .columns
+                .into_iter()
+                .map(|column| (column.name, column.sql_type))
+                .collect()
+        };
+        let files = providers::source_files::LazySourceFiles::new(Vec::new());
+        assert_eq!(files.columns(), registered("source_files").as_slice());
+        let lines = providers::source_lines::LazySourceLines::new(Vec::new());
+        assert_eq!(lines.columns(), registered("source_lines").as_slice());
+    }
+
+    #[test]
+    fn examples_cover_every_category_and_compile_against_the_registry() {
+        let conn = scratch_connection().expect("scratch");
+        for category in CATEGORIES {
+            assert!(
+                EXAMPLES.iter().any(|example| example.category == *category),
+                "no example for {category}"
+            );
+        }
+        for example in EXAMPLES {
+            conn.prepare(example.sql)
+                .unwrap_or_else(|error| panic!("{}: {error}", example.description));
+        }
+    }
+}
@@ -0,0 +1,753 @@
+//! `devsql serve` — devsql over HTTP for dashboards and notebooks.
+//!
+//! `POST /query` takes a JSON body with `sql` and optional `params`,
+//! `format` (`json`, `csv`, or `columns`), and the source options of
+//! `devsql query` (`repo`, `workspace`, `data_dir`, `since`, `until`).
+//! Engines are kept between requests, one per combination of source
+//! options, so a table is read from its source once and answered from
+//! memory until a [`stamp`] of the source's inputs changes. Relative spans
+//! such as `since: "7d"` are measured from the engine's first request.
+//!
+//! The `recall`, `gather`, `today`, `day`, `days`, `tables`, `schema`, and
+//! `examples` commands are served at `/<command>` by incurs' router, which
+//! takes their options as a JSON body or query string and answers with the
+//! same `{ok, data, meta}` envelope `/query` uses. They read their sources
+//! on every call.
+//!
+//! [`stamp`]: crate::source::stamp
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use axum::body::{Body, Bytes};
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::Router;
+use incurs::cli::Cli;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use vcsql::TimeWindow;
+
+use crate::engine::QueryParams;
+use crate::source::{self, InputStamp};
+use crate::{Error, Result, UnifiedEngine};
+
+/// Engines kept at once; past this the least recently used is dropped.
+pub const CACHED_ENGINES: usize = 4;
+
+/// Environment variable holding the bearer token, when `--token` is not
+/// given.
+pub const TOKEN_ENV: &str = "DEVSQL_SERVE_TOKEN";
+
+/// The body of `POST /query`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QueryRequest {
+    pub sql: String,
+    /// A JSON array for `?` placeholders or an object for `:name` ones
+    #[serde(default)]
+    pub params: Option<Value>,
+    #[serde(default)]
+    pub format: ResultFormat,
+    #[serde(flatten)]
+    pub sources: SourceOptions,
+}
+
+/// Where an engine reads its tables from, as `devsql query` takes it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct SourceOptions {
+    #[serde(default)]
+    pub repo: Vec<String>,
+    pub workspace: Option<String>,
+    pub data_dir: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+}
+
+/// How `POST /query` returns rows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultFormat {
+    /// One object per row, in the JSON envelope.
+    #[default]
+    Json,
+    /// A header row, then one line per row, as `text/csv`.
+    Csv,
+    /// One array of values per column, laid out as an Arrow record batch
+    /// is, in the JSON envelope.
+    Columns,
+}
+
+/// Rows answering one query.
+#[derive(Debug, Clone, Default)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+    /// Tables read from their sources for this query, dependencies first;
+    /// empty when every table was already loaded and current.
+    pub loaded: Vec<String>,
+}
+
+struct CachedEngine {
+    engine: UnifiedEngine,
+    /// Each source with loaded tables, and its inputs' stamp taken just
+    /// before they were loaded.
+    stamps: HashMap<String, InputStamp>,
+}
+
+/// Engines kept between queries, most recently used last. Each engine has
+/// its own lock, so requests for different source options run at once; the
+/// list's lock is held only to find or add an engine.
+#[derive(Default)]
+pub struct EngineCache {
+    engines: Mutex<Vec<(SourceOptions, Arc<Mutex<CachedEngine>>)>>,
+}
+
+impl EngineCache {
+    /// Answer `request` from the engine for its source options, first
+    /// unloading tables whose source changed since they were loaded and
+    /// loading the tables the query reads. Errors are `(code, message)`
+    /// pairs.
+    pub fn query(
+        &self,
+        request: &QueryRequest,
+    ) -> std::result::Result<QueryResult, (&'static str, String)> {
+        let params = QueryParams::from_json(request.params.clone().unwrap_or(Value::Null))
+            .map_err(|e| ("INVALID_PARAMS", e.to_string()))?;
+        let named = match &params {
+            QueryParams::Named(values) => values.clone(),
+            _ => Map::new(),
+        };
+        let views = crate::saved::views(&request.sql, &named)
+            .map_err(|e| ("SAVED_QUERY_ERROR", e.to_string()))?;
+        let mut tables = source::tables_in(&request.sql);
+        for view in &views {
+            for table in source::tables_in(&view.sql) {
+                if !tables.contains(&table) {
+                    tables.push(table);
+                }
+            }
+        }
+
+        let cached = match self.cached(&request.sources) {
+            Some(cached) => cached,
+            None => {
+                // Opened without the list's lock; if another request opened
+                // one for the same options meanwhile, that one is used.
+                let opened = CachedEngine {
+                    engine: open_engine(&request.sources)?,
+                    stamps: HashMap::new(),
+                };
+                self.insert(&request.sources, opened)
+            }
+        };
+        let mut cached = cached
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let loaded = cached
+            .refresh(&tables)
+            .map_err(|e| ("LOAD_ERROR", e.to_string()))?;
+        let engine = &cached.engine;
+        for view in &views {
+            engine.create_view(&view.name, &view.sql).map_err(|e| {
+                (
+                    "SAVED_QUERY_ERROR",
+                    format!("Failed to register saved query {}: {e}", view.name),
+                )
+            })?;
+        }
+        let result = run_query(engine, &request.sql, &params);
+        // Views bind this request's parameters; the next one makes its own.
+        for view in &views {
+            let _ = engine
+                .conn()
+                .execute_batch(&format!("DROP VIEW IF EXISTS temp.\"{}\"", view.name));
+        }
+        let (columns, rows) = result.map_err(|e| ("QUERY_ERROR", format!("Query failed: {e}")))?;
+        Ok(QueryResult {
+            columns,
+            rows,
+            loaded,
+        })
+    }
+
+    /// The engine for `sources`, marked most recently used.
+    fn cached(&self, sources: &SourceOptions) -> Option<Arc<Mutex<CachedEngine>>> {
+        let mut engines = self
+            .engines
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let index = engines.iter().position(|(cached, _)| cached == sources)?;
+        let entry = engines.remove(index);
+        let cached = Arc::clone(&entry.1);
+        engines.push(entry);
+        Some(cached)
+    }
+
+    /// Add `opened` as the engine for `sources`, dropping the least recently
+    /// used past [`CACHED_ENGINES`], unless one was added since
+    /// [`Self::cached`] found none; return the engine to use.
+    fn insert(&self, sources: &SourceOptions, opened: CachedEngine) -> Arc<Mutex<CachedEngine>> {
+        let mut engines = self
+            .engines
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some((_, cached)) = engines.iter().find(|(cached, _)| cached == sources) {
+            return Arc::clone(cached);
+        }
+        if engines.len() >= CACHED_ENGINES {
+            engines.remove(0);
+        }
+        let cached = Arc::new(Mutex::new(opened));
+        engines.push((sources.clone(), Arc::clone(&cached)));
+        cached
+    }
+}
+
+impl CachedEngine {
+    /// Unload the tables of every source whose inputs changed, then load
+    /// `tables` and their dependencies, returning the tables loaded.
+    fn refresh(&mut self, tables: &[String]) -> Result<Vec<String>> {
+        let sources = source::sources();
+        for source in &sources {
+            let loaded: Vec<String> = source
+                .tables()
+                .into_iter()
+                .map(|table| table.name)
+                .filter(|table| self.engine.is_loaded(table))
+                .collect();
+            if loaded.is_empty() {
+                self.stamps.remove(source.name());
+                continue;
+            }
+            let current = source::stamp(&source.inputs(&self.engine));
+            if self.stamps.get(source.name()) != Some(&current) {
+                let loaded: Vec<&str> = loaded.iter().map(|s| s.as_str()).collect();
+                self.engine.unload_tables(&loaded)?;
+                self.stamps.remove(source.name());
+            }
+        }
+
+        let needed: Vec<String> = with_dependencies(&sources, tables)
+            .into_iter()
+            .filter(|table| !self.engine.is_loaded(table))
+            .collect();
+        for source in &sources {
+            if !self.stamps.contains_key(source.name())
+                && source
+                    .tables()
+                    .iter()
+                    .any(|table| needed.contains(&table.name))
+            {
+                let stamp = source::stamp(&source.inputs(&self.engine));
+                self.stamps.insert(source.name().to_string(), stamp);
+            }
+        }
+        let table_refs: Vec<&str> = needed.iter().map(|s| s.as_str()).collect();
+        self.engine.load_tables(&table_refs)?;
+        Ok(needed)
+    }
+}
+
+/// `tables` and every table they depend on, dependencies first. A cycle
+/// is cut short here; loading reports it.
+fn with_dependencies(sources: &[Arc<dyn source::DataSource>], tables: &[String]) -> Vec<String> {
+    fn visit(
+        catalog: &[source::SourceTable],
+        table: &str,
+        visiting: &mut Vec<String>,
+        order: &mut Vec<String>,
+    ) {
+        let Some(entry) = catalog.iter().find(|t| t.name == table) else {
+            return;
+        };
+        if order.iter().chain(visiting.iter()).any(|t| t == table) {
+            return;
+        }
+        visiting.push(table.to_string());
+        for dependency in &entry.depends_on {
+            visit(catalog, dependency, visiting, order);
+        }
+        visiting.pop();
+        order.push(table.to_string());
+    }
+
+    let catalog: Vec<source::SourceTable> = sources.iter().flat_map(|s| s.tables()).collect();
+    let mut order = Vec::new();
+    for table in tables {
+        visit(&catalog, table, &mut Vec::new(), &mut order);
+    }
+    order
+}
+
+fn open_engine(
+    sources: &SourceOptions,
+) -> std::result::Result<UnifiedEngine, (&'static str, String)> {
+    let window = TimeWindow::parse(sources.since.as_deref(), sources.until.as_deref())
+        .map_err(|e| ("INVALID_TIME", e.to_string()))?;
+    let (mut engine, _) = crate::tools::engine_from_repos(
+        &sources.repo,
+        sources.workspace.as_deref(),
+        sources.data_dir.as_deref(),
+    )
+    .map_err(|error| (error.code, error.message))?;
+    engine.set_time_window(window);
+    Ok(engine)
+}
+
+/// The query's column names and its rows as values in column order.
+fn run_query(
+    engine: &UnifiedEngine,
+    sql: &str,
+    params: &QueryParams,
+) -> Result<(Vec<String>, Vec<Vec<Value>>)> {
+    let columns: Vec<String> = engine
+        .conn()
+        .prepare(sql)?
+        .column_names()
+        .into_iter()
+        .map(|name| name.to_string())
+        .collect();
+    let mut rows = Vec::new();
+    engine.query_each(sql, params, |row| {
+        rows.push(
+            columns
+                .iter()
+                .map(|column| row.get(column).cloned().unwrap_or_default())
+                .collect(),
+        );
+        Ok(())
+    })?;
+    Ok((columns, rows))
+}
+
+// ---------------------------------------------------------------------------
+// HTTP
+// ---------------------------------------------------------------------------
+
+/// The commands served next to `/query`.
+fn tools_cli() -> Cli {
+    Cli::create("devsql")
+        .version(env!("CARGO_PKG_VERSION"))
+        .command("tables", crate::tools::schema::build_tables())
+        .command("schema", crate::tools::schema::build_schema())
+        .command("examples", crate::tools::schema::build_examples())
+        .command("recall", crate::tools::recall::build())
+        .command("gather", crate::tools::gather::build())
+        .command("today", crate::tools::day::build_today())
+        .command("day", crate::tools::day::build_day())
+        .command("days", crate::tools::day::build_days())
+}
+
+/// The server's routes, requiring `Authorization: Bearer <token>` on every
+/// request when `token` is set.
+pub fn router(token: Option<String>) -> Result<Router> {
+    let tools =
+        incurs::http::build_cli_router(&tools_cli()).map_err(|e| Error::Serve(e.to_string()))?;
+    let router = Router::new()
+        .route("/query", post(handle_query))
+        .with_state(Arc::new(EngineCache::default()))
+        .merge(tools);
+    Ok(match token {
+        Some(token) => router.layer(middleware::from_fn_with_state(Arc::new(token), authorize)),
+        None => router,
+    })
+}
+
+/// Serve [`router`] on `listen` until the process exits. Addresses other
+/// than loopback are refused unless `allow_remote` is set and there is a
+/// token.
+pub async fn serve(listen: SocketAddr, token: Option<String>, allow_remote: bool) -> Result<()> {
+    let permitted = listen.ip().is_loopback() || (allow_remote && token.is_some());
+    if !permitted {
+        return Err(Error::Serve(format!(
+            "{listen} is not a loopback address; serving it needs --allow-remote and a token"
+        )));
+    }
+    let app = router(token)?;
+    let listener = tokio::net::TcpListener::bind(listen).await?;
+    eprintln!("Listening on http://{}", listener.local_addr()?);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+async fn authorize(State(token): State<Arc<String>>, request: Request, next: Next) -> Response {
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if presented.is_some_and(|presented| same_token(presented, &token)) {
+        return next.run(request).await;
+    }
+    let mut response = envelope_error(
+        StatusCode::UNAUTHORIZED,
+        "UNAUTHORIZED",
+        "Missing or wrong bearer token",
+        request.uri().path().trim_start_matches('/'),
+        Instant::now(),
+    );
+    response.headers_mut().insert(
+        header::WWW_AUTHENTICATE,
+        "Bearer".parse().expect("valid header"),
+    );
+    response
+}
+
+/// Compare without stopping at the first differing byte.
+fn same_token(presented: &str, token: &str) -> bool {
+    presented.len() == token.len()
+        && presented
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn handle_query(
+    State(cache): State<Arc<EngineCache>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let start = Instant::now();
+    let is_json = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_none_or(|value| value.starts_with("application/json"));
+    let request = match serde_json::from_slice::<QueryRequest>(&body) {
+        Ok(request) if is_json => request,
+        Ok(_) => {
+            return envelope_error(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "VALIDATION_ERROR",
+                "The body must be application/json",
+                "query",
+                start,
+            )
+        }
+        Err(e) => {
+            return envelope_error(
+                StatusCode::BAD_REQUEST,
+                "VALIDATION_ERROR",
+                &format!("Invalid query request: {e}"),
+                "query",
+                start,
+            )
+        }
+    };
+    let format = request.format;
+    let result = tokio::task::spawn_blocking(move || cache.query(&request)).await;
+    let result = match result {
+        Ok(Ok(result)) => result,
+        Ok(Err((code, message))) => {
+            let status = match code {
+                "LOAD_ERROR" | "ENGINE_ERROR" => StatusCode::INTERNAL_SERVER_ERROR,
+                _ => StatusCode::BAD_REQUEST,
+            };
+            return envelope_error(status, code, &message, "query", start);
+        }
+        Err(e) => {
+            return envelope_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "QUERY_ERROR",
+                &e.to_string(),
+                "query",
+                start,
+            )
+        }
+    };
+
+    let data = match format {
+        ResultFormat::Csv => return csv_response(&result),
+        ResultFormat::Json => Value::Array(
+            result
+                .rows
+                .into_iter()
+                .map(|row| Value::Object(result.columns.iter().cloned().zip(row).collect()))
+                .collect(),
+        ),
+        ResultFormat::Columns => {
+            let num_rows = result.rows.len();
+            let mut columns: Vec<Vec<Value>> =
+                vec![Vec::with_capacity(num_rows); result.columns.len()];
+            for row in result.rows {
+                for (column, value) in columns.iter_mut().zip(row) {
+                    column.push(value);
+                }
+            }
+            json!({
+                "num_rows": num_rows,
+                "columns": result
+                    .columns
+                    .iter()
+                    .zip(columns)
+                    .map(|(name, values)| json!({ "name": name, "values": values }))
+                    .collect::<Vec<_>>(),
+            })
+        }
+    };
+    json_response(
+        StatusCode::OK,
+        &json!({
+            "ok": true,
+            "data": data,
+            "meta": {
+                "command": "query",
+                "duration": format!("{}ms", start.elapsed().as_millis()),
+                "loaded": result.loaded,
+            },
+        }),
+    )
+}
+
+fn csv_response(result: &QueryResult) -> Response {
+    let mut out = csv::Writer::from_writer(Vec::new());
+    let mut written = out.write_record(&result.columns);
+    for row in &result.rows {
+        let cells = row.iter().map(|value| match value {
+            Value::Null => String::new(),
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        });
+        written = written.and_then(|()| out.write_record(cells));
+    }
+    match written
+        .map_err(|e| e.to_string())
+        .and_then(|()| out.into_inner().map_err(|e| e.to_string()))
+    {
+        Ok(body) => Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "text/csv; charset=utf-8")
+            .body(Body::from(body))
+            .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response()),
+        Err(message) => envelope_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "SERIALIZATION_ERROR",
+            &message,
+            "query",
+            Instant::now(),
+        ),
+    }
+}
+
+/// An error in the envelope incurs' routes answer with.
+fn envelope_error(
+    status: StatusCode,
+    code: &str,
+    message: &str,
+    command: &str,
+    start: Instant,
+) -> Response {
+    json_response(
+        status,
+        &json!({
+            "ok": false,
+            "error": { "code": code, "message": message },
+            "meta": {
+                "command": command.replace('/', " "),
+                "duration": format!("{}ms", start.elapsed().as_millis()),
+            },
+        }),
+    )
+}
+
+fn json_response(status: StatusCode, body: &Value) -> Response {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use tower::ServiceExt;
+
+    fn write(path: &Path, contents: &str) {
+        std::fs::create_dir_all(path.parent().expect("parent")).expect("dir");
+        std::fs::write(path, contents).expect("write");
+    }
+
+    fn prompt(display: &str, timestamp: i64) -> String {
+        format!(
+            "{}\n",
+            json!({ "display": display, "timestamp": timestamp, "project": "/p" })
+        )
+    }
+
+    async fn post_query(app: &Router, body: Value, token: Option<&str>) -> (StatusCode, Vec<u8>) {
+        let mut request =
+            axum::http::Request::post("/query").header(header::CONTENT_TYPE, "application/json");
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+        }
+        let response = app
+            .clone()
+            .oneshot(request.body(Body::from(body.to_string())).expect("request"))
+            .await
+            .expect("response");
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body");
+        (status, body.to_vec())
+    }
+
+    #[tokio::test]
+    async fn queries_reuse_loaded_tables_until_their_source_changes() {
+        let dir = tempfile::TempDir::new().expect("tempdir");
+        let history = dir.path().join("history.jsonl");
+        write(&history, &prompt("first", 1_700_000_000_000));
+        let app = router(None).expect("router");
+        let body = json!({
+            "sql": "SELECT display FROM history ORDER BY timestamp",
+            "data_dir": dir.path(),
+            "repo": [dir.path()],
+        });
+
+        let (status, first) = post_query(&app, body.clone(), None).await;
+        assert_eq!(status, StatusCode::OK);
+        let first: Value = serde_json::from_slice(&first).expect("json");
+        assert_eq!(first["data"], json!([{ "display": "first" }]));
+        assert_eq!(first["meta"]["loaded"], json!(["history"]));
+
+        let (_, second) = post_query(&app, body.clone(), None).await;
+        let second: Value = serde_json::from_slice(&second).expect("json");
+        assert_eq!(second["data"], first["data"]);
+        assert_eq!(second["meta"]["loaded"], json!([]));
+
+        write(
+            &history,
+            &(prompt("first", 1_700_000_000_000) + &prompt("second", 1_700_000_001_000)),
+        );
+        let mut columns = body.clone();
+        columns["format"] = json!("columns");
+        let (_, third) = post_query(&app, columns, None).await;
+        let third: Value = serde_json::from_slice(&third).expect("json");
+        assert_eq!(
+            third["data"],
+            json!({
+                "num_rows": 2,
+                "columns": [{ "name": "display", "values": ["first", "second"] }],
+            })
+        );
+        assert_eq!(third["meta"]["loaded"], json!(["history"]));
+
+        let mut csv = body;
+        csv["format"] = json!("csv");
+        csv["sql"] = json!("SELECT display, :n AS n FROM history ORDER BY timestamp");
+        csv["params"] = json!({ "n": 7 });
+        let (status, text) = post_query(&app, csv, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            String::from_utf8(text).expect("utf-8"),
+            "display,n\nfirst,7\nsecond,7\n"
+        );
+
+        let schema = app
+            .clone()
+            .oneshot(
+                axum::http::Request::get("/schema/history")
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        assert_eq!(schema.status(), StatusCode::OK);
+        let schema: Value = serde_json::from_slice(
+            &axum::body::to_bytes(schema.into_body(), usize::MAX)
+                .await
+                .expect("body"),
+        )
+        .expect("json");
+        assert_eq!(schema["ok"], true);
+        assert!(schema["data"].to_string().contains("timestamp"));
+    }
+
+    #[test]
+    fn a_busy_engine_does_not_hold_up_queries_for_other_sources() {
+        let cache = Arc::new(EngineCache::default());
+        let request = |dir: &std::path::Path| QueryRequest {
+            sql: "SELECT display FROM history".to_string(),
+            sources: SourceOptions {
+                repo: vec![dir.display().to_string()],
+                data_dir: Some(dir.display().to_string()),
+                ..SourceOptions::default()
+            },
+            ..QueryRequest::default()
+        };
+        let (busy, other) = (
+            tempfile::TempDir::new().expect("tempdir"),
+            tempfile::TempDir::new().expect("tempdir"),
+        );
+        write(
+            &busy.path().join("history.jsonl"),
+            &prompt("busy", 1_700_000_000_000),
+        );
+        write(
+            &other.path().join("history.jsonl"),
+            &prompt("other", 1_700_000_000_000),
+        );
+        cache.query(&request(busy.path())).expect("query");
+
+        let engine = cache.cached(&request(busy.path()).sources).expect("cached");
+        let _held = engine.lock().expect("lock");
+        let (done, finished) = std::sync::mpsc::channel();
+        let other_request = request(other.path());
+        let other_cache = Arc::clone(&cache);
+        std::thread::spawn(move || {
+            let _ = done.send(other_cache.query(&other_request));
+        });
+        let result = finished
+            .recv_timeout(std::time::Duration::from_secs(30))
+            .expect("not held up by the busy engine")
+            .expect("query");
+        assert_eq!(result.rows, vec![vec![json!("other")]]);
+    }
+
+    #[tokio::test]
+    async fn a_token_guards_every_route_and_errors_use_the_envelope() {
+        let app = router(Some("s3cret".to_string())).expect("router");
+        let body = json!({ "sql": "SELECT 1 AS one" });
+
+        let (status, denied) = post_query(&app, body.clone(), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let denied: Value = serde_json::from_slice(&denied).expect("json");
+        assert_eq!(denied["error"]["code"], "UNAUTHORIZED");
+        let (status, _) = post_query(&app, body.clone(), Some("wrong!")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let tables = app
+            .clone()
+            .oneshot(
+                axum::http::Request::get("/tables")
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        assert_eq!(tables.status(), StatusCode::UNAUTHORIZED);
+
+        let (status, ok) = post_query(&app, body, Some("s3cret")).await;
+        assert_eq!(status, StatusCode::OK);
+        let ok: Value = serde_json::from_slice(&ok).expect("json");
+        assert_eq!(ok["data"], json!([{ "one": 1 }]));
+
+        let (status, failed) =
+            post_query(&app, json!({ "sql": "SELECT nope FROM" }), Some("s3cret")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let failed: Value = serde_json::from_slice(&failed).expect("json");
+        assert_eq!(failed["ok"], false);
+        assert_eq!(failed["error"]["code"], "QUERY_ERROR");
+
+        let (status, _) = post_query(&app, json!({ "query": "SELECT 1" }), Some("s3cret")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,348 @@
+//! Portable snapshots: loaded tables copied into a standalone SQLite file.
+//!
+//! [`export`] runs the normal loaders, honoring the engine's repositories
+//! and time window, then writes each table as a plain table with its
+//! indexes. Full-text indexes are rebuilt over the exported rows, and two
+//! metadata tables record what was exported, from where, and when:
+//!
+//! - [`META_TABLE`]: `key`, `value` pairs (`exported_at`, `devsql_version`,
+//!   `since`, `until`, `repos`, `redacted`)
+//! - [`TABLES_TABLE`]: `table_name`, `category`, `row_count`, and `sources`,
+//!   a JSON array of the paths the rows were read from
+//!
+//! `devsql --db <file>` queries a snapshot through
+//! [`UnifiedEngine::open_snapshot`] without reading any source.
+
+use std::path::{Path, PathBuf};
+
+use chrono::{SecondsFormat, Utc};
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::engine::{create_fts_sql, FTS_TABLES};
+use crate::{Error, Result, UnifiedEngine};
+
+/// Snapshot-wide metadata.
+pub const META_TABLE: &str = "devsql_snapshot";
+
+/// One row per exported table.
+pub const TABLES_TABLE: &str = "devsql_snapshot_tables";
+
+/// `codex_messages_fts` persists in the Codex index, outside [`FTS_TABLES`];
+/// a snapshot rebuilds it like the others.
+const CODEX_MESSAGES_FTS: (&str, &str, &str, &str) = (
+    "codex_messages_fts",
+    "codex_messages",
+    "text",
+    "porter unicode61",
+);
+
+/// A table written to a snapshot.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema, serde::Deserialize)]
+pub struct ExportedTable {
+    pub name: String,
+    pub category: String,
+    pub rows: i64,
+    /// Files, directories, or repositories the rows were read from.
+    pub sources: Vec<String>,
+}
+
+/// Load `tables` into `engine` and write them to a new SQLite database at
+/// `out`, replacing any file there once the export is complete. A
+/// full-text index brings its content table along. With `redact`, every
+/// text value passes through the `redact` SQL function.
+pub fn export(
+    engine: &mut UnifiedEngine,
+    tables: &[&str],
+    out: &Path,
+    redact: bool,
+) -> Result<Vec<ExportedTable>> {
+    let sources = crate::source::sources();
+    let category = |table: &str| {
+        sources
+            .iter()
+            .find(|source| source.tables().iter().any(|t| t.name == table))
+            .map(|source| source.name().to_string())
+    };
+
+    let mut names: Vec<String> = Vec::new();
+    for table in tables {
+        if category(table).is_none() {
+            return Err(Error::Query(format!("Unknown table: {table}")));
+        }
+        if let Some((_, content, ..)) = fts_index(table) {
+            if !names.iter().any(|name| name == content) {
+                names.push(content.to_string());
+            }
+        }
+        if !names.iter().any(|name| name == table) {
+            names.push(table.to_string());
+        }
+    }
+    let name_refs: Vec<&str> = names.iter().map(|s| s.as_str()).collect();
+    engine.load_tables(&name_refs)?;
+
+    let partial = partial_path(out);
+    let _ = std::fs::remove_file(&partial);
+    let conn = engine.conn();
+    conn.execute(
+        "ATTACH DATABASE ?1 AS snapshot",
+        [partial.to_string_lossy()],
+    )?;
+    let written = write_tables(engine, &names, redact, &category);
+    conn.execute_batch("DETACH DATABASE snapshot")?;
+    let exported = match written {
+        Ok(exported) => exported,
+        Err(error) => {
+            let _ = std::fs::remove_file(&partial);
+            return Err(error);
+        }
+    };
+    std::fs::rename(&partial, out)?;
+    Ok(exported)
+}
+
+fn partial_path(out: &Path) -> PathBuf {
+    let mut name = out.file_name().unwrap_or_default().to_os_string();
+    name.push(".partial");
+    out.with_file_name(name)
+}
+
+fn fts_index(table: &str) -> Option<(&'static str, &'static str, &'static str, &'static str)> {
+    FTS_TABLES
+        .iter()
+        .copied()
+        .chain([CODEX_MESSAGES_FTS])
+        .find(|(index, ..)| *index == table)
+}
+
+/// Write `names` and the metadata tables into the attached `snapshot`.
+fn write_tables(
+    engine: &UnifiedEngine,
+    names: &[String],
+    redact: bool,
+    category: &dyn Fn(&str) -> Option<String>,
+) -> Result<Vec<ExportedTable>> {
+    let conn = engine.conn();
+
+    let mut exported = Vec::new();
+    for name in names {
+        match fts_index(name) {
+            Some((index, content, column, tokenize)) => conn.execute_batch(&format!(
+                "{};
+                 INSERT INTO snapshot.{index} ({index}) VALUES ('rebuild');",
+                create_fts_sql(&format!("snapshot.{index}"), content, column, tokenize)
+            ))?,
+            None => copy_table(conn, name, "snapshot", redact)?,
+        }
+        let rows: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM snapshot.\"{name}\""),
+            [],
+            |row| row.get(0),
+        )?;
+        let category = category(name).unwrap_or_default();
+        exported.push(ExportedTable {
+            sources: source_paths(engine, name, &category),
+            name: name.clone(),
+            category,
+            rows,
+        });
+    }
+
+    write_metadata(engine, &exported, redact)?;
+    Ok(exported)
+}
+
+/// Copy `table` (a table, view, or virtual table) into a plain table of the
+/// same columns in the attached database `into`, then recreate the indexes
+/// that cover it.
+pub(crate) fn copy_table(conn: &Connection, table: &str, into: &str, redact: bool) -> Result<()> {
+    let schema: String = conn
+        .query_row(
+            "SELECT 'temp' FROM sqlite_temp_master WHERE name = ?1
+             UNION ALL SELECT 'main' FROM sqlite_master WHERE name = ?1",
+            [table],
+            |row| row.get(0),
+        )
+        .map_err(|_| Error::Query(format!("Table {table} was not loaded")))?;
+
+    let columns: Vec<(String, String, i64)> = conn
+        .prepare("SELECT name, type, pk FROM pragma_table_info(?1, ?2)")?
+        .query_map([table, schema.as_str()], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    let mut definitions: Vec<String> = columns
+        .iter()
+        .map(|(name, sql_type, _)| format!("\"{name}\" {sql_type}"))
+        .collect();
+    let mut key: Vec<(i64, String)> = columns
+        .iter()
+        .filter(|(_, _, pk)| *pk > 0)
+        .map(|(name, _, pk)| (*pk, format!("\"{name}\"")))
+        .collect();
+    key.sort();
+    if !key.is_empty() {
+        let key: Vec<String> = key.into_iter().map(|(_, name)| name).collect();
+        definitions.push(format!("PRIMARY KEY ({})", key.join(", ")));
+    }
+    conn.execute_batch(&format!(
+        "CREATE TABLE {into}.\"{table}\" ({})",
+        definitions.join(", ")
+    ))?;
+
+    let names: Vec<String> = columns
+        .iter()
+        .map(|(name, ..)| format!("\"{name}\""))
+        .collect();
+    let values: Vec<String> = if redact {
+        names.iter().map(|name| format!("redact({name})")).collect()
+    } else {
+        names.clone()
+    };
+    conn.execute_batch(&format!(
+        "INSERT INTO {into}.\"{table}\" ({}) SELECT {} FROM {schema}.\"{table}\"",
+        names.join(", "),
+        values.join(", ")
+    ))?;
+
+    for (index, columns) in index_columns(conn, table, &schema)? {
+        conn.execute_batch(&format!(
+            "CREATE INDEX IF NOT EXISTS {into}.\"{index}\" ON \"{table}\" ({})",
+            columns
+                .iter()
+                .map(|column| format!("\"{column}\""))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))?;
+    }
+    Ok(())
+}
+
+/// `(name, columns)` of each index to create on an exported `table`: the
+/// loaded table's own, those of the Codex index table a view reads, and
+/// the Git index's commit and path indexes. Expression indexes and primary
+/// keys are skipped.
+fn index_columns(
+    conn: &Connection,
+    table: &str,
+    schema: &str,
+) -> Result<Vec<(String, Vec<String>)>> {
+    let mut indexes = Vec::new();
+    let codex_attached: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_database_list WHERE name = 'codex_index'",
+        [],
+        |row| row.get(0),
+    )?;
+    let mut schemas = vec![schema];
+    if codex_attached {
+        schemas.push("codex_index");
+    }
+    for schema in schemas {
+        let listed: Vec<String> = conn
+            .prepare("SELECT name FROM pragma_index_list(?1, ?2) WHERE origin = 'c'")?
+            .query_map([table, schema], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        for index in listed {
+            let columns: Vec<Option<String>> = conn
+                .prepare("SELECT name FROM pragma_index_info(?1, ?2) ORDER BY seqno")?
+                .query_map([index.as_str(), schema], |row| row.get(0))?
+                .collect::<rusqlite::Result<_>>()?;
+            if let Some(columns) = columns.into_iter().collect::<Option<Vec<String>>>() {
+                indexes.push((index, columns));
+            }
+        }
+    }
+    for (name, columns) in crate::git_index::snapshot_indexes(table) {
+        indexes.push((name, columns));
+    }
+    Ok(indexes)
+}
+
+/// Paths rows of `table`, in `category`, were read from.
+fn source_paths(engine: &UnifiedEngine, table: &str, category: &str) -> Vec<String> {
+    let display = |path: &Path| path.to_string_lossy().into_owned();
+    match category {
+        "CLAUDE" => vec![display(engine.claude_data_dir())],
+        "CODEX" => vec![display(engine.codex_data_dir())],
+        "GIT" | "CODE" => engine.repo_paths().iter().map(|p| display(p)).collect(),
+        "SHELL" => {
+            let mut paths: Vec<String> = crate::providers::shell_history::history_paths()
+                .iter()
+                .map(|p| display(p))
+                .collect();
+            if table == "command_events" {
+                paths.push(display(engine.claude_data_dir()));
+                paths.push(display(engine.codex_data_dir()));
+            }
+            paths
+        }
+        "WORKLOG" => vec![display(&crate::worklog::db_path())],
+        "FULL-TEXT" => match fts_index(table) {
+            Some((_, content, ..)) => {
+                let category = crate::source::sources()
+                    .iter()
+                    .find(|source| source.tables().iter().any(|t| t.name == content))
+                    .map(|source| source.name().to_string())
+                    .unwrap_or_default();
+                source_paths(engine, content, &category)
+            }
+            None => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+fn write_metadata(engine: &UnifiedEngine, exported: &[ExportedTable], redact: bool) -> Result<()> {
+    let conn = engine.conn();
+    let window = engine.time_window();
+    let time = |time: Option<chrono::DateTime<Utc>>| {
+        time.map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true))
+    };
+    let repos: Vec<String> = engine
+        .repo_paths()
+        .iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+    conn.execute_batch(&format!(
+        "CREATE TABLE snapshot.{META_TABLE} (key TEXT PRIMARY KEY, value TEXT);
+         CREATE TABLE snapshot.{TABLES_TABLE} (
+             table_name TEXT PRIMARY KEY,
+             category TEXT,
+             row_count INTEGER,
+             sources TEXT
+         );"
+    ))?;
+    let mut insert = conn.prepare(&format!(
+        "INSERT INTO snapshot.{META_TABLE} (key, value) VALUES (?1, ?2)"
+    ))?;
+    for (key, value) in [
+        (
+            "exported_at",
+            Some(Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)),
+        ),
+        (
+            "devsql_version",
+            Some(env!("CARGO_PKG_VERSION").to_string()),
+        ),
+        ("since", time(window.since)),
+        ("until", time(window.until)),
+        ("repos", Some(serde_json::to_string(&repos)?)),
+        ("redacted", Some(redact.to_string())),
+    ] {
+        insert.execute(rusqlite::params![key, value])?;
+    }
+    let mut insert = conn.prepare(&format!(
+        "INSERT INTO snapshot.{TABLES_TABLE} (table_name, category, row_count, sources)
+         VALUES (?1, ?2, ?3, ?4)"
+    ))?;
+    for table in exported {
+        insert.execute(rusqlite::params![
+            table.name,
+            table.category,
+            table.rows,
+            serde_json::to_string(&table.sources)?,
+        ])?;
+    }
+    Ok(())
+}
@@ -0,0 +1,608 @@
+//! Pluggable data sources.
+//!
+//! Every table devsql can query belongs to a [`DataSource`], which declares
+//! the tables' names, schemas, and what each reads while loading, and loads
+//! them on demand. [`UnifiedEngine::load_tables`] resolves a query's tables
+//! through [`sources`]: the built-in Claude, Codex, Git, code, shell,
+//! worklog, and full-text sources, then any added with [`register`].
+//!
+//! A crate shipping its own tables implements the trait and registers it
+//! before running the stock CLI:
+//!
+//! ```no_run
+//! use devsql::source::{self, DataSource, SourceTable};
+//! use devsql::UnifiedEngine;
+//!
+//! struct Tickets;
+//!
+//! impl DataSource for Tickets {
+//!     fn name(&self) -> &str {
+//!         "TICKETS"
+//!     }
+//!
+//!     fn tables(&self) -> Vec<SourceTable> {
+//!         vec![SourceTable::new(
+//!             "tickets",
+//!             "Open tickets from the team tracker",
+//!             "CREATE TABLE tickets (id TEXT, title TEXT, opened_at TEXT)",
+//!         )]
+//!     }
+//!
+//!     fn load(&self, engine: &mut UnifiedEngine, _tables: &[&str]) -> devsql::Result<()> {
+//!         engine.conn().execute_batch(
+//!             "CREATE TABLE tickets (id TEXT, title TEXT, opened_at TEXT);
+//!              INSERT INTO tickets VALUES ('T-1', 'Flaky login test', '2026-10-01')",
+//!         )?;
+//!         Ok(())
+//!     }
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     source::register(Tickets);
+//!     devsql::cli::main().await;
+//! }
+//! ```
+
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+use crate::{engine, providers, schema, Result, UnifiedEngine};
+
+/// A provider of one or more tables.
+pub trait DataSource: Send + Sync {
+    /// Category the source's tables are listed under by `devsql tables`,
+    /// such as `GIT`.
+    fn name(&self) -> &str;
+
+    /// Every table the source provides.
+    fn tables(&self) -> Vec<SourceTable>;
+
+    /// Create and fill `tables`, each one of [`Self::tables`], in
+    /// `engine`'s connection. The tables they depend on are loaded first.
+    fn load(&self, engine: &mut UnifiedEngine, tables: &[&str]) -> Result<()>;
+
+    /// Files and directories `engine` would read the tables from; a
+    /// directory stands for every file under it. Long-running engines
+    /// compare their [`stamp`] to tell when loaded tables are stale. The
+    /// default, none, means the tables never are.
+    fn inputs(&self, _engine: &UnifiedEngine) -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+/// A table declared by a [`DataSource`].
+#[derive(Debug, Clone)]
+pub struct SourceTable {
+    pub name: String,
+    pub description: String,
+    /// Statement creating the table, empty, in a fresh connection;
+    /// `devsql schema` reads the columns from it. Empty when the table
+    /// comes with another's schema.
+    pub schema: String,
+    /// Tables, from any source, loaded before this one because its loader
+    /// reads them.
+    pub depends_on: Vec<String>,
+}
+
+impl SourceTable {
+    pub fn new(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        schema: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            schema: schema.into(),
+            depends_on: Vec::new(),
+        }
+    }
+
+    /// Declare tables this one's loader reads.
+    pub fn depends_on(mut self, tables: &[&str]) -> Self {
+        self.depends_on = tables.iter().map(|table| table.to_string()).collect();
+        self
+    }
+}
+
+static REGISTERED: RwLock<Vec<Arc<dyn DataSource>>> = RwLock::new(Vec::new());
+
+/// Add `source` to every engine and schema listing from now on. A table
+/// name a built-in or earlier source already provides stays with it.
+pub fn register(source: impl DataSource + 'static) {
+    REGISTERED
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .push(Arc::new(source));
+}
+
+/// Sources added with [`register`], in registration order.
+pub fn registered() -> Vec<Arc<dyn DataSource>> {
+    REGISTERED
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+}
+
+/// The built-in sources, then the registered ones.
+pub fn sources() -> Vec<Arc<dyn DataSource>> {
+    let mut sources: Vec<Arc<dyn DataSource>> = BUILTIN
+        .iter()
+        .map(|builtin| Arc::new(*builtin) as Arc<dyn DataSource>)
+        .collect();
+    sources.extend(registered());
+    sources
+}
+
+/// Whether some source provides a table called `name`.
+pub fn provides(name: &str) -> bool {
+    sources()
+        .iter()
+        .any(|source| source.tables().iter().any(|table| table.name == name))
+}
+
+/// The source tables `query` reads, in [`sources`] order. Tables are found
+/// with SQLite's own name resolution, so columns, literals, and CTEs that
+/// shadow a table do not count.
+pub fn tables_in(query: &str) -> Vec<String> {
+    let referenced = vcsql::sql::referenced_tables(query);
+    let mut tables: Vec<String> = Vec::new();
+    for source in sources() {
+        for table in source.tables() {
+            if referenced.contains(&table.name) && !tables.contains(&table.name) {
+                tables.push(table.name);
+            }
+        }
+    }
+    tables
+}
+
+/// How many files a source's inputs hold, their total size, and when the
+/// latest was written. Appending to, rewriting, adding, or removing a file
+/// changes it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InputStamp {
+    pub files: u64,
+    pub bytes: u64,
+    pub modified: Option<SystemTime>,
+}
+
+/// Stamp the files at `paths`, walking directories with the filters source
+/// walks use (see [`providers::walk_source_files`]). Paths that do not
+/// exist count as empty.
+pub fn stamp(paths: &[PathBuf]) -> InputStamp {
+    let mut stamp = InputStamp::default();
+    for path in paths {
+        let walker = ignore::WalkBuilder::new(path)
+            .hidden(false)
+            .follow_links(false)
+            .filter_entry(|entry| {
+                !entry.file_type().is_some_and(|ft| ft.is_dir())
+                    || entry
+                        .file_name()
+                        .to_str()
+                        .is_none_or(|name| !crate::config::current().excludes_dir(name))
+            })
+            .build();
+        for entry in walker.flatten() {
+            if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            stamp.files += 1;
+            stamp.bytes += metadata.len();
+            stamp.modified = stamp.modified.max(metadata.modified().ok());
+        }
+    }
+    stamp
+}
+
+// ---------------------------------------------------------------------------
+// Built-in sources
+// ---------------------------------------------------------------------------
+
+/// A built-in source: its category, table list, loader, and inputs.
+#[derive(Clone, Copy)]
+struct Builtin {
+    name: &'static str,
+    tables: fn() -> Vec<SourceTable>,
+    load: fn(&mut UnifiedEngine, &[&str]) -> Result<()>,
+    inputs: fn(&UnifiedEngine) -> Vec<PathBuf>,
+}
+
+impl DataSource for Builtin {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn tables(&self) -> Vec<SourceTable> {
+        (self.tables)()
+    }
+
+    fn load(&self, engine: &mut UnifiedEngine, tables: &[&str]) -> Result<()> {
+        (self.load)(engine, tables)
+    }
+
+    fn inputs(&self, engine: &UnifiedEngine) -> Vec<PathBuf> {
+        (self.inputs)(engine)
+    }
+}
+
+static BUILTIN: &[Builtin] = &[
+    Builtin {
+        name: "CLAUDE",
+        tables: claude_tables,
+        load: UnifiedEngine::load_claude_tables,
+        inputs: claude_inputs,
+    },
+    Builtin {
+        name: "CODEX",
+        tables: codex_tables,
+        load: load_codex,
+        inputs: codex_inputs,
+    },
+    Builtin {
+        name: "GIT",
+        tables: git_tables,
+        load: UnifiedEngine::load_git_tables,
+        inputs: git_inputs,
+    },
+    Builtin {
+        name: "CODE",
+        tables: code_tables,
+        load: UnifiedEngine::load_code_tables,
+        inputs: code_inputs,
+    },
+    Builtin {
+        name: "SHELL",
+        tables: shell_tables,
+        load: load_shell,
+        inputs: shell_inputs,
+    },
+    Builtin {
+        name: "WORKLOG",
+        tables: worklog_tables,
+        load: UnifiedEngine::load_work_tables,
+        inputs: worklog_inputs,
+    },
+    Builtin {
+        name: "FULL-TEXT",
+        tables: full_text_tables,
+        load: UnifiedEngine::load_fts_tables,
+        inputs: no_inputs,
+    },
+];
+
+/// A built-in table, described by the schema registry.
+fn documented(name: &str, schema: impl Into<String>) -> SourceTable {
+    SourceTable::new(name, schema::description(name), schema)
+}
+
+fn claude_tables() -> Vec<SourceTable> {
+    vec![
+        documented("history", engine::HISTORY_TABLE),
+        documented("transcripts", engine::create_transcripts_sql()),
+        documented("sessions", engine::SESSIONS_TABLE),
+        documented("todos", engine::TODOS_TABLE),
+        documented("tool_calls", engine::TOOL_CALLS_TABLE),
+    ]
+}
+
+fn claude_inputs(engine: &UnifiedEngine) -> Vec<PathBuf> {
+    let dir = engine.claude_data_dir();
+    vec![
+        dir.join("history.jsonl"),
+        dir.join("projects"),
+        dir.join("todos"),
+    ]
+}
+
+fn codex_tables() -> Vec<SourceTable> {
+    let mut tables = vec![
+        documented("jhistory", engine::JHISTORY_TABLE),
+        documented("codex_history", engine::CODEX_HISTORY_VIEW).depends_on(&["jhistory"]),
+    ];
+    tables.extend(
+        engine::codex_views(&vcsql::TimeWindow::default())
+            .into_iter()
+            .map(|(name, sql)| documented(name, sql)),
+    );
+    tables
+}
+
+/// `codex_history` is created with `jhistory`, which it depends on, and
+/// again on its own after being unloaded without it.
+fn load_codex(engine: &mut UnifiedEngine, tables: &[&str]) -> Result<()> {
+    let others: Vec<&str> = tables
+        .iter()
+        .copied()
+        .filter(|table| *table != "codex_history")
+        .collect();
+    engine.load_claude_tables(&others)?;
+    if tables.contains(&"codex_history") {
+        engine.conn().execute(engine::CODEX_HISTORY_VIEW, [])?;
+    }
+    Ok(())
+}
+
+fn codex_inputs(engine: &UnifiedEngine) -> Vec<PathBuf> {
+    let dir = engine.codex_data_dir();
+    vec![
+        dir.join("history.jsonl"),
+        dir.join("sessions"),
+        dir.join("archived_sessions"),
+    ]
+}
+
+fn git_tables() -> Vec<SourceTable> {
+    vcsql::TABLES
+        .iter()
+        .map(|info| SourceTable::new(info.name, info.description, info.create_sql))
+        .collect()
+}
+
+fn git_inputs(engine: &UnifiedEngine) -> Vec<PathBuf> {
+    git_ref_paths(engine.repo_paths())
+}
+
+/// The refs, HEAD, and config of each repository at `repos`, not its
+/// objects: new commits always move a ref.
+pub(crate) fn git_ref_paths(repos: &[PathBuf]) -> Vec<PathBuf> {
+    let mut inputs = Vec::new();
+    for repo in repos {
+        let Ok(repo) = git2::Repository::discover(repo) else {
+            continue;
+        };
+        let git_dir = repo.path();
+        // A worktree keeps its refs in the main repository's directory.
+        let common = std::fs::read_to_string(git_dir.join("commondir"))
+            .map(|common| git_dir.join(common.trim()))
+            .unwrap_or_else(|_| git_dir.to_path_buf());
+        inputs.push(git_dir.join("HEAD"));
+        inputs.extend(["refs", "packed-refs", "config"].map(|name| common.join(name)));
+    }
+    inputs
+}
+
+fn code_tables() -> Vec<SourceTable> {
+    vec![
+        documented("source_files", providers::source_files::CREATE_TABLE),
+        documented("source_lines", providers::source_lines::CREATE_TABLE),
+        documented("symbols", providers::symbols::CREATE_SYMBOLS_TABLE),
+        documented("symbol_refs", providers::SYMBOL_REFS_TABLE),
+        documented("imports", providers::IMPORTS_TABLE),
+        documented("ast_nodes", providers::AST_NODES_TABLE),
+    ]
+}
+
+fn code_inputs(engine: &UnifiedEngine) -> Vec<PathBuf> {
+    engine.repo_paths().to_vec()
+}
+
+fn shell_tables() -> Vec<SourceTable> {
+    vec![
+        documented("shell_history", providers::shell_history::CREATE_TABLE),
+        documented("command_events", engine::COMMAND_EVENTS_TABLE).depends_on(&[
+            "shell_history",
+            "tool_calls",
+            "codex_tool_executions",
+            "codex_threads",
+        ]),
+    ]
+}
+
+fn load_shell(engine: &mut UnifiedEngine, tables: &[&str]) -> Result<()> {
+    for table in tables {
+        match *table {
+            "shell_history" => engine.load_shell_history()?,
+            "command_events" => engine.load_command_events()?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn shell_inputs(_engine: &UnifiedEngine) -> Vec<PathBuf> {
+    providers::shell_history::history_paths()
+}
+
+fn worklog_tables() -> Vec<SourceTable> {
+    vec![
+        documented("work_tasks", crate::worklog::WORK_TASKS_TABLE),
+        documented("work_events", crate::worklog::WORK_EVENTS_TABLE),
+    ]
+}
+
+fn worklog_inputs(_engine: &UnifiedEngine) -> Vec<PathBuf> {
+    let db = crate::worklog::db_path();
+    let mut wal = db.clone().into_os_string();
+    wal.push("-wal");
+    vec![db, PathBuf::from(wal)]
+}
+
+fn full_text_tables() -> Vec<SourceTable> {
+    let mut tables: Vec<SourceTable> = engine::FTS_TABLES
+        .iter()
+        .map(|&(index, content, column, tokenize)| {
+            documented(
+                index,
+                engine::create_fts_sql(index, content, column, tokenize),
+            )
+            .depends_on(&[content])
+        })
+        .collect();
+    // Persisted in the Codex index next to the table it indexes.
+    tables.push(documented("codex_messages_fts", "").depends_on(&["codex_messages"]));
+    tables
+}
+
+/// Full-text indexes go stale with the tables they index, which are
+/// unloaded with their dependents.
+fn no_inputs(_engine: &UnifiedEngine) -> Vec<PathBuf> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    struct Numbers;
+
+    impl DataSource for Numbers {
+        fn name(&self) -> &str {
+            "TEST"
+        }
+
+        fn tables(&self) -> Vec<SourceTable> {
+            vec![
+                SourceTable::new(
+                    "test_numbers",
+                    "1 to 3",
+                    "CREATE TABLE test_numbers (n INTEGER)",
+                ),
+                SourceTable::new(
+                    "test_doubled",
+                    "Each number doubled",
+                    "CREATE TABLE test_doubled (n INTEGER)",
+                )
+                .depends_on(&["test_numbers"]),
+            ]
+        }
+
+        fn load(&self, engine: &mut UnifiedEngine, tables: &[&str]) -> Result<()> {
+            for table in tables {
+                let sql = match *table {
+                    "test_numbers" => {
+                        "CREATE TABLE test_numbers (n INTEGER);
+                         INSERT INTO test_numbers VALUES (1), (2), (3)"
+                    }
+                    _ => "CREATE TABLE test_doubled AS SELECT n * 2 AS n FROM test_numbers",
+                };
+                engine.conn().execute_batch(sql)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn builtin_sources_provide_each_table_once() {
+        let mut provided: Vec<String> = BUILTIN
+            .iter()
+            .flat_map(|source| source.tables())
+            .map(|table| table.name)
+            .collect();
+        let count = provided.len();
+        provided.sort();
+        provided.dedup();
+        assert_eq!(provided.len(), count, "a table is provided twice");
+        for name in &provided {
+            assert_eq!(
+                tables_in(&format!("SELECT * FROM {name}")),
+                vec![name.clone()],
+                "{name} is not found in queries"
+            );
+        }
+        for source in BUILTIN {
+            for table in source.tables() {
+                for dependency in &table.depends_on {
+                    assert!(provides(dependency), "{} needs {dependency}", table.name);
+                }
+            }
+        }
+    }
+
+    fn sorted_tables_in(query: &str) -> Vec<String> {
+        let mut tables = tables_in(query);
+        tables.sort();
+        tables
+    }
+
+    #[test]
+    fn tables_in_finds_fts_indexes_and_joined_tables() {
+        assert_eq!(
+            sorted_tables_in(
+                "SELECT * FROM history_fts, codex_messages_fts, source_lines_fts, shell_history_fts",
+            ),
+            [
+                "codex_messages_fts",
+                "history_fts",
+                "shell_history_fts",
+                "source_lines_fts"
+            ]
+        );
+        assert_eq!(
+            sorted_tables_in(
+                "SELECT * FROM source_files JOIN symbols ON source_files.path = symbols.file_path",
+            ),
+            ["source_files", "symbols"]
+        );
+        assert_eq!(
+            sorted_tables_in(
+                "SELECT * FROM work_events JOIN work_tasks ON work_events.task_id = work_tasks.id",
+            ),
+            ["work_events", "work_tasks"]
+        );
+    }
+
+    #[test]
+    fn tables_in_ignores_columns_literals_and_shadowing_ctes() {
+        assert_eq!(
+            tables_in(
+                "WITH commits AS (SELECT name AS status FROM symbols) \
+                 SELECT c.status, 'config notes history' AS refs FROM commits c",
+            ),
+            ["symbols"]
+        );
+        assert_eq!(
+            sorted_tables_in(
+                "SELECT display FROM \"History\" \
+                 WHERE timestamp > (SELECT MAX(authored_at) FROM commits WHERE summary LIKE '%status%')",
+            ),
+            ["commits", "history"]
+        );
+    }
+
+    #[test]
+    fn tables_in_does_not_mistake_prefixed_names_for_history() {
+        for name in [
+            "jhistory",
+            "codex_history",
+            "shell_history",
+            "command_events",
+        ] {
+            assert_eq!(tables_in(&format!("SELECT * FROM {name} LIMIT 5")), [name]);
+        }
+    }
+
+    #[test]
+    fn registered_sources_load_with_their_dependencies() {
+        register(Numbers);
+        assert!(provides("test_doubled"));
+
+        let query = "WITH history AS (SELECT 1) SELECT SUM(n) AS total FROM test_doubled, history";
+        assert_eq!(tables_in(query), ["test_doubled"]);
+
+        let mut engine =
+            UnifiedEngine::new(PathBuf::from("/nonexistent"), PathBuf::from(".")).expect("engine");
+        engine.load_tables(&["test_doubled"]).expect("load");
+        // Already loaded, so not created a second time.
+        engine
+            .load_tables(&["test_numbers", "test_doubled"])
+            .expect("reload");
+        let rows = engine
+            .query("SELECT SUM(n) AS total FROM test_doubled")
+            .expect("query");
+        assert_eq!(rows[0]["total"], 12);
+
+        let listed = schema::table("test_doubled")
+            .expect("schema")
+            .expect("registered");
+        assert_eq!(listed.category, "TEST");
+        assert_eq!(listed.columns[0].name, "n");
+    }
+}
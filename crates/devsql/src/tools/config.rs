@@ -0,0 +1,55 @@
+//! `devsql config show` — print the resolved configuration.
+
+use incurs::cli::Cli;
+use incurs::command::{CommandDef, Example, TypedContext, TypedResult};
+
+use super::read_only_mcp;
+use crate::config::{Config, ConfigEntry};
+
+#[derive(incurs::Args, serde::Deserialize, serde::Serialize)]
+#[allow(dead_code)]
+struct ShowArgs {}
+
+#[derive(incurs::Options, serde::Deserialize, serde::Serialize)]
+#[allow(dead_code)]
+struct ShowOptions {}
+
+async fn run_show(_ctx: TypedContext<ShowArgs, ShowOptions, ()>) -> TypedResult<Vec<ConfigEntry>> {
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => return TypedResult::error("CONFIG_ERROR", e.to_string()),
+    };
+    let mut entries = vec![ConfigEntry {
+        setting: "file".to_string(),
+        value: config.path.display().to_string(),
+        source: if config.path.exists() {
+            "read"
+        } else {
+            "not found"
+        }
+        .to_string(),
+    }];
+    entries.extend(config.entries());
+    TypedResult::ok(entries)
+}
+
+fn show_cmd() -> CommandDef {
+    CommandDef::typed::<ShowArgs, ShowOptions, (), Vec<ConfigEntry>, _, _>("show", run_show)
+        .description(
+            "Print every setting with its resolved value and where it came from: the default, \
+             ~/.devsql/config.toml, or an environment variable",
+        )
+        .examples(vec![Example {
+            command: "--format json".into(),
+            description: Some("Resolved settings as JSON".into()),
+        }])
+        .mcp(read_only_mcp())
+        .done()
+}
+
+/// Build the `config` command group (`devsql config show`).
+pub fn build_group() -> Cli {
+    Cli::create("config")
+        .description("Inspect devsql's configuration in ~/.devsql/config.toml")
+        .command("show", show_cmd())
+}
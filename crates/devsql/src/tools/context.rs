@@ -5,6 +5,7 @@ use incurs::output::CommandResult;
 use serde_json::{json, Value};
 
 use super::{engine_from_options, legacy_context, read_only_mcp, typed_from_result};
+use crate::engine::{like_contains, QueryParams};
 
 // ---------------------------------------------------------------------------
 // Schema
@@ -88,13 +89,12 @@ impl CommandHandler for ContextHandler {
         }
 
         // Query source files matching the path
-        let files_sql = format!(
-            "SELECT path, language, line_count, size_bytes \
-             FROM source_files \
-             WHERE path LIKE '%{file}%'"
-        );
+        let files_sql = "SELECT path, language, line_count, size_bytes \
+                         FROM source_files \
+                         WHERE path LIKE :pattern ESCAPE '\\'";
+        let params = QueryParams::None.with("pattern", like_contains(&file));
 
-        let file_rows = match engine.query(&files_sql) {
+        let file_rows = match engine.query_with_params(files_sql, &params) {
             Ok(rows) => rows,
             Err(e) => {
                 return CommandResult::Error {
@@ -115,14 +115,13 @@ impl CommandHandler for ContextHandler {
             let mut entry = file_row.clone();
 
             if include_symbols {
-                let sym_sql = format!(
-                    "SELECT name, kind, line_start, line_end, signature, visibility \
-                     FROM symbols \
-                     WHERE file_path = '{path}' \
-                     ORDER BY line_start"
-                );
-
-                if let Ok(syms) = engine.query(&sym_sql) {
+                let sym_sql = "SELECT name, kind, line_start, line_end, signature, visibility \
+                               FROM symbols \
+                               WHERE file_path = :path \
+                               ORDER BY line_start";
+                let sym_params = QueryParams::None.with("path", path);
+
+                if let Ok(syms) = engine.query_with_params(sym_sql, &sym_params) {
                     if let Some(obj) = entry.as_object_mut() {
                         obj.insert("symbols".to_string(), Value::Array(syms));
                     }
@@ -0,0 +1,190 @@
+//! `devsql daemon` — keep a persistent database of the Claude Code, Codex,
+//! shell, and worklog tables current (see [`crate::daemon`]).
+
+use std::time::Duration;
+
+use incurs::command::{CommandDef, Example, TypedContext, TypedResult};
+
+use crate::daemon::{Daemon, Status, SyncStats};
+
+#[derive(incurs::Args, serde::Deserialize, serde::Serialize)]
+#[allow(dead_code)]
+struct DaemonArgs {}
+
+#[derive(incurs::Options, serde::Deserialize, serde::Serialize)]
+#[allow(dead_code)]
+struct DaemonOptions {
+    /// Git repository path or glob whose index to keep current; repeat for
+    /// several repositories
+    #[incurs(alias = "r")]
+    #[serde(default)]
+    repo: Vec<String>,
+    /// File listing one repository root (or glob) per line
+    #[incurs(alias = "w")]
+    workspace: Option<String>,
+    /// Claude data directory (defaults to ~/.claude)
+    #[incurs(alias = "d")]
+    data_dir: Option<String>,
+    /// Seconds between syncs (default 2)
+    interval: Option<f64>,
+    /// Sync once and exit, without marking the database as served
+    #[serde(default)]
+    once: bool,
+    /// Report the database's state without syncing
+    #[serde(default)]
+    status: bool,
+}
+
+/// What `devsql daemon --once` or `--status` reports.
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+struct DaemonReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<Status>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    synced: Option<SyncStats>,
+}
+
+async fn run_daemon(ctx: TypedContext<DaemonArgs, DaemonOptions, ()>) -> TypedResult<DaemonReport> {
+    if ctx.request.is_some() {
+        return TypedResult::error(
+            "DAEMON_UNAVAILABLE",
+            "devsql daemon runs from the command line",
+        );
+    }
+    let options = ctx.options;
+    let path = crate::daemon::db_path();
+    let status = match crate::daemon::status(&path) {
+        Ok(status) => status,
+        Err(e) => return TypedResult::error("DAEMON_ERROR", e.to_string()),
+    };
+    if options.status {
+        return TypedResult::ok(DaemonReport {
+            status,
+            synced: None,
+        });
+    }
+    if let Some(status) = status.filter(|status| status.running) {
+        return TypedResult::error(
+            "DAEMON_RUNNING",
+            format!(
+                "a daemon (pid {}) synced {} at {}; stop it first",
+                status
+                    .pid
+                    .map_or("unknown".to_string(), |pid| pid.to_string()),
+                status.path,
+                status.synced_at.unwrap_or_default()
+            ),
+        );
+    }
+
+    let interval = match options.interval {
+        None => crate::daemon::DEFAULT_INTERVAL,
+        Some(seconds) if seconds.is_finite() && seconds > 0.0 => Duration::from_secs_f64(seconds),
+        Some(seconds) => {
+            return TypedResult::error(
+                "INVALID_INTERVAL",
+                format!("--interval must be a positive number of seconds, not {seconds}"),
+            )
+        }
+    };
+    let repo = if options.repo.is_empty() {
+        vec![".".to_string()]
+    } else {
+        options.repo
+    };
+    let (engine, repos) = match super::engine_from_repos(
+        &repo,
+        options.workspace.as_deref(),
+        options.data_dir.as_deref(),
+    ) {
+        Ok(found) => found,
+        Err(e) => return e.into_typed(),
+    };
+    let mut daemon = match Daemon::open(
+        engine.claude_data_dir().to_path_buf(),
+        engine.codex_data_dir().to_path_buf(),
+        repos,
+    ) {
+        Ok(daemon) => daemon,
+        Err(e) => return TypedResult::error("DAEMON_ERROR", e.to_string()),
+    };
+    drop(engine);
+
+    if options.once {
+        return match tokio::task::spawn_blocking(move || daemon.sync()).await {
+            Ok(Ok(synced)) => TypedResult::ok(DaemonReport {
+                status: None,
+                synced: Some(synced),
+            }),
+            Ok(Err(e)) => TypedResult::error("DAEMON_ERROR", e.to_string()),
+            Err(e) => TypedResult::error("DAEMON_ERROR", e.to_string()),
+        };
+    }
+
+    eprintln!(
+        "Syncing {} every {}s",
+        path.display(),
+        interval.as_secs_f64()
+    );
+    let (stop, stopped) = std::sync::mpsc::channel();
+    let mut running = tokio::task::spawn_blocking(move || daemon.run(interval, &stopped));
+    let result = tokio::select! {
+        result = &mut running => result,
+        () = stop_signal() => {
+            let _ = stop.send(());
+            running.await
+        }
+    };
+    match result {
+        Ok(Ok(())) => TypedResult::ok(DaemonReport {
+            status: None,
+            synced: None,
+        }),
+        Ok(Err(e)) => TypedResult::error("DAEMON_ERROR", e.to_string()),
+        Err(e) => TypedResult::error("DAEMON_ERROR", e.to_string()),
+    }
+}
+
+/// Resolves on Ctrl-C or, on Unix, SIGTERM.
+async fn stop_signal() {
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(_) => std::future::pending().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        () = terminate => {}
+    }
+}
+
+pub fn build() -> CommandDef {
+    CommandDef::typed::<DaemonArgs, DaemonOptions, (), DaemonReport, _, _>("daemon", run_daemon)
+        .description(
+            "Keep ~/.devsql/daemon.sqlite current with Claude Code and Codex history, \
+             transcripts, tool calls, shell history, and the worklog, and keep the Codex and Git \
+             indexes synced; queries copy those tables from it while it runs",
+        )
+        .examples(vec![
+            Example {
+                command: "--repo ~/src/app &".to_string(),
+                description: Some("Run in the background, also indexing a repository".to_string()),
+            },
+            Example {
+                command: "--status".to_string(),
+                description: Some("Show when the daemon last synced and what it holds".to_string()),
+            },
+            Example {
+                command: "--once".to_string(),
+                description: Some("Sync once, as from a cron job".to_string()),
+            },
+        ])
+        .hint("Set DEVSQL_NO_DAEMON=1 to make a query read its sources directly.")
+        .done()
+}
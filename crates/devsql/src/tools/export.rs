@@ -0,0 +1,121 @@
+//! `devsql export` — write loaded tables to a standalone SQLite snapshot.
+
+use std::path::PathBuf;
+
+use incurs::command::{
+    CommandDef, Example, McpAnnotations, McpCommandOptions, TypedContext, TypedResult,
+};
+use vcsql::TimeWindow;
+
+use crate::snapshot::{self, ExportedTable};
+
+#[derive(incurs::Args, serde::Deserialize, serde::Serialize)]
+#[allow(dead_code)]
+struct ExportArgs {}
+
+#[derive(incurs::Options, serde::Deserialize, serde::Serialize)]
+#[allow(dead_code)]
+struct ExportOptions {
+    /// Tables to export, comma-separated or repeated
+    #[incurs(alias = "t")]
+    #[serde(default)]
+    tables: Vec<String>,
+    /// SQLite file to write; replaced if it exists
+    #[incurs(alias = "o")]
+    out: String,
+    /// Redact tokens, passwords, and other secrets in every text value
+    #[serde(default)]
+    redact: bool,
+    /// Git repository path or glob; repeat to query several repositories
+    #[incurs(alias = "r")]
+    #[serde(default)]
+    repo: Vec<String>,
+    /// File listing one repository root (or glob) per line
+    #[incurs(alias = "w")]
+    workspace: Option<String>,
+    /// Claude data directory (defaults to ~/.claude)
+    #[incurs(alias = "d")]
+    data_dir: Option<String>,
+    /// Only load rows at or after this time: an RFC 3339 timestamp, a
+    /// YYYY-MM-DD date (UTC), or a span back from now such as 7d or 12h
+    since: Option<String>,
+    /// Only load rows before this time (same formats as --since)
+    until: Option<String>,
+}
+
+#[derive(schemars::JsonSchema, serde::Deserialize, serde::Serialize)]
+struct ExportOutput {
+    /// The snapshot written
+    out: String,
+    tables: Vec<ExportedTable>,
+}
+
+async fn run_export(ctx: TypedContext<ExportArgs, ExportOptions, ()>) -> TypedResult<ExportOutput> {
+    let options = ctx.options;
+    let tables: Vec<&str> = options
+        .tables
+        .iter()
+        .flat_map(|tables| tables.split(','))
+        .map(str::trim)
+        .filter(|table| !table.is_empty())
+        .collect();
+    if tables.is_empty() {
+        return TypedResult::error(
+            "INVALID_TABLES",
+            "Name the tables to export with --tables (see `devsql tables`)",
+        );
+    }
+    let window = match TimeWindow::parse(options.since.as_deref(), options.until.as_deref()) {
+        Ok(window) => window,
+        Err(e) => return TypedResult::error("INVALID_TIME", e.to_string()),
+    };
+    let (mut engine, _) = match super::engine_from_repos(
+        &options.repo,
+        options.workspace.as_deref(),
+        options.data_dir.as_deref(),
+    ) {
+        Ok(engine) => engine,
+        Err(error) => return error.into_typed(),
+    };
+    engine.set_time_window(window);
+
+    let out = PathBuf::from(&options.out);
+    match snapshot::export(&mut engine, &tables, &out, options.redact) {
+        Ok(tables) => TypedResult::ok(ExportOutput {
+            out: out.to_string_lossy().into_owned(),
+            tables,
+        }),
+        Err(e) => TypedResult::error("EXPORT_ERROR", format!("Export failed: {e}")),
+    }
+}
+
+pub fn build() -> CommandDef {
+    CommandDef::typed::<ExportArgs, ExportOptions, (), ExportOutput, _, _>("export", run_export)
+        .description(
+            "Write tables to a standalone SQLite snapshot, with indexes and a devsql_snapshot \
+             metadata table; query it later with `devsql --db <file>`",
+        )
+        .examples(vec![
+            Example {
+                command: "--tables transcripts,commits,diff_files --since 14d --out sprint.sqlite"
+                    .to_string(),
+                description: Some("Last sprint's sessions and commits in one file".to_string()),
+            },
+            Example {
+                command: "--tables history,codex_messages --redact --out prompts.sqlite"
+                    .to_string(),
+                description: Some("Prompts with secrets redacted, safe to share".to_string()),
+            },
+        ])
+        .mcp(McpCommandOptions {
+            annotations: Some(McpAnnotations {
+                read_only_hint: Some(false),
+                destructive_hint: Some(true),
+                idempotent_hint: Some(true),
+                open_world_hint: Some(false),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .done()
+}
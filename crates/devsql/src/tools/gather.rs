@@ -12,8 +12,9 @@ use rusqlite::{params_from_iter, Connection};
 use serde_json::{json, Value};
 
 use super::engine_from_options;
-use super::recall::{match_expr, score_expr};
+use super::recall::{codex_hits_sql, match_expr, prompt_hits_sql, score_expr, term_params};
 use super::{legacy_context, read_only_mcp, typed_from_result};
+use crate::engine::QueryParams;
 use crate::UnifiedEngine;
 
 // ---------------------------------------------------------------------------
@@ -37,13 +38,16 @@ struct GatherOptions {
     #[incurs(alias = "d")]
     data_dir: Option<String>,
     /// Token budget for the bundle; lowest-ranked rows are dropped round-robin
-    /// per section (never mid-row) until the bundle fits
-    #[incurs(default = 8000)]
-    budget: i64,
+    /// per section (never mid-row) until the bundle fits (defaults to
+    /// gather.budget in ~/.devsql/config.toml, or 8000)
+    budget: Option<i64>,
 }
 
-/// Rows fetched per section before budget trimming.
-const SECTION_LIMIT: i64 = 10;
+/// Rows fetched for `section` before budget trimming
+/// (`gather.section_limits` in the config file).
+fn section_limit(section: &str) -> i64 {
+    crate::config::current().section_limit(section) as i64
+}
 
 /// Section names, in materialization/round-robin-trim order.
 const SECTION_ORDER: [&str; 6] = [
@@ -123,13 +127,14 @@ impl CommandHandler for GatherHandler {
             .options
             .get("budget")
             .and_then(|v| v.as_i64())
-            .unwrap_or(8000)
-            .max(0) as usize;
+            .map_or(crate::config::current().gather_budget.value, |budget| {
+                budget.max(0) as usize
+            });
 
         let terms: Vec<String> = terms_arg
             .split_whitespace()
             .filter(|t| t.chars().count() >= 2)
-            .map(|t| t.replace('\'', "''"))
+            .map(String::from)
             .collect();
 
         let (render_engine, repo_path) = match engine_from_options(&ctx.options) {
@@ -144,32 +149,52 @@ impl CommandHandler for GatherHandler {
                 scope.spawn(|| {
                     (
                         "prior_work",
-                        section_prior_work(&claude_dir, &repo_path, &terms, SECTION_LIMIT),
+                        section_prior_work(
+                            &claude_dir,
+                            &repo_path,
+                            &terms,
+                            section_limit("prior_work"),
+                        ),
                     )
                 }),
                 scope.spawn(|| ("repo_state", section_repo_state(&repo_path))),
                 scope.spawn(|| {
                     (
                         "code_search",
-                        section_code_search(&claude_dir, &repo_path, &terms, SECTION_LIMIT),
+                        section_code_search(
+                            &claude_dir,
+                            &repo_path,
+                            &terms,
+                            section_limit("code_search"),
+                        ),
                     )
                 }),
                 scope.spawn(|| {
                     (
                         "symbols",
-                        section_symbols(&claude_dir, &repo_path, &terms, SECTION_LIMIT),
+                        section_symbols(&claude_dir, &repo_path, &terms, section_limit("symbols")),
                     )
                 }),
                 scope.spawn(|| {
                     (
                         "excerpts",
-                        section_excerpts(&claude_dir, &repo_path, &terms),
+                        section_excerpts(
+                            &claude_dir,
+                            &repo_path,
+                            &terms,
+                            section_limit("excerpts"),
+                        ),
                     )
                 }),
                 scope.spawn(|| {
                     (
                         "activity",
-                        section_activity(&claude_dir, &repo_path, &terms, SECTION_LIMIT),
+                        section_activity(
+                            &claude_dir,
+                            &repo_path,
+                            &terms,
+                            section_limit("activity"),
+                        ),
                     )
                 }),
             ];
@@ -237,9 +262,6 @@ fn build_data_json(
     Value::Object(obj)
 }
 
-/// The BPE table is expensive to build, and budget enforcement calls
-/// `count_tokens` once per row dropped -- build it once per process.
-static BPE: std::sync::OnceLock<Option<tiktoken_rs::CoreBPE>> = std::sync::OnceLock::new();
 const CLI_CTA_TOKEN_RESERVE: usize = 32;
 
 /// Count tokens the same way `--format json` will render the bundle
@@ -247,10 +269,7 @@ const CLI_CTA_TOKEN_RESERVE: usize = 32;
 /// built-in `--token-count`/`--token-limit` flags.
 fn count_tokens(value: &Value) -> usize {
     let text = serde_json::to_string_pretty(value).unwrap_or_default();
-    match BPE.get_or_init(|| tiktoken_rs::cl100k_base().ok()) {
-        Some(bpe) => bpe.encode_with_special_tokens(&text).len(),
-        None => text.split_whitespace().count(),
-    }
+    vcsql::sql::functions::count_tokens(&text)
 }
 
 fn count_rendered_tokens(value: &Value) -> usize {
@@ -391,9 +410,7 @@ fn json_to_sql(value: &Value) -> SqlValue {
 fn resolve_claude_dir(options: &Value) -> PathBuf {
     match options.get("data_dir").and_then(|v| v.as_str()) {
         Some(d) => PathBuf::from(d),
-        None => dirs::home_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join(".claude"),
+        None => crate::config::current().claude_data_dir.value.clone(),
     }
 }
 
@@ -401,7 +418,8 @@ fn resolve_claude_dir(options: &Value) -> PathBuf {
 // Sections
 // ---------------------------------------------------------------------------
 
-/// 1. prior_work -- reuse recall's ranking (sessions, commits, prompts).
+/// 1. prior_work -- reuse recall's ranking (sessions, Codex threads,
+///    commits, prompts). Codex and prompt rows also carry their `bm25`.
 fn section_prior_work(
     claude_dir: &Path,
     repo_path: &Path,
@@ -421,49 +439,34 @@ fn section_prior_work(
     {
         return SectionResult::err(format!("failed to load claude tables: {e}"));
     }
+    if let Err(e) = engine.load_fts_tables(&["history_fts", "codex_messages_fts"]) {
+        return SectionResult::err(format!("failed to build full-text indexes: {e}"));
+    }
     if let Err(e) = engine.load_git_tables(&["commits"]) {
         return SectionResult::err(format!("failed to load git tables: {e}"));
     }
 
+    let params = term_params(terms, limit);
     let mut rows = Vec::new();
 
     let sessions_sql = format!(
         "SELECT 'session' AS kind, title AS text, substr(last_timestamp, 1, 10) AS date, \
                 {score} AS score \
-         FROM sessions WHERE {matches} ORDER BY score DESC, last_timestamp DESC LIMIT {limit}",
+         FROM sessions WHERE {matches} ORDER BY score DESC, last_timestamp DESC LIMIT :limit",
         score = score_expr("title", terms),
         matches = match_expr("title", terms),
     );
-    match engine.query(&sessions_sql) {
+    match engine.query_with_params(&sessions_sql, &params) {
         Ok(r) => rows.extend(r),
         Err(e) => return SectionResult::err(format!("sessions query failed: {e}")),
     }
 
-    let codex_score = score_expr("message.text", terms);
-    let codex_sql = format!(
-        "WITH ranked AS (
-           SELECT 'codex_thread' AS kind,
-                  substr(replace(message.text, char(10), ' '), 1, 200) AS text,
-                  substr(COALESCE(message.timestamp, thread.last_event_at), 1, 10) AS date,
-                  {score} AS score,
-                  ROW_NUMBER() OVER (
-                    PARTITION BY message.thread_id
-                    ORDER BY {score} DESC, message.timestamp DESC
-                  ) AS rank
-           FROM codex_messages AS message
-           JOIN codex_threads AS thread
-             ON thread.thread_id = message.thread_id
-           WHERE message.is_canonical = 1 AND {matches}
-         )
-         SELECT kind, text, date, score
-         FROM ranked
-         WHERE rank = 1
-         ORDER BY score DESC, date DESC
-         LIMIT {limit}",
-        score = codex_score,
-        matches = match_expr("message.text", terms),
+    let codex_sql = codex_hits_sql(
+        "'codex_thread' AS kind, substr(replace(hit.text, char(10), ' '), 1, 200) AS text",
+        "kind, text, date, score, bm25",
+        terms,
     );
-    match engine.query(&codex_sql) {
+    match engine.query_with_params(&codex_sql, &params) {
         Ok(mut codex_rows) => {
             for row in &mut codex_rows {
                 redact_row_field(row, "text");
@@ -477,23 +480,21 @@ fn section_prior_work(
     let commits_sql = format!(
         "SELECT 'commit' AS kind, substr(summary, 1, 90) AS text, substr(authored_at, 1, 10) AS date, \
                 {score} AS score \
-         FROM commits WHERE {matches} ORDER BY score DESC, authored_at DESC LIMIT {limit}",
+         FROM commits WHERE {matches} ORDER BY score DESC, authored_at DESC LIMIT :limit",
         score = score_expr(commit_expr, terms),
         matches = match_expr(commit_expr, terms),
     );
-    match engine.query(&commits_sql) {
+    match engine.query_with_params(&commits_sql, &params) {
         Ok(r) => rows.extend(r),
         Err(e) => return SectionResult::err(format!("commits query failed: {e}")),
     }
 
-    let prompts_sql = format!(
-        "SELECT 'prompt' AS kind, substr(replace(display, char(10), ' '), 1, 110) AS text, \
-                date(timestamp / 1000, 'unixepoch') AS date, {score} AS score \
-         FROM history WHERE {matches} ORDER BY score DESC, timestamp DESC LIMIT {limit}",
-        score = score_expr("display", terms),
-        matches = match_expr("display", terms),
+    let prompts_sql = prompt_hits_sql(
+        "'prompt' AS kind, substr(replace(history.display, char(10), ' '), 1, 110) AS text, \
+         date(history.timestamp / 1000, 'unixepoch') AS date",
+        terms,
     );
-    match engine.query(&prompts_sql) {
+    match engine.query_with_params(&prompts_sql, &params) {
         Ok(r) => rows.extend(r),
         Err(e) => return SectionResult::err(format!("prompts query failed: {e}")),
     }
@@ -585,8 +586,9 @@ fn section_repo_state(repo_path: &Path) -> SectionResult {
     SectionResult::ok(rows)
 }
 
-/// 3. code_search -- term hits from `source_lines`, ranked by match count
-///    per file.
+/// 3. code_search -- term hits from `source_lines_fts`, ranked per file by
+///    summed `bm25()`. The index is trigram-tokenized, so terms match as
+///    substrings of identifiers; terms under three characters never match.
 fn section_code_search(
     claude_dir: &Path,
     repo_path: &Path,
@@ -601,17 +603,19 @@ fn section_code_search(
         Ok(e) => e,
         Err(e) => return SectionResult::err(format!("engine init failed: {e}")),
     };
-    if let Err(e) = engine.load_code_tables(&["source_lines"]) {
+    if let Err(e) = engine.load_code_tables(&["source_lines_fts"]) {
         return SectionResult::err(format!("failed to load source_lines: {e}"));
     }
 
+    let params = term_params(terms, limit);
     let sql = format!(
-        "SELECT file_path AS path, COUNT(*) AS hits FROM source_lines \
-         WHERE {matches} GROUP BY file_path ORDER BY hits DESC LIMIT {limit}",
-        matches = match_expr("content", terms),
+        "WITH {LINE_HITS} \
+         SELECT line.file_path AS path, COUNT(*) AS hits, SUM(hit.bm25) AS bm25 \
+         FROM line_hits AS hit JOIN source_lines AS line ON line.rowid = hit.rowid \
+         GROUP BY line.file_path ORDER BY bm25, hits DESC LIMIT :limit"
     );
 
-    match engine.query(&sql) {
+    match engine.query_with_params(&sql, &params) {
         Ok(rows) => SectionResult::ok(rows),
         Err(e) => SectionResult::err(format!("code_search query failed: {e}")),
     }
@@ -636,22 +640,35 @@ fn section_symbols(
         return SectionResult::err(format!("failed to load symbols: {e}"));
     }
 
+    let params = term_params(terms, limit);
     let sql = format!(
         "SELECT file_path, name, kind, line_start AS line, {score} AS score \
-         FROM symbols WHERE {matches} ORDER BY score DESC, line_start LIMIT {limit}",
+         FROM symbols WHERE {matches} ORDER BY score DESC, line_start LIMIT :limit",
         score = score_expr("name", terms),
         matches = match_expr("name", terms),
     );
 
-    match engine.query(&sql) {
+    match engine.query_with_params(&sql, &params) {
         Ok(rows) => SectionResult::ok(rows),
         Err(e) => SectionResult::err(format!("symbols query failed: {e}")),
     }
 }
 
-/// 5. excerpts -- top-5 files by match count, with matched line ranges
-///    (+/-3 lines) from `source_lines`.
-fn section_excerpts(claude_dir: &Path, repo_path: &Path, terms: &[String]) -> SectionResult {
+/// `line_hits(rowid, bm25)` -- `source_lines` rows matching `:fts`.
+/// Materialized so `bm25()` is evaluated against the index cursor before
+/// any grouping.
+const LINE_HITS: &str = "line_hits AS MATERIALIZED ( \
+     SELECT rowid, bm25(source_lines_fts) AS bm25 \
+     FROM source_lines_fts WHERE source_lines_fts MATCH :fts)";
+
+/// 5. excerpts -- top-5 files by `bm25()` relevance, with the first matched
+///    line's range (+/-3 lines) from `source_lines`.
+fn section_excerpts(
+    claude_dir: &Path,
+    repo_path: &Path,
+    terms: &[String],
+    limit: i64,
+) -> SectionResult {
     if terms.is_empty() {
         return SectionResult::ok(Vec::new());
     }
@@ -660,16 +677,18 @@ fn section_excerpts(claude_dir: &Path, repo_path: &Path, terms: &[String]) -> Se
         Ok(e) => e,
         Err(e) => return SectionResult::err(format!("engine init failed: {e}")),
     };
-    if let Err(e) = engine.load_code_tables(&["source_lines"]) {
+    if let Err(e) = engine.load_code_tables(&["source_lines_fts"]) {
         return SectionResult::err(format!("failed to load source_lines: {e}"));
     }
 
-    let matches = match_expr("content", terms);
+    let params = term_params(terms, limit);
     let top_files_sql = format!(
-        "SELECT file_path FROM source_lines WHERE {matches} \
-         GROUP BY file_path ORDER BY COUNT(*) DESC LIMIT 5"
+        "WITH {LINE_HITS} \
+         SELECT line.file_path FROM line_hits AS hit \
+         JOIN source_lines AS line ON line.rowid = hit.rowid \
+         GROUP BY line.file_path ORDER BY SUM(hit.bm25) LIMIT :limit"
     );
-    let top_files = match engine.query(&top_files_sql) {
+    let top_files = match engine.query_with_params(&top_files_sql, &params) {
         Ok(r) => r,
         Err(e) => return SectionResult::err(format!("excerpts top-files query failed: {e}")),
     };
@@ -679,13 +698,14 @@ fn section_excerpts(claude_dir: &Path, repo_path: &Path, terms: &[String]) -> Se
         let Some(path) = file_row.get("file_path").and_then(|v| v.as_str()) else {
             continue;
         };
-        let escaped = path.replace('\'', "''");
-
         let first_match_sql = format!(
-            "SELECT line_number FROM source_lines \
-             WHERE file_path = '{escaped}' AND {matches} ORDER BY line_number LIMIT 1"
+            "WITH {LINE_HITS} \
+             SELECT line.line_number FROM line_hits AS hit \
+             JOIN source_lines AS line ON line.rowid = hit.rowid \
+             WHERE line.file_path = :path ORDER BY line.line_number LIMIT 1"
         );
-        let Ok(first_match) = engine.query(&first_match_sql) else {
+        let file_params = params.clone().with("path", path);
+        let Ok(first_match) = engine.query_with_params(&first_match_sql, &file_params) else {
             continue;
         };
         let Some(line_no) = first_match
@@ -698,12 +718,14 @@ fn section_excerpts(claude_dir: &Path, repo_path: &Path, terms: &[String]) -> Se
 
         let lo = (line_no - 3).max(1);
         let hi = line_no + 3;
-        let ctx_sql = format!(
-            "SELECT line_number, content FROM source_lines \
-             WHERE file_path = '{escaped}' AND line_number BETWEEN {lo} AND {hi} \
-             ORDER BY line_number"
-        );
-        let Ok(ctx_rows) = engine.query(&ctx_sql) else {
+        let ctx_sql = "SELECT line_number, content FROM source_lines \
+                       WHERE file_path = :path AND line_number BETWEEN :lo AND :hi \
+                       ORDER BY line_number";
+        let ctx_params = QueryParams::None
+            .with("path", path)
+            .with("lo", lo)
+            .with("hi", hi);
+        let Ok(ctx_rows) = engine.query_with_params(ctx_sql, &ctx_params) else {
             continue;
         };
 
@@ -749,14 +771,15 @@ fn section_activity(
         return SectionResult::err(format!("failed to load command events: {e}"));
     }
 
+    let params = term_params(terms, limit);
     let mut rows = Vec::new();
 
     let todos_sql = format!(
         "SELECT 'todo' AS kind, content AS text, status \
-         FROM todos WHERE status != 'completed' AND {matches} LIMIT {limit}",
+         FROM todos WHERE status != 'completed' AND {matches} LIMIT :limit",
         matches = match_expr("content", terms),
     );
-    match engine.query(&todos_sql) {
+    match engine.query_with_params(&todos_sql, &params) {
         Ok(r) => rows.extend(r),
         Err(e) => return SectionResult::err(format!("todos query failed: {e}")),
     }
@@ -765,10 +788,10 @@ fn section_activity(
         "SELECT 'tool' AS kind, tool_name AS text, target, COUNT(*) AS count \
          FROM tool_calls \
          WHERE tool_name != 'Bash' AND {matches} \
-         GROUP BY tool_name, target ORDER BY count DESC LIMIT {limit}",
+         GROUP BY tool_name, target ORDER BY count DESC LIMIT :limit",
         matches = match_expr("target", terms),
     );
-    match engine.query(&tools_sql) {
+    match engine.query_with_params(&tools_sql, &params) {
         Ok(r) => rows.extend(r),
         Err(e) => return SectionResult::err(format!("tool_calls query failed: {e}")),
     }
@@ -779,10 +802,10 @@ fn section_activity(
                 substr(arguments_json, 1, 240) AS target, COUNT(*) AS count \
          FROM codex_tool_calls \
          WHERE tool_name NOT IN ('exec_command', 'shell') AND {matches} \
-         GROUP BY tool_name, arguments_json ORDER BY count DESC LIMIT {limit}",
+         GROUP BY tool_name, arguments_json ORDER BY count DESC LIMIT :limit",
         matches = match_expr(codex_tool_expr, terms),
     );
-    match engine.query(&codex_sql) {
+    match engine.query_with_params(&codex_sql, &params) {
         Ok(mut codex_rows) => {
             for row in &mut codex_rows {
                 redact_row_field(row, "target");
@@ -799,11 +822,11 @@ fn section_activity(
          FROM command_events \
          WHERE channel = 'agent_tool' AND {matches} \
          ORDER BY score DESC, coalesce(timestamp, '') DESC, source_order DESC \
-         LIMIT {limit}",
+         LIMIT :limit",
         score = score_expr(command_expr, terms),
         matches = match_expr(command_expr, terms),
     );
-    match engine.query(&agent_sql) {
+    match engine.query_with_params(&agent_sql, &params) {
         Ok(r) => rows.extend(r),
         Err(e) => return SectionResult::err(format!("agent command query failed: {e}")),
     }
@@ -814,11 +837,11 @@ fn section_activity(
          FROM command_events \
          WHERE channel = 'shell' AND {matches} \
          ORDER BY score DESC, coalesce(timestamp, '') DESC, source_order DESC \
-         LIMIT {limit}",
+         LIMIT :limit",
         score = score_expr(command_expr, terms),
         matches = match_expr(command_expr, terms),
     );
-    match engine.query(&shell_sql) {
+    match engine.query_with_params(&shell_sql, &params) {
         Ok(r) => rows.extend(r),
         Err(e) => return SectionResult::err(format!("shell command query failed: {e}")),
     }
@@ -829,7 +852,7 @@ fn section_activity(
 fn redact_row_field(row: &mut Value, field: &str) {
     if let Some(value) = row.get_mut(field) {
         if let Some(text) = value.as_str() {
-            *value = Value::String(crate::redaction::redact_sensitive_text(text));
+            *value = Value::String(crate::config::current().redact(text));
         }
     }
 }
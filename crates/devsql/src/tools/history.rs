@@ -5,6 +5,7 @@ use incurs::output::CommandResult;
 use serde_json::{json, Value};
 
 use super::{engine_from_options, legacy_context, read_only_mcp, typed_from_result};
+use crate::engine::{like_contains, QueryParams};
 
 // ---------------------------------------------------------------------------
 // Schema
@@ -82,17 +83,18 @@ impl CommandHandler for HistoryHandler {
             };
         }
 
-        let sql = format!(
-            "SELECT c.short_id, c.author_name, c.authored_at, c.summary, \
-                    df.status, df.insertions, df.deletions \
-             FROM diff_files df \
-             JOIN commits c ON df.commit_id = c.id \
-             WHERE df.path LIKE '%{file}%' \
-             ORDER BY c.authored_at DESC \
-             LIMIT {limit}"
-        );
-
-        match engine.query(&sql) {
+        let sql = "SELECT c.short_id, c.author_name, c.authored_at, c.summary, \
+                          df.status, df.insertions, df.deletions \
+                   FROM diff_files df \
+                   JOIN commits c ON df.commit_id = c.id \
+                   WHERE df.path LIKE :pattern ESCAPE '\\' \
+                   ORDER BY c.authored_at DESC \
+                   LIMIT :limit";
+        let params = QueryParams::None
+            .with("pattern", like_contains(&file))
+            .with("limit", limit);
+
+        match engine.query_with_params(sql, &params) {
             Ok(commits) => CommandResult::Ok {
                 data: json!({
                     "file_pattern": file,
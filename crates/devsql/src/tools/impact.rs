@@ -1,10 +1,24 @@
-//! `devsql impact` -- analyze a file's exported symbols and potential dependents.
+//! `devsql impact` -- analyze a file's exported symbols and the code that
+//! depends on them.
+//!
+//! Dependents come from `symbol_refs`: every definition outside the file
+//! that calls, names the type of, or imports an exported symbol, directly or
+//! through code in the file, then the definitions that reference those, up
+//! to `--depth` levels. `symbol_refs`
+//! is only populated with the `tree-sitter-ast` feature; without it
+//! `dependents` is empty and `dependents_available` is false.
+//!
+//! `potential_dependents` is reported in every build: the definitions whose
+//! signature mentions an exported name, at most 20 in all, with
+//! `potential_dependents_truncated` set when more were left out.
 
 use incurs::command::{CommandContext, CommandDef, CommandHandler, Example, TypedContext};
 use incurs::output::CommandResult;
 use serde_json::{json, Value};
+use std::collections::HashSet;
 
 use super::{engine_from_options, legacy_context, read_only_mcp, typed_from_result};
+use crate::engine::{like_contains, QueryParams};
 
 // ---------------------------------------------------------------------------
 // Schema
@@ -26,8 +40,8 @@ struct ImpactOptions {
     /// Claude data directory (defaults to ~/.claude)
     #[incurs(alias = "d")]
     data_dir: Option<String>,
-    /// Depth of dependency analysis (reserved for future use)
-    #[incurs(default = 1)]
+    /// Levels of transitive dependents to follow (1 = direct references only)
+    #[incurs(default = 3)]
     depth: i64,
 }
 
@@ -35,7 +49,10 @@ struct ImpactOptions {
 struct ImpactOutput {
     file_pattern: String,
     exported_symbols: Vec<Value>,
+    dependents: Vec<Value>,
+    dependents_available: bool,
     potential_dependents: Vec<Value>,
+    potential_dependents_truncated: bool,
 }
 
 // ---------------------------------------------------------------------------
@@ -65,8 +82,14 @@ impl CommandHandler for ImpactHandler {
             Err(e) => return e,
         };
 
-        // Load symbols and imports tables
-        if let Err(e) = engine.load_code_tables(&["symbols", "imports"]) {
+        let depth = ctx
+            .options
+            .get("depth")
+            .and_then(|v| v.as_i64())
+            .unwrap_or(3)
+            .max(1);
+
+        if let Err(e) = engine.load_code_tables(&["symbols", "symbol_refs"]) {
             return CommandResult::Error {
                 code: "LOAD_ERROR".into(),
                 message: format!("Failed to load code tables: {e}"),
@@ -77,15 +100,15 @@ impl CommandHandler for ImpactHandler {
         }
 
         // Find exported symbols from the target file
-        let exports_sql = format!(
-            "SELECT name, kind, line_start, signature, visibility \
-             FROM symbols \
-             WHERE file_path LIKE '%{file}%' \
-               AND (visibility = 'pub' OR visibility = 'public' OR visibility IS NULL) \
-             ORDER BY line_start"
-        );
-
-        let exported_symbols = match engine.query(&exports_sql) {
+        let exports_sql = "SELECT name, kind, line_start, signature, visibility \
+                           FROM symbols \
+                           WHERE file_path LIKE :pattern ESCAPE '\\' \
+                             AND (visibility IS NULL OR visibility IN ('', 'pub', 'public', 'export') \
+                                  OR visibility LIKE 'pub(%') \
+                           ORDER BY line_start";
+        let params = QueryParams::None.with("pattern", like_contains(&file));
+
+        let exported_symbols = match engine.query_with_params(exports_sql, &params) {
             Ok(rows) => rows,
             Err(e) => {
                 return CommandResult::Error {
@@ -98,58 +121,190 @@ impl CommandHandler for ImpactHandler {
             }
         };
 
-        // Collect exported symbol names for dependency search
         let symbol_names: Vec<String> = exported_symbols
             .iter()
             .filter_map(|s| s.get("name").and_then(|v| v.as_str()).map(String::from))
             .collect();
 
-        // Find potential dependents via imports table
-        let mut potential_dependents = Vec::new();
-        if !symbol_names.is_empty() {
-            // Build a query to find files that import from this module
-            let imports_sql = format!(
-                "SELECT DISTINCT file_path, source \
-                 FROM imports \
-                 WHERE source LIKE '%{file}%'"
-            );
-
-            if let Ok(import_rows) = engine.query(&imports_sql) {
-                for row in import_rows {
-                    potential_dependents.push(row);
+        let (potential_dependents, potential_dependents_truncated) =
+            match find_potential_dependents(engine.conn(), &file, &symbol_names) {
+                Ok(found) => found,
+                Err(e) => {
+                    return CommandResult::Error {
+                        code: "QUERY_ERROR".into(),
+                        message: format!("Potential dependents query failed: {e}"),
+                        retryable: false,
+                        exit_code: Some(1),
+                        cta: None,
+                    };
                 }
-            }
+            };
 
-            // Also search for files that reference exported symbol names
-            // via the symbols table (e.g., types used as parameters/return types)
-            for name in &symbol_names {
-                let refs_sql = format!(
-                    "SELECT DISTINCT file_path, name, kind \
-                     FROM symbols \
-                     WHERE file_path NOT LIKE '%{file}%' \
-                       AND (parameters LIKE '%{name}%' OR return_type LIKE '%{name}%') \
-                     LIMIT 20"
-                );
-
-                if let Ok(ref_rows) = engine.query(&refs_sql) {
-                    for row in ref_rows {
-                        potential_dependents.push(row);
-                    }
-                }
+        let dependents_available = cfg!(feature = "tree-sitter-ast");
+        let dependents = match find_dependents(engine.conn(), &file, symbol_names, depth) {
+            Ok(rows) => rows,
+            Err(e) => {
+                return CommandResult::Error {
+                    code: "QUERY_ERROR".into(),
+                    message: format!("Dependents query failed: {e}"),
+                    retryable: false,
+                    exit_code: Some(1),
+                    cta: None,
+                };
             }
-        }
+        };
 
         CommandResult::Ok {
             data: json!({
                 "file_pattern": file,
                 "exported_symbols": Value::Array(exported_symbols),
+                "dependents": Value::Array(dependents),
+                "dependents_available": dependents_available,
                 "potential_dependents": Value::Array(potential_dependents),
+                "potential_dependents_truncated": potential_dependents_truncated,
             }),
             cta: None,
         }
     }
 }
 
+/// Breadth-first walk over `symbol_refs`: level 1 holds the definitions (or
+/// files, for top-level references) that reference `names`; each further
+/// level holds the definitions referencing the previous one. Definitions in
+/// `file` are walked through but not reported, so code outside it that
+/// reaches an export through a private helper still counts.
+fn find_dependents(
+    conn: &rusqlite::Connection,
+    file: &str,
+    names: Vec<String>,
+    max_depth: i64,
+) -> rusqlite::Result<Vec<Value>> {
+    let mut stmt = conn.prepare(
+        "SELECT file_path, caller, caller_line, MIN(line_number), kind, repo, \
+                file_path LIKE ?2 ESCAPE '\\' \
+         FROM symbol_refs \
+         WHERE name = ?1 \
+         GROUP BY repo, file_path, caller, caller_line, kind \
+         ORDER BY repo, file_path, caller_line",
+    )?;
+
+    let mut visited_names: HashSet<String> = names.iter().cloned().collect();
+    let mut seen = HashSet::new();
+    let mut frontier = names;
+    let file_pattern = like_contains(file);
+    let mut dependents = Vec::new();
+
+    for depth in 1..=max_depth {
+        let mut next = Vec::new();
+        for name in &frontier {
+            let rows = stmt.query_map(rusqlite::params![name, file_pattern], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<i64>>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                    row.get::<_, bool>(6)?,
+                ))
+            })?;
+            for row in rows {
+                let (file_path, caller, caller_line, line, kind, repo, in_file) = row?;
+                if !seen.insert((repo.clone(), file_path.clone(), caller.clone(), caller_line)) {
+                    continue;
+                }
+                if let Some(caller) = &caller {
+                    if visited_names.insert(caller.clone()) {
+                        next.push(caller.clone());
+                    }
+                }
+                if in_file {
+                    continue;
+                }
+                dependents.push(json!({
+                    "file_path": file_path,
+                    "symbol": caller,
+                    "line": caller_line.unwrap_or(line),
+                    "references": name,
+                    "kind": kind,
+                    "depth": depth,
+                    "repo": repo,
+                }));
+            }
+        }
+        if next.is_empty() {
+            break;
+        }
+        frontier = next;
+    }
+
+    Ok(dependents)
+}
+
+/// Most `potential_dependents` reported, across every exported name.
+const POTENTIAL_DEPENDENTS_LIMIT: usize = 20;
+
+/// Definitions outside `file` whose signature, parameters, or return type
+/// mention one of `names`: a name-match heuristic that needs only the
+/// `symbols` table. At most [`POTENTIAL_DEPENDENTS_LIMIT`] are returned,
+/// with whether more were left out.
+fn find_potential_dependents(
+    conn: &rusqlite::Connection,
+    file: &str,
+    names: &[String],
+) -> rusqlite::Result<(Vec<Value>, bool)> {
+    // One row past the limit for each name is enough to tell whether it
+    // adds any the earlier names did not.
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT file_path, name, kind, line_start, repo \
+         FROM symbols \
+         WHERE file_path NOT LIKE ?1 ESCAPE '\\' \
+           AND (signature LIKE ?2 ESCAPE '\\' OR parameters LIKE ?2 ESCAPE '\\' \
+                OR return_type LIKE ?2 ESCAPE '\\') \
+         ORDER BY repo, file_path, line_start \
+         LIMIT ?3",
+    )?;
+    let file_pattern = like_contains(file);
+    let mut seen = HashSet::new();
+    let mut dependents = Vec::new();
+    for name in names {
+        let rows = stmt.query_map(
+            rusqlite::params![
+                file_pattern,
+                like_contains(name),
+                POTENTIAL_DEPENDENTS_LIMIT as i64 + 1
+            ],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                ))
+            },
+        )?;
+        for row in rows {
+            let (file_path, symbol, kind, line, repo) = row?;
+            if !seen.insert((repo.clone(), file_path.clone(), symbol.clone(), line)) {
+                continue;
+            }
+            if dependents.len() == POTENTIAL_DEPENDENTS_LIMIT {
+                return Ok((dependents, true));
+            }
+            dependents.push(json!({
+                "file_path": file_path,
+                "name": symbol,
+                "kind": kind,
+                "line": line,
+                "mentions": name,
+                "repo": repo,
+            }));
+        }
+    }
+    Ok((dependents, false))
+}
+
 // ---------------------------------------------------------------------------
 // Builder
 // ---------------------------------------------------------------------------
@@ -164,13 +319,19 @@ pub fn build() -> CommandDef {
             }
         },
     )
-    .description("Analyze a file's exported symbols and potential dependents")
+    .description("Analyze a file's exported symbols and their transitive dependents")
     .args::<ImpactArgs>()
     .options::<ImpactOptions>()
-    .examples(vec![Example {
-        command: "src/engine.rs --json".to_string(),
-        description: Some("Analyze impact of changes to engine.rs".to_string()),
-    }])
+    .examples(vec![
+        Example {
+            command: "src/engine.rs --json".to_string(),
+            description: Some("Analyze impact of changes to engine.rs".to_string()),
+        },
+        Example {
+            command: "src/engine.rs --depth 1 --json".to_string(),
+            description: Some("Only direct references to engine.rs exports".to_string()),
+        },
+    ])
     .mcp(read_only_mcp())
     .done()
 }
@@ -3,15 +3,22 @@
 //! Each submodule provides a `build()` function that returns a `CommandDef`
 //! for use with the incurs CLI framework.
 
+pub mod config;
 pub mod context;
+pub mod daemon;
 pub mod day;
 pub mod diff;
+pub mod export;
 pub mod gather;
 pub mod history;
 pub mod impact;
 pub mod recall;
+pub mod saved;
+pub mod schema;
 pub mod search;
 pub mod semantic_diff;
+pub mod serve;
+pub mod shell;
 pub mod work;
 
 use std::path::PathBuf;
@@ -128,26 +135,56 @@ pub fn engine_from_paths(
     repo_str: &str,
     data_dir: Option<&str>,
 ) -> Result<(UnifiedEngine, PathBuf), ToolError> {
-    let repo_path = if repo_str == "." {
-        std::env::current_dir().map_err(|e| ToolError {
-            code: "PATH_ERROR",
-            message: format!("Cannot determine current directory: {e}"),
-        })?
-    } else {
-        PathBuf::from(repo_str)
-    };
+    let (engine, mut repo_paths) = engine_from_repos(&[repo_str.to_string()], None, data_dir)?;
+    Ok((engine, repo_paths.remove(0)))
+}
+
+/// Create an engine over every repository named by `repos` (paths or globs)
+/// and the optional workspace file.
+///
+/// Returns `(engine, repo_paths)` on success; there is always at least one path.
+pub fn engine_from_repos(
+    repos: &[String],
+    workspace: Option<&str>,
+    data_dir: Option<&str>,
+) -> Result<(UnifiedEngine, Vec<PathBuf>), ToolError> {
+    let repo_paths = crate::repos::resolve_repo_paths(repos, workspace.map(std::path::Path::new))
+        .map_err(|e| ToolError {
+        code: "PATH_ERROR",
+        message: e.to_string(),
+    })?;
 
     let claude_dir = match data_dir {
         Some(d) => PathBuf::from(d),
-        None => dirs::home_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join(".claude"),
+        None => crate::config::current().claude_data_dir.value.clone(),
     };
 
-    let engine = UnifiedEngine::new(claude_dir, repo_path.clone()).map_err(|e| ToolError {
-        code: "ENGINE_ERROR",
-        message: format!("Failed to create engine: {e}"),
-    })?;
+    let mut engine =
+        UnifiedEngine::new(claude_dir, repo_paths[0].clone()).map_err(|e| ToolError {
+            code: "ENGINE_ERROR",
+            message: format!("Failed to create engine: {e}"),
+        })?;
+    engine.set_git_repos(repo_paths.clone());
 
-    Ok((engine, repo_path))
+    Ok((engine, repo_paths))
+}
+
+/// Load each `--attach name=path` file into the engine, returning the
+/// tables they added.
+pub fn attach_files(
+    engine: &mut UnifiedEngine,
+    specs: &[String],
+) -> Result<Vec<vcsql::sql::AttachedTable>, ToolError> {
+    let mut tables = Vec::new();
+    for spec in specs {
+        let attachment: vcsql::sql::Attachment = spec.parse().map_err(|e| ToolError {
+            code: "INVALID_ATTACH",
+            message: format!("{e}"),
+        })?;
+        tables.extend(engine.attach(&attachment).map_err(|e| ToolError {
+            code: "ATTACH_ERROR",
+            message: format!("Failed to attach {}: {e}", attachment.name),
+        })?);
+    }
+    Ok(tables)
 }
@@ -1,11 +1,13 @@
-//! `devsql recall` -- load prior work relevant to search terms, ranked by
-//! how many terms each row matches (then recency).
+//! `devsql recall` -- load prior work relevant to search terms. Codex
+//! messages and prompts are ranked by FTS5 `bm25()` relevance; the other
+//! sources by how many terms each row matches (then recency).
 
 use incurs::command::{CommandContext, CommandDef, CommandHandler, Example, TypedContext};
 use incurs::output::CommandResult;
 use serde_json::{json, Value};
 
 use super::{engine_from_options, legacy_context, read_only_mcp, typed_from_result};
+use crate::engine::{fts_any, like_contains, QueryParams};
 
 // ---------------------------------------------------------------------------
 // Schema
@@ -49,23 +51,36 @@ struct RecallOutput {
 
 struct RecallHandler;
 
-/// Build `((E LIKE '%t1%') + (E LIKE '%t2%') + ...)` -- SQLite booleans sum to
-/// a match count, so this scores a row by how many terms it hits.
+/// Build `((E LIKE :t0) + (E LIKE :t1) + ...)` -- SQLite booleans sum to a
+/// match count, so this scores a row by how many terms it hits. Bind the
+/// terms with [`term_params`].
 pub(crate) fn score_expr(expr: &str, terms: &[String]) -> String {
-    let parts: Vec<String> = terms
-        .iter()
-        .map(|t| format!("({expr} LIKE '%{t}%')"))
-        .collect();
-    format!("({})", parts.join(" + "))
+    format!("({})", term_predicates(expr, terms).join(" + "))
 }
 
-/// Build `((E LIKE '%t1%') OR (E LIKE '%t2%') OR ...)` -- the match predicate.
+/// Build `((E LIKE :t0) OR (E LIKE :t1) OR ...)` -- the match predicate.
 pub(crate) fn match_expr(expr: &str, terms: &[String]) -> String {
-    let parts: Vec<String> = terms
+    format!("({})", term_predicates(expr, terms).join(" OR "))
+}
+
+fn term_predicates(expr: &str, terms: &[String]) -> Vec<String> {
+    (0..terms.len())
+        .map(|i| format!("({expr} LIKE :t{i} ESCAPE '\\')"))
+        .collect()
+}
+
+/// Named parameters `:t0`, `:t1`, ... matching each term literally as a
+/// substring, `:fts` matching any term through a full-text index, plus
+/// `:limit`.
+pub(crate) fn term_params(terms: &[String], limit: i64) -> QueryParams {
+    terms
         .iter()
-        .map(|t| format!("({expr} LIKE '%{t}%')"))
-        .collect();
-    format!("({})", parts.join(" OR "))
+        .enumerate()
+        .fold(QueryParams::None, |params, (i, term)| {
+            params.with(&format!("t{i}"), like_contains(term))
+        })
+        .with("fts", fts_any(terms))
+        .with("limit", limit)
 }
 
 #[async_trait::async_trait]
@@ -90,11 +105,11 @@ impl CommandHandler for RecallHandler {
             .and_then(|v| v.as_i64())
             .unwrap_or(8);
 
-        // Parse terms: split on whitespace, drop tokens < 2 chars, escape quotes.
+        // Parse terms: split on whitespace, drop tokens < 2 chars.
         let terms: Vec<String> = query
             .split_whitespace()
             .filter(|t| t.chars().count() >= 2)
-            .map(|t| t.replace('\'', "''"))
+            .map(String::from)
             .collect();
 
         // No usable terms -> empty result, not an error.
@@ -129,6 +144,15 @@ impl CommandHandler for RecallHandler {
                 cta: None,
             };
         }
+        if let Err(e) = engine.load_fts_tables(&["history_fts", "codex_messages_fts"]) {
+            return CommandResult::Error {
+                code: "LOAD_ERROR".into(),
+                message: format!("Failed to build full-text indexes: {e}"),
+                retryable: false,
+                exit_code: Some(1),
+                cta: None,
+            };
+        }
         if let Err(e) = engine.load_git_tables(&["commits"]) {
             return CommandResult::Error {
                 code: "LOAD_ERROR".into(),
@@ -148,6 +172,8 @@ impl CommandHandler for RecallHandler {
             };
         }
 
+        let params = term_params(&terms, limit);
+
         // sessions
         let sessions_sql = format!(
             "SELECT title, substr(last_timestamp, 1, 10) AS date, project, \
@@ -155,50 +181,29 @@ impl CommandHandler for RecallHandler {
              FROM sessions \
              WHERE {matches} \
              ORDER BY score DESC, last_timestamp DESC \
-             LIMIT {limit}",
+             LIMIT :limit",
             score = score_expr("title", &terms),
             matches = match_expr("title", &terms),
         );
-        let sessions = match engine.query(&sessions_sql) {
+        let sessions = match engine.query_with_params(&sessions_sql, &params) {
             Ok(rows) => rows,
             Err(e) => return query_error("Sessions", e),
         };
 
-        let codex_score = score_expr("message.text", &terms);
-        let codex_sql = format!(
-            "WITH ranked AS (
-               SELECT message.thread_id,
-                      substr(COALESCE(message.timestamp, thread.last_event_at), 1, 10) AS date,
-                      thread.cwd,
-                      message.role,
-                      substr(replace(message.text, char(10), ' '), 1, 200) AS excerpt,
-                      thread.state,
-                      {score} AS score,
-                      ROW_NUMBER() OVER (
-                        PARTITION BY message.thread_id
-                        ORDER BY {score} DESC, message.timestamp DESC
-                      ) AS rank
-               FROM codex_messages AS message
-               JOIN codex_threads AS thread
-                 ON thread.thread_id = message.thread_id
-               WHERE message.is_canonical = 1 AND {matches}
-             )
-             SELECT thread_id, date, cwd, role, excerpt, state, score
-             FROM ranked
-             WHERE rank = 1
-             ORDER BY score DESC, date DESC
-             LIMIT {limit}",
-            score = codex_score,
-            matches = match_expr("message.text", &terms),
+        let codex_sql = codex_hits_sql(
+            "thread.cwd, hit.role, \
+             substr(replace(hit.text, char(10), ' '), 1, 200) AS excerpt, thread.state",
+            "thread_id, date, cwd, role, excerpt, state, score, bm25",
+            &terms,
         );
-        let mut codex_threads = match engine.query(&codex_sql) {
+        let mut codex_threads = match engine.query_with_params(&codex_sql, &params) {
             Ok(rows) => rows,
             Err(e) => return query_error("Codex threads", e),
         };
         for thread in &mut codex_threads {
             if let Some(excerpt) = thread.get_mut("excerpt") {
                 if let Some(text) = excerpt.as_str() {
-                    *excerpt = Value::String(crate::redaction::redact_sensitive_text(text));
+                    *excerpt = Value::String(crate::config::current().redact(text));
                 }
             }
         }
@@ -211,28 +216,22 @@ impl CommandHandler for RecallHandler {
              FROM commits \
              WHERE {matches} \
              ORDER BY score DESC, authored_at DESC \
-             LIMIT {limit}",
+             LIMIT :limit",
             score = score_expr(commit_expr, &terms),
             matches = match_expr(commit_expr, &terms),
         );
-        let commits = match engine.query(&commits_sql) {
+        let commits = match engine.query_with_params(&commits_sql, &params) {
             Ok(rows) => rows,
             Err(e) => return query_error("Commits", e),
         };
 
         // prompts (history)
-        let prompts_sql = format!(
-            "SELECT substr(replace(display, char(10), ' '), 1, 110) AS prompt, \
-                    date(timestamp / 1000, 'unixepoch') AS date, project, \
-                    {score} AS score \
-             FROM history \
-             WHERE {matches} \
-             ORDER BY score DESC, timestamp DESC \
-             LIMIT {limit}",
-            score = score_expr("display", &terms),
-            matches = match_expr("display", &terms),
+        let prompts_sql = prompt_hits_sql(
+            "substr(replace(history.display, char(10), ' '), 1, 110) AS prompt, \
+             date(history.timestamp / 1000, 'unixepoch') AS date, history.project",
+            &terms,
         );
-        let prompts = match engine.query(&prompts_sql) {
+        let prompts = match engine.query_with_params(&prompts_sql, &params) {
             Ok(rows) => rows,
             Err(e) => return query_error("Prompts", e),
         };
@@ -246,11 +245,11 @@ impl CommandHandler for RecallHandler {
              FROM command_events \
              WHERE {matches} \
              ORDER BY score DESC, coalesce(timestamp, '') DESC, source_order DESC \
-             LIMIT {limit}",
+             LIMIT :limit",
             score = score_expr(command_expr, &terms),
             matches = match_expr(command_expr, &terms),
         );
-        let commands = match engine.query(&commands_sql) {
+        let commands = match engine.query_with_params(&commands_sql, &params) {
             Ok(rows) => rows,
             Err(e) => return query_error("Command events", e),
         };
@@ -273,6 +272,59 @@ impl CommandHandler for RecallHandler {
     }
 }
 
+/// Rank `history` prompts matching `:fts` by `bm25(history_fts)`, best
+/// first. `columns` select from `history`; `score` and `bm25` are appended.
+pub(crate) fn prompt_hits_sql(columns: &str, terms: &[String]) -> String {
+    format!(
+        "SELECT {columns}, {score} AS score, bm25(history_fts) AS bm25 \
+         FROM history_fts \
+         JOIN history ON history.rowid = history_fts.rowid \
+         WHERE history_fts MATCH :fts \
+         ORDER BY bm25, history.timestamp DESC \
+         LIMIT :limit",
+        score = score_expr("history.display", terms),
+    )
+}
+
+/// Rank Codex threads by their best canonical message matching `:fts`,
+/// scored with `bm25(codex_messages_fts)`. `columns` select from the
+/// matched message `hit` and its `thread`, alongside `date`, `score`, and
+/// `bm25`; `output` lists the columns returned per thread.
+pub(crate) fn codex_hits_sql(columns: &str, output: &str, terms: &[String]) -> String {
+    // The index lives in the attached Codex cache and shares rowids with
+    // its `codex_messages` table (the temp view has no rowid to join on).
+    format!(
+        "WITH hits AS (
+           SELECT message.thread_id, message.timestamp, message.role, message.text,
+                  bm25(codex_messages_fts) AS bm25
+           FROM codex_messages_fts
+           JOIN codex_index.codex_messages AS message
+             ON message.rowid = codex_messages_fts.rowid
+           WHERE codex_messages_fts MATCH :fts AND message.is_canonical = 1
+         ),
+         ranked AS (
+           SELECT hit.thread_id,
+                  substr(COALESCE(hit.timestamp, thread.last_event_at), 1, 10) AS date,
+                  {columns},
+                  {score} AS score,
+                  hit.bm25,
+                  ROW_NUMBER() OVER (
+                    PARTITION BY hit.thread_id
+                    ORDER BY hit.bm25, hit.timestamp DESC
+                  ) AS rank
+           FROM hits AS hit
+           JOIN codex_threads AS thread
+             ON thread.thread_id = hit.thread_id
+         )
+         SELECT {output}
+         FROM ranked
+         WHERE rank = 1
+         ORDER BY bm25, date DESC
+         LIMIT :limit",
+        score = score_expr("hit.text", terms),
+    )
+}
+
 fn query_error(source: &str, e: crate::Error) -> CommandResult {
     CommandResult::Error {
         code: "QUERY_ERROR".into(),
@@ -299,7 +351,7 @@ pub fn build() -> CommandDef {
     )
     .description(
         "Load prior work (Claude sessions, Codex threads, commits, prompts, shell commands) relevant to search terms, \
-             ranked by term-match count then recency",
+             ranked by BM25 relevance (Codex threads, prompts) or term-match count, then recency",
     )
     .args::<RecallArgs>()
     .options::<RecallOptions>()
@@ -0,0 +1,89 @@
+//! `devsql queries` — list the saved-query library.
+
+use incurs::command::{CommandDef, Example, TypedContext, TypedResult};
+
+use super::read_only_mcp;
+use crate::saved::{self, SavedParam, SavedQuery};
+
+#[derive(incurs::Args, serde::Deserialize, serde::Serialize)]
+#[allow(dead_code)]
+struct QueriesArgs {}
+
+#[derive(incurs::Options, serde::Deserialize, serde::Serialize)]
+#[allow(dead_code)]
+struct QueriesOptions {}
+
+#[derive(schemars::JsonSchema, serde::Deserialize, serde::Serialize)]
+struct QuerySummary {
+    name: String,
+    description: String,
+    /// Parameters as `name` or `name = default`
+    params: Vec<String>,
+    path: String,
+}
+
+/// `name` or `name = default`.
+fn param_signature(param: &SavedParam) -> String {
+    match &param.default {
+        Some(default) => format!("{} = {default}", param.name),
+        None => param.name.clone(),
+    }
+}
+
+/// One-paragraph help for a saved query: its description, then each
+/// parameter with its default and description.
+pub fn describe(query: &SavedQuery) -> String {
+    let mut text = if query.description.is_empty() {
+        format!("Saved query from {}", query.path.display())
+    } else {
+        query.description.clone()
+    };
+    if !query.params.is_empty() {
+        let params: Vec<String> = query
+            .params
+            .iter()
+            .map(|param| match param.description.as_str() {
+                "" => param_signature(param),
+                description => format!("{} ({description})", param_signature(param)),
+            })
+            .collect();
+        text.push_str(&format!(". Params: {}", params.join(", ")));
+    }
+    text
+}
+
+async fn run_queries(
+    _ctx: TypedContext<QueriesArgs, QueriesOptions, ()>,
+) -> TypedResult<Vec<QuerySummary>> {
+    let library = match saved::library() {
+        Ok(library) => library,
+        Err(e) => return TypedResult::error("SAVED_QUERY_ERROR", e.to_string()),
+    };
+    let queries = library
+        .into_iter()
+        .map(|query| QuerySummary {
+            params: query.params.iter().map(param_signature).collect(),
+            path: query.path.display().to_string(),
+            name: query.name,
+            description: query.description,
+        })
+        .collect();
+    TypedResult::ok(queries)
+}
+
+pub fn build_list() -> CommandDef {
+    CommandDef::typed::<QueriesArgs, QueriesOptions, (), Vec<QuerySummary>, _, _>(
+        "queries",
+        run_queries,
+    )
+    .description(
+        "List saved queries from ~/.devsql/queries (run with `devsql run <name>`, or read one as \
+         a table from any query)",
+    )
+    .examples(vec![Example {
+        command: "--json".into(),
+        description: Some("Every saved query with its parameters".into()),
+    }])
+    .mcp(read_only_mcp())
+    .done()
+}
@@ -0,0 +1,235 @@
+//! `devsql tables` / `schema` / `examples` — discover what can be queried.
+
+use incurs::command::{CommandDef, Example, TypedContext, TypedResult};
+
+use super::read_only_mcp;
+use crate::schema::{self, EXAMPLES};
+
+fn check_category(category: Option<&str>) -> Result<(), String> {
+    let categories = schema::categories();
+    match category {
+        Some(category) if !categories.iter().any(|c| c == category) => Err(format!(
+            "Unknown category '{category}'. Categories: {}",
+            categories.join(", ")
+        )),
+        _ => Ok(()),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// tables
+// ---------------------------------------------------------------------------
+
+#[derive(incurs::Args, serde::Deserialize, serde::Serialize)]
+#[allow(dead_code)]
+struct TablesArgs {}
+
+#[derive(incurs::Options, serde::Deserialize, serde::Serialize)]
+#[allow(dead_code)]
+struct TablesOptions {
+    /// Only list tables in this category (CLAUDE, CODEX, GIT, CODE, SHELL, WORKLOG, FULL-TEXT)
+    #[incurs(alias = "c")]
+    category: Option<String>,
+}
+
+#[derive(schemars::JsonSchema, serde::Deserialize, serde::Serialize)]
+struct TableSummary {
+    name: String,
+    category: String,
+    description: String,
+    /// Files, directories, or databases the rows are read from
+    sources: Vec<String>,
+    /// Cargo feature the table needs, if any
+    feature: Option<String>,
+    /// Whether this build can load the table
+    available: bool,
+    columns: usize,
+}
+
+async fn run_tables(
+    ctx: TypedContext<TablesArgs, TablesOptions, ()>,
+) -> TypedResult<Vec<TableSummary>> {
+    let category = ctx.options.category.as_deref();
+    if let Err(message) = check_category(category) {
+        return TypedResult::error("UNKNOWN_CATEGORY", message);
+    }
+    let tables = match schema::tables() {
+        Ok(tables) => tables,
+        Err(e) => return TypedResult::error("SCHEMA_ERROR", e.to_string()),
+    };
+    let tables = tables
+        .into_iter()
+        .filter(|table| category.is_none_or(|category| table.category == category))
+        .map(|table| TableSummary {
+            columns: table.columns.len(),
+            name: table.name,
+            category: table.category,
+            description: table.description,
+            sources: table.sources,
+            feature: table.feature,
+            available: table.available,
+        })
+        .collect();
+    TypedResult::ok(tables)
+}
+
+pub fn build_tables() -> CommandDef {
+    CommandDef::typed::<TablesArgs, TablesOptions, (), Vec<TableSummary>, _, _>(
+        "tables", run_tables,
+    )
+    .description(
+        "List every queryable table with its category, description, sources, and required feature",
+    )
+    .options::<TablesOptions>()
+    .examples(vec![
+        Example {
+            command: "".into(),
+            description: Some("All Claude, Codex, Git, code, shell, and worklog tables".into()),
+        },
+        Example {
+            command: "--category CODEX --json".into(),
+            description: Some("Only the Codex tables".into()),
+        },
+    ])
+    .mcp(read_only_mcp())
+    .done()
+}
+
+// ---------------------------------------------------------------------------
+// schema
+// ---------------------------------------------------------------------------
+
+#[derive(incurs::Args, serde::Deserialize, serde::Serialize)]
+#[allow(dead_code)]
+struct SchemaArgs {
+    /// Table to describe (default: every table)
+    table: Option<String>,
+}
+
+#[derive(incurs::Options, serde::Deserialize, serde::Serialize)]
+#[allow(dead_code)]
+struct SchemaOptions {}
+
+#[derive(schemars::JsonSchema, serde::Deserialize, serde::Serialize)]
+struct ColumnRow {
+    table: String,
+    column: String,
+    sql_type: String,
+    nullable: bool,
+    description: String,
+}
+
+async fn run_schema(
+    ctx: TypedContext<SchemaArgs, SchemaOptions, ()>,
+) -> TypedResult<Vec<ColumnRow>> {
+    let tables = match schema::tables() {
+        Ok(tables) => tables,
+        Err(e) => return TypedResult::error("SCHEMA_ERROR", e.to_string()),
+    };
+    if let Some(name) = &ctx.args.table {
+        if !tables.iter().any(|table| &table.name == name) {
+            let names: Vec<&str> = tables.iter().map(|table| table.name.as_str()).collect();
+            return TypedResult::error(
+                "UNKNOWN_TABLE",
+                format!("Table '{name}' not found. Tables: {}", names.join(", ")),
+            );
+        }
+    }
+    let rows = tables
+        .into_iter()
+        .filter(|table| {
+            ctx.args
+                .table
+                .as_ref()
+                .is_none_or(|name| &table.name == name)
+        })
+        .flat_map(|table| {
+            table.columns.into_iter().map(move |column| ColumnRow {
+                table: table.name.clone(),
+                column: column.name,
+                sql_type: column.sql_type,
+                nullable: column.nullable,
+                description: column.description,
+            })
+        })
+        .collect();
+    TypedResult::ok(rows)
+}
+
+pub fn build_schema() -> CommandDef {
+    CommandDef::typed::<SchemaArgs, SchemaOptions, (), Vec<ColumnRow>, _, _>("schema", run_schema)
+        .description(
+            "Show the columns of a table (or of every table) with type, nullability, and description",
+        )
+        .args::<SchemaArgs>()
+        .examples(vec![
+            Example {
+                command: "transcripts".into(),
+                description: Some("Columns of the Claude transcripts table".into()),
+            },
+            Example {
+                command: "--json".into(),
+                description: Some("Every table's schema as JSON".into()),
+            },
+        ])
+        .mcp(read_only_mcp())
+        .done()
+}
+
+// ---------------------------------------------------------------------------
+// examples
+// ---------------------------------------------------------------------------
+
+#[derive(incurs::Args, serde::Deserialize, serde::Serialize)]
+#[allow(dead_code)]
+struct ExamplesArgs {}
+
+#[derive(incurs::Options, serde::Deserialize, serde::Serialize)]
+#[allow(dead_code)]
+struct ExamplesOptions {
+    /// Only show examples in this category (CLAUDE, CODEX, GIT, CODE, SHELL, WORKLOG, FULL-TEXT)
+    #[incurs(alias = "c")]
+    category: Option<String>,
+}
+
+#[derive(schemars::JsonSchema, serde::Deserialize, serde::Serialize)]
+struct ExampleQuery {
+    category: String,
+    description: String,
+    sql: String,
+}
+
+async fn run_examples(
+    ctx: TypedContext<ExamplesArgs, ExamplesOptions, ()>,
+) -> TypedResult<Vec<ExampleQuery>> {
+    let category = ctx.options.category.as_deref();
+    if let Err(message) = check_category(category) {
+        return TypedResult::error("UNKNOWN_CATEGORY", message);
+    }
+    let examples: Vec<ExampleQuery> = EXAMPLES
+        .iter()
+        .filter(|example| category.is_none_or(|category| example.category == category))
+        .map(|example| ExampleQuery {
+            category: example.category.to_string(),
+            description: example.description.to_string(),
+            // One line, so it pastes straight into `devsql "<sql>"`.
+            sql: example.sql.split_whitespace().collect::<Vec<_>>().join(" "),
+        })
+        .collect();
+    TypedResult::ok(examples)
+}
+
+pub fn build_examples() -> CommandDef {
+    CommandDef::typed::<ExamplesArgs, ExamplesOptions, (), Vec<ExampleQuery>, _, _>(
+        "examples",
+        run_examples,
+    )
+    .description("Show example queries for every source")
+    .options::<ExamplesOptions>()
+    .examples(vec![Example {
+        command: "--category GIT".into(),
+        description: Some("Example queries over the Git tables".into()),
+    }])
+    .mcp(read_only_mcp())
+    .done()
+}
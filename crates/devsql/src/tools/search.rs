@@ -5,6 +5,7 @@ use incurs::output::CommandResult;
 use serde_json::{json, Value};
 
 use super::{engine_from_options, legacy_context, read_only_mcp, typed_from_result};
+use crate::engine::{like_contains, QueryParams};
 
 // ---------------------------------------------------------------------------
 // Schema
@@ -92,23 +93,20 @@ impl CommandHandler for SearchHandler {
         }
 
         // Build SQL query
-        let sql = if let Some(ref k) = kind {
-            format!(
-                "SELECT file_path, name, kind, line_start, signature \
-                 FROM symbols \
-                 WHERE name LIKE '%{query}%' AND kind = '{k}' \
-                 LIMIT {limit}"
-            )
-        } else {
-            format!(
-                "SELECT file_path, name, kind, line_start, signature \
-                 FROM symbols \
-                 WHERE name LIKE '%{query}%' \
-                 LIMIT {limit}"
-            )
-        };
+        let mut sql = "SELECT file_path, name, kind, line_start, signature \
+                       FROM symbols \
+                       WHERE name LIKE :pattern ESCAPE '\\'"
+            .to_string();
+        let mut params = QueryParams::None
+            .with("pattern", like_contains(&query))
+            .with("limit", limit);
+        if let Some(ref k) = kind {
+            sql.push_str(" AND kind = :kind");
+            params = params.with("kind", k.as_str());
+        }
+        sql.push_str(" LIMIT :limit");
 
-        match engine.query(&sql) {
+        match engine.query_with_params(&sql, &params) {
             Ok(matches) => CommandResult::Ok {
                 data: json!({
                     "query": query,
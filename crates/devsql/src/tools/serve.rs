@@ -0,0 +1,76 @@
+//! `devsql serve` — answer queries and tools over HTTP (see
+//! [`crate::server`]).
+
+use incurs::command::{CommandDef, Example, TypedContext, TypedResult};
+
+#[derive(incurs::Args, serde::Deserialize, serde::Serialize)]
+#[allow(dead_code)]
+struct ServeArgs {}
+
+#[derive(incurs::Options, serde::Deserialize, serde::Serialize)]
+#[allow(dead_code)]
+struct ServeOptions {
+    /// Address to listen on, such as 127.0.0.1:7878 (port 0 picks a free one)
+    #[incurs(alias = "l")]
+    listen: Option<String>,
+    /// Require `Authorization: Bearer <token>` on every request (defaults
+    /// to $DEVSQL_SERVE_TOKEN)
+    token: Option<String>,
+    /// Allow listening on an address other than loopback; needs a token
+    #[serde(default)]
+    allow_remote: bool,
+}
+
+/// Listen here when `--listen` is not given.
+const DEFAULT_LISTEN: &str = "127.0.0.1:7878";
+
+async fn run_serve(ctx: TypedContext<ServeArgs, ServeOptions, ()>) -> TypedResult<()> {
+    if ctx.request.is_some() {
+        return TypedResult::error(
+            "SERVE_UNAVAILABLE",
+            "devsql serve runs a server from the command line",
+        );
+    }
+    let options = ctx.options;
+    let listen = options.listen.as_deref().unwrap_or(DEFAULT_LISTEN);
+    let listen = match listen.parse() {
+        Ok(listen) => listen,
+        Err(e) => {
+            return TypedResult::error(
+                "INVALID_LISTEN",
+                format!("--listen must be an IP address and port such as {DEFAULT_LISTEN}: {e}"),
+            )
+        }
+    };
+    let token = options
+        .token
+        .or_else(|| std::env::var(crate::server::TOKEN_ENV).ok())
+        .filter(|token| !token.is_empty());
+    match crate::server::serve(listen, token, options.allow_remote).await {
+        Ok(()) => TypedResult::ok(()),
+        Err(e) => TypedResult::error("SERVE_ERROR", e.to_string()),
+    }
+}
+
+pub fn build() -> CommandDef {
+    CommandDef::typed::<ServeArgs, ServeOptions, (), (), _, _>("serve", run_serve)
+        .description(
+            "Serve POST /query, recall, gather, day, and the schema registry over HTTP, keeping \
+             loaded tables between requests until their sources change",
+        )
+        .examples(vec![
+            Example {
+                command: "--listen 127.0.0.1:7878".to_string(),
+                description: Some("Serve on loopback for a local dashboard".to_string()),
+            },
+            Example {
+                command: "--listen 0.0.0.0:7878 --allow-remote --token $TOKEN".to_string(),
+                description: Some("Serve other machines, requiring a bearer token".to_string()),
+            },
+        ])
+        .hint(
+            "curl -s localhost:7878/query -H 'content-type: application/json' \
+             -d '{\"sql\": \"SELECT COUNT(*) AS n FROM history\", \"format\": \"columns\"}'",
+        )
+        .done()
+}
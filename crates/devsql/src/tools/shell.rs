@@ -0,0 +1,226 @@
+//! `devsql shell` — an interactive SQL shell over one engine.
+//!
+//! The shell is vcsql's ([`vcsql::cli::Shell`]); this module supplies the
+//! session. Tables are loaded the first time a statement reads them and
+//! stay loaded, so later statements run against them without reading the
+//! sources again until `.reload`.
+
+use std::collections::HashSet;
+
+use incurs::command::{CommandDef, Example, TypedContext, TypedResult};
+use incurs::output::Format;
+use vcsql::cli::{OutputFormat, Session, Shell, ShellColumn, ShellTable};
+use vcsql::sql::engine::QueryResult;
+use vcsql::{TimeWindow, VcsqlError};
+
+use crate::engine::QueryParams;
+use crate::UnifiedEngine;
+
+#[derive(incurs::Args, serde::Deserialize, serde::Serialize)]
+#[allow(dead_code)]
+struct ShellArgs {}
+
+#[derive(incurs::Options, serde::Deserialize, serde::Serialize)]
+#[allow(dead_code)]
+struct ShellOptions {
+    /// Git repository path or glob; repeat to query several repositories
+    #[incurs(alias = "r")]
+    #[serde(default)]
+    repo: Vec<String>,
+    /// File listing one repository root (or glob) per line
+    #[incurs(alias = "w")]
+    workspace: Option<String>,
+    /// Claude data directory (defaults to ~/.claude)
+    #[incurs(alias = "d")]
+    data_dir: Option<String>,
+    /// Only load rows at or after this time: an RFC 3339 timestamp, a
+    /// YYYY-MM-DD date (UTC), or a span back from now such as 7d or 12h
+    since: Option<String>,
+    /// Only load rows before this time (same formats as --since)
+    until: Option<String>,
+    /// Load a CSV, JSON, JSON Lines, or SQLite file as a table, given as
+    /// name=path; repeat to attach several
+    #[serde(default)]
+    attach: Vec<String>,
+}
+
+/// One engine for the life of the shell, and the saved queries already
+/// registered as views in it.
+struct EngineSession {
+    engine: UnifiedEngine,
+    tables: Vec<ShellTable>,
+    views: HashSet<String>,
+}
+
+fn vcsql_error(error: crate::Error) -> VcsqlError {
+    VcsqlError::Other(error.to_string())
+}
+
+impl Session for EngineSession {
+    fn tables(&self) -> Vec<ShellTable> {
+        self.tables.clone()
+    }
+
+    fn execute(&mut self, sql: &str) -> vcsql::Result<QueryResult> {
+        let views = crate::saved::views(sql, &serde_json::Map::new()).map_err(vcsql_error)?;
+        let mut tables = crate::source::tables_in(sql);
+        for view in &views {
+            for table in crate::source::tables_in(&view.sql) {
+                if !tables.contains(&table) {
+                    tables.push(table);
+                }
+            }
+        }
+        let table_refs: Vec<&str> = tables.iter().map(|s| s.as_str()).collect();
+        self.engine.load_tables(&table_refs).map_err(vcsql_error)?;
+        for view in views {
+            if self.views.contains(&view.name) {
+                continue;
+            }
+            self.engine
+                .create_view(&view.name, &view.sql)
+                .map_err(vcsql_error)?;
+            self.views.insert(view.name);
+        }
+
+        let columns: Vec<String> = self
+            .engine
+            .conn()
+            .prepare(sql)?
+            .column_names()
+            .into_iter()
+            .map(|name| name.to_string())
+            .collect();
+        let mut rows = Vec::new();
+        self.engine
+            .query_each(sql, &QueryParams::None, |row| {
+                rows.push(
+                    columns
+                        .iter()
+                        .map(|column| row.get(column).cloned().unwrap_or_default())
+                        .collect(),
+                );
+                Ok(())
+            })
+            .map_err(vcsql_error)?;
+        Ok(QueryResult { columns, rows })
+    }
+
+    fn reload(&mut self, table: &str) -> vcsql::Result<Vec<String>> {
+        let mut tables = self.engine.unload_tables(&[table]).map_err(vcsql_error)?;
+        if !tables.iter().any(|t| t == table) {
+            tables.insert(0, table.to_string());
+        }
+        let table_refs: Vec<&str> = tables.iter().map(|s| s.as_str()).collect();
+        self.engine.load_tables(&table_refs).map_err(vcsql_error)?;
+        Ok(tables)
+    }
+}
+
+/// Every table this build can load, as the shell lists and completes them.
+fn shell_tables() -> crate::Result<Vec<ShellTable>> {
+    Ok(crate::schema::tables()?
+        .into_iter()
+        .filter(|table| table.available)
+        .map(|table| ShellTable {
+            name: table.name,
+            description: table.description,
+            columns: table
+                .columns
+                .into_iter()
+                .map(|column| ShellColumn {
+                    name: column.name,
+                    sql_type: column.sql_type,
+                    description: column.description,
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+fn output_format(format: Format) -> OutputFormat {
+    match format {
+        Format::Json => OutputFormat::Json,
+        Format::Jsonl => OutputFormat::Jsonl,
+        Format::Csv => OutputFormat::Csv,
+        _ => OutputFormat::Table,
+    }
+}
+
+async fn run_shell(ctx: TypedContext<ShellArgs, ShellOptions, ()>) -> TypedResult<()> {
+    if ctx.request.is_some() {
+        return TypedResult::error(
+            "SHELL_UNAVAILABLE",
+            "devsql shell reads from a terminal; use devsql.query instead",
+        );
+    }
+    let options = ctx.options;
+    let window = match TimeWindow::parse(options.since.as_deref(), options.until.as_deref()) {
+        Ok(window) => window,
+        Err(e) => return TypedResult::error("INVALID_TIME", e.to_string()),
+    };
+    let (mut engine, _) = match super::engine_from_repos(
+        &options.repo,
+        options.workspace.as_deref(),
+        options.data_dir.as_deref(),
+    ) {
+        Ok(engine) => engine,
+        Err(error) => return error.into_typed(),
+    };
+    engine.set_time_window(window);
+    let attached = match super::attach_files(&mut engine, &options.attach) {
+        Ok(attached) => attached,
+        Err(error) => return error.into_typed(),
+    };
+    let mut tables = match shell_tables() {
+        Ok(tables) => tables,
+        Err(e) => return TypedResult::error("SCHEMA_ERROR", e.to_string()),
+    };
+    tables.extend(attached.into_iter().map(ShellTable::from));
+    let format = if ctx.format_explicit {
+        output_format(ctx.format)
+    } else {
+        OutputFormat::Table
+    };
+
+    let mut session = EngineSession {
+        engine,
+        tables,
+        views: HashSet::new(),
+    };
+    let shell = Shell::new("devsql")
+        .history(Some(crate::worklog::home_dir().join("history")))
+        .format(format);
+    let result = tokio::task::spawn_blocking(move || shell.run(&mut session)).await;
+    match result {
+        Ok(Ok(())) => {
+            // The shell printed every result itself. Returning would have
+            // incurs render this handler's empty output after them.
+            let _ = std::io::Write::flush(&mut std::io::stdout());
+            std::process::exit(0)
+        }
+        Ok(Err(e)) => TypedResult::error("SHELL_ERROR", e.to_string()),
+        Err(e) => TypedResult::error("SHELL_ERROR", e.to_string()),
+    }
+}
+
+pub fn build() -> CommandDef {
+    CommandDef::typed::<ShellArgs, ShellOptions, (), (), _, _>("shell", run_shell)
+        .description(
+            "Start an interactive SQL shell that keeps loaded tables between statements \
+             (.tables, .schema, .reload, .format, .timer)",
+        )
+        .examples(vec![
+            Example {
+                command: "--repo ~/src/api --since 30d".to_string(),
+                description: Some(
+                    "Iterate on queries over a month of one repository's history".to_string(),
+                ),
+            },
+            Example {
+                command: r#"< report.sql"#.to_string(),
+                description: Some("Run a file of statements against one engine".to_string()),
+            },
+        ])
+        .done()
+}
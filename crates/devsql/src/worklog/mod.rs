@@ -4,7 +4,7 @@
 //! Agents write via `devsql work …`; humans read via `devsql today` / `day`.
 
 use crate::{Error, Result};
-use chrono::{Local, NaiveDate, Utc};
+use chrono::{NaiveDate, Utc};
 use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
@@ -12,6 +12,20 @@ use std::sync::atomic::{AtomicU64, Ordering};
 
 const SCHEMA_VERSION: i32 = 1;
 
+/// Schema of `work_tasks` as copied into a query connection.
+pub(crate) const WORK_TASKS_TABLE: &str = "CREATE TABLE IF NOT EXISTS work_tasks (
+    id TEXT, title TEXT, project TEXT, cwd TEXT, agent TEXT,
+    status TEXT, created_at TEXT, updated_at TEXT,
+    session_id TEXT, source TEXT
+)";
+
+/// Schema of `work_events` as copied into a query connection.
+pub(crate) const WORK_EVENTS_TABLE: &str = "CREATE TABLE IF NOT EXISTS work_events (
+    id TEXT, task_id TEXT, ts TEXT, local_date TEXT, kind TEXT,
+    title TEXT, body TEXT, project TEXT, cwd TEXT, agent TEXT,
+    session_id TEXT, meta_json TEXT
+)";
+
 // ---------------------------------------------------------------------------
 // Paths
 // ---------------------------------------------------------------------------
@@ -46,8 +60,14 @@ fn now_iso() -> String {
     Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
 }
 
+/// Today in the configured timezone.
 fn today_local() -> String {
-    Local::now().date_naive().format("%Y-%m-%d").to_string()
+    crate::config::current()
+        .timezone
+        .value
+        .today()
+        .format("%Y-%m-%d")
+        .to_string()
 }
 
 // ---------------------------------------------------------------------------
@@ -678,24 +698,26 @@ impl Worklog {
         Ok(out)
     }
 
-    /// Copy work tables into an in-memory connection for ad-hoc SQL.
-    pub fn materialize_into(&self, conn: &Connection, tables: &[&str]) -> Result<()> {
+    /// Copy work tables into an in-memory connection for ad-hoc SQL, keeping
+    /// the tasks active and the events logged inside `window`.
+    pub fn materialize_into(
+        &self,
+        conn: &Connection,
+        tables: &[&str],
+        window: &vcsql::TimeWindow,
+    ) -> Result<()> {
+        let (since, until) = crate::engine::window_bounds(window);
         for table in tables {
             match *table {
                 "work_tasks" => {
-                    conn.execute_batch(
-                        "CREATE TABLE IF NOT EXISTS work_tasks (
-                            id TEXT, title TEXT, project TEXT, cwd TEXT, agent TEXT,
-                            status TEXT, created_at TEXT, updated_at TEXT,
-                            session_id TEXT, source TEXT
-                        );
-                        DELETE FROM work_tasks;",
-                    )?;
+                    conn.execute_batch(&format!("{WORK_TASKS_TABLE}; DELETE FROM work_tasks;"))?;
                     let mut stmt = self.conn.prepare(
                         "SELECT id, title, project, cwd, agent, status, created_at,
-                                updated_at, session_id, source FROM work_tasks",
+                                updated_at, session_id, source FROM work_tasks
+                         WHERE (?1 IS NULL OR updated_at >= ?1)
+                           AND (?2 IS NULL OR created_at < ?2)",
                     )?;
-                    let rows = stmt.query_map([], |row| {
+                    let rows = stmt.query_map(params![since, until], |row| {
                         Ok((
                             row.get::<_, String>(0)?,
                             row.get::<_, String>(1)?,
@@ -720,19 +742,13 @@ impl Worklog {
                     }
                 }
                 "work_events" => {
-                    conn.execute_batch(
-                        "CREATE TABLE IF NOT EXISTS work_events (
-                            id TEXT, task_id TEXT, ts TEXT, local_date TEXT, kind TEXT,
-                            title TEXT, body TEXT, project TEXT, cwd TEXT, agent TEXT,
-                            session_id TEXT, meta_json TEXT
-                        );
-                        DELETE FROM work_events;",
-                    )?;
+                    conn.execute_batch(&format!("{WORK_EVENTS_TABLE}; DELETE FROM work_events;"))?;
                     let mut stmt = self.conn.prepare(
                         "SELECT id, task_id, ts, local_date, kind, title, body, project,
-                                cwd, agent, session_id, meta_json FROM work_events",
+                                cwd, agent, session_id, meta_json FROM work_events
+                         WHERE (?1 IS NULL OR ts >= ?1) AND (?2 IS NULL OR ts < ?2)",
                     )?;
-                    let rows = stmt.query_map([], |row| {
+                    let rows = stmt.query_map(params![since, until], |row| {
                         Ok((
                             row.get::<_, String>(0)?,
                             row.get::<_, Option<String>>(1)?,
@@ -853,7 +869,7 @@ pub fn normalize_project(project: Option<&str>, cwd: Option<&str>) -> Option<Str
 
 /// Parse `today`, `yesterday`, or `YYYY-MM-DD` into a local calendar date string.
 pub fn parse_day(spec: Option<&str>) -> Result<String> {
-    let today = Local::now().date_naive();
+    let today = crate::config::current().timezone.value.today();
     match spec.map(str::trim).filter(|s| !s.is_empty()) {
         None | Some("today") => Ok(today.format("%Y-%m-%d").to_string()),
         Some("yesterday") => Ok((today - chrono::Duration::days(1))
@@ -980,7 +996,14 @@ pub fn format_day_markdown(view: &DayView) -> String {
 
 fn format_local_time(iso: &str) -> String {
     parse_datetime(iso)
-        .map(|dt| dt.with_timezone(&Local).format("%-I:%M %p").to_string())
+        .map(|dt| {
+            crate::config::current()
+                .timezone
+                .value
+                .from_utc(dt)
+                .format("%-I:%M %p")
+                .to_string()
+        })
         .unwrap_or_else(|| iso.chars().take(16).collect())
 }
 
@@ -1095,10 +1118,7 @@ mod tests {
     #[test]
     fn parse_day_variants() {
         let today = parse_day(Some("today")).unwrap();
-        assert_eq!(
-            today,
-            Local::now().date_naive().format("%Y-%m-%d").to_string()
-        );
+        assert_eq!(today, today_local());
         assert_eq!(parse_day(Some("2026-07-01")).unwrap(), "2026-07-01");
         assert!(parse_day(Some("not-a-date")).is_err());
     }
@@ -125,6 +125,37 @@ fn recall_ranks_more_matches_first() {
     );
 }
 
+#[test]
+fn recall_matches_prompts_by_stem_with_bm25_scores() {
+    let data = create_claude_data_dir();
+    let codex_home = TempDir::new().expect("codex");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .env("CODEX_HOME", codex_home.path())
+        .args([
+            "recall",
+            "simulators booting",
+            "--data-dir",
+            data.path().to_str().unwrap(),
+            "--format",
+            "json",
+        ])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let parsed: Value = serde_json::from_slice(&output).expect("valid json");
+    let prompts = parsed["prompts"].as_array().expect("prompts array");
+    assert_eq!(prompts.len(), 1, "{parsed}");
+    assert!(prompts[0]["prompt"]
+        .as_str()
+        .unwrap()
+        .contains("simulator boot"));
+    assert!(prompts[0]["bm25"].as_f64().expect("bm25 score") < 0.0);
+}
+
 #[test]
 fn recall_empty_terms_exit_zero() {
     let data = create_claude_data_dir();
@@ -193,6 +224,159 @@ fn root_and_named_query_are_compatible() {
     }
 }
 
+#[test]
+fn query_binds_named_and_positional_params() {
+    let output = Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .args([
+            "SELECT :term AS term, :n + 1 AS next, '100%' LIKE :pattern AS literal",
+            "--params",
+            r#"{"term": "it's", "n": 41, ":pattern": "100%"}"#,
+            "--format",
+            "json",
+        ])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let parsed: Value = serde_json::from_slice(&output).expect("valid json");
+    assert_eq!(
+        parsed,
+        serde_json::json!([{ "term": "it's", "next": 42, "literal": 1 }])
+    );
+
+    Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .args(["SELECT ? + ? AS total", "-p", "[2, 3]", "--format", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"total\": 5"));
+
+    Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .args(["SELECT :missing AS value", "--params", "{}"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "missing value for parameter :missing",
+        ));
+}
+
+#[test]
+fn since_and_until_limit_every_loaded_source() {
+    let claude = create_claude_data_dir();
+    let repo = TempDir::new().expect("temp");
+    create_repo(repo.path(), "main.rs");
+    let counts = |window: &[&str]| -> Value {
+        let output = Command::new(env!("CARGO_BIN_EXE_devsql"))
+            .args([
+                "SELECT (SELECT COUNT(*) FROM sessions) AS sessions, \
+                 (SELECT COUNT(*) FROM transcripts) AS entries, \
+                 (SELECT COUNT(*) FROM history) AS prompts, \
+                 (SELECT COUNT(*) FROM commits) AS commits",
+                "--format",
+                "json",
+                "--data-dir",
+                claude.path().to_str().unwrap(),
+                "--repo",
+                repo.path().to_str().unwrap(),
+            ])
+            .args(window)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        serde_json::from_slice::<Value>(&output).expect("valid json")[0].clone()
+    };
+
+    assert_eq!(
+        counts(&[]),
+        serde_json::json!({"sessions": 2, "entries": 4, "prompts": 2, "commits": 1})
+    );
+    assert_eq!(
+        counts(&["--since", "2026-05-15", "--until", "2026-07-01"]),
+        serde_json::json!({"sessions": 1, "entries": 1, "prompts": 0, "commits": 0})
+    );
+    assert_eq!(
+        counts(&["--until", "2026-05-15T00:00:00Z"]),
+        serde_json::json!({"sessions": 1, "entries": 1, "prompts": 2, "commits": 0})
+    );
+    assert_eq!(
+        counts(&["--since", "1h"]),
+        serde_json::json!({"sessions": 0, "entries": 0, "prompts": 0, "commits": 1})
+    );
+
+    Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .args(["SELECT 1", "--since", "soon"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("INVALID_TIME"));
+}
+
+#[test]
+fn tables_schema_and_examples_describe_every_source() {
+    let json = |args: &[&str]| -> Value {
+        let output = Command::new(env!("CARGO_BIN_EXE_devsql"))
+            .args(args)
+            .arg("--json")
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        serde_json::from_slice(&output).expect("valid json")
+    };
+
+    let tables = json(&["tables"]);
+    let categories: std::collections::BTreeSet<&str> = tables
+        .as_array()
+        .expect("array")
+        .iter()
+        .filter_map(|table| table["category"].as_str())
+        .collect();
+    for category in [
+        "CLAUDE",
+        "CODEX",
+        "GIT",
+        "CODE",
+        "SHELL",
+        "WORKLOG",
+        "FULL-TEXT",
+    ] {
+        assert!(categories.contains(category), "{category}: {tables}");
+    }
+    let symbol_refs = tables
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|table| table["name"] == "symbol_refs")
+        .expect("symbol_refs");
+    assert_eq!(symbol_refs["feature"], "tree-sitter-ast");
+
+    let columns = json(&["schema", "transcripts"]);
+    let usage = columns
+        .as_array()
+        .expect("array")
+        .iter()
+        .find(|column| column["column"] == "usage_output_tokens")
+        .expect("usage_output_tokens");
+    assert_eq!(usage["table"], "transcripts");
+    assert_eq!(usage["sql_type"], "INTEGER");
+    assert!(!usage["description"].as_str().unwrap().is_empty());
+
+    let examples = json(&["examples", "--category", "CODEX"]);
+    assert!(examples
+        .as_array()
+        .expect("array")
+        .iter()
+        .all(|example| example["category"] == "CODEX"));
+
+    Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .args(["schema", "no_such_table"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("UNKNOWN_TABLE"));
+}
+
 #[test]
 fn root_help_leads_agents_to_code_mode() {
     Command::new(env!("CARGO_BIN_EXE_devsql"))
@@ -220,3 +404,731 @@ fn table_and_csv_formats_remain_available() {
         .success()
         .stdout(predicate::str::contains("value\n1"));
 }
+
+#[test]
+fn jsonl_and_csv_output_stream_one_row_at_a_time() {
+    let sql = "SELECT 'a,b' AS label, NULL AS note UNION ALL SELECT 'plain', 2";
+
+    let jsonl = Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .args([sql, "--format", "jsonl"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let records: Vec<Value> = String::from_utf8(jsonl)
+        .expect("utf8")
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("json line"))
+        .collect();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[0]["type"], "chunk");
+    assert_eq!(records[0]["data"]["label"], "a,b");
+    assert_eq!(records[1]["data"]["note"], 2);
+    assert_eq!(records[2]["type"], "done");
+
+    Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .args([sql, "--format", "csv"])
+        .assert()
+        .success()
+        .stdout("label,note\n\"a,b\",\nplain,2\n");
+
+    Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .args([sql, "--format", "csv", "-H"])
+        .assert()
+        .success()
+        .stdout("\"a,b\",\nplain,2\n");
+
+    // The header comes from the statement, not the first row.
+    Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .args(["SELECT 'a' AS label, 1 AS note WHERE 0", "--format", "csv"])
+        .assert()
+        .success()
+        .stdout("label,note\n");
+
+    Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .args(["SELECT json_extract('{', '$') AS bad", "--format", "jsonl"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("QUERY_ERROR"));
+}
+
+fn create_repo(path: &std::path::Path, file: &str) {
+    let repo = git2::Repository::init(path).expect("git init");
+    write(&path.join(file), "fn main() {}\n");
+    let mut index = repo.index().expect("index");
+    index.add_path(std::path::Path::new(file)).expect("add");
+    index.write().expect("index write");
+    let tree = repo
+        .find_tree(index.write_tree().expect("tree"))
+        .expect("find tree");
+    let sig = git2::Signature::now("Test", "test@example.com").expect("sig");
+    repo.commit(Some("HEAD"), &sig, &sig, &format!("Add {file}"), &tree, &[])
+        .expect("commit");
+}
+
+#[test]
+fn repeated_repo_and_workspace_file_query_every_repository() {
+    let temp = TempDir::new().expect("temp");
+    create_repo(&temp.path().join("api"), "auth.rs");
+    create_repo(&temp.path().join("web"), "index.rs");
+    write(&temp.path().join("services.workspace"), "api\nweb\n");
+
+    let sql = "SELECT c.summary, COUNT(f.path) AS files \
+               FROM commits c JOIN source_files f ON f.repo = c.repo \
+               GROUP BY c.repo ORDER BY c.summary";
+    let api = temp.path().join("api");
+    let web = temp.path().join("web");
+    let workspace = temp.path().join("services.workspace");
+    for repo_args in [
+        vec![
+            "--repo",
+            api.to_str().unwrap(),
+            "--repo",
+            web.to_str().unwrap(),
+        ],
+        vec!["--workspace", workspace.to_str().unwrap()],
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_devsql"))
+            .env("XDG_CACHE_HOME", temp.path().join("cache"))
+            .arg(sql)
+            .args(&repo_args)
+            .args(["--format", "json"])
+            .output()
+            .expect("run devsql");
+        assert!(output.status.success(), "{output:?}");
+        let rows: Value = serde_json::from_slice(&output.stdout).expect("json");
+        assert_eq!(
+            rows,
+            serde_json::json!([
+                {"summary": "Add auth.rs", "files": 1},
+                {"summary": "Add index.rs", "files": 1},
+            ])
+        );
+    }
+}
+
+#[test]
+fn saved_queries_run_with_params_and_read_as_views() {
+    let temp = TempDir::new().expect("temp");
+    create_repo(&temp.path().join("api"), "auth.rs");
+    let home = temp.path().join("home");
+    write(
+        &home.join("queries/commits_by.sql"),
+        "-- description: Commits by one author\n\
+         -- param author: Author name\n\
+         -- param n = 10: How many commits\n\
+         SELECT summary, author_name FROM commits WHERE author_name = :author LIMIT :n;\n",
+    );
+    write(
+        &home.join("queries/author_files.sql"),
+        "-- description: Files next to an author's commits\n\
+         SELECT b.summary, f.path FROM commits_by b JOIN source_files f\n",
+    );
+    let api = temp.path().join("api");
+    let devsql = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_devsql"))
+            .env("DEVSQL_HOME", &home)
+            .env("XDG_CACHE_HOME", temp.path().join("cache"))
+            .args(args)
+            .arg("--repo")
+            .arg(&api)
+            .args(["--format", "json"])
+            .output()
+            .expect("run devsql")
+    };
+
+    let output = devsql(&["run", "commits_by", "--param", "author=Test"]);
+    assert!(output.status.success(), "{output:?}");
+    let rows: Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(
+        rows,
+        serde_json::json!([{"summary": "Add auth.rs", "author_name": "Test"}])
+    );
+
+    // A saved query that reads another takes its parameters from --params.
+    let output = devsql(&[
+        "SELECT * FROM author_files",
+        "--params",
+        r#"{"author": "Test"}"#,
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    let rows: Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(
+        rows,
+        serde_json::json!([{"summary": "Add auth.rs", "path": "auth.rs"}])
+    );
+
+    let output = devsql(&["run", "commits_by"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("needs parameter 'author'"));
+    let output = devsql(&["SELECT * FROM author_files"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("SAVED_QUERY_ERROR"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .env("DEVSQL_HOME", &home)
+        .args(["queries", "--format", "json"])
+        .output()
+        .expect("run devsql");
+    assert!(output.status.success(), "{output:?}");
+    let queries: Value = serde_json::from_slice(&output.stdout).expect("json");
+    let listed: Vec<(&str, &Value)> = queries
+        .as_array()
+        .expect("array")
+        .iter()
+        .map(|query| (query["name"].as_str().unwrap(), &query["params"]))
+        .collect();
+    assert_eq!(
+        listed,
+        [
+            ("author_files", &serde_json::json!([])),
+            ("commits_by", &serde_json::json!(["author", "n = 10"])),
+        ]
+    );
+}
+
+#[cfg(feature = "tree-sitter-ast")]
+#[test]
+fn impact_follows_references_to_transitive_dependents() {
+    let temp = TempDir::new().expect("temp");
+    write(&temp.path().join("src/engine.rs"), "pub fn start() {}\n");
+    write(
+        &temp.path().join("src/app.rs"),
+        "use crate::engine::start;\n\npub fn run() {\n    start();\n}\n",
+    );
+    write(
+        &temp.path().join("src/main.rs"),
+        "fn main() {\n    run();\n}\n",
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .args(["impact", "engine.rs", "--repo"])
+        .arg(temp.path())
+        .args(["--format", "json"])
+        .output()
+        .expect("run devsql");
+    assert!(output.status.success(), "{output:?}");
+    let parsed: Value = serde_json::from_slice(&output.stdout).expect("json");
+    let dependents: Vec<(String, Value, i64)> = parsed["dependents"]
+        .as_array()
+        .expect("dependents")
+        .iter()
+        .map(|row| {
+            (
+                row["file_path"].as_str().unwrap_or_default().to_string(),
+                row["symbol"].clone(),
+                row["depth"].as_i64().unwrap_or_default(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        dependents,
+        vec![
+            ("src/app.rs".to_string(), Value::Null, 1),
+            ("src/app.rs".to_string(), Value::from("run"), 1),
+            ("src/main.rs".to_string(), Value::from("main"), 2),
+        ]
+    );
+}
+
+#[cfg(feature = "tree-sitter-ast")]
+#[test]
+fn impact_follows_references_through_private_code_in_the_file() {
+    let temp = TempDir::new().expect("temp");
+    write(
+        &temp.path().join("engine/engine.go"),
+        "package engine\n\nfunc Start() {}\n\nfunc restart() {\n\tStart()\n}\n",
+    );
+    write(
+        &temp.path().join("engine/serve.go"),
+        "package engine\n\nfunc Serve() {\n\trestart()\n}\n",
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .args(["impact", "engine.go", "--repo"])
+        .arg(temp.path())
+        .args(["--format", "json"])
+        .output()
+        .expect("run devsql");
+    assert!(output.status.success(), "{output:?}");
+    let parsed: Value = serde_json::from_slice(&output.stdout).expect("json");
+    let dependents: Vec<(&str, &str, &str, i64)> = parsed["dependents"]
+        .as_array()
+        .expect("dependents")
+        .iter()
+        .map(|row| {
+            (
+                row["file_path"].as_str().unwrap_or_default(),
+                row["symbol"].as_str().unwrap_or_default(),
+                row["references"].as_str().unwrap_or_default(),
+                row["depth"].as_i64().unwrap_or_default(),
+            )
+        })
+        .collect();
+    assert_eq!(dependents, vec![("engine/serve.go", "Serve", "restart", 2)]);
+}
+
+#[test]
+fn impact_reports_potential_dependents_in_every_build() {
+    let temp = TempDir::new().expect("temp");
+    write(&temp.path().join("src/engine.rs"), "pub struct Config {}\n");
+    write(
+        &temp.path().join("src/app.rs"),
+        "pub fn load(config: Config) -> Config {\n    config\n}\n",
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .args(["impact", "engine.rs", "--repo"])
+        .arg(temp.path())
+        .args(["--format", "json"])
+        .output()
+        .expect("run devsql");
+    assert!(output.status.success(), "{output:?}");
+    let parsed: Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(
+        parsed["dependents_available"],
+        cfg!(feature = "tree-sitter-ast")
+    );
+    let potential: Vec<(&str, &str, &str)> = parsed["potential_dependents"]
+        .as_array()
+        .expect("potential_dependents")
+        .iter()
+        .map(|row| {
+            (
+                row["file_path"].as_str().unwrap_or_default(),
+                row["name"].as_str().unwrap_or_default(),
+                row["mentions"].as_str().unwrap_or_default(),
+            )
+        })
+        .collect();
+    assert_eq!(potential, vec![("src/app.rs", "load", "Config")]);
+    assert_eq!(parsed["potential_dependents_truncated"], false);
+}
+
+#[test]
+fn impact_caps_potential_dependents_across_every_export() {
+    let temp = TempDir::new().expect("temp");
+    write(
+        &temp.path().join("src/engine.rs"),
+        "pub struct Config {}\npub struct Store {}\n",
+    );
+    let app: String = (0..12)
+        .map(|n| {
+            format!("pub fn load{n}(config: Config) {{}}\npub fn open{n}(store: Store) {{}}\n")
+        })
+        .collect();
+    write(&temp.path().join("src/app.rs"), &app);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .args(["impact", "engine.rs", "--repo"])
+        .arg(temp.path())
+        .args(["--format", "json"])
+        .output()
+        .expect("run devsql");
+    assert!(output.status.success(), "{output:?}");
+    let parsed: Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(
+        parsed["potential_dependents"]
+            .as_array()
+            .expect("potential_dependents")
+            .len(),
+        20
+    );
+    assert_eq!(parsed["potential_dependents_truncated"], true);
+}
+
+#[test]
+fn shell_keeps_one_engine_across_statements_and_commands() {
+    let temp = TempDir::new().expect("temp");
+    create_repo(&temp.path().join("api"), "auth.rs");
+    let output = Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .env("DEVSQL_HOME", temp.path().join("home"))
+        .env("XDG_CACHE_HOME", temp.path().join("cache"))
+        .args(["shell", "--format", "csv", "--repo"])
+        .arg(temp.path().join("api"))
+        .write_stdin(
+            "CREATE TEMP TABLE picked AS SELECT summary FROM commits;\n\
+             .reload commits\n\
+             SELECT p.summary, COUNT(c.id) AS n\n  FROM picked p JOIN commits c ON c.summary = p.summary;\n\
+             .format json\n\
+             SELECT path FROM source_files\n",
+        )
+        .output()
+        .expect("run devsql");
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.starts_with("summary,n\nAdd auth.rs,1\n[\n"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("\"path\": \"auth.rs\""), "{stdout}");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Reloaded commits"));
+
+    Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .env("XDG_CACHE_HOME", temp.path().join("cache"))
+        .args(["shell", "--repo"])
+        .arg(temp.path().join("api"))
+        .write_stdin(".reload nonesuch\nSELECT 1;\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("nonesuch"));
+}
+
+#[test]
+fn export_writes_a_snapshot_that_db_queries_without_the_sources() {
+    let temp = TempDir::new().expect("temp");
+    create_repo(&temp.path().join("api"), "auth.rs");
+    write(
+        &temp.path().join("claude/history.jsonl"),
+        r#"{"display":"retry login with password=hunter2","timestamp":1754402102000,"project":"/app"}"#,
+    );
+    let snapshot = temp.path().join("snapshot.sqlite");
+    let output = Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .env("XDG_CACHE_HOME", temp.path().join("cache"))
+        .args(["export", "--tables", "commits,history_fts", "--redact"])
+        .arg("--out")
+        .arg(&snapshot)
+        .arg("--repo")
+        .arg(temp.path().join("api"))
+        .arg("--data-dir")
+        .arg(temp.path().join("claude"))
+        .args(["--format", "json"])
+        .output()
+        .expect("run devsql");
+    assert!(output.status.success(), "{output:?}");
+    let exported: Value = serde_json::from_slice(&output.stdout).expect("json");
+    let tables: Vec<&str> = exported["tables"]
+        .as_array()
+        .expect("tables")
+        .iter()
+        .map(|table| table["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(tables, ["commits", "history", "history_fts"]);
+
+    // Move the sources away: the snapshot must answer on its own.
+    std::fs::remove_dir_all(temp.path().join("api")).expect("rm repo");
+    std::fs::remove_dir_all(temp.path().join("claude")).expect("rm claude");
+    let query = |sql: &str| {
+        let output = Command::new(env!("CARGO_BIN_EXE_devsql"))
+            .arg("--db")
+            .arg(&snapshot)
+            .args([sql, "--format", "json"])
+            .output()
+            .expect("run devsql");
+        assert!(output.status.success(), "{output:?}");
+        serde_json::from_slice::<Value>(&output.stdout).expect("json")
+    };
+    assert_eq!(
+        query("SELECT summary FROM commits")[0]["summary"],
+        "Add auth.rs"
+    );
+    let matched = query(
+        "SELECT h.display FROM history_fts JOIN history h ON h.rowid = history_fts.rowid \
+         WHERE history_fts MATCH 'login'",
+    );
+    let display = matched[0]["display"].as_str().expect("display");
+    assert!(!display.contains("hunter2"), "{display}");
+    assert_eq!(
+        query("SELECT value FROM devsql_snapshot WHERE key = 'redacted'")[0]["value"],
+        "true"
+    );
+
+    Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .arg("--db")
+        .arg(&snapshot)
+        .args(["SELECT 1", "--since", "7d"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("INVALID_OPTIONS"));
+}
+
+#[test]
+fn attach_joins_local_files_with_devsql_tables() {
+    let temp = TempDir::new().expect("temp");
+    create_repo(&temp.path().join("api"), "auth.rs");
+    write(
+        &temp.path().join("tickets.jsonl"),
+        "{\"summary\":\"Add auth.rs\",\"ticket\":\"T-7\",\"points\":3}\n",
+    );
+    let output = Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .env("XDG_CACHE_HOME", temp.path().join("cache"))
+        .args([
+            "SELECT t.ticket, typeof(t.points) AS points, c.summary \
+             FROM commits c JOIN tickets t USING (summary)",
+            "--format",
+            "json",
+            "--repo",
+        ])
+        .arg(temp.path().join("api"))
+        .arg("--attach")
+        .arg(format!(
+            "tickets={}",
+            temp.path().join("tickets.jsonl").display()
+        ))
+        .output()
+        .expect("run devsql");
+    assert!(output.status.success(), "{output:?}");
+    let rows: Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(
+        rows,
+        serde_json::json!([{"ticket": "T-7", "points": "integer", "summary": "Add auth.rs"}])
+    );
+
+    Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .env("XDG_CACHE_HOME", temp.path().join("cache"))
+        .args(["SELECT 1", "--attach"])
+        .arg(format!(
+            "commits={}",
+            temp.path().join("tickets.jsonl").display()
+        ))
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("ATTACH_ERROR"));
+}
+
+#[test]
+fn explain_and_profile_report_loads_codex_sync_and_query_plan() {
+    let claude = create_claude_data_dir();
+    let codex_home = create_codex_data_dir();
+    let query = "SELECT (SELECT COUNT(*) FROM history) AS prompts, \
+                 (SELECT COUNT(*) FROM codex_threads) AS threads";
+    let run = |flag: &str| {
+        Command::new(env!("CARGO_BIN_EXE_devsql"))
+            .env("CODEX_HOME", codex_home.path())
+            .args([
+                query,
+                flag,
+                "--data-dir",
+                claude.path().to_str().unwrap(),
+                "--format",
+                "json",
+            ])
+            .assert()
+            .success()
+            .get_output()
+            .clone()
+    };
+
+    let explained = run("--explain");
+    let report: Value = serde_json::from_slice(&explained.stdout).expect("valid json");
+    let report = &report[0];
+    assert_eq!(
+        report["tables"][0],
+        serde_json::json!({"table": "history", "source": "CLAUDE", "reason": "referenced by the query"})
+    );
+    assert_eq!(report["tables"][1]["source"], "CODEX");
+    let history = &report["loads"][0];
+    assert_eq!(history["tables"], serde_json::json!(["history"]));
+    assert_eq!(history["rows_inserted"], 2);
+    assert_eq!(history["files_read"], 1);
+    assert_eq!(report["codex_sync"]["reparsed_journals"], 1);
+    assert_eq!(report["codex_sync"]["parsed_records"], 2);
+    assert!(!report["query_plan"].as_array().expect("plan").is_empty());
+    assert!(report.get("query").is_none());
+
+    // Rows go to stdout and the profile, now with the query's cost, to
+    // stderr; the second run finds the Codex journal already indexed.
+    let profiled = run("--profile");
+    let rows: Value = serde_json::from_slice(&profiled.stdout).expect("valid json");
+    assert_eq!(rows, serde_json::json!([{"prompts": 2, "threads": 1}]));
+    let stderr = String::from_utf8(profiled.stderr).expect("utf8");
+    let line = stderr
+        .lines()
+        .find(|line| line.starts_with("{\"profile\""))
+        .expect("profile on stderr");
+    let report: Value = serde_json::from_str(line).expect("valid json");
+    assert_eq!(report["profile"]["query"]["rows"], 1);
+    assert_eq!(report["profile"]["codex_sync"]["unchanged_journals"], 1);
+}
+
+#[test]
+fn config_file_supplies_defaults_that_env_and_options_override() {
+    let claude = create_claude_data_dir();
+    let codex_home = create_codex_data_dir();
+    let home = TempDir::new().expect("devsql home");
+    write(
+        &home.path().join("config.toml"),
+        &format!(
+            "sources = [\"claude\", \"codex\", \"full-text\"]\n\
+             timezone = \"UTC\"\n\
+             [paths]\n\
+             claude_data_dir = {:?}\n\
+             codex_home = \"/nonexistent/codex\"\n\
+             [gather]\n\
+             budget = 1234\n",
+            claude.path().to_str().unwrap()
+        ),
+    );
+    let devsql = || {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_devsql"));
+        command
+            .env("DEVSQL_HOME", home.path())
+            .env_remove("CLAUDE_DATA_DIR")
+            .env("CODEX_HOME", codex_home.path());
+        command
+    };
+
+    let output = devsql()
+        .args(["config", "show", "--format", "json"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let entries: Value = serde_json::from_slice(&output).expect("valid json");
+    let source_of = |setting: &str| {
+        entries
+            .as_array()
+            .expect("entries")
+            .iter()
+            .find(|entry| entry["setting"] == setting)
+            .map(|entry| (entry["value"].clone(), entry["source"].clone()))
+            .expect(setting)
+    };
+    assert_eq!(source_of("file").1, "read");
+    assert_eq!(
+        source_of("paths.claude_data_dir"),
+        (
+            Value::from(claude.path().to_str().unwrap()),
+            Value::from("config file")
+        )
+    );
+    assert_eq!(
+        source_of("paths.codex_home"),
+        (
+            Value::from(codex_home.path().to_str().unwrap()),
+            Value::from("$CODEX_HOME")
+        )
+    );
+    assert_eq!(source_of("gather.budget").0, "1234");
+    assert_eq!(source_of("gather.section_limits.excerpts").1, "default");
+
+    // The configured Claude directory is read without --data-dir, and the
+    // environment's Codex home wins over the file's.
+    devsql()
+        .args([
+            "SELECT (SELECT COUNT(*) FROM history) AS prompts, \
+             (SELECT COUNT(*) FROM codex_threads) AS threads",
+            "--format",
+            "json",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"prompts\": 2"))
+        .stdout(predicate::str::contains("\"threads\": 1"));
+
+    devsql()
+        .args(["SELECT COUNT(*) FROM shell_history"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "shell_history comes from the SHELL source",
+        ));
+
+    write(
+        &home.path().join("config.toml"),
+        "[paths]\nclaude = \"~\"\n",
+    );
+    devsql()
+        .args(["config", "show"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("unknown field `claude`"));
+}
+
+#[test]
+fn serve_refuses_non_loopback_addresses_without_allow_remote_and_a_token() {
+    Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .args(["serve", "--listen", "0.0.0.0:0"])
+        .env_remove("DEVSQL_SERVE_TOKEN")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("--allow-remote and a token"));
+
+    Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .args(["serve", "--listen", "0.0.0.0:0", "--allow-remote"])
+        .env_remove("DEVSQL_SERVE_TOKEN")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("not a loopback address"));
+
+    Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .args(["serve", "--listen", "localhost"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("INVALID_LISTEN"));
+}
+
+#[test]
+fn queries_copy_held_tables_from_a_running_daemon() {
+    let claude = create_claude_data_dir();
+    let codex = create_codex_data_dir();
+    let home = TempDir::new().expect("home");
+    let configure = |command: &mut std::process::Command| {
+        command
+            .env("DEVSQL_HOME", home.path().join("devsql"))
+            .env("CODEX_HOME", codex.path())
+            .env("HOME", home.path())
+            .env("XDG_CACHE_HOME", home.path().join("cache"))
+            .env_remove("HISTFILE")
+            .env_remove("DEVSQL_NO_DAEMON");
+    };
+    let devsql = |args: &[&str]| {
+        let mut command = std::process::Command::new(env!("CARGO_BIN_EXE_devsql"));
+        command.args(args);
+        configure(&mut command);
+        command.output().expect("run devsql")
+    };
+    let data_dir = claude.path().to_str().expect("utf8 path");
+    let history =
+        |args: &[&str]| devsql(&[&["SELECT display FROM history", "-d", data_dir], args].concat());
+
+    let mut daemon = std::process::Command::new(env!("CARGO_BIN_EXE_devsql"));
+    daemon
+        .args(["daemon", "-d", data_dir, "--interval", "0.2"])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null());
+    configure(&mut daemon);
+    let mut daemon = daemon.spawn().expect("spawn daemon");
+    let wait_for = |done: &dyn Fn() -> bool| {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(30);
+        while !done() {
+            assert!(std::time::Instant::now() < deadline, "timed out");
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+    };
+    wait_for(&|| {
+        let status: Value =
+            serde_json::from_slice(&devsql(&["daemon", "--status", "--format", "json"]).stdout)
+                .unwrap_or_default();
+        status["status"]["running"] == true
+    });
+
+    // A second daemon would race the first.
+    let second = devsql(&["daemon", "--once", "-d", data_dir]);
+    assert!(!second.status.success());
+    assert!(String::from_utf8_lossy(&second.stdout).contains("DAEMON_RUNNING"));
+
+    let profiled = history(&["--profile", "--format", "json"]);
+    let stderr = String::from_utf8(profiled.stderr).expect("utf8");
+    assert!(stderr.contains("\"source\":\"DAEMON\""), "{stderr}");
+    let rows: Value = serde_json::from_slice(&profiled.stdout).expect("json");
+    assert_eq!(rows.as_array().map(Vec::len), Some(2));
+
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(claude.path().join("history.jsonl"))
+        .expect("open history");
+    std::io::Write::write_all(
+        &mut file,
+        b"\n{\"display\":\"appended later\",\"timestamp\":1754402200000,\"project\":\"/x\"}\n",
+    )
+    .expect("append");
+    wait_for(&|| {
+        String::from_utf8_lossy(&history(&["--format", "json"]).stdout).contains("appended later")
+    });
+
+    daemon.kill().expect("stop daemon");
+    daemon.wait().expect("wait");
+}
@@ -25,7 +25,16 @@ fn response(receiver: &Receiver<Value>, id: i64) -> Value {
 
 #[test]
 fn exposes_code_mode_and_executes_a_devsql_query() {
+    let home = tempfile::TempDir::new().expect("temp");
+    std::fs::create_dir(home.path().join("queries")).expect("mkdir");
+    std::fs::write(
+        home.path().join("queries/agent_commands_by_repo.sql"),
+        "-- description: Agent shell commands grouped by repository\n\
+         SELECT cwd, COUNT(*) AS commands FROM command_events GROUP BY cwd",
+    )
+    .expect("write saved query");
     let mut child = Command::new(env!("CARGO_BIN_EXE_devsql"))
+        .env("DEVSQL_HOME", home.path())
         .arg("--mcp")
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
@@ -99,6 +108,37 @@ fn exposes_code_mode_and_executes_a_devsql_query() {
     assert!(searched.get("error").is_none(), "{searched}");
     assert!(searched.to_string().contains("devsql.query"), "{searched}");
 
+    send(
+        &mut stdin,
+        json!({
+            "jsonrpc": "2.0",
+            "id": 30,
+            "method": "tools/call",
+            "params": {"name": "codemode_search", "arguments": {"query": "schema"}}
+        }),
+    );
+    let searched = response(&receiver, 30);
+    assert!(searched.get("error").is_none(), "{searched}");
+    assert!(searched.to_string().contains("devsql.schema"), "{searched}");
+
+    send(
+        &mut stdin,
+        json!({
+            "jsonrpc": "2.0",
+            "id": 31,
+            "method": "tools/call",
+            "params": {"name": "codemode_search", "arguments": {"query": "agent commands by repository"}}
+        }),
+    );
+    let searched = response(&receiver, 31);
+    assert!(searched.get("error").is_none(), "{searched}");
+    assert!(
+        searched
+            .to_string()
+            .contains("devsql.run_agent_commands_by_repo"),
+        "{searched}"
+    );
+
     send(
         &mut stdin,
         json!({
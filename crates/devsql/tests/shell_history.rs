@@ -219,6 +219,44 @@ fn query_unifies_atuin_zsh_and_bash_without_deduplicating() {
     assert_eq!(duplicate_count, 2);
 }
 
+#[test]
+fn time_window_keeps_only_commands_with_a_time_inside_it() {
+    let fixtures = ShellFixtures::new();
+    let commands = |window: &[&str]| -> Vec<Value> {
+        let output = fixtures
+            .command()
+            .args([
+                "SELECT source, command FROM shell_history ORDER BY timestamp",
+                "--format",
+                "json",
+            ])
+            .args(window)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        parse_json(&output).as_array().expect("rows").clone()
+    };
+
+    let zsh_only = commands(&[
+        "--since",
+        "2025-08-05T13:56:00Z",
+        "--until",
+        "2025-08-05T13:58:00Z",
+    ]);
+    assert_eq!(zsh_only.len(), 2);
+    assert!(zsh_only.iter().all(|row| row["source"] == "zsh"));
+
+    let atuin_only = commands(&["--until", "2025-08-05T13:56:00Z"]);
+    assert_eq!(atuin_only.len(), 1);
+    assert_eq!(atuin_only[0]["source"], "atuin");
+
+    let bash_only = commands(&["--since", "2025-08-05T13:58:00Z"]);
+    assert_eq!(bash_only.len(), 1);
+    assert_eq!(bash_only[0]["command"], "echo bash-shell-term");
+}
+
 #[test]
 fn missing_optional_sources_yield_an_empty_table() {
     let root = TempDir::new().expect("temp");
@@ -1,6 +1,8 @@
 use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
+use crate::sql::Attachment;
+
 #[derive(Parser, Debug)]
 #[command(
     name = "vcsql",
@@ -17,6 +19,20 @@ pub struct Args {
     #[arg(short, long, default_value = ".")]
     pub repo: Vec<PathBuf>,
 
+    /// Only load history at or after this time (RFC 3339, YYYY-MM-DD, or a
+    /// span back from now such as 7d)
+    #[arg(long, value_name = "TIME")]
+    pub since: Option<String>,
+
+    /// Only load history before this time (same formats as --since)
+    #[arg(long, value_name = "TIME")]
+    pub until: Option<String>,
+
+    /// Load a CSV, JSON, JSON Lines, or SQLite file as a table (name=path;
+    /// repeatable)
+    #[arg(long, value_name = "NAME=PATH")]
+    pub attach: Vec<Attachment>,
+
     /// Output format
     #[arg(short, long, value_enum, default_value = "table")]
     pub format: OutputFormat,
@@ -50,6 +66,10 @@ pub enum Command {
 
     /// Show example queries
     Examples,
+
+    /// Start an interactive SQL shell that keeps loaded tables between
+    /// statements
+    Shell,
 }
 
 #[derive(ValueEnum, Clone, Debug, Default)]
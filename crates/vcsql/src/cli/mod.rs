@@ -1,5 +1,7 @@
 mod args;
 mod output;
+mod shell;
 
 pub use args::{Args, Command, OutputFormat};
 pub use output::format_output;
+pub use shell::{Session, Shell, ShellColumn, ShellTable};
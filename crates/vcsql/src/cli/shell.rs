@@ -0,0 +1,410 @@
+//! Interactive SQL shell, shared by `vcsql shell` and `devsql shell`.
+//!
+//! Every statement runs against the same [`Session`], so a table loaded for
+//! one statement is still there for the next. Input is read until it forms
+//! complete SQL statements; lines starting with `.` are shell commands.
+
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+
+use clap::ValueEnum;
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+use crate::cli::{format_output, OutputFormat};
+use crate::error::{Result, VcsqlError};
+use crate::sql::engine::QueryResult;
+use crate::sql::AttachedTable;
+
+/// A table the shell lists, describes, and completes.
+#[derive(Debug, Clone)]
+pub struct ShellTable {
+    pub name: String,
+    pub description: String,
+    pub columns: Vec<ShellColumn>,
+}
+
+/// A column of a [`ShellTable`].
+#[derive(Debug, Clone)]
+pub struct ShellColumn {
+    pub name: String,
+    pub sql_type: String,
+    pub description: String,
+}
+
+impl From<AttachedTable> for ShellTable {
+    fn from(table: AttachedTable) -> Self {
+        Self {
+            description: "Attached with --attach".to_string(),
+            name: table.name,
+            columns: table
+                .columns
+                .into_iter()
+                .map(|(name, sql_type)| ShellColumn {
+                    name,
+                    sql_type,
+                    description: String::new(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The database a shell runs statements against.
+pub trait Session {
+    /// Every table that can be queried, for `.tables`, `.schema`, and tab
+    /// completion.
+    fn tables(&self) -> Vec<ShellTable>;
+
+    /// Load the tables `sql` reads that are not loaded yet, then run it.
+    fn execute(&mut self, sql: &str) -> Result<QueryResult>;
+
+    /// Drop `table` and load it from its source again. Returns every table
+    /// reloaded with it.
+    fn reload(&mut self, table: &str) -> Result<Vec<String>>;
+}
+
+const HELP: &str = "\
+.tables              List the tables that can be queried
+.schema [TABLE]      Show the columns of TABLE, or of every table
+.reload TABLE...     Read TABLE from its source again
+.format [FORMAT]     Show or set the output format: table, json, jsonl, csv
+.timer [on|off]      Show or set whether each statement is timed
+.help                Show this message
+.quit                Leave the shell (also .exit or Ctrl-D)";
+
+const COMMANDS: &[&str] = &[
+    ".tables", ".schema", ".reload", ".format", ".timer", ".help", ".quit", ".exit",
+];
+
+/// An interactive shell over a [`Session`].
+///
+/// When standard input is not a terminal the shell reads statements from it
+/// without prompting, which makes it usable from scripts, and reports
+/// failure if any statement failed.
+pub struct Shell {
+    name: String,
+    history: Option<PathBuf>,
+    format: OutputFormat,
+    timer: bool,
+    tables: Vec<ShellTable>,
+    failures: usize,
+}
+
+enum Flow {
+    Continue,
+    Quit,
+}
+
+impl Shell {
+    /// A shell whose prompt is `name`, printing tables.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            history: None,
+            format: OutputFormat::Table,
+            timer: false,
+            tables: Vec::new(),
+            failures: 0,
+        }
+    }
+
+    /// Keep interactive history in `path` across shells.
+    pub fn history(mut self, path: Option<PathBuf>) -> Self {
+        self.history = path;
+        self
+    }
+
+    /// Start in `format` instead of `table`.
+    pub fn format(mut self, format: OutputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Read and run statements until end of input or `.quit`.
+    pub fn run(mut self, session: &mut dyn Session) -> Result<()> {
+        let interactive = io::stdin().is_terminal();
+        self.tables = session.tables();
+
+        let mut editor = Editor::<Completion>::new();
+        editor.set_helper(Some(Completion::new(&self.tables)));
+        if interactive {
+            if let Some(path) = &self.history {
+                // No history yet is fine.
+                let _ = editor.load_history(path);
+            }
+            println!(
+                "{} shell. End statements with \";\", or enter .help for commands.",
+                self.name
+            );
+        }
+
+        let prompt = format!("{}> ", self.name);
+        let continuation = format!("{:>width$}> ", "...", width = self.name.len());
+        let mut buffer = String::new();
+        loop {
+            let line = match editor.readline(if buffer.is_empty() {
+                &prompt
+            } else {
+                &continuation
+            }) {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => {
+                    buffer.clear();
+                    continue;
+                }
+                Err(ReadlineError::Eof) => break,
+                Err(e) => return Err(VcsqlError::Other(e.to_string())),
+            };
+            if interactive && !line.trim().is_empty() {
+                editor.add_history_entry(line.as_str());
+            }
+
+            if buffer.is_empty() && line.trim_start().starts_with('.') {
+                match self.command(session, line.trim()) {
+                    Flow::Continue => continue,
+                    Flow::Quit => break,
+                }
+            }
+            buffer.push_str(&line);
+            buffer.push('\n');
+            if is_complete(&buffer) {
+                for statement in split_statements(&buffer) {
+                    self.statement(session, statement);
+                }
+                buffer.clear();
+            } else if buffer.trim().is_empty() {
+                buffer.clear();
+            }
+        }
+        // Input that ends without a final `;` still runs.
+        if !buffer.trim().is_empty() {
+            self.statement(session, buffer.trim());
+        }
+
+        if interactive {
+            if let Some(path) = &self.history {
+                if let Some(parent) = path.parent() {
+                    let _ = std::fs::create_dir_all(parent);
+                }
+                if let Err(e) = editor.save_history(path) {
+                    eprintln!("Warning: could not save history to {}: {e}", path.display());
+                }
+            }
+        } else if self.failures > 0 {
+            return Err(VcsqlError::Other(format!(
+                "{} statement(s) or command(s) failed",
+                self.failures
+            )));
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self, session: &mut dyn Session, sql: &str) {
+        let start = Instant::now();
+        let result = session.execute(sql).and_then(|result| {
+            // Statements such as CREATE return no columns and print nothing.
+            if result.columns.is_empty() {
+                return Ok(0);
+            }
+            let mut stdout = io::stdout().lock();
+            format_output(&result, &self.format, false, &mut stdout)?;
+            stdout.flush()?;
+            Ok(result.row_count())
+        });
+        match result {
+            Ok(rows) if self.timer => {
+                eprintln!(
+                    "Run Time: {:.3}s, {rows} row(s)",
+                    start.elapsed().as_secs_f64()
+                );
+            }
+            Ok(_) => {}
+            Err(e) => self.fail(e),
+        }
+    }
+
+    fn command(&mut self, session: &mut dyn Session, line: &str) -> Flow {
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or_default();
+        let args: Vec<&str> = words.collect();
+        match (command, args.as_slice()) {
+            (".quit" | ".exit", []) => return Flow::Quit,
+            (".help", []) => println!("{HELP}"),
+            (".tables", []) => {
+                for table in &self.tables {
+                    println!("{:24} {}", table.name, table.description);
+                }
+            }
+            (".schema", []) => {
+                for table in &self.tables {
+                    print_schema(table);
+                }
+            }
+            (".schema", [name]) => match self.tables.iter().find(|t| t.name == *name) {
+                Some(table) => print_schema(table),
+                None => self.fail(VcsqlError::TableNotFound(name.to_string())),
+            },
+            (".reload", [_, ..]) => {
+                for name in args {
+                    if !self.tables.iter().any(|t| t.name == name) {
+                        self.fail(VcsqlError::TableNotFound(name.to_string()));
+                        continue;
+                    }
+                    let start = Instant::now();
+                    match session.reload(name) {
+                        Ok(tables) => eprintln!(
+                            "Reloaded {} in {:.3}s",
+                            tables.join(", "),
+                            start.elapsed().as_secs_f64()
+                        ),
+                        Err(e) => self.fail(e),
+                    }
+                }
+            }
+            (".format", []) => {
+                if let Some(value) = self.format.to_possible_value() {
+                    println!("{}", value.get_name());
+                }
+            }
+            (".format", [format]) => match OutputFormat::from_str(format, true) {
+                Ok(format) => self.format = format,
+                Err(_) => self.fail(VcsqlError::Other(format!(
+                    "unknown format {format}; use table, json, jsonl, or csv"
+                ))),
+            },
+            (".timer", []) => println!("{}", if self.timer { "on" } else { "off" }),
+            (".timer", ["on"]) => self.timer = true,
+            (".timer", ["off"]) => self.timer = false,
+            _ => self.fail(VcsqlError::Other(format!(
+                "unknown command or arguments: {line}; enter .help for commands"
+            ))),
+        }
+        Flow::Continue
+    }
+
+    fn fail(&mut self, error: VcsqlError) {
+        self.failures += 1;
+        eprintln!("Error: {error}");
+    }
+}
+
+fn print_schema(table: &ShellTable) {
+    println!("\nTABLE: {}", table.name);
+    println!("{}", table.description);
+    println!("\nCOLUMNS:");
+    for column in &table.columns {
+        println!(
+            "  {:20} {:10} {}",
+            column.name, column.sql_type, column.description
+        );
+    }
+}
+
+/// Whether `sql` ends at the end of a statement, by SQLite's own rules:
+/// a `;` outside any string, identifier, comment, or trigger body.
+fn is_complete(sql: &str) -> bool {
+    let Ok(sql) = std::ffi::CString::new(sql) else {
+        return true;
+    };
+    // SAFETY: `sql` is a valid NUL-terminated string for the whole call.
+    unsafe { rusqlite::ffi::sqlite3_complete(sql.as_ptr()) != 0 }
+}
+
+/// Split complete input into its statements, each with its `;`.
+fn split_statements(sql: &str) -> Vec<&str> {
+    let mut statements = Vec::new();
+    let mut start = 0;
+    for (end, _) in sql.match_indices(';') {
+        let statement = &sql[start..=end];
+        if is_complete(statement) {
+            if !statement.trim().trim_end_matches(';').trim().is_empty() {
+                statements.push(statement.trim());
+            }
+            start = end + 1;
+        }
+    }
+    statements
+}
+
+/// Tab completion of shell commands, table names, and column names.
+struct Completion {
+    tables: Vec<String>,
+    /// Table and column names, for completing SQL.
+    words: Vec<String>,
+}
+
+impl Completion {
+    fn new(tables: &[ShellTable]) -> Self {
+        let names: Vec<String> = tables.iter().map(|table| table.name.clone()).collect();
+        let mut words = names.clone();
+        words.extend(
+            tables
+                .iter()
+                .flat_map(|table| table.columns.iter().map(|column| column.name.clone())),
+        );
+        words.sort();
+        words.dedup();
+        Self {
+            tables: names,
+            words,
+        }
+    }
+}
+
+impl Completer for Completion {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let before = &line[..pos];
+        let indent = before.len() - before.trim_start().len();
+        if before[indent..].starts_with('.') && !before[indent..].contains(char::is_whitespace) {
+            let commands = COMMANDS.iter().copied();
+            return Ok((indent, matching(commands, &before[indent..])));
+        }
+
+        let start = before
+            .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .map_or(0, |i| i + 1);
+        // Arguments of `.schema` and `.reload` are table names.
+        let candidates = if before[indent..].starts_with('.') {
+            &self.tables
+        } else {
+            &self.words
+        };
+        let candidates = candidates.iter().map(String::as_str);
+        Ok((start, matching(candidates, &before[start..])))
+    }
+}
+
+/// `candidates` that start with `prefix`, ignoring case.
+fn matching<'a>(candidates: impl Iterator<Item = &'a str>, prefix: &str) -> Vec<Pair> {
+    let prefix = prefix.to_lowercase();
+    candidates
+        .filter(|candidate| candidate.to_lowercase().starts_with(&prefix))
+        .map(|candidate| Pair {
+            display: candidate.to_string(),
+            replacement: candidate.to_string(),
+        })
+        .collect()
+}
+
+impl Hinter for Completion {
+    type Hint = String;
+}
+
+impl Highlighter for Completion {}
+
+impl Validator for Completion {}
+
+impl Helper for Completion {}
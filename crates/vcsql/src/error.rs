@@ -20,6 +20,9 @@ pub enum VcsqlError {
     #[error("Repository not found: {0}")]
     RepoNotFound(String),
 
+    #[error("Invalid time: {0}")]
+    InvalidTime(String),
+
     #[error("Table not found: {0}")]
     TableNotFound(String),
 
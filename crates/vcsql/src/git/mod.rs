@@ -1,3 +1,5 @@
 mod repository;
+mod window;
 
 pub use repository::GitRepo;
+pub use window::TimeWindow;
@@ -1,5 +1,6 @@
 //! Git repository wrapper for vcsql.
 
+use super::TimeWindow;
 use crate::error::{Result, VcsqlError};
 use git2::{BranchType, Commit, Reference, Repository};
 use std::path::Path;
@@ -21,6 +22,7 @@ use std::path::Path;
 pub struct GitRepo {
     repo: Repository,
     path: String,
+    window: TimeWindow,
 }
 
 impl GitRepo {
@@ -50,9 +52,21 @@ impl GitRepo {
         Ok(Self {
             repo,
             path: workdir,
+            window: TimeWindow::default(),
         })
     }
 
+    /// Limits history to `window`: [`GitRepo::walk_commits`] and the reflog
+    /// and stash providers skip entries outside it.
+    pub fn set_time_window(&mut self, window: TimeWindow) {
+        self.window = window;
+    }
+
+    /// Returns the window history is limited to.
+    pub fn time_window(&self) -> &TimeWindow {
+        &self.window
+    }
+
     /// Returns the working directory path of the repository.
     pub fn path(&self) -> &str {
         &self.path
@@ -78,18 +92,40 @@ impl GitRepo {
         Ok(commit)
     }
 
+    /// Walks the commits reachable from HEAD, newest first.
+    ///
+    /// With a time window set, commits made at or after `until` are skipped
+    /// and the walk stops at the first commit made before `since`, so older
+    /// history is never read. Both compare the commit time, which orders the
+    /// walk.
     pub fn walk_commits(&self) -> Result<impl Iterator<Item = Result<Commit<'_>>>> {
         let mut revwalk = self.repo.revwalk()?;
-        revwalk.push_head()?;
+        // An unborn HEAD (fresh `git init`) simply has no history to walk.
+        match revwalk.push_head() {
+            Ok(()) => {}
+            Err(e)
+                if matches!(
+                    e.code(),
+                    git2::ErrorCode::UnbornBranch | git2::ErrorCode::NotFound
+                ) => {}
+            Err(e) => return Err(VcsqlError::Git(e)),
+        }
         revwalk.set_sorting(git2::Sort::TIME | git2::Sort::TOPOLOGICAL)?;
 
-        Ok(revwalk.map(move |oid_result| match oid_result {
-            Ok(oid) => self
-                .repo
-                .find_commit(oid)
-                .map_err(VcsqlError::Git),
-            Err(e) => Err(VcsqlError::Git(e)),
-        }))
+        let window = self.window;
+        Ok(revwalk
+            .map(move |oid_result| match oid_result {
+                Ok(oid) => self.repo.find_commit(oid).map_err(VcsqlError::Git),
+                Err(e) => Err(VcsqlError::Git(e)),
+            })
+            .take_while(move |commit| match (commit, window.since) {
+                (Ok(commit), Some(since)) => commit.time().seconds() >= since.timestamp(),
+                _ => true,
+            })
+            .filter(move |commit| match (commit, window.until) {
+                (Ok(commit), Some(until)) => commit.time().seconds() < until.timestamp(),
+                _ => true,
+            }))
     }
 
     pub fn branches(&self, branch_type: Option<BranchType>) -> Result<git2::Branches<'_>> {
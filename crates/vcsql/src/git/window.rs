@@ -0,0 +1,107 @@
+//! Time windows that limit how much history providers load.
+
+use crate::error::{Result, VcsqlError};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+
+/// A half-open time range `[since, until)`; either end may be open.
+///
+/// Bounds are parsed from RFC 3339 timestamps (`2026-01-05T09:00:00Z`),
+/// dates (`2026-01-05`, midnight UTC), UTC date-times without an offset
+/// (`2026-01-05 09:00:00`), or a span back from now (`90m`, `36h`, `7d`,
+/// `2w`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimeWindow {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl TimeWindow {
+    /// Parses `--since` and `--until` values, with spans counted back from
+    /// the current time.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VcsqlError::InvalidTime` if a bound is not in a recognized
+    /// format, or if `since` is not before `until`.
+    pub fn parse(since: Option<&str>, until: Option<&str>) -> Result<Self> {
+        Self::parse_at(since, until, Utc::now())
+    }
+
+    /// Like [`TimeWindow::parse`], counting spans back from `now`.
+    pub fn parse_at(since: Option<&str>, until: Option<&str>, now: DateTime<Utc>) -> Result<Self> {
+        let window = Self {
+            since: since.map(|text| parse_instant(text, now)).transpose()?,
+            until: until.map(|text| parse_instant(text, now)).transpose()?,
+        };
+        if let (Some(since), Some(until)) = (window.since, window.until) {
+            if since >= until {
+                return Err(VcsqlError::InvalidTime(format!(
+                    "--since ({}) must be before --until ({})",
+                    since.to_rfc3339(),
+                    until.to_rfc3339()
+                )));
+            }
+        }
+        Ok(window)
+    }
+
+    /// Returns `true` if neither end is bounded.
+    pub fn is_unbounded(&self) -> bool {
+        self.since.is_none() && self.until.is_none()
+    }
+
+    /// Returns `true` if `time` falls inside the window.
+    pub fn contains(&self, time: DateTime<Utc>) -> bool {
+        self.since.is_none_or(|since| time >= since) && self.until.is_none_or(|until| time < until)
+    }
+
+    /// Returns `true` if the Unix time `seconds` falls inside the window.
+    pub fn contains_seconds(&self, seconds: i64) -> bool {
+        DateTime::from_timestamp(seconds, 0).is_some_and(|time| self.contains(time))
+    }
+
+    /// Returns `true` if the RFC 3339 timestamp `text` falls inside the
+    /// window. A timestamp that does not parse is inside only an unbounded
+    /// window.
+    pub fn contains_rfc3339(&self, text: &str) -> bool {
+        if self.is_unbounded() {
+            return true;
+        }
+        DateTime::parse_from_rfc3339(text).is_ok_and(|time| self.contains(time.with_timezone(&Utc)))
+    }
+}
+
+fn parse_instant(text: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let text = text.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S"] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(text, format) {
+            return Ok(time.and_utc());
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        return Ok(date.and_time(chrono::NaiveTime::MIN).and_utc());
+    }
+    if let Some(span) = parse_span(text) {
+        return Ok(now - span);
+    }
+    Err(VcsqlError::InvalidTime(format!(
+        "`{text}` is not a timestamp, a YYYY-MM-DD date, or a span like 7d"
+    )))
+}
+
+fn parse_span(text: &str) -> Option<Duration> {
+    let split = text.find(|c: char| !c.is_ascii_digit())?;
+    let (count, unit) = text.split_at(split);
+    let count: i64 = count.parse().ok()?;
+    match unit {
+        "s" => Duration::try_seconds(count),
+        "m" => Duration::try_minutes(count),
+        "h" => Duration::try_hours(count),
+        "d" => Duration::try_days(count),
+        "w" => Duration::try_weeks(count),
+        _ => None,
+    }
+}
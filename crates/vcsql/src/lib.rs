@@ -39,9 +39,10 @@ pub mod cli;
 pub mod error;
 pub mod git;
 pub mod providers;
+pub mod redaction;
 pub mod sql;
 
 pub use cli::{Args, Command, OutputFormat};
 pub use error::{Result, VcsqlError};
-pub use git::GitRepo;
-pub use sql::{SqlEngine, TableInfo, TABLES};
+pub use git::{GitRepo, TimeWindow};
+pub use sql::{Example, SqlEngine, TableInfo, EXAMPLES, TABLES};
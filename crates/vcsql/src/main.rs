@@ -1,11 +1,13 @@
 use anyhow::{Context, Result};
 use clap::Parser;
 use std::io;
+use std::path::PathBuf;
 use std::time::Instant;
 
-use vcsql::cli::{format_output, Args, Command};
-use vcsql::git::GitRepo;
-use vcsql::sql::engine::SqlEngine;
+use vcsql::cli::{format_output, Args, Command, Session, Shell, ShellColumn, ShellTable};
+use vcsql::git::{GitRepo, TimeWindow};
+use vcsql::sql::engine::{QueryResult, SqlEngine};
+use vcsql::sql::examples::EXAMPLES;
 use vcsql::sql::schema::{get_table_info, get_tables_by_category, TABLES};
 
 fn main() -> Result<()> {
@@ -15,6 +17,7 @@ fn main() -> Result<()> {
         Some(Command::Tables) => show_tables(),
         Some(Command::Schema { table }) => show_schema(table.as_deref()),
         Some(Command::Examples) => show_examples(),
+        Some(Command::Shell) => run_shell(&args),
         None => {
             if let Some(sql) = &args.sql {
                 execute_query(&args, sql)
@@ -33,11 +36,14 @@ fn main() -> Result<()> {
 fn execute_query(args: &Args, sql: &str) -> Result<()> {
     let start = Instant::now();
 
+    let window = TimeWindow::parse(args.since.as_deref(), args.until.as_deref())?;
     let mut engine = SqlEngine::new()?;
+    attach_files(&mut engine, args)?;
 
     for repo_path in &args.repo {
         let mut repo = GitRepo::open(repo_path)
             .with_context(|| format!("Failed to open repository: {}", repo_path.display()))?;
+        repo.set_time_window(window);
 
         engine
             .load_tables_for_query(sql, &mut repo)
@@ -63,6 +69,88 @@ fn execute_query(args: &Args, sql: &str) -> Result<()> {
     Ok(())
 }
 
+/// Loads every `--attach` file into the engine.
+fn attach_files(engine: &mut SqlEngine, args: &Args) -> Result<Vec<ShellTable>> {
+    let mut tables = Vec::new();
+    for attachment in &args.attach {
+        let attached = engine
+            .attach(attachment)
+            .with_context(|| format!("Failed to attach {}", attachment.name))?;
+        tables.extend(attached.into_iter().map(ShellTable::from));
+    }
+    Ok(tables)
+}
+
+/// The repositories a shell reads, opened once, and the engine holding
+/// every table loaded so far.
+struct GitSession {
+    engine: SqlEngine,
+    repos: Vec<GitRepo>,
+    attached: Vec<ShellTable>,
+}
+
+impl Session for GitSession {
+    fn tables(&self) -> Vec<ShellTable> {
+        TABLES
+            .iter()
+            .map(|info| ShellTable {
+                name: info.name.to_string(),
+                description: info.description.to_string(),
+                columns: info
+                    .columns
+                    .iter()
+                    .map(|col| ShellColumn {
+                        name: col.name.to_string(),
+                        sql_type: col.sql_type.to_string(),
+                        description: col.description.to_string(),
+                    })
+                    .collect(),
+            })
+            .chain(self.attached.iter().cloned())
+            .collect()
+    }
+
+    fn execute(&mut self, sql: &str) -> vcsql::Result<QueryResult> {
+        for repo in &mut self.repos {
+            self.engine.load_tables_for_query(sql, repo)?;
+        }
+        self.engine.execute(sql)
+    }
+
+    fn reload(&mut self, table: &str) -> vcsql::Result<Vec<String>> {
+        self.engine.unload_table(table)?;
+        for repo in &mut self.repos {
+            self.engine.load_table(table, repo)?;
+        }
+        Ok(vec![table.to_string()])
+    }
+}
+
+fn run_shell(args: &Args) -> Result<()> {
+    let window = TimeWindow::parse(args.since.as_deref(), args.until.as_deref())?;
+    let mut repos = Vec::new();
+    for repo_path in &args.repo {
+        let mut repo = GitRepo::open(repo_path)
+            .with_context(|| format!("Failed to open repository: {}", repo_path.display()))?;
+        repo.set_time_window(window);
+        repos.push(repo);
+    }
+    let mut engine = SqlEngine::new()?;
+    let attached = attach_files(&mut engine, args)?;
+    let mut session = GitSession {
+        engine,
+        repos,
+        attached,
+    };
+
+    let history = std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".vcsql_history"));
+    Shell::new("vcsql")
+        .history(history)
+        .format(args.format.clone())
+        .run(&mut session)?;
+    Ok(())
+}
+
 fn show_tables() -> Result<()> {
     println!("\nAvailable tables:\n");
 
@@ -132,65 +220,27 @@ fn print_table_schema(info: &vcsql::sql::TableInfo) {
 }
 
 fn show_examples() -> Result<()> {
+    println!("\nVCSQL EXAMPLE QUERIES\n====================\n");
+
+    let mut category = "";
+    for example in EXAMPLES {
+        if example.category != category {
+            category = example.category;
+            println!("{}\n{}\n", category, "-".repeat(category.len()));
+        }
+        println!("  # {}", example.description);
+        let mut lines = example.sql.lines();
+        if let Some(first) = lines.next() {
+            print!("  vcsql \"{}", first);
+        }
+        for line in lines {
+            print!("\n         {}", line);
+        }
+        println!("\"\n");
+    }
+
     println!(
-        r#"
-VCSQL EXAMPLE QUERIES
-====================
-
-BASIC QUERIES
--------------
-
-  # Recent commits
-  vcsql "SELECT short_id, summary, authored_at
-         FROM commits
-         ORDER BY authored_at DESC
-         LIMIT 10"
-
-  # Current branch
-  vcsql "SELECT name FROM branches WHERE is_head = 1"
-
-  # All branches with their targets
-  vcsql "SELECT name, target_id, is_remote FROM branches"
-
-ANALYTICS
----------
-
-  # Commits by author
-  vcsql "SELECT author_name, COUNT(*) as commits
-         FROM commits
-         GROUP BY author_name
-         ORDER BY commits DESC"
-
-  # Commits by day of week
-  vcsql "SELECT
-           CASE CAST(strftime('%w', authored_at) AS INTEGER)
-             WHEN 0 THEN 'Sun' WHEN 1 THEN 'Mon'
-             WHEN 2 THEN 'Tue' WHEN 3 THEN 'Wed'
-             WHEN 4 THEN 'Thu' WHEN 5 THEN 'Fri' WHEN 6 THEN 'Sat'
-           END as day,
-           COUNT(*) as commits
-         FROM commits
-         GROUP BY day"
-
-  # Merge commits
-  vcsql "SELECT short_id, summary FROM commits WHERE is_merge = 1"
-
-JOINS
------
-
-  # Commits with branch info
-  vcsql "SELECT c.short_id, c.summary, b.name as branch
-         FROM commits c
-         JOIN branches b ON b.target_id = c.id"
-
-  # Find merge commits with their parents
-  vcsql "SELECT c.summary, p.parent_id, p.parent_index
-         FROM commits c
-         JOIN commit_parents p ON p.commit_id = c.id
-         WHERE c.is_merge = 1
-         LIMIT 10"
-
-OUTPUT FORMATS
+        r#"OUTPUT FORMATS
 --------------
 
   # JSON output
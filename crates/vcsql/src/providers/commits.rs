@@ -1,7 +1,8 @@
 use crate::error::Result;
 use crate::git::GitRepo;
-use crate::providers::Provider;
+use crate::providers::{CommitProvider, Provider};
 use chrono::{TimeZone, Utc};
+use git2::Commit;
 use rusqlite::Connection;
 
 pub struct CommitsProvider;
@@ -12,7 +13,23 @@ impl Provider for CommitsProvider {
     }
 
     fn populate(&self, conn: &Connection, repo: &mut GitRepo) -> Result<()> {
-        let mut stmt = conn.prepare(
+        for commit_result in repo.walk_commits()? {
+            let commit = commit_result?;
+            self.populate_commit(conn, repo, &commit)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl CommitProvider for CommitsProvider {
+    fn populate_commit(
+        &self,
+        conn: &Connection,
+        repo: &GitRepo,
+        commit: &Commit<'_>,
+    ) -> Result<()> {
+        let mut stmt = conn.prepare_cached(
             r#"
             INSERT INTO commits (
                 id, short_id, tree_id,
@@ -26,53 +43,47 @@ impl Provider for CommitsProvider {
             "#,
         )?;
 
-        let repo_path = repo.path().to_string();
-
-        for commit_result in repo.walk_commits()? {
-            let commit = commit_result?;
-
-            let id = commit.id().to_string();
-            let short_id = &id[..7.min(id.len())];
-            let tree_id = commit.tree_id().to_string();
+        let id = commit.id().to_string();
+        let short_id = &id[..7.min(id.len())];
+        let tree_id = commit.tree_id().to_string();
 
-            let author = commit.author();
-            let author_name = author.name().unwrap_or("").to_string();
-            let author_email = author.email().unwrap_or("").to_string();
-            let authored_at = format_git_time(author.when());
+        let author = commit.author();
+        let author_name = author.name().unwrap_or("").to_string();
+        let author_email = author.email().unwrap_or("").to_string();
+        let authored_at = format_git_time(author.when());
 
-            let committer = commit.committer();
-            let committer_name = committer.name().unwrap_or("").to_string();
-            let committer_email = committer.email().unwrap_or("").to_string();
-            let committed_at = format_git_time(committer.when());
+        let committer = commit.committer();
+        let committer_name = committer.name().unwrap_or("").to_string();
+        let committer_email = committer.email().unwrap_or("").to_string();
+        let committed_at = format_git_time(committer.when());
 
-            let message = commit.message().unwrap_or("").to_string();
-            let summary = commit.summary().unwrap_or("").to_string();
-            let body = commit
-                .body()
-                .map(|s| s.to_string())
-                .filter(|s| !s.is_empty());
+        let message = commit.message().unwrap_or("").to_string();
+        let summary = commit.summary().unwrap_or("").to_string();
+        let body = commit
+            .body()
+            .map(|s| s.to_string())
+            .filter(|s| !s.is_empty());
 
-            let parent_count = commit.parent_count() as i64;
-            let is_merge = if parent_count > 1 { 1 } else { 0 };
+        let parent_count = commit.parent_count() as i64;
+        let is_merge = if parent_count > 1 { 1 } else { 0 };
 
-            stmt.execute((
-                &id,
-                short_id,
-                &tree_id,
-                &author_name,
-                &author_email,
-                &authored_at,
-                &committer_name,
-                &committer_email,
-                &committed_at,
-                &message,
-                &summary,
-                &body,
-                parent_count,
-                is_merge,
-                &repo_path,
-            ))?;
-        }
+        stmt.execute((
+            &id,
+            short_id,
+            &tree_id,
+            &author_name,
+            &author_email,
+            &authored_at,
+            &committer_name,
+            &committer_email,
+            &committed_at,
+            &message,
+            &summary,
+            &body,
+            parent_count,
+            is_merge,
+            repo.path(),
+        ))?;
 
         Ok(())
     }
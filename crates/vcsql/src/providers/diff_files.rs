@@ -1,7 +1,7 @@
 use crate::error::Result;
 use crate::git::GitRepo;
-use crate::providers::Provider;
-use git2::{Delta, DiffOptions};
+use crate::providers::{CommitProvider, Provider};
+use git2::{Commit, Delta, DiffOptions};
 use rusqlite::Connection;
 
 pub struct DiffFilesProvider;
@@ -12,42 +12,55 @@ impl Provider for DiffFilesProvider {
     }
 
     fn populate(&self, conn: &Connection, repo: &mut GitRepo) -> Result<()> {
-        let mut stmt = conn.prepare(
+        for commit_result in repo.walk_commits()? {
+            let commit = commit_result?;
+            self.populate_commit(conn, repo, &commit)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl CommitProvider for DiffFilesProvider {
+    fn populate_commit(
+        &self,
+        conn: &Connection,
+        repo: &GitRepo,
+        commit: &Commit<'_>,
+    ) -> Result<()> {
+        let mut stmt = conn.prepare_cached(
             r#"
             INSERT INTO diff_files (
-                commit_id, parent_id, old_path, new_path, status,
+                commit_id, parent_id, old_path, new_path, path, status,
                 insertions, deletions, is_binary, similarity, repo
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
             "#,
         )?;
 
-        let repo_path = repo.path().to_string();
+        let repo_path = repo.path();
         let git_repo = repo.inner();
 
         let mut diff_opts = DiffOptions::new();
         diff_opts.include_untracked(false);
 
-        for commit_result in repo.walk_commits()? {
-            let commit = commit_result?;
-            let commit_id = commit.id().to_string();
-            let tree = commit.tree()?;
-
-            if commit.parent_count() == 0 {
-                // Root commit
-                let diff = git_repo.diff_tree_to_tree(None, Some(&tree), Some(&mut diff_opts))?;
-                insert_diff_files(&mut stmt, &diff, &commit_id, None, &repo_path)?;
-            } else {
-                for parent in commit.parents() {
-                    let parent_id = parent.id().to_string();
-                    let parent_tree = parent.tree()?;
-
-                    let diff = git_repo.diff_tree_to_tree(
-                        Some(&parent_tree),
-                        Some(&tree),
-                        Some(&mut diff_opts),
-                    )?;
-                    insert_diff_files(&mut stmt, &diff, &commit_id, Some(&parent_id), &repo_path)?;
-                }
+        let commit_id = commit.id().to_string();
+        let tree = commit.tree()?;
+
+        if commit.parent_count() == 0 {
+            // Root commit
+            let diff = git_repo.diff_tree_to_tree(None, Some(&tree), Some(&mut diff_opts))?;
+            insert_diff_files(&mut stmt, &diff, &commit_id, None, repo_path)?;
+        } else {
+            for parent in commit.parents() {
+                let parent_id = parent.id().to_string();
+                let parent_tree = parent.tree()?;
+
+                let diff = git_repo.diff_tree_to_tree(
+                    Some(&parent_tree),
+                    Some(&tree),
+                    Some(&mut diff_opts),
+                )?;
+                insert_diff_files(&mut stmt, &diff, &commit_id, Some(&parent_id), repo_path)?;
             }
         }
 
@@ -71,6 +84,10 @@ fn insert_diff_files(
             .new_file()
             .path()
             .map(|p| p.to_string_lossy().to_string());
+        let path = new_path
+            .clone()
+            .or_else(|| old_path.clone())
+            .unwrap_or_default();
 
         let status = match delta.status() {
             Delta::Added => "A",
@@ -107,6 +124,7 @@ fn insert_diff_files(
             parent_id,
             &old_path,
             &new_path,
+            &path,
             status,
             insertions,
             deletions,
@@ -1,6 +1,7 @@
 use crate::error::Result;
 use crate::git::GitRepo;
-use crate::providers::Provider;
+use crate::providers::{CommitProvider, Provider};
+use git2::Commit;
 use rusqlite::Connection;
 
 pub struct DiffsProvider;
@@ -11,7 +12,23 @@ impl Provider for DiffsProvider {
     }
 
     fn populate(&self, conn: &Connection, repo: &mut GitRepo) -> Result<()> {
-        let mut stmt = conn.prepare(
+        for commit_result in repo.walk_commits()? {
+            let commit = commit_result?;
+            self.populate_commit(conn, repo, &commit)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl CommitProvider for DiffsProvider {
+    fn populate_commit(
+        &self,
+        conn: &Connection,
+        repo: &GitRepo,
+        commit: &Commit<'_>,
+    ) -> Result<()> {
+        let mut stmt = conn.prepare_cached(
             r#"
             INSERT INTO diffs (
                 commit_id, parent_id, files_changed, insertions, deletions, repo
@@ -19,45 +36,41 @@ impl Provider for DiffsProvider {
             "#,
         )?;
 
-        let repo_path = repo.path().to_string();
+        let repo_path = repo.path();
         let git_repo = repo.inner();
+        let commit_id = commit.id().to_string();
+        let tree = commit.tree()?;
 
-        for commit_result in repo.walk_commits()? {
-            let commit = commit_result?;
-            let commit_id = commit.id().to_string();
-            let tree = commit.tree()?;
+        if commit.parent_count() == 0 {
+            // Root commit - diff against empty tree
+            let diff = git_repo.diff_tree_to_tree(None, Some(&tree), None)?;
+            let stats = diff.stats()?;
 
-            if commit.parent_count() == 0 {
-                // Root commit - diff against empty tree
-                let diff = git_repo.diff_tree_to_tree(None, Some(&tree), None)?;
+            stmt.execute((
+                &commit_id,
+                Option::<String>::None,
+                stats.files_changed() as i64,
+                stats.insertions() as i64,
+                stats.deletions() as i64,
+                repo_path,
+            ))?;
+        } else {
+            // Diff against each parent
+            for parent in commit.parents() {
+                let parent_id = parent.id().to_string();
+                let parent_tree = parent.tree()?;
+
+                let diff = git_repo.diff_tree_to_tree(Some(&parent_tree), Some(&tree), None)?;
                 let stats = diff.stats()?;
 
                 stmt.execute((
                     &commit_id,
-                    Option::<String>::None,
+                    Some(&parent_id),
                     stats.files_changed() as i64,
                     stats.insertions() as i64,
                     stats.deletions() as i64,
-                    &repo_path,
+                    repo_path,
                 ))?;
-            } else {
-                // Diff against each parent
-                for parent in commit.parents() {
-                    let parent_id = parent.id().to_string();
-                    let parent_tree = parent.tree()?;
-
-                    let diff = git_repo.diff_tree_to_tree(Some(&parent_tree), Some(&tree), None)?;
-                    let stats = diff.stats()?;
-
-                    stmt.execute((
-                        &commit_id,
-                        Some(&parent_id),
-                        stats.files_changed() as i64,
-                        stats.insertions() as i64,
-                        stats.deletions() as i64,
-                        &repo_path,
-                    ))?;
-                }
             }
         }
 
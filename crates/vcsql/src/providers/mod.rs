@@ -36,9 +36,55 @@ pub use worktrees::WorktreesProvider;
 
 use crate::error::Result;
 use crate::git::GitRepo;
+use git2::Commit;
 use rusqlite::Connection;
 
 pub trait Provider {
     fn table_name(&self) -> &'static str;
     fn populate(&self, conn: &Connection, repo: &mut GitRepo) -> Result<()>;
 }
+
+/// A provider whose rows are derived from a single commit, so callers can
+/// index history incrementally instead of re-walking it.
+pub trait CommitProvider: Provider {
+    /// Inserts the rows this table holds for `commit`.
+    fn populate_commit(&self, conn: &Connection, repo: &GitRepo, commit: &Commit<'_>)
+        -> Result<()>;
+}
+
+/// Returns the provider that populates `table_name`, if vcsql knows the table.
+pub fn provider_for(table_name: &str) -> Option<Box<dyn Provider>> {
+    let provider: Box<dyn Provider> = match table_name {
+        "commits" => Box::new(CommitsProvider),
+        "commit_parents" => Box::new(CommitParentsProvider),
+        "branches" => Box::new(BranchesProvider),
+        "tags" => Box::new(TagsProvider),
+        "refs" => Box::new(RefsProvider),
+        "stashes" => Box::new(StashesProvider),
+        "reflog" => Box::new(ReflogProvider),
+        "diffs" => Box::new(DiffsProvider),
+        "diff_files" => Box::new(DiffFilesProvider),
+        "blame" => Box::new(BlameProvider::new(None)),
+        "config" => Box::new(ConfigProvider),
+        "remotes" => Box::new(RemotesProvider),
+        "submodules" => Box::new(SubmodulesProvider),
+        "status" => Box::new(StatusProvider),
+        "worktrees" => Box::new(WorktreesProvider),
+        "hooks" => Box::new(HooksProvider),
+        "notes" => Box::new(NotesProvider),
+        _ => return None,
+    };
+    Some(provider)
+}
+
+/// Returns the per-commit provider for `table_name` (`commits`, `diffs`, or
+/// `diff_files`).
+pub fn commit_provider_for(table_name: &str) -> Option<Box<dyn CommitProvider>> {
+    let provider: Box<dyn CommitProvider> = match table_name {
+        "commits" => Box::new(CommitsProvider),
+        "diffs" => Box::new(DiffsProvider),
+        "diff_files" => Box::new(DiffFilesProvider),
+        _ => return None,
+    };
+    Some(provider)
+}
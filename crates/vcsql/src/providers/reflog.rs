@@ -23,6 +23,7 @@ impl Provider for ReflogProvider {
         )?;
 
         let repo_path = repo.path().to_string();
+        let window = *repo.time_window();
         let git_repo = repo.inner();
 
         // Get reflog for HEAD
@@ -32,6 +33,9 @@ impl Provider for ReflogProvider {
                 let new_id = entry.id_new().to_string();
 
                 let committer = entry.committer();
+                if !window.contains_seconds(committer.when().seconds()) {
+                    continue;
+                }
                 let committer_name = committer.name().unwrap_or("").to_string();
                 let committer_email = committer.email().unwrap_or("").to_string();
                 let committed_at = format_git_time(committer.when());
@@ -64,6 +68,9 @@ impl Provider for ReflogProvider {
                             let new_id = entry.id_new().to_string();
 
                             let committer = entry.committer();
+                            if !window.contains_seconds(committer.when().seconds()) {
+                                continue;
+                            }
                             let committer_name = committer.name().unwrap_or("").to_string();
                             let committer_email = committer.email().unwrap_or("").to_string();
                             let committed_at = format_git_time(committer.when());
@@ -43,6 +43,9 @@ impl Provider for StashesProvider {
                 let commit_id = oid.to_string();
 
                 let author = commit.author();
+                if !repo.time_window().contains_seconds(author.when().seconds()) {
+                    continue;
+                }
                 let author_name = author.name().unwrap_or("").to_string();
                 let author_email = author.email().unwrap_or("").to_string();
                 let created_at = format_git_time(author.when());
@@ -1,7 +1,11 @@
+//! Masking of tokens, passwords, and other secrets in free text.
+
 use regex::Regex;
 use std::sync::OnceLock;
 
-pub(crate) fn redact_sensitive_text(text: &str) -> String {
+/// Replaces bearer tokens, API keys, passwords, credentials in URLs, and
+/// similar secrets in `text` with `<redacted>`.
+pub fn redact_sensitive_text(text: &str) -> String {
     let mut redacted = json_secret_regex()
         .replace_all(text, "${1}<redacted>${2}")
         .into_owned();
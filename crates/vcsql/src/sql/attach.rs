@@ -0,0 +1,383 @@
+//! Load external files into an engine's connection as ad-hoc tables.
+//!
+//! `--attach name=path` reads a CSV (or TSV) file, a JSON array, JSON Lines,
+//! or an existing SQLite database. Delimited and JSON files become one
+//! temporary table called `name`, with each column's type inferred from its
+//! values; a SQLite database is attached read-only as the schema `name`, and
+//! when it holds a single table that table is also readable as `name`.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params_from_iter, Connection};
+use serde_json::Value;
+
+use crate::error::{Result, VcsqlError};
+use crate::sql::engine::quote_identifier;
+
+/// An external file to load under a table name, parsed from `name=path`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attachment {
+    /// The table (or, for a SQLite database, schema) name.
+    pub name: String,
+    /// The file to read.
+    pub path: PathBuf,
+}
+
+impl FromStr for Attachment {
+    type Err = VcsqlError;
+
+    fn from_str(spec: &str) -> Result<Self> {
+        let (name, path) = spec
+            .split_once('=')
+            .ok_or_else(|| VcsqlError::Other(format!("--attach takes name=path, got `{spec}`")))?;
+        let name = name.trim();
+        let valid = name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(VcsqlError::Other(format!(
+                "attachment name `{name}` must be letters, digits, and underscores"
+            )));
+        }
+        if path.is_empty() {
+            return Err(VcsqlError::Other(format!(
+                "--attach {name}= needs a file path"
+            )));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            path: PathBuf::from(path),
+        })
+    }
+}
+
+/// A table made readable by [`attach`].
+#[derive(Debug, Clone)]
+pub struct AttachedTable {
+    /// The name to query it by: `name`, or `name.table` inside a database.
+    pub name: String,
+    /// Column names and declared types, in order.
+    pub columns: Vec<(String, String)>,
+}
+
+/// How a file is read, from its extension or, failing that, its contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Delimited(u8),
+    Json,
+    Sqlite,
+}
+
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+fn detect_format(path: &Path) -> Result<Format> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("csv") => return Ok(Format::Delimited(b',')),
+        Some("tsv" | "tab") => return Ok(Format::Delimited(b'\t')),
+        Some("json" | "jsonl" | "ndjson") => return Ok(Format::Json),
+        Some("sqlite" | "sqlite3" | "db") => return Ok(Format::Sqlite),
+        _ => {}
+    }
+    let mut head = Vec::new();
+    File::open(path)
+        .and_then(|file| file.take(512).read_to_end(&mut head))
+        .map_err(|e| file_error(path, e))?;
+    if head.starts_with(SQLITE_HEADER) {
+        return Ok(Format::Sqlite);
+    }
+    match head.iter().find(|b| !b.is_ascii_whitespace()) {
+        Some(b'[' | b'{') => Ok(Format::Json),
+        _ => Ok(Format::Delimited(b',')),
+    }
+}
+
+fn file_error(path: &Path, error: impl std::fmt::Display) -> VcsqlError {
+    VcsqlError::Other(format!("{}: {error}", path.display()))
+}
+
+/// Loads `attachment` into `conn` and returns the tables it added.
+///
+/// Tables are created in the `temp` schema, so this works on a read-only
+/// connection and an unqualified `name` finds them before any table of the
+/// same name in `main`.
+pub fn attach(conn: &Connection, attachment: &Attachment) -> Result<Vec<AttachedTable>> {
+    let path = attachment.path.as_path();
+    match detect_format(path)? {
+        Format::Sqlite => attach_database(conn, &attachment.name, path),
+        Format::Delimited(delimiter) => {
+            let (columns, rows) = read_delimited(path, delimiter)?;
+            Ok(vec![create_table(conn, &attachment.name, columns, rows)?])
+        }
+        Format::Json => {
+            let (columns, rows) = read_json(path)?;
+            Ok(vec![create_table(conn, &attachment.name, columns, rows)?])
+        }
+    }
+}
+
+fn attach_database(conn: &Connection, name: &str, path: &Path) -> Result<Vec<AttachedTable>> {
+    if !path.is_file() {
+        return Err(file_error(path, "no such file"));
+    }
+    // Read-only, so a query cannot write to someone else's database.
+    let uri = format!(
+        "file:{}?mode=ro",
+        path.to_string_lossy()
+            .replace('%', "%25")
+            .replace('?', "%3f")
+            .replace('#', "%23")
+    );
+    conn.execute(
+        &format!("ATTACH DATABASE ?1 AS {}", quote_identifier(name)),
+        [uri],
+    )?;
+
+    let schema = quote_identifier(name);
+    let names: Vec<String> = conn
+        .prepare(&format!(
+            "SELECT name FROM {schema}.sqlite_master \
+             WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite_%' ORDER BY name"
+        ))?
+        .query_map([], |row| row.get(0))?
+        .collect::<std::result::Result<_, _>>()?;
+
+    let mut tables = Vec::new();
+    for table in &names {
+        let columns = table_columns(conn, name, table)?;
+        if names.len() == 1 {
+            conn.execute_batch(&format!(
+                "CREATE TEMP VIEW {schema} AS SELECT * FROM {schema}.{}",
+                quote_identifier(table)
+            ))?;
+            tables.push(AttachedTable {
+                name: name.to_string(),
+                columns: columns.clone(),
+            });
+        }
+        tables.push(AttachedTable {
+            name: format!("{name}.{table}"),
+            columns,
+        });
+    }
+    Ok(tables)
+}
+
+fn table_columns(conn: &Connection, schema: &str, table: &str) -> Result<Vec<(String, String)>> {
+    let columns = conn
+        .prepare("SELECT name, type FROM pragma_table_info(?1, ?2)")?
+        .query_map([table, schema], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<std::result::Result<_, _>>()?;
+    Ok(columns)
+}
+
+/// The narrowest SQLite type that holds every value seen in a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Inferred {
+    Null,
+    Integer,
+    Real,
+    Text,
+}
+
+impl Inferred {
+    fn widen(self, other: Inferred) -> Inferred {
+        self.max(other)
+    }
+
+    fn sql_type(self) -> &'static str {
+        match self {
+            Inferred::Integer => "INTEGER",
+            Inferred::Real => "REAL",
+            Inferred::Null | Inferred::Text => "TEXT",
+        }
+    }
+
+    fn of(value: &SqlValue) -> Inferred {
+        match value {
+            SqlValue::Null => Inferred::Null,
+            SqlValue::Integer(_) => Inferred::Integer,
+            SqlValue::Real(_) => Inferred::Real,
+            SqlValue::Text(_) | SqlValue::Blob(_) => Inferred::Text,
+        }
+    }
+}
+
+/// A delimited field as a typed value. Empty fields are NULL; numbers with
+/// a leading zero, such as ZIP codes or padded IDs, stay text.
+fn field_value(field: &str) -> SqlValue {
+    if field.is_empty() {
+        return SqlValue::Null;
+    }
+    let digits = field.strip_prefix('-').unwrap_or(field);
+    let padded = digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.");
+    if !padded && digits.starts_with(|c: char| c.is_ascii_digit()) {
+        if let Ok(n) = field.parse::<i64>() {
+            return SqlValue::Integer(n);
+        }
+        if let Ok(n) = field.parse::<f64>() {
+            if n.is_finite() {
+                return SqlValue::Real(n);
+            }
+        }
+    }
+    SqlValue::Text(field.to_string())
+}
+
+fn read_delimited(path: &Path, delimiter: u8) -> Result<(Vec<String>, Vec<Vec<SqlValue>>)> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_path(path)
+        .map_err(|e| file_error(path, e))?;
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| file_error(path, e))?
+        .iter()
+        .map(str::to_string)
+        .collect();
+    let columns = column_names(headers);
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| file_error(path, e))?;
+        let mut row: Vec<SqlValue> = record.iter().take(columns.len()).map(field_value).collect();
+        row.resize(columns.len(), SqlValue::Null);
+        rows.push(row);
+    }
+    Ok((columns, rows))
+}
+
+/// Reads a JSON array of records, or one record per value as in JSON Lines.
+/// Keys become columns in order of first appearance; a value that is not an
+/// object is read as a record with a single `value` column.
+fn read_json(path: &Path) -> Result<(Vec<String>, Vec<Vec<SqlValue>>)> {
+    let text = std::fs::read_to_string(path).map_err(|e| file_error(path, e))?;
+    let mut values = Vec::new();
+    for value in serde_json::Deserializer::from_str(&text).into_iter::<Value>() {
+        values.push(value.map_err(|e| file_error(path, e))?);
+    }
+    let records = match <[Value; 1]>::try_from(values) {
+        Ok([Value::Array(records)]) => records,
+        Ok([value]) => vec![value],
+        Err(values) => values,
+    };
+
+    let mut columns: Vec<String> = Vec::new();
+    let objects: Vec<serde_json::Map<String, Value>> = records
+        .into_iter()
+        .map(|record| match record {
+            Value::Object(object) => object,
+            other => serde_json::Map::from_iter([("value".to_string(), other)]),
+        })
+        .collect();
+    for object in &objects {
+        for key in object.keys() {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+    }
+    let rows = objects
+        .iter()
+        .map(|object| {
+            columns
+                .iter()
+                .map(|column| json_value(object.get(column)))
+                .collect()
+        })
+        .collect();
+    Ok((columns, rows))
+}
+
+fn json_value(value: Option<&Value>) -> SqlValue {
+    match value {
+        None | Some(Value::Null) => SqlValue::Null,
+        Some(Value::Bool(b)) => SqlValue::Integer(i64::from(*b)),
+        Some(Value::Number(n)) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        Some(Value::String(s)) => SqlValue::Text(s.clone()),
+        // Nested values stay JSON, for json_extract and friends.
+        Some(nested) => SqlValue::Text(nested.to_string()),
+    }
+}
+
+/// Header names made usable as columns: blanks are numbered and repeats
+/// suffixed, so every column can be selected.
+fn column_names(headers: Vec<String>) -> Vec<String> {
+    let mut columns: Vec<String> = Vec::with_capacity(headers.len());
+    for (i, header) in headers.into_iter().enumerate() {
+        let base = match header.trim() {
+            "" => format!("column{}", i + 1),
+            name => name.to_string(),
+        };
+        let mut name = base.clone();
+        let mut n = 2;
+        while columns.iter().any(|c| c.eq_ignore_ascii_case(&name)) {
+            name = format!("{base}_{n}");
+            n += 1;
+        }
+        columns.push(name);
+    }
+    columns
+}
+
+fn create_table(
+    conn: &Connection,
+    name: &str,
+    columns: Vec<String>,
+    rows: Vec<Vec<SqlValue>>,
+) -> Result<AttachedTable> {
+    if columns.is_empty() {
+        return Err(VcsqlError::Other(format!(
+            "{name}: the file has no columns to load"
+        )));
+    }
+    let types: Vec<Inferred> = (0..columns.len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| Inferred::of(&row[i]))
+                .fold(Inferred::Null, Inferred::widen)
+        })
+        .collect();
+    let definitions: Vec<String> = columns
+        .iter()
+        .zip(&types)
+        .map(|(column, ty)| format!("{} {}", quote_identifier(column), ty.sql_type()))
+        .collect();
+    let table = quote_identifier(name);
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute_batch(&format!(
+        "CREATE TEMP TABLE {table} ({})",
+        definitions.join(", ")
+    ))?;
+    {
+        let placeholders = vec!["?"; columns.len()].join(", ");
+        let mut insert =
+            tx.prepare(&format!("INSERT INTO temp.{table} VALUES ({placeholders})"))?;
+        for row in rows {
+            insert.execute(params_from_iter(row))?;
+        }
+    }
+    tx.commit()?;
+
+    Ok(AttachedTable {
+        name: name.to_string(),
+        columns: columns
+            .into_iter()
+            .zip(types)
+            .map(|(column, ty)| (column, ty.sql_type().to_string()))
+            .collect(),
+    })
+}
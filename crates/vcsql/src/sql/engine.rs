@@ -2,17 +2,15 @@
 
 use crate::error::{Result, VcsqlError};
 use crate::git::GitRepo;
-use crate::providers::{
-    BlameProvider, BranchesProvider, CommitParentsProvider, CommitsProvider, ConfigProvider,
-    DiffFilesProvider, DiffsProvider, HooksProvider, NotesProvider, Provider, ReflogProvider,
-    RefsProvider, RemotesProvider, StashesProvider, StatusProvider, SubmodulesProvider,
-    TagsProvider, WorktreesProvider,
-};
+use crate::providers::provider_for;
+use crate::sql::attach::{attach, AttachedTable, Attachment};
+use crate::sql::functions;
 use crate::sql::schema::{get_table_info, TABLES};
-use regex::Regex;
+use rusqlite::hooks::{AuthAction, AuthContext, Authorization};
 use rusqlite::{Connection, Row};
 use serde_json::{Map, Value};
 use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
 
 /// The SQL query engine that executes queries against Git repository data.
 ///
@@ -37,13 +35,15 @@ use std::collections::HashSet;
 /// ```
 pub struct SqlEngine {
     conn: Connection,
-    loaded_tables: HashSet<String>,
+    loaded_tables: HashSet<(String, String)>,
 }
 
 impl SqlEngine {
-    /// Creates a new SQL engine with an empty in-memory database.
+    /// Creates a new SQL engine with an empty in-memory database and the
+    /// [`functions`](crate::sql::functions) library registered.
     pub fn new() -> Result<Self> {
         let conn = Connection::open_in_memory()?;
+        functions::register(&conn)?;
         Ok(Self {
             conn,
             loaded_tables: HashSet::new(),
@@ -52,41 +52,22 @@ impl SqlEngine {
 
     /// Extracts table names referenced in a SQL query.
     ///
-    /// Parses the query for FROM, JOIN, INTO, and UPDATE clauses to identify
-    /// which tables need to be loaded.
+    /// Only names of vcsql tables are returned; see [`referenced_tables`] for how
+    /// references are detected.
     pub fn extract_table_names(query: &str) -> HashSet<String> {
-        let mut tables = HashSet::new();
-
-        let table_names: Vec<&str> = TABLES.iter().map(|t| t.name).collect();
-
-        let pattern = r"(?i)\b(FROM|JOIN|INTO|UPDATE)\s+(\w+)";
-        let re = Regex::new(pattern).unwrap();
-
-        for cap in re.captures_iter(query) {
-            if let Some(table_match) = cap.get(2) {
-                let table_name = table_match.as_str().to_lowercase();
-                if table_names.contains(&table_name.as_str()) {
-                    tables.insert(table_name);
-                }
-            }
-        }
-
-        // Also check for table aliases like "commits c"
-        for table in &table_names {
-            let pattern = format!(r"(?i)\b{}\b", regex::escape(table));
-            if Regex::new(&pattern).unwrap().is_match(query) {
-                tables.insert(table.to_string());
-            }
-        }
-
-        tables
+        referenced_tables(query)
+            .into_iter()
+            .filter(|name| TABLES.iter().any(|table| table.name == name))
+            .collect()
     }
 
     /// Loads a single table's data from the repository into the database.
     ///
-    /// Tables are cached after first load - subsequent calls for the same table are no-ops.
+    /// Loads are cached per repository - subsequent calls for the same table and
+    /// repository are no-ops, while other repositories append their rows.
     pub fn load_table(&mut self, table_name: &str, repo: &mut GitRepo) -> Result<()> {
-        if self.loaded_tables.contains(table_name) {
+        let key = (table_name.to_string(), repo.path().to_string());
+        if self.loaded_tables.contains(&key) {
             return Ok(());
         }
 
@@ -95,33 +76,41 @@ impl SqlEngine {
 
         self.conn.execute(table_info.create_sql, [])?;
 
-        let provider: Box<dyn Provider> = match table_name {
-            "commits" => Box::new(CommitsProvider),
-            "commit_parents" => Box::new(CommitParentsProvider),
-            "branches" => Box::new(BranchesProvider),
-            "tags" => Box::new(TagsProvider),
-            "refs" => Box::new(RefsProvider),
-            "stashes" => Box::new(StashesProvider),
-            "reflog" => Box::new(ReflogProvider),
-            "diffs" => Box::new(DiffsProvider),
-            "diff_files" => Box::new(DiffFilesProvider),
-            "blame" => Box::new(BlameProvider::new(None)),
-            "config" => Box::new(ConfigProvider),
-            "remotes" => Box::new(RemotesProvider),
-            "submodules" => Box::new(SubmodulesProvider),
-            "status" => Box::new(StatusProvider),
-            "worktrees" => Box::new(WorktreesProvider),
-            "hooks" => Box::new(HooksProvider),
-            "notes" => Box::new(NotesProvider),
-            _ => return Err(VcsqlError::TableNotFound(table_name.to_string())),
-        };
+        let provider = provider_for(table_name)
+            .ok_or_else(|| VcsqlError::TableNotFound(table_name.to_string()))?;
 
         provider.populate(&self.conn, repo)?;
-        self.loaded_tables.insert(table_name.to_string());
+        self.loaded_tables.insert(key);
 
         Ok(())
     }
 
+    /// Drops a loaded table, so the next [`Self::load_table`] reads it from
+    /// each repository again.
+    pub fn unload_table(&mut self, table_name: &str) -> Result<()> {
+        if get_table_info(table_name).is_none() {
+            return Err(VcsqlError::TableNotFound(table_name.to_string()));
+        }
+        self.conn
+            .execute_batch(&format!("DROP TABLE IF EXISTS {table_name}"))?;
+        self.loaded_tables.retain(|(name, _)| name != table_name);
+        Ok(())
+    }
+
+    /// Loads an external CSV, JSON, JSON Lines, or SQLite file under the
+    /// attachment's name and returns the tables it added.
+    ///
+    /// The name may not be one of vcsql's own tables.
+    pub fn attach(&mut self, attachment: &Attachment) -> Result<Vec<AttachedTable>> {
+        if get_table_info(&attachment.name).is_some() {
+            return Err(VcsqlError::Other(format!(
+                "cannot attach as `{}`: it is a vcsql table",
+                attachment.name
+            )));
+        }
+        attach(&self.conn, attachment)
+    }
+
     /// Loads all tables referenced in a query from the repository.
     ///
     /// Analyzes the query to determine which tables are needed, then loads each one.
@@ -154,6 +143,141 @@ impl SqlEngine {
     }
 }
 
+/// Returns the lowercased names of the `main` tables a query reads.
+///
+/// Instead of scanning the SQL text, the query is prepared against an empty
+/// in-memory database. SQLite locates every `FROM` target before it resolves
+/// any column, so each failed prepare names one missing table; a placeholder is
+/// created for it and the prepare retried until the failure is something else.
+/// CTEs, subqueries, aliases, string literals and quoted identifiers are thereby
+/// resolved exactly as they will be when the query runs. Tables qualified with
+/// another schema, such as an attached database, are not reported.
+pub fn referenced_tables(query: &str) -> HashSet<String> {
+    let mut tables = HashSet::new();
+    let Ok(conn) = Connection::open_in_memory() else {
+        return tables;
+    };
+    // Unknown functions would stop the prepare loop early.
+    let _ = functions::register(&conn);
+    let mut placeholders: Vec<String> = Vec::new();
+    let mut seen = HashSet::new();
+
+    let resolved = loop {
+        let message = match conn.prepare(query) {
+            Ok(_) => break true,
+            Err(rusqlite::Error::SqliteFailure(_, Some(message))) => message,
+            Err(_) => break false,
+        };
+        if !seen.insert(message.clone()) {
+            break false;
+        }
+
+        let progressed = if let Some(missing) = message.strip_prefix("no such table: ") {
+            let (schema, name) = missing.split_once('.').unwrap_or(("main", missing));
+            if schema.eq_ignore_ascii_case("main") {
+                tables.insert(name.to_lowercase());
+            } else if !schema.eq_ignore_ascii_case("temp") {
+                let _ = conn.execute(
+                    &format!("ATTACH DATABASE ':memory:' AS {}", quote_identifier(schema)),
+                    [],
+                );
+            }
+            let table = format!("{}.{}", quote_identifier(schema), quote_identifier(name));
+            placeholders.push(table.clone());
+            conn.execute(&format!("CREATE TABLE {table} (_)"), [])
+                .is_ok()
+        } else if let Some(column) = message
+            .strip_prefix("cannot join using column ")
+            .and_then(|rest| rest.strip_suffix(" - column not present in both tables"))
+        {
+            // USING is checked while tables are still being located, so give
+            // every placeholder the column and keep going.
+            let column = quote_identifier(column);
+            let mut added = false;
+            for table in &placeholders {
+                let sql = format!("ALTER TABLE {table} ADD COLUMN {column}");
+                added |= conn.execute(&sql, []).is_ok();
+            }
+            added
+        } else {
+            false
+        };
+        if !progressed {
+            break false;
+        }
+    };
+
+    if resolved {
+        tables.extend(introspected_tables(&conn, query));
+    }
+    tables
+}
+
+/// Pragmas whose argument names a table.
+const TABLE_PRAGMAS: &[&str] = &[
+    "table_info",
+    "table_xinfo",
+    "index_list",
+    "foreign_key_list",
+];
+
+/// Returns the tables named in `pragma_table_info('commits')` and similar calls.
+///
+/// A pragma function only looks its argument up when the statement runs, so a
+/// query that reads one is stepped against the placeholder tables while the
+/// authorizer records each pragma issued. A progress handler interrupts the
+/// run once the arguments have had ample time to show up.
+fn introspected_tables(conn: &Connection, query: &str) -> HashSet<String> {
+    #[derive(Default)]
+    struct Seen {
+        pragma_function: bool,
+        tables: HashSet<String>,
+    }
+
+    let seen = Arc::new(Mutex::new(Seen::default()));
+    let sink = Arc::clone(&seen);
+    conn.authorizer(Some(move |ctx: AuthContext<'_>| {
+        let mut seen = sink.lock().unwrap_or_else(|e| e.into_inner());
+        match ctx.action {
+            AuthAction::Read { table_name, .. } if table_name.starts_with("pragma_") => {
+                seen.pragma_function = true;
+            }
+            AuthAction::Pragma {
+                pragma_name,
+                pragma_value: Some(table),
+            } if TABLE_PRAGMAS.contains(&pragma_name) => {
+                seen.tables.insert(table.to_lowercase());
+            }
+            _ => {}
+        }
+        Authorization::Allow
+    }));
+
+    if let Ok(mut stmt) = conn.prepare(query) {
+        if seen.lock().is_ok_and(|seen| seen.pragma_function) {
+            let mut budget = 1_000;
+            conn.progress_handler(
+                1_000,
+                Some(move || {
+                    budget -= 1;
+                    budget == 0
+                }),
+            );
+            let mut rows = stmt.raw_query();
+            while let Ok(Some(_)) = rows.next() {}
+            conn.progress_handler(0, None::<fn() -> bool>);
+        }
+    }
+    conn.authorizer(None::<fn(AuthContext<'_>) -> Authorization>);
+
+    let tables = std::mem::take(&mut seen.lock().unwrap_or_else(|e| e.into_inner()).tables);
+    tables
+}
+
+pub(crate) fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
 fn row_to_values(row: &Row, col_count: usize) -> Vec<Value> {
     (0..col_count)
         .map(|i| {
@@ -0,0 +1,71 @@
+//! Example queries shown by `vcsql examples`.
+//!
+//! Kept as data so other front ends (such as devsql) can check that every
+//! documented query runs unchanged against their engine.
+
+/// A documented example query.
+#[derive(Debug, Clone)]
+pub struct Example {
+    /// Section heading the example is listed under.
+    pub category: &'static str,
+    /// One-line description shown above the query.
+    pub description: &'static str,
+    /// The SQL text. Continuation lines are indented relative to the first.
+    pub sql: &'static str,
+}
+
+pub static EXAMPLES: &[Example] = &[
+    // BASIC QUERIES
+    Example {
+        category: "BASIC QUERIES",
+        description: "Recent commits",
+        sql: "SELECT short_id, summary, authored_at\nFROM commits\nORDER BY authored_at DESC\nLIMIT 10",
+    },
+    Example {
+        category: "BASIC QUERIES",
+        description: "Current branch",
+        sql: "SELECT name FROM branches WHERE is_head = 1",
+    },
+    Example {
+        category: "BASIC QUERIES",
+        description: "All branches with their targets",
+        sql: "SELECT name, target_id, is_remote FROM branches",
+    },
+    // ANALYTICS
+    Example {
+        category: "ANALYTICS",
+        description: "Commits by author",
+        sql: "SELECT author_name, COUNT(*) as commits\nFROM commits\nGROUP BY author_name\nORDER BY commits DESC",
+    },
+    Example {
+        category: "ANALYTICS",
+        description: "Commits by day of week",
+        sql: "SELECT\n  CASE CAST(strftime('%w', substr(authored_at, 1, 10)) AS INTEGER)\n    WHEN 0 THEN 'Sun' WHEN 1 THEN 'Mon'\n    WHEN 2 THEN 'Tue' WHEN 3 THEN 'Wed'\n    WHEN 4 THEN 'Thu' WHEN 5 THEN 'Fri' WHEN 6 THEN 'Sat'\n  END as day,\n  COUNT(*) as commits\nFROM commits\nGROUP BY day",
+    },
+    Example {
+        category: "ANALYTICS",
+        description: "Merge commits",
+        sql: "SELECT short_id, summary FROM commits WHERE is_merge = 1",
+    },
+    Example {
+        category: "ANALYTICS",
+        description: "Most frequently changed files",
+        sql: "SELECT path, COUNT(*) as changes, SUM(insertions) as lines_added\nFROM diff_files\nGROUP BY path\nORDER BY changes DESC\nLIMIT 10",
+    },
+    // JOINS
+    Example {
+        category: "JOINS",
+        description: "Commits with branch info",
+        sql: "SELECT c.short_id, c.summary, b.name as branch\nFROM commits c\nJOIN branches b ON b.target_id = c.id",
+    },
+    Example {
+        category: "JOINS",
+        description: "Find merge commits with their parents",
+        sql: "SELECT c.summary, p.parent_id, p.parent_index\nFROM commits c\nJOIN commit_parents p ON p.commit_id = c.id\nWHERE c.is_merge = 1\nLIMIT 10",
+    },
+    Example {
+        category: "JOINS",
+        description: "Largest commits by lines changed",
+        sql: "SELECT c.short_id, c.summary, d.insertions + d.deletions as churn\nFROM commits c\nJOIN diffs d ON d.commit_id = c.id\nORDER BY churn DESC\nLIMIT 10",
+    },
+];
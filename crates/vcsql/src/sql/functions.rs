@@ -0,0 +1,300 @@
+//! SQL functions registered on every engine's connection.
+//!
+//! | Function | Returns |
+//! |----------|---------|
+//! | `regexp(pattern, text)` | 1 if `text` matches; also backs `text REGEXP pattern` |
+//! | `regexp_replace(text, pattern, replacement)` | `text` with every match replaced (`$1` names a group) |
+//! | `basename(path)`, `dirname(path)` | The last component of `path`, and everything before it |
+//! | `extension(path)` | The extension without its dot, or NULL |
+//! | `path_under(path, root)` | 1 if `path` is `root` or inside it, compared by component |
+//! | `json_extract_path(json, key, ...)` | The value at `key`s (text keys, integer indexes), as `json_extract` returns it |
+//! | `json_extract_path_text(json, key, ...)` | The same value as text |
+//! | `duration_ms(start, end)` | Milliseconds from `start` to `end` |
+//! | `token_count(text)` | Tokens in `text` (cl100k_base) |
+//! | `redact(text)` | `text` with tokens, passwords, and other secrets masked |
+//! | `median(x)`, `percentile(x, p)` | Aggregates: the median, and the `p`th percentile (0-100), interpolated |
+//!
+//! Every function returns NULL when a required argument is NULL.
+
+use std::path::{Component, Path};
+use std::sync::OnceLock;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use regex::Regex;
+use rusqlite::functions::{Aggregate, Context, FunctionFlags};
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::Connection;
+use serde_json::Value;
+
+use crate::error::Result;
+use crate::redaction::redact_sensitive_text;
+
+const DETERMINISTIC: FunctionFlags =
+    FunctionFlags::SQLITE_UTF8.union(FunctionFlags::SQLITE_DETERMINISTIC);
+
+/// Registers every function in this module on `conn`.
+pub fn register(conn: &Connection) -> Result<()> {
+    conn.create_scalar_function("regexp", 2, DETERMINISTIC, |ctx| {
+        let Some(text) = text_arg(ctx, 1)? else {
+            return Ok(None);
+        };
+        let regex = cached_regex(ctx, 0)?;
+        Ok((*regex).as_ref().map(|regex| regex.is_match(&text)))
+    })?;
+    conn.create_scalar_function("regexp_replace", 3, DETERMINISTIC, |ctx| {
+        let (Some(text), Some(replacement)) = (text_arg(ctx, 0)?, text_arg(ctx, 2)?) else {
+            return Ok(None);
+        };
+        let regex = cached_regex(ctx, 1)?;
+        Ok((*regex)
+            .as_ref()
+            .map(|regex| regex.replace_all(&text, replacement.as_str()).into_owned()))
+    })?;
+
+    conn.create_scalar_function("basename", 1, DETERMINISTIC, |ctx| {
+        Ok(text_arg(ctx, 0)?.map(|path| {
+            let trimmed = path.trim_end_matches('/');
+            match trimmed.rsplit_once('/') {
+                Some((_, name)) => name.to_string(),
+                None => trimmed.to_string(),
+            }
+        }))
+    })?;
+    conn.create_scalar_function("dirname", 1, DETERMINISTIC, |ctx| {
+        Ok(text_arg(ctx, 0)?.map(|path| {
+            let trimmed = path.trim_end_matches('/');
+            match trimmed.rsplit_once('/') {
+                Some(("", _)) => "/".to_string(),
+                Some((dir, _)) => dir.to_string(),
+                None => ".".to_string(),
+            }
+        }))
+    })?;
+    conn.create_scalar_function("extension", 1, DETERMINISTIC, |ctx| {
+        Ok(text_arg(ctx, 0)?.and_then(|path| {
+            Path::new(&path)
+                .extension()
+                .map(|ext| ext.to_string_lossy().into_owned())
+        }))
+    })?;
+    conn.create_scalar_function("path_under", 2, DETERMINISTIC, |ctx| {
+        let (Some(path), Some(root)) = (text_arg(ctx, 0)?, text_arg(ctx, 1)?) else {
+            return Ok(None);
+        };
+        let root: Vec<Component> = Path::new(&root)
+            .components()
+            .filter(|c| *c != Component::CurDir)
+            .collect();
+        let mut path = Path::new(&path)
+            .components()
+            .filter(|c| *c != Component::CurDir);
+        Ok(Some(root.iter().all(|part| path.next() == Some(*part))))
+    })?;
+
+    conn.create_scalar_function("json_extract_path", -1, DETERMINISTIC, |ctx| {
+        Ok(match json_at_path(ctx)? {
+            Some(Value::Bool(b)) => SqlValue::Integer(i64::from(b)),
+            Some(Value::Number(n)) => match n.as_i64() {
+                Some(i) => SqlValue::Integer(i),
+                None => n.as_f64().map_or(SqlValue::Null, SqlValue::Real),
+            },
+            Some(Value::String(s)) => SqlValue::Text(s),
+            Some(Value::Null) | None => SqlValue::Null,
+            Some(nested) => SqlValue::Text(nested.to_string()),
+        })
+    })?;
+    conn.create_scalar_function("json_extract_path_text", -1, DETERMINISTIC, |ctx| {
+        Ok(match json_at_path(ctx)? {
+            Some(Value::String(s)) => Some(s),
+            Some(Value::Null) | None => None,
+            Some(other) => Some(other.to_string()),
+        })
+    })?;
+
+    conn.create_scalar_function("duration_ms", 2, DETERMINISTIC, |ctx| {
+        let start = timestamp_ms(ctx.get_raw(0));
+        let end = timestamp_ms(ctx.get_raw(1));
+        Ok(start.zip(end).map(|(start, end)| end - start))
+    })?;
+    conn.create_scalar_function("token_count", 1, DETERMINISTIC, |ctx| {
+        Ok(text_arg(ctx, 0)?.map(|text| count_tokens(&text) as i64))
+    })?;
+    conn.create_scalar_function("redact", 1, DETERMINISTIC, |ctx| {
+        Ok(match ctx.get_raw(0) {
+            ValueRef::Text(text) => {
+                SqlValue::Text(redact_sensitive_text(&String::from_utf8_lossy(text)))
+            }
+            value => SqlValue::from(value),
+        })
+    })?;
+
+    conn.create_aggregate_function("median", 1, DETERMINISTIC, Percentile { fixed: Some(50.0) })?;
+    conn.create_aggregate_function("percentile", 2, DETERMINISTIC, Percentile { fixed: None })?;
+    Ok(())
+}
+
+/// Argument `index` as text; numbers are read as their decimal text.
+fn text_arg(ctx: &Context<'_>, index: usize) -> rusqlite::Result<Option<String>> {
+    Ok(match ctx.get_raw(index) {
+        ValueRef::Null => None,
+        ValueRef::Integer(i) => Some(i.to_string()),
+        ValueRef::Real(f) => Some(f.to_string()),
+        ValueRef::Text(t) | ValueRef::Blob(t) => Some(String::from_utf8_lossy(t).into_owned()),
+    })
+}
+
+/// The pattern in argument `index`, compiled once per statement.
+fn cached_regex(
+    ctx: &Context<'_>,
+    index: usize,
+) -> rusqlite::Result<std::sync::Arc<Option<Regex>>> {
+    ctx.get_or_create_aux(index as i32, |value| match value {
+        ValueRef::Null => Ok(None),
+        value => {
+            let pattern = value.as_str().map_err(|e| e.to_string())?;
+            Regex::new(pattern)
+                .map(Some)
+                .map_err(|e| format!("invalid regular expression: {e}"))
+        }
+    })
+}
+
+/// The value reached by following arguments 1.. from the JSON in argument 0:
+/// text keys name object members and integers index arrays (negative ones
+/// from the end).
+fn json_at_path(ctx: &Context<'_>) -> rusqlite::Result<Option<Value>> {
+    let Some(json) = text_arg(ctx, 0)? else {
+        return Ok(None);
+    };
+    let mut value: Value = serde_json::from_str(&json)
+        .map_err(|e| rusqlite::Error::UserFunctionError(format!("malformed JSON: {e}").into()))?;
+    for index in 1..ctx.len() {
+        let next = match (ctx.get_raw(index), value) {
+            (ValueRef::Integer(i), Value::Array(mut items)) => {
+                let i = if i < 0 { items.len() as i64 + i } else { i };
+                usize::try_from(i)
+                    .ok()
+                    .filter(|&i| i < items.len())
+                    .map(|i| items.swap_remove(i))
+            }
+            (ValueRef::Text(key), Value::Object(mut members)) => {
+                members.remove(String::from_utf8_lossy(key).as_ref())
+            }
+            _ => None,
+        };
+        match next {
+            Some(next) => value = next,
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(value))
+}
+
+/// Milliseconds since the Unix epoch. Numbers of 10^11 or more are read as
+/// milliseconds and smaller ones as seconds; text may be RFC 3339, SQLite's
+/// `YYYY-MM-DD HH:MM:SS[.SSS]` (UTC), a date, or a number.
+fn timestamp_ms(value: ValueRef<'_>) -> Option<i64> {
+    fn from_number(n: f64) -> i64 {
+        if n.abs() >= 1e11 {
+            n as i64
+        } else {
+            (n * 1000.0) as i64
+        }
+    }
+    match value {
+        ValueRef::Integer(i) => Some(from_number(i as f64)),
+        ValueRef::Real(f) => Some(from_number(f)),
+        ValueRef::Text(text) => {
+            let text = std::str::from_utf8(text).ok()?.trim();
+            if let Ok(n) = text.parse::<f64>() {
+                return Some(from_number(n));
+            }
+            if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+                return Some(time.timestamp_millis());
+            }
+            for format in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"] {
+                if let Ok(time) = NaiveDateTime::parse_from_str(text, format) {
+                    return Some(time.and_utc().timestamp_millis());
+                }
+            }
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|time| time.and_utc().timestamp_millis())
+        }
+        ValueRef::Null | ValueRef::Blob(_) => None,
+    }
+}
+
+/// The BPE table is expensive to build; build it once per process.
+static BPE: OnceLock<Option<tiktoken_rs::CoreBPE>> = OnceLock::new();
+
+/// Counts the cl100k_base tokens in `text`, falling back to whitespace-
+/// separated words if the encoder cannot be built.
+pub fn count_tokens(text: &str) -> usize {
+    match BPE.get_or_init(|| tiktoken_rs::cl100k_base().ok()) {
+        Some(bpe) => bpe.encode_with_special_tokens(text).len(),
+        None => text.split_whitespace().count(),
+    }
+}
+
+/// `median(x)` and `percentile(x, p)`: the value below which `p` percent of
+/// the non-NULL values fall, interpolating between the two nearest.
+struct Percentile {
+    fixed: Option<f64>,
+}
+
+impl Aggregate<(Vec<f64>, Option<f64>), Option<f64>> for Percentile {
+    fn init(&self, _: &mut Context<'_>) -> rusqlite::Result<(Vec<f64>, Option<f64>)> {
+        Ok((Vec::new(), self.fixed))
+    }
+
+    fn step(
+        &self,
+        ctx: &mut Context<'_>,
+        (values, percent): &mut (Vec<f64>, Option<f64>),
+    ) -> rusqlite::Result<()> {
+        if percent.is_none() {
+            let p: f64 = ctx.get(1)?;
+            if !(0.0..=100.0).contains(&p) {
+                return Err(rusqlite::Error::UserFunctionError(
+                    "percentile must be between 0 and 100".into(),
+                ));
+            }
+            *percent = Some(p);
+        }
+        match ctx.get_raw(0) {
+            ValueRef::Integer(i) => values.push(i as f64),
+            ValueRef::Real(f) => values.push(f),
+            ValueRef::Text(text) => {
+                if let Some(n) = std::str::from_utf8(text)
+                    .ok()
+                    .and_then(|t| t.trim().parse().ok())
+                {
+                    values.push(n);
+                }
+            }
+            ValueRef::Null | ValueRef::Blob(_) => {}
+        }
+        Ok(())
+    }
+
+    fn finalize(
+        &self,
+        _: &mut Context<'_>,
+        acc: Option<(Vec<f64>, Option<f64>)>,
+    ) -> rusqlite::Result<Option<f64>> {
+        let Some((mut values, Some(percent))) = acc else {
+            return Ok(None);
+        };
+        if values.is_empty() {
+            return Ok(None);
+        }
+        values.sort_by(f64::total_cmp);
+        let rank = percent / 100.0 * (values.len() - 1) as f64;
+        let (low, high) = (rank.floor() as usize, rank.ceil() as usize);
+        Ok(Some(
+            values[low] + (values[high] - values[low]) * (rank - low as f64),
+        ))
+    }
+}
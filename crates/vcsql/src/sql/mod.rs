@@ -1,5 +1,10 @@
+pub mod attach;
 pub mod engine;
+pub mod examples;
+pub mod functions;
 pub mod schema;
 
-pub use engine::{QueryResult, SqlEngine};
+pub use attach::{attach, AttachedTable, Attachment};
+pub use engine::{referenced_tables, QueryResult, SqlEngine};
+pub use examples::{Example, EXAMPLES};
 pub use schema::{get_table_info, get_tables_by_category, TableInfo, TABLES};
@@ -513,6 +513,12 @@ pub static DIFF_FILES_COLUMNS: &[ColumnInfo] = &[
         nullable: true,
         description: "Path after",
     },
+    ColumnInfo {
+        name: "path",
+        sql_type: "TEXT",
+        nullable: false,
+        description: "Path after, or path before for deletions",
+    },
     ColumnInfo {
         name: "status",
         sql_type: "TEXT",
@@ -1040,7 +1046,7 @@ pub static TABLES: &[TableInfo] = &[
         description: "Per-file changes",
         category: "CHANGES",
         columns: DIFF_FILES_COLUMNS,
-        create_sql: "CREATE TABLE IF NOT EXISTS diff_files (commit_id TEXT NOT NULL, parent_id TEXT, old_path TEXT, new_path TEXT, status TEXT NOT NULL, insertions INTEGER NOT NULL, deletions INTEGER NOT NULL, is_binary INTEGER NOT NULL, similarity INTEGER, repo TEXT NOT NULL)",
+        create_sql: "CREATE TABLE IF NOT EXISTS diff_files (commit_id TEXT NOT NULL, parent_id TEXT, old_path TEXT, new_path TEXT, path TEXT NOT NULL, status TEXT NOT NULL, insertions INTEGER NOT NULL, deletions INTEGER NOT NULL, is_binary INTEGER NOT NULL, similarity INTEGER, repo TEXT NOT NULL)",
     },
     TableInfo {
         name: "blame",
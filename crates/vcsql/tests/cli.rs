@@ -138,3 +138,74 @@ fn test_nonexistent_repo() {
         .failure()
         .stderr(predicate::str::contains("Repository not found"));
 }
+
+#[test]
+fn test_since_and_until_limit_loaded_history() {
+    let temp = create_test_repo();
+    let repo = temp.path().to_str().unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_vcsql"))
+        .args(["--repo", repo, "--format", "csv", "--since", "30d"])
+        .arg("SELECT COUNT(*) AS n FROM commits")
+        .assert()
+        .success()
+        .stdout("n\n1\n");
+
+    Command::new(env!("CARGO_BIN_EXE_vcsql"))
+        .args(["--repo", repo, "--format", "csv", "--until", "2000-01-01"])
+        .arg("SELECT COUNT(*) AS n FROM commits")
+        .assert()
+        .success()
+        .stdout("n\n0\n");
+
+    Command::new(env!("CARGO_BIN_EXE_vcsql"))
+        .args(["--repo", repo, "--since", "soon"])
+        .arg("SELECT * FROM commits")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid time"));
+}
+
+#[test]
+fn test_shell_runs_piped_statements() {
+    let temp = create_test_repo();
+    let repo = temp.path().to_str().unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_vcsql"))
+        .args(["--repo", repo, "--format", "csv", "shell"])
+        .write_stdin(
+            "SELECT summary\n  FROM commits;\n.reload commits\n.format jsonl\nSELECT COUNT(*) AS n FROM commits;\n",
+        )
+        .assert()
+        .success()
+        .stdout("summary\nInitial commit\n{\"n\":1}\n")
+        .stderr(predicate::str::contains("Reloaded commits"));
+
+    Command::new(env!("CARGO_BIN_EXE_vcsql"))
+        .args(["--repo", repo, "shell"])
+        .write_stdin("SELECT * FROM nonesuch;\n")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_attach_joins_a_file_with_git_tables() {
+    let temp = create_test_repo();
+    let repo = temp.path().to_str().unwrap();
+    let tickets = temp.path().join("tickets.csv");
+    std::fs::write(&tickets, "summary,ticket\nInitial commit,T-1\n").unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_vcsql"))
+        .args(["--repo", repo, "--format", "csv", "--attach"])
+        .arg(format!("tickets={}", tickets.display()))
+        .arg("SELECT t.ticket, c.summary FROM commits c JOIN tickets t USING (summary)")
+        .assert()
+        .success()
+        .stdout("ticket,summary\nT-1,Initial commit\n");
+
+    Command::new(env!("CARGO_BIN_EXE_vcsql"))
+        .args(["--repo", repo, "--attach", "tickets", "SELECT 1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("name=path"));
+}
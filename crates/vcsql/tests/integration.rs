@@ -1,8 +1,11 @@
 //! Integration tests for vcsql library API.
 
+use serde_json::json;
+use std::collections::HashSet;
 use std::process::Command;
 use tempfile::TempDir;
-use vcsql::{GitRepo, SqlEngine, VcsqlError, TABLES};
+use vcsql::sql::Attachment;
+use vcsql::{GitRepo, SqlEngine, TimeWindow, VcsqlError, TABLES};
 
 /// Creates a temporary Git repository with some commits for testing.
 fn create_test_repo() -> TempDir {
@@ -126,6 +129,46 @@ fn test_extract_table_names() {
     assert!(tables.contains("branches"));
 }
 
+#[test]
+fn test_extract_table_names_ignores_columns_aliases_and_literals() {
+    let tables = SqlEngine::extract_table_names(
+        "SELECT c.id AS status, 'notes refs config' AS label FROM commits c WHERE c.summary != 'tags'",
+    );
+    assert_eq!(tables, HashSet::from(["commits".to_string()]));
+}
+
+#[test]
+fn test_extract_table_names_follows_sqlite_scoping() {
+    // A CTE shadows the table it is named after.
+    let tables = SqlEngine::extract_table_names(
+        "WITH status AS (SELECT id FROM commits) SELECT * FROM status",
+    );
+    assert_eq!(tables, HashSet::from(["commits".to_string()]));
+
+    let tables = SqlEngine::extract_table_names(
+        "SELECT \"Branches\".name FROM \"Branches\" \
+         WHERE target_id IN (SELECT id FROM (SELECT id FROM main.commits)) \
+         AND EXISTS (SELECT 1 FROM tags t JOIN refs r USING (name))",
+    );
+    assert_eq!(
+        tables,
+        HashSet::from([
+            "branches".to_string(),
+            "commits".to_string(),
+            "tags".to_string(),
+            "refs".to_string(),
+        ])
+    );
+
+    let tables = SqlEngine::extract_table_names(
+        "SELECT name FROM pragma_table_info('Diffs') WHERE name != 'tags'",
+    );
+    assert_eq!(tables, HashSet::from(["diffs".to_string()]));
+
+    assert!(SqlEngine::extract_table_names("SELECT * FROM other.commits").is_empty());
+    assert!(SqlEngine::extract_table_names("SELEC * FROM commits").is_empty());
+}
+
 #[test]
 fn test_table_info() {
     assert_eq!(TABLES.len(), 17, "Should have 17 tables defined");
@@ -168,3 +211,222 @@ fn test_table_not_found() {
     let result = engine.load_table("nonexistent_table", &mut repo);
     assert!(matches!(result, Err(VcsqlError::TableNotFound(_))));
 }
+
+#[test]
+fn test_query_commits_across_repos() {
+    let first = create_test_repo();
+    let second = create_test_repo();
+    let mut engine = SqlEngine::new().expect("Failed to create engine");
+
+    for temp in [&first, &second] {
+        let mut repo = GitRepo::open(temp.path()).expect("Failed to open repo");
+        engine
+            .load_tables_for_query("SELECT * FROM commits", &mut repo)
+            .expect("Failed to load tables");
+    }
+
+    let result = engine
+        .execute("SELECT repo, COUNT(*) FROM commits GROUP BY repo")
+        .expect("Failed to execute query");
+
+    assert_eq!(result.row_count(), 2, "Should have one group per repo");
+}
+
+#[test]
+fn test_attach_infers_types_from_csv_json_and_sqlite() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let path = temp.path();
+    std::fs::write(
+        path.join("tickets.csv"),
+        "id,title,points,zip\n1,Fix login,3,02139\n2,\"Add, export\",2.5,\n",
+    )
+    .expect("Failed to write file");
+    std::fs::write(
+        path.join("events.log"),
+        "{\"sha\":\"abc\",\"ok\":true,\"meta\":{\"n\":1}}\n{\"sha\":\"def\",\"extra\":7}\n",
+    )
+    .expect("Failed to write file");
+    let db = rusqlite::Connection::open(path.join("other")).expect("Failed to create db");
+    db.execute_batch(
+        "CREATE TABLE owners (path TEXT, team TEXT); INSERT INTO owners VALUES ('src', 'core');",
+    )
+    .expect("Failed to fill db");
+    drop(db);
+
+    let mut engine = SqlEngine::new().expect("Failed to create engine");
+    for spec in ["tickets=tickets.csv", "events=events.log", "other=other"] {
+        let mut attachment: Attachment = spec.parse().expect("Failed to parse attachment");
+        attachment.path = path.join(attachment.path);
+        engine.attach(&attachment).expect("Failed to attach");
+    }
+
+    let result = engine
+        .execute("SELECT typeof(id), typeof(points), typeof(zip), zip FROM tickets ORDER BY id")
+        .expect("Failed to execute query");
+    assert_eq!(
+        result.rows[0],
+        [
+            json!("integer"),
+            json!("real"),
+            json!("text"),
+            json!("02139")
+        ]
+    );
+    assert_eq!(result.rows[1][3], json!(null));
+
+    let result = engine
+        .execute("SELECT sha, ok, json_extract(meta, '$.n'), extra FROM events ORDER BY sha")
+        .expect("Failed to execute query");
+    assert_eq!(
+        result.rows[0],
+        [json!("abc"), json!(1), json!(1), json!(null)]
+    );
+    assert_eq!(
+        result.rows[1],
+        [json!("def"), json!(null), json!(null), json!(7)]
+    );
+
+    let result = engine
+        .execute("SELECT o.team FROM other o JOIN other.owners USING (path)")
+        .expect("Failed to execute query");
+    assert_eq!(result.rows, [[json!("core")]]);
+
+    let conflict: Attachment = "commits=tickets.csv".parse().expect("Failed to parse");
+    assert!(engine.attach(&conflict).is_err());
+    assert!("1st=tickets.csv".parse::<Attachment>().is_err());
+}
+
+#[test]
+fn test_function_library_is_registered() {
+    let engine = SqlEngine::new().expect("Failed to create engine");
+    let result = engine
+        .execute(
+            "SELECT 'src/lib.rs' REGEXP '\\.rs$', \
+                    regexp_replace('v1.22', '(\\d+)', '<$1>'), \
+                    basename('src/sql/engine.rs'), dirname('src/sql/engine.rs'), \
+                    extension('engine.rs'), path_under('src/sql/engine.rs', 'src'), \
+                    path_under('srcx/lib.rs', 'src'), \
+                    json_extract_path('{\"a\": [{\"b.c\": 7}]}', 'a', 0, 'b.c'), \
+                    duration_ms('2026-01-01T00:00:00Z', '2026-01-01 00:01:30'), \
+                    token_count('hello world'), redact('password=hunter2')",
+        )
+        .expect("Failed to execute query");
+    assert_eq!(
+        result.rows[0],
+        [
+            json!(1),
+            json!("v<1>.<22>"),
+            json!("engine.rs"),
+            json!("src/sql"),
+            json!("rs"),
+            json!(1),
+            json!(0),
+            json!(7),
+            json!(90_000),
+            json!(2),
+            json!("password=<redacted>"),
+        ]
+    );
+
+    let result = engine
+        .execute(
+            "WITH t(x) AS (VALUES (1), (2), (10), (NULL)) \
+             SELECT median(x), percentile(x, 90) FROM t",
+        )
+        .expect("Failed to execute query");
+    assert_eq!(result.rows[0], [json!(2.0), json!(8.4)]);
+}
+
+/// Creates a repository with one commit on each of `dates` (oldest first).
+fn create_dated_repo(dates: &[&str]) -> TempDir {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let path = temp.path();
+    let git = |args: &[&str], date: Option<&str>| {
+        let mut command = Command::new("git");
+        command
+            .args([
+                "-c",
+                "user.name=Test User",
+                "-c",
+                "user.email=test@example.com",
+            ])
+            .args(args)
+            .current_dir(path);
+        if let Some(date) = date {
+            command
+                .env("GIT_AUTHOR_DATE", date)
+                .env("GIT_COMMITTER_DATE", date);
+        }
+        command.output().expect("Failed to run git");
+    };
+
+    git(&["init"], None);
+    for date in dates {
+        std::fs::write(path.join("log.txt"), date).expect("Failed to write file");
+        git(&["add", "."], None);
+        git(&["commit", "-m", &format!("Commit of {date}")], Some(date));
+    }
+    temp
+}
+
+#[test]
+fn test_time_window_limits_the_commit_walk() {
+    let temp = create_dated_repo(&[
+        "2026-01-05T12:00:00Z",
+        "2026-01-15T12:00:00Z",
+        "2026-01-25T12:00:00Z",
+    ]);
+    let mut repo = GitRepo::open(temp.path()).expect("Failed to open repo");
+    repo.set_time_window(
+        TimeWindow::parse(Some("2026-01-10"), Some("2026-01-20")).expect("window"),
+    );
+    let mut engine = SqlEngine::new().expect("Failed to create engine");
+    let sql = "SELECT c.summary, p.parent_index FROM commits c \
+               LEFT JOIN commit_parents p ON p.commit_id = c.id";
+    engine
+        .load_tables_for_query(sql, &mut repo)
+        .expect("Failed to load tables");
+
+    let rows = engine.execute(sql).expect("query").to_json_array();
+    assert_eq!(
+        rows,
+        vec![serde_json::json!({"summary": "Commit of 2026-01-15T12:00:00Z", "parent_index": 0})]
+    );
+}
+
+#[test]
+fn test_time_window_parsing() {
+    let now = chrono::DateTime::parse_from_rfc3339("2026-03-10T08:00:00Z")
+        .expect("now")
+        .with_timezone(&chrono::Utc);
+    let at = |text: &str| {
+        TimeWindow::parse_at(Some(text), None, now)
+            .expect("parse")
+            .since
+            .expect("since")
+            .to_rfc3339()
+    };
+
+    assert_eq!(at("2026-03-01"), "2026-03-01T00:00:00+00:00");
+    assert_eq!(at("2026-03-01 09:30:00"), "2026-03-01T09:30:00+00:00");
+    assert_eq!(at("2026-03-01T09:30:00+02:00"), "2026-03-01T07:30:00+00:00");
+    assert_eq!(at("7d"), "2026-03-03T08:00:00+00:00");
+    assert_eq!(at("2w"), "2026-02-24T08:00:00+00:00");
+    assert_eq!(at("36h"), "2026-03-08T20:00:00+00:00");
+
+    assert!(TimeWindow::default().is_unbounded());
+    assert!(matches!(
+        TimeWindow::parse_at(Some("last tuesday"), None, now),
+        Err(VcsqlError::InvalidTime(_))
+    ));
+    assert!(matches!(
+        TimeWindow::parse_at(Some("1d"), Some("2d"), now),
+        Err(VcsqlError::InvalidTime(_))
+    ));
+
+    let window = TimeWindow::parse_at(Some("2026-03-01"), Some("2026-03-02"), now).expect("window");
+    assert!(window.contains_rfc3339("2026-03-01T23:59:59.999Z"));
+    assert!(!window.contains_rfc3339("2026-03-02T00:00:00Z"));
+    assert!(!window.contains_rfc3339("yesterday"));
+    assert!(TimeWindow::default().contains_rfc3339("yesterday"));
+}